@@ -26,7 +26,7 @@ ${BIN} update-environment --uuid $ENV_UUID --name name_foo7 --display-name displ
 ${BIN} update-environment --uuid $ENV_UUID --name name_foo8 --contact-name contact_name_foo8
 ${BIN} update-environment --uuid $ENV_UUID --name name_foo9 --contact-name contact_name_foo9 --contact-email-address contact_name_foo7
 ${BIN} delete-environment --uuid $ENV_UUID
-${BIN} list-environments | awk -F '│' '{print $2}' | sed -n '3,$p' | sed '$d' | awk '{print $1}' | xargs -I {} ${BIN} delete-environment --uuid {} 
+${BIN} list-environments --output id | xargs -I {} ${BIN} delete-environment --uuid {}
 ${BIN} create-environment --name production --production
 ${BIN} publish pacts -r
 ${BIN} publish pacts -a foo --branch bar
@@ -26,14 +26,30 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use serde_with::skip_serializing_none;
 use tracing::{debug, error, info, trace, warn};
+pub mod api;
+pub mod apply;
+pub mod backup;
 pub mod branches;
 pub mod can_i_deploy;
+pub mod client;
+pub mod config;
+pub mod credentials;
 pub mod deployments;
 pub mod environments;
+pub mod http_archive;
+pub mod lint;
+pub mod migrate;
+#[cfg(any(test, feature = "mock-broker"))]
+pub mod mock_broker;
+pub mod oauth;
+pub mod output;
 pub mod pact_publish;
 pub mod pacticipants;
 pub mod pacts;
+pub mod promote;
 pub mod provider_states;
+pub mod proxy;
+pub mod query;
 pub mod subcommands;
 pub mod tags;
 #[cfg(test)]
@@ -56,7 +72,8 @@ use reqwest_middleware::{Middleware, Next};
 use reqwest_retry::{DefaultRetryableStrategy, Retryable, RetryableStrategy};
 use reqwest_tracing::TracingMiddleware;
 
-use crate::cli::pact_broker::main::types::SslOptions;
+use crate::cli::pact_broker::main::http_archive::HttpArchive;
+use crate::cli::pact_broker::main::types::{ConnectionOptions, Pagination};
 
 pub fn process_notices(notices: &[Notice]) {
     for notice in notices {
@@ -155,6 +172,9 @@ pub enum PactBrokerError {
     /// Validation error with notices
     #[error("failed validation - {0:?}")]
     ValidationErrorWithNotices(Vec<String>, Vec<Notice>),
+    /// A request that would change the broker was not sent, as `--dry-run` was given
+    #[error("{0} was not sent (--dry-run)")]
+    DryRun(String),
 }
 
 impl PartialEq<String> for PactBrokerError {
@@ -166,6 +186,7 @@ impl PartialEq<String> for PactBrokerError {
             PactBrokerError::IoError(s) => buffer.push_str(s),
             PactBrokerError::NotFound(s) => buffer.push_str(s),
             PactBrokerError::UrlError(s) => buffer.push_str(s),
+            PactBrokerError::DryRun(s) => buffer.push_str(s),
             PactBrokerError::ValidationError(errors) => {
                 buffer.push_str(errors.iter().join(", ").as_str())
             }
@@ -185,6 +206,7 @@ impl PartialEq<&str> for PactBrokerError {
             PactBrokerError::IoError(s) => s.clone(),
             PactBrokerError::NotFound(s) => s.clone(),
             PactBrokerError::UrlError(s) => s.clone(),
+            PactBrokerError::DryRun(s) => s.clone(),
             PactBrokerError::ValidationError(errors) => errors.iter().join(", "),
             PactBrokerError::ValidationErrorWithNotices(errors, _) => errors.iter().join(", "),
         };
@@ -192,6 +214,20 @@ impl PartialEq<&str> for PactBrokerError {
     }
 }
 
+impl PactBrokerError {
+    /// Replaces the error using `f`, except for a [`PactBrokerError::DryRun`], which is kept so
+    /// that the command can report the request that was not sent
+    pub(crate) fn map_unless_dry_run(
+        self,
+        f: impl FnOnce(PactBrokerError) -> PactBrokerError,
+    ) -> PactBrokerError {
+        match self {
+            PactBrokerError::DryRun(_) => self,
+            err => f(err),
+        }
+    }
+}
+
 impl From<url::ParseError> for PactBrokerError {
     fn from(err: url::ParseError) -> Self {
         PactBrokerError::UrlError(format!("{}", err))
@@ -257,6 +293,18 @@ impl Default for Link {
     }
 }
 
+/// The path and query of a link, which like [`HALClient::fetch_url`] is fetched from the
+/// configured broker URL rather than the host in the link
+fn href_path(href: &str) -> String {
+    match Url::parse(href) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
+        Err(_) => href.to_string(),
+    }
+}
+
 /// HAL aware HTTP client
 #[derive(Clone)]
 pub struct HALClient {
@@ -265,7 +313,7 @@ pub struct HALClient {
     path_info: Option<Value>,
     auth: Option<HttpAuth>,
     custom_headers: Option<CustomHeaders>,
-    ssl_options: SslOptions,
+    connection_options: ConnectionOptions,
     pub retries: u8,
 }
 
@@ -297,6 +345,33 @@ impl Middleware for OtelPropagatorMiddleware {
     }
 }
 
+/// Middleware for `--print-curl`, which prints each request to stderr as a curl command, with the
+/// credentials masked, before it is sent
+struct CurlMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for CurlMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let custom_headers = extensions.get::<CustomHeaders>();
+        eprintln!(
+            "{}",
+            crate::cli::utils::AsCurl::new(&req)
+                .masked()
+                .masking_headers(
+                    custom_headers
+                        .iter()
+                        .flat_map(|custom| custom.headers.keys())
+                )
+        );
+        next.run(req, extensions).await
+    }
+}
+
 /// Parses the value of a `Retry-After` response header into a [`Duration`].
 ///
 /// The header may be either:
@@ -438,6 +513,8 @@ impl HALClient {
             for (name, value) in &custom_headers.headers {
                 builder = builder.header(name, value);
             }
+            // So the HttpRecordMiddleware knows which headers to redact
+            builder = builder.with_extension(custom_headers.clone());
         }
         builder
     }
@@ -446,15 +523,15 @@ impl HALClient {
     pub fn with_url(
         url: &str,
         auth: Option<HttpAuth>,
-        ssl_options: SslOptions,
+        connection_options: ConnectionOptions,
         custom_headers: Option<CustomHeaders>,
     ) -> HALClient {
         HALClient {
             url: url.to_string(),
             auth: auth.clone(),
             custom_headers,
-            ssl_options: ssl_options.clone(),
-            ..HALClient::setup(url, auth, ssl_options)
+            connection_options: connection_options.clone(),
+            ..HALClient::setup(url, auth, connection_options)
         }
     }
 
@@ -466,7 +543,7 @@ impl HALClient {
             auth: self.auth,
             custom_headers: self.custom_headers,
             retries: self.retries,
-            ssl_options: self.ssl_options,
+            connection_options: self.connection_options,
         }
     }
 
@@ -643,6 +720,81 @@ impl HALClient {
         Ok(url)
     }
 
+    /// The pages of a paginated resource, fetched as they are read by following the `next` link
+    /// of each page
+    pub fn fetch_pages<'a>(
+        &'a self,
+        path: &str,
+    ) -> impl Stream<Item = Result<Value, PactBrokerError>> + 'a {
+        try_unfold(
+            (Some(path.to_string()), vec![]),
+            move |(next, mut fetched): (Option<String>, Vec<String>)| async move {
+                let Some(path) = next else {
+                    return Ok(None);
+                };
+                let page = self.fetch(&href_path(&path)).await?;
+                fetched.push(path);
+                // A broker that links back to a page already fetched would otherwise never finish
+                let next = page["_links"]["next"]["href"]
+                    .as_str()
+                    .map(str::to_string)
+                    .filter(|href| !fetched.contains(href));
+                Ok(Some((page, (next, fetched))))
+            },
+        )
+    }
+
+    /// Fetches a paginated collection. The result is the first page, with the items of every
+    /// page fetched in the arrays found with the `items` JSON pointers, and without the links
+    /// and details of the page.
+    pub async fn fetch_collection(
+        &self,
+        path: &str,
+        items: &[&str],
+        pagination: &Pagination,
+    ) -> Result<Value, PactBrokerError> {
+        let path = match pagination.page_size {
+            Some(size) => format!(
+                "{}{}pageNumber=1&pageSize={}",
+                path,
+                if path.contains('?') { '&' } else { '?' },
+                size
+            ),
+            None => path.to_string(),
+        };
+        let limit = pagination.limit.unwrap_or(usize::MAX);
+        let mut pages = std::pin::pin!(self.fetch_pages(&path));
+        let mut collection: Option<Value> = None;
+        let mut collected: Vec<Vec<Value>> = vec![vec![]; items.len()];
+        while collected.iter().map(Vec::len).max().unwrap_or(0) < limit {
+            let Some(page) = pages.try_next().await? else {
+                break;
+            };
+            for (pointer, collected) in items.iter().zip(collected.iter_mut()) {
+                if let Some(Value::Array(page_items)) = page.pointer(pointer) {
+                    let remaining = limit - collected.len();
+                    collected.extend(page_items.iter().take(remaining).cloned());
+                }
+            }
+            collection.get_or_insert(page);
+        }
+
+        let mut collection = collection.unwrap_or_else(|| json!({}));
+        for (pointer, collected) in items.iter().zip(collected) {
+            if let Some(target) = collection.pointer_mut(pointer) {
+                *target = Value::Array(collected);
+            }
+        }
+        if let Some(links) = collection["_links"].as_object_mut() {
+            links.remove("next");
+            links.remove("previous");
+        }
+        if let Some(fields) = collection.as_object_mut() {
+            fields.remove("page");
+        }
+        Ok(collection)
+    }
+
     pub async fn delete(self, path: &str) -> Result<Value, PactBrokerError> {
         info!("Deleting path '{}' from pact broker", path);
 
@@ -671,6 +823,7 @@ impl HALClient {
             None => self.client.delete(url),
         }
         .header("Accept", "application/hal+json");
+        self.hold_back_for_dry_run(&request_builder)?;
 
         let response = request_builder.send().await.map_err(|err| {
             PactBrokerError::IoError(format!(
@@ -764,7 +917,7 @@ impl HALClient {
         }
     }
 
-    fn parse_link_url(
+    pub(crate) fn parse_link_url(
         &self,
         link: &Link,
         values: &HashMap<String, String>,
@@ -821,6 +974,92 @@ impl HALClient {
     }
     }
 
+    /// Send a request with any method to a path or URL on the Pact Broker, with optional query
+    /// parameters and JSON body
+    pub async fn send_request(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(String, String)],
+        body: Option<&str>,
+    ) -> Result<Value, PactBrokerError> {
+        info!(
+            "Sending {} request to path '{}' on pact broker",
+            method, path
+        );
+        let mut url = self.resolve_path(path)?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        let mut request_builder = match self.auth {
+            Some(ref auth) => match auth {
+                HttpAuth::User(username, password) => self
+                    .client
+                    .request(method.clone(), url.clone())
+                    .basic_auth(username, password.clone()),
+                HttpAuth::Token(token) => self
+                    .client
+                    .request(method.clone(), url.clone())
+                    .bearer_auth(token),
+                _ => self.client.request(method.clone(), url.clone()),
+            },
+            None => self.client.request(method.clone(), url.clone()),
+        }
+        .header("Accept", "application/hal+json, application/json");
+        if let Some(body) = body {
+            request_builder = request_builder
+                .header("Content-Type", "application/json")
+                .body(body.to_string());
+        }
+        request_builder = self.apply_custom_headers(request_builder);
+        if !method.is_safe() {
+            self.hold_back_for_dry_run(&request_builder)?;
+        }
+
+        let response = request_builder.send().await.map_err(|err| {
+            PactBrokerError::IoError(format!(
+                "Failed to send {} request to pact broker path '{}' - {}. URL: '{}'",
+                method, path, err, self.url,
+            ))
+        })?;
+
+        self.parse_broker_response(url.path().to_string(), response)
+            .await
+    }
+
+    /// With `--dry-run`, prints the request as a curl command with the credentials masked, and
+    /// returns [`PactBrokerError::DryRun`] so that it is not sent
+    fn hold_back_for_dry_run(
+        &self,
+        request_builder: &reqwest_middleware::RequestBuilder,
+    ) -> Result<(), PactBrokerError> {
+        if !self.connection_options.dry_run {
+            return Ok(());
+        }
+        let request = request_builder
+            .try_clone()
+            .ok_or_else(|| PactBrokerError::IoError("Request could not be cloned".to_string()))?
+            .build()
+            .map_err(|err| PactBrokerError::IoError(format!("Invalid request - {}", err)))?;
+        eprintln!(
+            "{}\n{}",
+            YELLOW.apply_to("# --dry-run: this request was not sent"),
+            crate::cli::utils::AsCurl::new(&request)
+                .masked()
+                .masking_headers(
+                    self.custom_headers
+                        .iter()
+                        .flat_map(|custom| custom.headers.keys())
+                )
+        );
+        Err(PactBrokerError::DryRun(format!(
+            "{} {}",
+            request.method(),
+            request.url()
+        )))
+    }
+
     pub async fn post_json(
         &self,
         url: &str,
@@ -829,7 +1068,22 @@ impl HALClient {
     ) -> Result<serde_json::Value, PactBrokerError> {
         trace!("post_json(url='{}', body='{}')", url, body);
 
-        self.send_document(url, body, Method::POST, headers).await
+        self.send_document(url, body, Method::POST, headers, true)
+            .await
+    }
+
+    /// POSTs a query to the broker, such as the pacts for verification. It does not change the
+    /// broker, so unlike [`HALClient::post_json`] it is also sent with `--dry-run`
+    pub async fn post_query_json(
+        &self,
+        url: &str,
+        body: &str,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<serde_json::Value, PactBrokerError> {
+        trace!("post_query_json(url='{}', body='{}')", url, body);
+
+        self.send_document(url, body, Method::POST, headers, false)
+            .await
     }
 
     pub async fn put_json(
@@ -840,7 +1094,8 @@ impl HALClient {
     ) -> Result<serde_json::Value, PactBrokerError> {
         trace!("put_json(url='{}', body='{}')", url, body);
 
-        self.send_document(url, body, Method::PUT, headers).await
+        self.send_document(url, body, Method::PUT, headers, true)
+            .await
     }
     pub async fn patch_json(
         &self,
@@ -850,7 +1105,8 @@ impl HALClient {
     ) -> Result<serde_json::Value, PactBrokerError> {
         trace!("put_json(url='{}', body='{}')", url, body);
 
-        self.send_document(url, body, Method::PATCH, headers).await
+        self.send_document(url, body, Method::PATCH, headers, true)
+            .await
     }
 
     async fn send_document(
@@ -859,6 +1115,7 @@ impl HALClient {
         body: &str,
         method: Method,
         headers: Option<HashMap<String, String>>,
+        changes_broker: bool,
     ) -> Result<Value, PactBrokerError> {
         let method_type = method.clone();
         debug!("Sending JSON to {} using {}: {}", url, method, body);
@@ -904,6 +1161,9 @@ impl HALClient {
         } else {
             request_builder.header("Content-Type", "application/json")
         };
+        if changes_broker {
+            self.hold_back_for_dry_run(&request_builder)?;
+        }
         match request_builder.send().await {
             Ok(res) => {
                 self.parse_broker_response(url.path().to_string(), res)
@@ -970,8 +1230,8 @@ fn handle_validation_errors(body: Value) -> PactBrokerError {
 }
 
 impl HALClient {
-    /// Builds the reqwest-middleware client stack for a given retry count and SSL
-    /// configuration.
+    /// Builds the reqwest-middleware client stack for a given retry count and connection
+    /// options.
     ///
     /// The middleware chain is (outermost → innermost):
     /// 1. [`TracingMiddleware`] — adds OpenTelemetry trace context to every request.
@@ -982,19 +1242,24 @@ impl HALClient {
     /// # Arguments
     ///
     /// * `retries` - Maximum number of total attempts (including the first send).
-    /// * `ssl_options` - TLS configuration (custom CA cert, skip-verify, …).
+    /// * `connection_options` - TLS (custom CA cert, skip-verify, …), client certificate, proxy,
+    ///   OAuth2, HAR and curl settings.
     ///
     /// # Returns
     ///
     /// A fully configured [`ClientWithMiddleware`] ready for use.
-    fn build_middleware_client(retries: u8, ssl_options: &SslOptions) -> ClientWithMiddleware {
+    fn build_middleware_client(
+        retries: u8,
+        connection_options: &ConnectionOptions,
+    ) -> ClientWithMiddleware {
         let mut builder = reqwest::Client::builder().user_agent(format!(
             "{}/{}",
             env!("CARGO_PKG_NAME"),
             env!("CARGO_PKG_VERSION")
         ));
 
-        debug!("Using ssl_options: {:?}", ssl_options);
+        debug!("Using connection_options: {:?}", connection_options);
+        let ssl_options = &connection_options.ssl;
         if let Some(ref path) = ssl_options.ssl_cert_path {
             if let Ok(cert_bytes) = std::fs::read(path) {
                 match reqwest::Certificate::from_pem_bundle(&cert_bytes) {
@@ -1029,28 +1294,91 @@ impl HALClient {
                 "ssl-trust-store disabled but no custom certificate provided; proceeding with system roots"
             );
         }
+        if let Some(ref path) = connection_options.proxy.ca_cert_path {
+            match std::fs::read(path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| {
+                    reqwest::Certificate::from_pem_bundle(&bytes).map_err(|err| err.to_string())
+                }) {
+                Ok(certs) => {
+                    debug!("Adding proxy CA certificates from path: {}", path);
+                    builder = builder.tls_certs_merge(certs);
+                }
+                Err(err) => warn!(
+                    "Could not load the proxy CA certificates from path {}: {}",
+                    path, err
+                ),
+            }
+        }
+        if let Some(ref identity) = connection_options.client_identity {
+            match identity.load() {
+                Ok(identity) => {
+                    debug!("Using client certificate for mutual TLS");
+                    builder = builder.identity(identity);
+                }
+                Err(err) => error!("{}", err),
+            }
+        }
         if ssl_options.skip_ssl {
             builder = builder.danger_accept_invalid_certs(true);
             debug!("Skipping SSL certificate validation");
         }
+        // reqwest is built without its system-proxy feature, so the environment variables are
+        // read into the proxy options, which are also what the ProxyTraceMiddleware logs
+        if let Some(proxy) = proxy::build_proxy(&connection_options.proxy) {
+            builder = builder.proxy(proxy);
+        }
 
         let built_client = builder.build().expect("failed to build reqwest client");
-        ClientBuilder::new(built_client)
+        let mut client = ClientBuilder::new(built_client.clone())
             .with(TracingMiddleware::default())
-            .with(OtelPropagatorMiddleware)
-            .with(RetryMiddleware {
-                max_attempts: retries,
-            })
-            .build()
+            .with(OtelPropagatorMiddleware);
+        // A replay never touches the network, so there is no proxy to use or token to fetch
+        let replaying = matches!(
+            connection_options.http_archive,
+            Some(HttpArchive::Replay(_))
+        );
+        if !replaying {
+            client = client.with(proxy::ProxyTraceMiddleware {
+                options: connection_options.proxy.clone(),
+            });
+            if let Some(ref options) = connection_options.oauth {
+                client = client.with(oauth::OAuthMiddleware {
+                    options: options.clone(),
+                    client: built_client,
+                });
+            }
+        }
+        client = client.with(RetryMiddleware {
+            max_attempts: retries,
+        });
+        if connection_options.print_curl {
+            client = client.with(CurlMiddleware);
+        }
+        // Innermost, so each attempt made by the RetryMiddleware is recorded or replayed
+        match connection_options.http_archive {
+            Some(HttpArchive::Record(ref path)) => {
+                client = client.with(http_archive::HttpRecordMiddleware { path: path.clone() })
+            }
+            Some(HttpArchive::Replay(ref path)) => {
+                client = client.with(http_archive::HttpReplayMiddleware { path: path.clone() })
+            }
+            None => {}
+        }
+        client.build()
     }
 
-    pub fn setup(url: &str, auth: Option<HttpAuth>, ssl_options: SslOptions) -> HALClient {
+    pub fn setup(
+        url: &str,
+        auth: Option<HttpAuth>,
+        connection_options: ConnectionOptions,
+    ) -> HALClient {
         let retries = std::env::var("PACT_BROKER_HTTP_RETRIES")
             .ok()
             .and_then(|v| v.parse::<u8>().ok())
             .unwrap_or(8);
 
-        let client = Self::build_middleware_client(retries, &ssl_options);
+        let client = Self::build_middleware_client(retries, &connection_options);
 
         HALClient {
             client,
@@ -1059,7 +1387,7 @@ impl HALClient {
             auth,
             custom_headers: None,
             retries,
-            ssl_options,
+            connection_options,
         }
     }
 
@@ -1070,7 +1398,7 @@ impl HALClient {
     /// This rebuilds the internal HTTP client so the new retry count takes effect immediately.
     pub fn with_retry_count(mut self, retries: u8) -> Self {
         self.retries = retries;
-        self.client = Self::build_middleware_client(retries, &self.ssl_options);
+        self.client = Self::build_middleware_client(retries, &self.connection_options);
         self
     }
 }
@@ -1096,7 +1424,7 @@ pub async fn fetch_pacts_from_broker(
     broker_url: &str,
     provider_name: &str,
     auth: Option<HttpAuth>,
-    ssl_options: SslOptions,
+    connection_options: ConnectionOptions,
     custom_headers: Option<CustomHeaders>,
 ) -> anyhow::Result<
     Vec<
@@ -1114,7 +1442,7 @@ pub async fn fetch_pacts_from_broker(
         auth.clone().unwrap_or_default()
     );
 
-    let mut hal_client = HALClient::with_url(broker_url, auth, ssl_options, custom_headers);
+    let mut hal_client = HALClient::with_url(broker_url, auth, connection_options, custom_headers);
     let template_values = hashmap! { "provider".to_string() => provider_name.to_string() };
 
     hal_client = hal_client
@@ -1181,7 +1509,7 @@ pub async fn fetch_pacts_dynamically_from_broker(
     provider_branch: Option<String>,
     consumer_version_selectors: Vec<ConsumerVersionSelector>,
     auth: Option<HttpAuth>,
-    ssl_options: SslOptions,
+    connection_options: ConnectionOptions,
     headers: Option<HashMap<String, String>>,
     custom_headers: Option<CustomHeaders>,
 ) -> anyhow::Result<
@@ -1208,7 +1536,7 @@ pub async fn fetch_pacts_dynamically_from_broker(
         auth.clone().unwrap_or_default()
     );
 
-    let mut hal_client = HALClient::with_url(broker_url, auth, ssl_options, custom_headers);
+    let mut hal_client = HALClient::with_url(broker_url, auth, connection_options, custom_headers);
     let template_values = hashmap! { "provider".to_string() => provider_name.clone() };
 
     hal_client = hal_client
@@ -1239,7 +1567,7 @@ pub async fn fetch_pacts_dynamically_from_broker(
             let link = hal_client.clone().parse_link_url(&link, &hashmap! {})?;
             match hal_client
                 .clone()
-                .post_json(link.as_str(), request_body.as_str(), headers)
+                .post_query_json(link.as_str(), request_body.as_str(), headers)
                 .await
             {
                 Ok(res) => Some(res),
@@ -1450,6 +1778,9 @@ pub struct PactVerificationProperties {
     #[serde(default)]
     /// If the Pact is pending
     pub pending: bool,
+    #[serde(default)]
+    /// If the Pact is a work in progress (WIP) pact
+    pub wip: bool,
     /// Notices provided by the Pact Broker
     pub notices: Vec<HashMap<String, String>>,
 }
@@ -1457,7 +1788,7 @@ pub struct PactVerificationProperties {
 #[cfg(test)]
 mod hal_client_custom_headers_tests {
     use super::*;
-    use crate::cli::pact_broker::main::types::SslOptions;
+    use crate::cli::pact_broker::main::types::ConnectionOptions;
     use std::collections::HashMap;
 
     fn create_test_custom_headers() -> CustomHeaders {
@@ -1483,12 +1814,12 @@ mod hal_client_custom_headers_tests {
     #[test]
     fn test_hal_client_with_custom_headers() {
         let custom_headers = Some(create_test_custom_headers());
-        let ssl_options = SslOptions::default();
+        let connection_options = ConnectionOptions::default();
 
         let client = HALClient::with_url(
             "https://test.example.com",
             None,
-            ssl_options,
+            connection_options,
             custom_headers.clone(),
         );
 
@@ -1510,12 +1841,12 @@ mod hal_client_custom_headers_tests {
     #[test]
     fn test_hal_client_with_cloudflare_headers() {
         let custom_headers = Some(create_cloudflare_custom_headers());
-        let ssl_options = SslOptions::default();
+        let connection_options = ConnectionOptions::default();
 
         let client = HALClient::with_url(
             "https://pact-broker.example.com",
             None,
-            ssl_options,
+            connection_options,
             custom_headers.clone(),
         );
 
@@ -1535,9 +1866,10 @@ mod hal_client_custom_headers_tests {
 
     #[test]
     fn test_hal_client_without_custom_headers() {
-        let ssl_options = SslOptions::default();
+        let connection_options = ConnectionOptions::default();
 
-        let client = HALClient::with_url("https://test.example.com", None, ssl_options, None);
+        let client =
+            HALClient::with_url("https://test.example.com", None, connection_options, None);
 
         assert!(client.custom_headers.is_none());
     }
@@ -1546,12 +1878,12 @@ mod hal_client_custom_headers_tests {
     fn test_hal_client_with_auth_and_custom_headers() {
         let auth = Some(HttpAuth::Token("bearer-token".to_string()));
         let custom_headers = Some(create_test_custom_headers());
-        let ssl_options = SslOptions::default();
+        let connection_options = ConnectionOptions::default();
 
         let client = HALClient::with_url(
             "https://test.example.com",
             auth.clone(),
-            ssl_options,
+            connection_options,
             custom_headers,
         );
 
@@ -1569,12 +1901,12 @@ mod hal_client_custom_headers_tests {
         use reqwest_middleware::ClientBuilder;
 
         let custom_headers = Some(create_test_custom_headers());
-        let ssl_options = SslOptions::default();
+        let connection_options = ConnectionOptions::default();
 
         let client = HALClient::with_url(
             "https://test.example.com",
             None,
-            ssl_options,
+            connection_options,
             custom_headers,
         );
 
@@ -1618,9 +1950,10 @@ mod hal_client_custom_headers_tests {
         use reqwest::Client;
         use reqwest_middleware::ClientBuilder;
 
-        let ssl_options = SslOptions::default();
+        let connection_options = ConnectionOptions::default();
 
-        let client = HALClient::with_url("https://test.example.com", None, ssl_options, None);
+        let client =
+            HALClient::with_url("https://test.example.com", None, connection_options, None);
 
         // Create a mock request builder
         let reqwest_client = Client::new();
@@ -1693,13 +2026,13 @@ mod tests {
 
     #[test]
     fn resolve_path_test() {
-        let client = HALClient::with_url("not a URL", None, SslOptions::default(), None);
+        let client = HALClient::with_url("not a URL", None, ConnectionOptions::default(), None);
         expect!(client.resolve_path("/any")).to(be_err());
 
         let client = HALClient::with_url(
             "http://localhost-ip4:1234",
             None,
-            SslOptions::default(),
+            ConnectionOptions::default(),
             None,
         );
         expect!(client.resolve_path(""))
@@ -1722,7 +2055,7 @@ mod tests {
         let client = HALClient::with_url(
             "http://localhost-ip4:1234/base-path",
             None,
-            SslOptions::default(),
+            ConnectionOptions::default(),
             None,
         );
         expect!(client.resolve_path(""))
@@ -1769,7 +2102,7 @@ mod tests {
         let client = HALClient::with_url(
             pact_broker.url().as_str(),
             None,
-            SslOptions::default(),
+            ConnectionOptions::default(),
             None,
         );
         let result = client.navigate("next", &hashmap! {}).await.unwrap();
@@ -1794,7 +2127,7 @@ mod tests {
         let mut client = HALClient::with_url(
             pact_broker.url().as_str(),
             None,
-            SslOptions::default(),
+            ConnectionOptions::default(),
             None,
         );
         client.path_info = Some(json!({
@@ -1833,7 +2166,7 @@ mod tests {
         let client = HALClient::with_url(
             pact_broker.url().join("/base-path").unwrap().as_str(),
             None,
-            SslOptions::default(),
+            ConnectionOptions::default(),
             None,
         );
         let result = client.navigate("next", &hashmap! {}).await.unwrap();
@@ -1853,7 +2186,7 @@ mod retry_middleware_tests {
     use axum::{Router, body::Body, http::StatusCode, response::Response, routing::get};
     use tokio::net::TcpListener;
 
-    use super::{HALClient, SslOptions};
+    use super::{ConnectionOptions, HALClient};
 
     async fn spawn_test_server(router: Router) -> String {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -1865,7 +2198,8 @@ mod retry_middleware_tests {
     }
 
     fn hal_client(base_url: &str, retries: u8) -> HALClient {
-        HALClient::with_url(base_url, None, SslOptions::default(), None).with_retry_count(retries)
+        HALClient::with_url(base_url, None, ConnectionOptions::default(), None)
+            .with_retry_count(retries)
     }
 
     #[tokio::test]
@@ -2094,3 +2428,102 @@ mod retry_middleware_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod curl_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{
+        Router,
+        http::{StatusCode, header::CONTENT_TYPE},
+        routing::get,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::{ConnectionOptions, HALClient, PactBrokerError};
+
+    /// A broker that counts the POST requests it receives
+    async fn spawn_test_server(posts: Arc<AtomicUsize>) -> String {
+        let router = Router::new().route(
+            "/",
+            get(|| async {
+                (
+                    StatusCode::OK,
+                    [(CONTENT_TYPE, "application/hal+json")],
+                    "{\"_links\":{}}",
+                )
+            })
+            .post(move || {
+                let posts = posts.clone();
+                async move {
+                    posts.fetch_add(1, Ordering::SeqCst);
+                    (
+                        StatusCode::OK,
+                        [(CONTENT_TYPE, "application/hal+json")],
+                        "{\"created\":true}",
+                    )
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn hal_client(base_url: &str, print_curl: bool, dry_run: bool) -> HALClient {
+        let connection_options = ConnectionOptions {
+            print_curl,
+            dry_run,
+            ..ConnectionOptions::default()
+        };
+        HALClient::with_url(base_url, None, connection_options, None).with_retry_count(0)
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_send_requests_that_change_the_broker() {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let base_url = spawn_test_server(posts.clone()).await;
+        let client = hal_client(&base_url, false, true);
+
+        assert!(client.fetch("/").await.is_ok());
+        let result = client.post_json("/", "{}", None).await;
+
+        match result {
+            Err(PactBrokerError::DryRun(request)) => {
+                assert_eq!(request, format!("POST {}/", base_url))
+            }
+            other => panic!("Expected the request to be held back, got {:?}", other),
+        }
+        assert_eq!(posts.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn dry_run_still_sends_queries() {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let base_url = spawn_test_server(posts.clone()).await;
+        let client = hal_client(&base_url, false, true);
+
+        let result = client.post_query_json("/", "{}", None).await;
+
+        assert_eq!(result.unwrap(), json!({"created": true}));
+        assert_eq!(posts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn print_curl_still_sends_the_requests() {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let base_url = spawn_test_server(posts.clone()).await;
+        let client = hal_client(&base_url, true, false);
+
+        let result = client.post_json("/", "{}", None).await;
+
+        assert_eq!(result.unwrap(), json!({"created": true}));
+        assert_eq!(posts.load(Ordering::SeqCst), 1);
+    }
+}
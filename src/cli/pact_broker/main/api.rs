@@ -0,0 +1,402 @@
+//! Authenticated requests to any Pact Broker endpoint, for when there is no command for it
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use clap::ArgMatches;
+use regex::Regex;
+use reqwest::Method;
+use serde_json::{Map, Value};
+
+use super::output::{self, OutputType};
+use super::types::BrokerDetails;
+use super::{HALClient, Link, PactBrokerError};
+
+/// A `key=value` pair given with `-f` or `-F`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// Name of the field
+    pub name: String,
+    /// Value, a string for `-f` and parsed as JSON where possible for `-F`
+    pub value: Value,
+}
+
+impl Field {
+    fn parse(field: &str, typed: bool) -> Result<Field, PactBrokerError> {
+        let (name, value) = field.split_once('=').ok_or_else(|| {
+            PactBrokerError::ValidationError(vec![format!(
+                "Field '{}' must be in the format key=value",
+                field
+            )])
+        })?;
+        let value = if typed {
+            serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+        } else {
+            Value::String(value.to_string())
+        };
+        Ok(Field {
+            name: name.to_string(),
+            value,
+        })
+    }
+
+    fn value_as_string(&self) -> String {
+        match &self.value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+fn parse_fields(args: &ArgMatches) -> Result<Vec<Field>, PactBrokerError> {
+    let raw = args
+        .get_many::<String>("field")
+        .into_iter()
+        .flatten()
+        .map(|field| Field::parse(field, false));
+    let typed = args
+        .get_many::<String>("typed-field")
+        .into_iter()
+        .flatten()
+        .map(|field| Field::parse(field, true));
+    raw.chain(typed).collect()
+}
+
+fn read_input(input: &str) -> Result<String, PactBrokerError> {
+    if input == "-" {
+        let mut body = String::new();
+        std::io::stdin()
+            .read_to_string(&mut body)
+            .map_err(|err| PactBrokerError::IoError(format!("Failed to read stdin: {}", err)))?;
+        Ok(body)
+    } else {
+        std::fs::read_to_string(input)
+            .map_err(|err| PactBrokerError::IoError(format!("Failed to read {}: {}", input, err)))
+    }
+}
+
+/// Find the named link in a HAL resource and expand it with the fields. Returns the URL and the
+/// names of the fields used to fill in the template.
+pub fn resolve_link(
+    hal_client: &HALClient,
+    resource: &Value,
+    rel: &str,
+    fields: &[Field],
+) -> Result<(String, Vec<String>), PactBrokerError> {
+    let links = resource.get("_links").ok_or_else(|| {
+        PactBrokerError::LinkError(format!(
+            "Expected a HAL+JSON response with '_links' to follow '{}'",
+            rel
+        ))
+    })?;
+    let link_data = links.get(rel).ok_or_else(|| {
+        PactBrokerError::LinkError(format!(
+            "Link '{}' was not found in the response, only the following links were found: {}",
+            rel,
+            links
+                .as_object()
+                .map(|links| links.keys().cloned().collect::<Vec<_>>().join(", "))
+                .unwrap_or_default()
+        ))
+    })?;
+    let link = match link_data {
+        Value::Object(data) => Link::from_json(rel, data),
+        Value::Array(items) if items.len() == 1 && items[0].is_object() => {
+            Link::from_json(rel, items[0].as_object().unwrap())
+        }
+        Value::Array(items) => {
+            return Err(PactBrokerError::LinkError(format!(
+                "Link '{}' has {} entries, only links with a single entry can be followed",
+                rel,
+                items.len()
+            )));
+        }
+        _ => {
+            return Err(PactBrokerError::LinkError(format!(
+                "Link '{}' is malformed, expected an object but got {}",
+                rel, link_data
+            )));
+        }
+    };
+
+    if link.templated {
+        let placeholder = Regex::new(r"\{(\w+)}").unwrap();
+        let href = link.href.clone().unwrap_or_default();
+        let used: Vec<String> = placeholder
+            .captures_iter(&href)
+            .map(|caps| caps[1].to_string())
+            .collect();
+        let values: HashMap<String, String> = fields
+            .iter()
+            .filter(|field| used.contains(&field.name))
+            .map(|field| (field.name.clone(), field.value_as_string()))
+            .collect();
+        let missing: Vec<&String> = used
+            .iter()
+            .filter(|name| !values.contains_key(*name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(PactBrokerError::ValidationError(vec![format!(
+                "Link '{}' is templated, provide values for {} with -f",
+                rel,
+                missing
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )]));
+        }
+        Ok((hal_client.parse_link_url(&link, &values)?, used))
+    } else {
+        link.href
+            .map(|href| (href, vec![]))
+            .ok_or_else(|| PactBrokerError::LinkError(format!("Link '{}' has no href", rel)))
+    }
+}
+
+/// Send the request described by the arguments, following any `--rel` links first
+pub fn api(args: &ArgMatches) -> Result<Value, PactBrokerError> {
+    let method = args
+        .get_one::<String>("method")
+        .unwrap()
+        .to_uppercase()
+        .parse::<Method>()
+        .map_err(|err| PactBrokerError::ValidationError(vec![err.to_string()]))?;
+    let path = args.get_one::<String>("path").unwrap();
+    let rels: Vec<&String> = args
+        .get_many::<String>("rel")
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut fields = parse_fields(args)?;
+    let input = args
+        .get_one::<String>("input")
+        .map(|input| read_input(input))
+        .transpose()?;
+    let broker_details = BrokerDetails::from_args(args)?;
+
+    let response = tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let hal_client = HALClient::with_url(
+            &broker_details.url,
+            broker_details.auth.clone(),
+            broker_details.connection_options.clone(),
+            broker_details.custom_headers.clone(),
+        )
+        .with_retry_count(broker_details.retries);
+
+        let mut target = path.clone();
+        for rel in rels {
+            let resource = hal_client.fetch(&target).await?;
+            let (href, used) = resolve_link(&hal_client, &resource, rel, &fields)?;
+            fields.retain(|field| !used.contains(&field.name));
+            // Links are absolute, but the broker may be behind a proxy, so only the path is used
+            // the same as when navigating links elsewhere
+            target = match reqwest::Url::parse(&href) {
+                Ok(url) => match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_string(),
+                },
+                Err(_) => href,
+            };
+        }
+
+        // Fields are sent as query parameters for GET and DELETE requests, or when the body
+        // comes from --input, otherwise as a JSON object body
+        let fields_as_query = input.is_some() || method == Method::GET || method == Method::DELETE;
+        let (query, body) = if fields_as_query {
+            let query: Vec<(String, String)> = fields
+                .iter()
+                .map(|field| (field.name.clone(), field.value_as_string()))
+                .collect();
+            (query, input)
+        } else {
+            let body: Map<String, Value> = fields
+                .into_iter()
+                .map(|field| (field.name, field.value))
+                .collect();
+            (vec![], Some(Value::Object(body).to_string()))
+        };
+
+        hal_client
+            .send_request(method, &target, &query, body.as_deref())
+            .await
+    })?;
+
+    let output = OutputType::from_args(args);
+    if output.is_structured() {
+        output::print(&response, &output)?;
+    } else {
+        println!("{}", serde_json::to_string_pretty(&response).unwrap());
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod api_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::subcommands::add_api_subcommand;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn mock_server_config() -> MockServerConfig {
+        MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        }
+    }
+
+    #[test]
+    fn parses_raw_and_typed_fields() {
+        assert_eq!(Field::parse("size=10", false).unwrap().value, json!("10"));
+        assert_eq!(Field::parse("size=10", true).unwrap().value, json!(10));
+        assert_eq!(Field::parse("main=true", true).unwrap().value, json!(true));
+        assert_eq!(
+            Field::parse("name=Foo Bar", true).unwrap().value,
+            json!("Foo Bar")
+        );
+        assert!(Field::parse("size", false).is_err());
+    }
+
+    #[test]
+    fn get_sends_fields_as_query_parameters() {
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction(
+                "a request for a page of pacticipant versions",
+                "",
+                |mut i| {
+                    i.given("the 'Foo' pacticipant has versions");
+                    i.request
+                        .get()
+                        .path("/pacticipants/Foo/versions")
+                        .query_param("size", "2");
+                    i.response
+                        .status(200)
+                        .header("Content-Type", "application/hal+json;charset=utf-8")
+                        .json_body(json_pattern!({
+                            "_embedded": { "versions": each_like!({ "number": like!("1.0.0") }) }
+                        }));
+                    i
+                },
+            )
+            .start_mock_server(None, Some(mock_server_config()));
+        let mock_server_url = pact_broker_service.url();
+
+        let matches = add_api_subcommand().get_matches_from(vec![
+            "api",
+            "get",
+            "/pacticipants/Foo/versions",
+            "-f",
+            "size=2",
+            "-b",
+            mock_server_url.as_str(),
+        ]);
+        let result = api(&matches).unwrap();
+
+        assert_eq!(result["_embedded"]["versions"][0]["number"], "1.0.0");
+    }
+
+    #[test]
+    fn post_sends_fields_as_a_json_body() {
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction("a request to create a pacticipant", "", |mut i| {
+                i.request
+                    .post()
+                    .path("/pacticipants")
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({ "name": "Foo", "mainBranch": "main" }));
+                i.response
+                    .status(201)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({ "name": like!("Foo") }));
+                i
+            })
+            .start_mock_server(None, Some(mock_server_config()));
+        let mock_server_url = pact_broker_service.url();
+
+        let matches = add_api_subcommand().get_matches_from(vec![
+            "api",
+            "POST",
+            "/pacticipants",
+            "-f",
+            "name=Foo",
+            "-f",
+            "mainBranch=main",
+            "-b",
+            mock_server_url.as_str(),
+        ]);
+        let result = api(&matches).unwrap();
+
+        assert_eq!(result["name"], "Foo");
+    }
+
+    #[test]
+    fn follows_templated_links() {
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction("a request for the index resource", "", |mut i| {
+                i.request.get().path("/");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "_links": {
+                            "pb:latest-version": {
+                                "href": term!("http:\\/\\/.*\\{pacticipant}.*", "http://localhost/pacticipants/{pacticipant}/latest-version"),
+                                "templated": true
+                            }
+                        }
+                    }));
+                i
+            })
+            .interaction("a request for the latest version of Foo", "", |mut i| {
+                i.given("the 'Foo' pacticipant has versions");
+                i.request.get().path("/pacticipants/Foo/latest-version");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({ "number": like!("1.0.0") }));
+                i
+            })
+            .start_mock_server(None, Some(mock_server_config()));
+        let mock_server_url = pact_broker_service.url();
+
+        let matches = add_api_subcommand().get_matches_from(vec![
+            "api",
+            "GET",
+            "/",
+            "--rel",
+            "pb:latest-version",
+            "-f",
+            "pacticipant=Foo",
+            "-b",
+            mock_server_url.as_str(),
+        ]);
+        let result = api(&matches).unwrap();
+
+        assert_eq!(result["number"], "1.0.0");
+    }
+
+    #[test]
+    fn templated_links_need_values_for_every_placeholder() {
+        let hal_client =
+            HALClient::with_url("http://localhost:9292", None, Default::default(), None);
+        let resource = json!({
+            "_links": {
+                "pb:pacticipant-version": {
+                    "href": "http://localhost:9292/pacticipants/{pacticipant}/versions/{version}",
+                    "templated": true
+                }
+            }
+        });
+        let fields = vec![Field::parse("pacticipant=Foo", false).unwrap()];
+
+        let result = resolve_link(&hal_client, &resource, "pb:pacticipant-version", &fields);
+
+        assert_eq!(
+            result.unwrap_err(),
+            "Link 'pb:pacticipant-version' is templated, provide values for version with -f"
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,715 @@
+//! Reconciles the environments, pacticipants and webhooks in a Pact Broker against a
+//! desired-state file.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::cli::{
+    pact_broker::main::{
+        HALClient, PactBrokerError,
+        client::{CreateEnvironmentRequest, PactBrokerClient},
+        migrate::fetch_all_from_index,
+        output::{self, OutputType},
+        types::{BrokerDetails, Pagination},
+        utils::get_broker_relation,
+        webhooks::{
+            create::create_or_update_webhook,
+            list::{fetch_webhooks, webhook_uuid},
+        },
+    },
+    utils,
+};
+
+/// The desired state of the broker. A section that is missing from the file is left untouched.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DesiredState {
+    pub environments: Option<Vec<Value>>,
+    pub pacticipants: Option<Vec<Value>>,
+    pub webhooks: Option<Vec<Value>>,
+}
+
+/// The resources currently in the broker, for the sections present in the desired state
+#[derive(Debug, Clone, Default)]
+pub struct CurrentState {
+    pub environments: Vec<Value>,
+    pub pacticipants: Vec<Value>,
+    pub webhooks: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceKind {
+    Environment,
+    Pacticipant,
+    Webhook,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// A single change required to move the broker to the desired state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub resource: ResourceKind,
+    pub action: ChangeAction,
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+    /// Request body to send to the broker
+    #[serde(skip)]
+    pub body: Value,
+    /// Link to the existing resource, for updates and deletes
+    #[serde(skip)]
+    pub href: Option<String>,
+}
+
+/// Loads the desired state from a YAML (or JSON) file
+pub fn load_desired_state(file: &str) -> Result<DesiredState, PactBrokerError> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| PactBrokerError::IoError(format!("Failed to read {}: {}", file, e)))?;
+    let state: DesiredState = serde_norway::from_str(&content)
+        .map_err(|e| PactBrokerError::ContentError(format!("Failed to parse {}: {}", file, e)))?;
+    validate_desired_state(&state)?;
+    Ok(state)
+}
+
+fn validate_desired_state(state: &DesiredState) -> Result<(), PactBrokerError> {
+    let mut errors = vec![];
+    let sections = [
+        ("environments", &state.environments, "name"),
+        ("pacticipants", &state.pacticipants, "name"),
+        ("webhooks", &state.webhooks, "uuid"),
+    ];
+    for (section, resources, key) in sections {
+        let mut seen = vec![];
+        for (index, resource) in resources.iter().flatten().enumerate() {
+            match resource[key].as_str().filter(|value| !value.is_empty()) {
+                Some(value) if seen.contains(&value) => errors.push(format!(
+                    "{} has more than one entry with {} '{}'",
+                    section, key, value
+                )),
+                Some(value) => seen.push(value),
+                None => errors.push(format!("{}[{}] must have a {}", section, index, key)),
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(PactBrokerError::ValidationError(errors))
+    }
+}
+
+fn self_href(resource: &Value) -> Option<String> {
+    resource["_links"]["self"]["href"]
+        .as_str()
+        .map(|href| href.to_string())
+}
+
+/// Compares the fields set in the desired resource with the current resource. Fields that are
+/// not set in the desired resource are ignored, as are webhook passwords, which the broker never
+/// returns.
+fn diff_fields(desired: &Value, current: &Value, prefix: &str) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    if let Some(fields) = desired.as_object() {
+        for (key, to) in fields {
+            let field = format!("{}{}", prefix, key);
+            let from = current.get(key).cloned().unwrap_or(Value::Null);
+            if field == "uuid" || field == "request.password" {
+                continue;
+            }
+            if field == "request" && to.is_object() {
+                changes.extend(diff_fields(to, &from, "request."));
+            } else if *to != from {
+                changes.push(FieldChange {
+                    field,
+                    from,
+                    to: to.clone(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+fn plan_resources(
+    kind: ResourceKind,
+    desired: &Option<Vec<Value>>,
+    current: &[Value],
+    key: &str,
+    prune: bool,
+) -> Vec<Change> {
+    let Some(desired) = desired else {
+        return vec![];
+    };
+    let identity = |resource: &Value| match kind {
+        ResourceKind::Webhook => webhook_uuid(resource),
+        _ => resource[key].as_str().unwrap_or_default().to_string(),
+    };
+
+    let mut changes = vec![];
+    for resource in desired {
+        let name = identity(resource);
+        match current.iter().find(|existing| identity(existing) == name) {
+            Some(existing) => {
+                let fields = diff_fields(resource, existing, "");
+                if !fields.is_empty() {
+                    let body = match kind {
+                        // Environments are updated with a PUT, so send the full resource
+                        ResourceKind::Environment => {
+                            let mut body = json!({});
+                            for field in ["name", "displayName", "production", "contacts"] {
+                                if let Some(value) = existing.get(field) {
+                                    body[field] = value.clone();
+                                }
+                            }
+                            for change in &fields {
+                                body[change.field.as_str()] = change.to.clone();
+                            }
+                            body
+                        }
+                        _ => resource.clone(),
+                    };
+                    changes.push(Change {
+                        resource: kind,
+                        action: ChangeAction::Update,
+                        name,
+                        fields,
+                        body,
+                        href: self_href(existing),
+                    });
+                }
+            }
+            None => changes.push(Change {
+                resource: kind,
+                action: ChangeAction::Create,
+                name,
+                fields: vec![],
+                body: resource.clone(),
+                href: None,
+            }),
+        }
+    }
+
+    if prune {
+        for existing in current {
+            let name = identity(existing);
+            if !desired.iter().any(|resource| identity(resource) == name) {
+                changes.push(Change {
+                    resource: kind,
+                    action: ChangeAction::Delete,
+                    name,
+                    fields: vec![],
+                    body: Value::Null,
+                    href: self_href(existing),
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// Works out the changes needed to move the broker from the current state to the desired state.
+/// Resources that are not in the desired state are only deleted when `prune` is set, and
+/// pacticipants are never deleted, as that would also delete all their pacts and versions.
+pub fn plan_changes(desired: &DesiredState, current: &CurrentState, prune: bool) -> Vec<Change> {
+    let mut changes = plan_resources(
+        ResourceKind::Environment,
+        &desired.environments,
+        &current.environments,
+        "name",
+        prune,
+    );
+    changes.extend(plan_resources(
+        ResourceKind::Pacticipant,
+        &desired.pacticipants,
+        &current.pacticipants,
+        "name",
+        false,
+    ));
+    changes.extend(plan_resources(
+        ResourceKind::Webhook,
+        &desired.webhooks,
+        &current.webhooks,
+        "uuid",
+        prune,
+    ));
+    changes
+}
+
+async fn fetch_current_state(
+    hal_client: &HALClient,
+    broker_url: &str,
+    desired: &DesiredState,
+) -> Result<CurrentState, PactBrokerError> {
+    let mut current = CurrentState::default();
+    if desired.environments.is_some() {
+        current.environments =
+            fetch_all_from_index(hal_client, broker_url, "pb:environments", "environments").await?;
+    }
+    if desired.pacticipants.is_some() {
+        current.pacticipants =
+            fetch_all_from_index(hal_client, broker_url, "pb:pacticipants", "pacticipants").await?;
+    }
+    if desired.webhooks.is_some() {
+        current.webhooks = fetch_webhooks(hal_client, broker_url, &Pagination::all()).await?;
+    }
+    Ok(current)
+}
+
+/// Reads the request for an environment from its desired-state entry
+fn environment_request(change: &Change) -> Result<CreateEnvironmentRequest, PactBrokerError> {
+    serde_json::from_value(change.body.clone()).map_err(|e| {
+        PactBrokerError::ContentError(format!("Environment '{}' is not valid: {}", change.name, e))
+    })
+}
+
+async fn apply_change(client: &PactBrokerClient, change: &Change) -> Result<(), PactBrokerError> {
+    let hal_client = client.hal_client();
+    let broker_url = client.broker_url();
+    let missing_href = || {
+        PactBrokerError::LinkError(format!(
+            "No self link was found for {:?} '{}'",
+            change.resource, change.name
+        ))
+    };
+    match (change.resource, change.action) {
+        (ResourceKind::Environment, ChangeAction::Create) => {
+            client
+                .create_environment_json(&environment_request(change)?)
+                .await?;
+        }
+        (ResourceKind::Environment, ChangeAction::Update) => {
+            // The self link of an environment is /environments/{uuid}
+            let href = change.href.as_ref().ok_or_else(missing_href)?;
+            let uuid = href
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or_default();
+            client
+                .update_environment_json(uuid, &environment_request(change)?)
+                .await?;
+        }
+        (ResourceKind::Pacticipant, ChangeAction::Create) => {
+            let href = get_broker_relation(
+                hal_client.clone(),
+                "pb:pacticipants".to_string(),
+                broker_url.to_string(),
+            )
+            .await?;
+            hal_client
+                .post_json(&href, &change.body.to_string(), None)
+                .await?;
+        }
+        (ResourceKind::Pacticipant, ChangeAction::Update) => {
+            let href = change.href.as_ref().ok_or_else(missing_href)?;
+            hal_client
+                .patch_json(href, &change.body.to_string(), None)
+                .await?;
+        }
+        (ResourceKind::Webhook, ChangeAction::Create | ChangeAction::Update) => {
+            let href = get_broker_relation(
+                hal_client.clone(),
+                "pb:webhook".to_string(),
+                broker_url.to_string(),
+            )
+            .await?;
+            create_or_update_webhook(hal_client, &href, &change.body).await?;
+        }
+        (_, ChangeAction::Delete) => {
+            let href = change.href.as_ref().ok_or_else(missing_href)?;
+            let path = href
+                .parse::<url::Url>()
+                .map(|url| url.path().to_string())
+                .unwrap_or_else(|_| href.clone());
+            hal_client.clone().delete(&path).await?;
+        }
+    }
+    Ok(())
+}
+
+fn format_change(change: &Change) -> String {
+    let resource = format!("{:?}", change.resource).to_lowercase();
+    let mut line = match change.action {
+        ChangeAction::Create => utils::GREEN
+            .apply_to(format!("  + create {} {}", resource, change.name))
+            .to_string(),
+        ChangeAction::Update => utils::YELLOW
+            .apply_to(format!("  ~ update {} {}", resource, change.name))
+            .to_string(),
+        ChangeAction::Delete => utils::RED
+            .apply_to(format!("  - delete {} {}", resource, change.name))
+            .to_string(),
+    };
+    for field in &change.fields {
+        line.push_str(&format!(
+            "\n      {}: {} → {}",
+            field.field, field.from, field.to
+        ));
+    }
+    line
+}
+
+fn summarise(changes: &[Change]) -> String {
+    let count = |action| changes.iter().filter(|c| c.action == action).count();
+    format!(
+        "{} to create, {} to update, {} to delete",
+        count(ChangeAction::Create),
+        count(ChangeAction::Update),
+        count(ChangeAction::Delete)
+    )
+}
+
+pub fn apply(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let file = args.get_one::<String>("file").unwrap();
+    let plan_only = args.get_flag("plan");
+    let prune = args.get_flag("prune");
+    let output = OutputType::from_args(args);
+
+    let desired = load_desired_state(file)?;
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let current =
+            fetch_current_state(client.hal_client(), client.broker_url(), &desired).await?;
+        let changes = plan_changes(&desired, &current, prune);
+
+        if output.is_structured() {
+            output::print(&serde_json::to_value(&changes).unwrap(), &output)?;
+        } else if changes.is_empty() {
+            println!("✅ No changes, the Pact Broker matches {}", file);
+        } else {
+            println!("🔍 Plan: {}", summarise(&changes));
+            for change in &changes {
+                println!("{}", format_change(change));
+            }
+        }
+
+        if plan_only || changes.is_empty() {
+            return Ok(serde_json::to_string(&changes).unwrap());
+        }
+
+        let mut not_sent = 0;
+        for change in &changes {
+            match apply_change(&client, change).await {
+                Ok(()) => {}
+                // The changes do not depend on each other, so a dry run previews all of them
+                Err(PactBrokerError::DryRun(_)) => not_sent += 1,
+                Err(err) => return Err(err),
+            }
+        }
+        if !output.is_structured() && not_sent > 0 {
+            println!(
+                "⚠️ Dry run, nothing was sent to the Pact Broker. Not applied: {}",
+                summarise(&changes)
+            );
+        } else if !output.is_structured() {
+            println!("✅ Applied {}", summarise(&changes));
+        }
+        Ok(serde_json::to_string(&changes).unwrap())
+    })
+}
+
+#[cfg(test)]
+mod apply_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::subcommands::add_apply_subcommand;
+    use pact_consumer::builders::InteractionBuilder;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+    use pretty_assertions::assert_eq;
+
+    fn setup_mock_server(interactions: Vec<InteractionBuilder>) -> Box<dyn ValidatingMockServer> {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+        let mut pact_builder = PactBuilder::new("pact-broker-cli", "Pact Broker");
+        for i in interactions {
+            pact_builder.push_interaction(&i.build());
+        }
+        pact_builder.start_mock_server(None, Some(config))
+    }
+
+    fn write_desired_state(content: &str) -> std::path::PathBuf {
+        let file = std::env::temp_dir().join(format!(
+            "pact-broker-cli-apply-{}-{:?}.yaml",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::write(&file, content).unwrap();
+        file
+    }
+
+    #[test]
+    fn load_desired_state_rejects_resources_without_identity() {
+        let file = write_desired_state(
+            "environments:\n  - displayName: Test\nwebhooks:\n  - uuid: a\n  - uuid: a\n",
+        );
+        let result = load_desired_state(file.to_str().unwrap());
+        let _ = std::fs::remove_file(&file);
+
+        match result {
+            Err(PactBrokerError::ValidationError(errors)) => assert_eq!(
+                errors,
+                vec![
+                    "environments[0] must have a name".to_string(),
+                    "webhooks has more than one entry with uuid 'a'".to_string(),
+                ]
+            ),
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn plan_changes_creates_updates_and_prunes() {
+        let desired = DesiredState {
+            environments: Some(vec![
+                json!({ "name": "test", "displayName": "Testing", "production": false }),
+                json!({ "name": "production", "production": true }),
+            ]),
+            pacticipants: Some(vec![json!({ "name": "Foo", "mainBranch": "main" })]),
+            webhooks: Some(vec![json!({
+                "uuid": "abc",
+                "events": [{ "name": "contract_published" }],
+                "request": { "method": "POST", "url": "https://example.org", "password": "secret" }
+            })]),
+        };
+        let current = CurrentState {
+            environments: vec![
+                json!({
+                    "uuid": "1", "name": "test", "displayName": "Test", "production": false,
+                    "_links": { "self": { "href": "http://localhost/environments/1" } }
+                }),
+                json!({
+                    "uuid": "2", "name": "old", "displayName": "Old", "production": false,
+                    "_links": { "self": { "href": "http://localhost/environments/2" } }
+                }),
+            ],
+            pacticipants: vec![
+                json!({ "name": "Foo", "mainBranch": "main" }),
+                json!({ "name": "Bar" }),
+            ],
+            webhooks: vec![json!({
+                "uuid": "abc",
+                "events": [{ "name": "contract_published" }],
+                "request": { "method": "POST", "url": "https://example.org", "username": "user" }
+            })],
+        };
+
+        let changes = plan_changes(&desired, &current, true);
+        let summary: Vec<(ResourceKind, ChangeAction, String)> = changes
+            .iter()
+            .map(|c| (c.resource, c.action, c.name.clone()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    ResourceKind::Environment,
+                    ChangeAction::Update,
+                    "test".to_string()
+                ),
+                (
+                    ResourceKind::Environment,
+                    ChangeAction::Create,
+                    "production".to_string()
+                ),
+                (
+                    ResourceKind::Environment,
+                    ChangeAction::Delete,
+                    "old".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            changes[0].fields,
+            vec![FieldChange {
+                field: "displayName".to_string(),
+                from: json!("Test"),
+                to: json!("Testing"),
+            }]
+        );
+        assert_eq!(
+            changes[0].body,
+            json!({ "name": "test", "displayName": "Testing", "production": false })
+        );
+        assert_eq!(
+            changes[2].href,
+            Some("http://localhost/environments/2".to_string())
+        );
+    }
+
+    #[test]
+    fn plan_changes_leaves_missing_sections_and_unlisted_resources_alone() {
+        let desired = DesiredState {
+            environments: Some(vec![]),
+            ..DesiredState::default()
+        };
+        let current = CurrentState {
+            environments: vec![json!({ "uuid": "1", "name": "test" })],
+            ..CurrentState::default()
+        };
+
+        assert_eq!(plan_changes(&desired, &current, false), vec![]);
+        assert_eq!(plan_changes(&desired, &current, true).len(), 1);
+    }
+
+    #[test]
+    fn plan_mode_does_not_change_the_broker() {
+        let index_interaction = |mut i: InteractionBuilder| {
+            i.given("the pb:pacticipants relation exists in the index resource");
+            i.request
+                .path("/")
+                .header("Accept", "application/hal+json")
+                .header("Accept", "application/json");
+            i.response
+                .header("Content-Type", "application/hal+json;charset=utf-8")
+                .json_body(json_pattern!({
+                    "_links": {
+                        "pb:pacticipants": {
+                            "href": term!("http:\\/\\/.*\\/pacticipants", "http://localhost/pacticipants")
+                        }
+                    }
+                }));
+            i
+        };
+        let pacticipants_interaction = |mut i: InteractionBuilder| {
+            i.given("a pacticipant with name Foo exists");
+            i.request
+                .get()
+                .path("/pacticipants")
+                .header("Accept", "application/hal+json")
+                .header("Accept", "application/json");
+            i.response
+                .status(200)
+                .header("Content-Type", "application/hal+json;charset=utf-8")
+                .json_body(json_pattern!({
+                    "_embedded": {
+                        "pacticipants": [{
+                            "name": "Foo",
+                            "mainBranch": "develop",
+                            "_links": {
+                                "self": {
+                                    "href": term!("http:\\/\\/.*\\/pacticipants\\/Foo", "http://localhost/pacticipants/Foo")
+                                }
+                            }
+                        }]
+                    }
+                }));
+            i
+        };
+        let mock_server = setup_mock_server(vec![
+            index_interaction(InteractionBuilder::new(
+                "a request for the index resource",
+                "",
+            )),
+            pacticipants_interaction(InteractionBuilder::new(
+                "a request to list pacticipants",
+                "",
+            )),
+        ]);
+        let mock_server_url = mock_server.url();
+
+        let file = write_desired_state(
+            "pacticipants:\n  - name: Foo\n    mainBranch: main\n  - name: Bar\n",
+        );
+        let matches = add_apply_subcommand().get_matches_from(vec![
+            "apply",
+            "-f",
+            file.to_str().unwrap(),
+            "--plan",
+            "-b",
+            mock_server_url.as_str(),
+            "--output",
+            "json",
+        ]);
+        let result = apply(&matches);
+        let _ = std::fs::remove_file(&file);
+
+        let changes: Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(
+            changes,
+            json!([
+                {
+                    "resource": "pacticipant",
+                    "action": "update",
+                    "name": "Foo",
+                    "fields": [{ "field": "mainBranch", "from": "develop", "to": "main" }]
+                },
+                { "resource": "pacticipant", "action": "create", "name": "Bar" }
+            ])
+        );
+    }
+
+    #[test]
+    fn applies_environments_and_webhooks() {
+        use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+        use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+
+        let state = serde_json::from_value::<MockBrokerState>(json!({
+            "environments": [{ "uuid": "1234", "name": "test", "displayName": "Test" }],
+            "webhooks": [{
+                "uuid": "abcd",
+                "events": [{ "name": "contract_published" }],
+                "request": { "method": "POST", "url": "https://old.example.com" }
+            }]
+        }))
+        .unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (broker, url) = runtime.block_on(spawn_mock_broker(state));
+
+        let file = write_desired_state(
+            "environments:\n  - name: test\n    displayName: Testing\n  - name: production\n    production: true\nwebhooks:\n  - uuid: abcd\n    events:\n      - name: contract_published\n    request:\n      method: POST\n      url: https://ci.example.com\n",
+        );
+        let matches = add_apply_subcommand().get_matches_from(vec![
+            "apply",
+            "-f",
+            file.to_str().unwrap(),
+            "-b",
+            url.as_str(),
+            "--output",
+            "json",
+        ]);
+        let result = apply(&matches);
+        let _ = std::fs::remove_file(&file);
+        result.unwrap();
+
+        let state = broker.snapshot();
+        let environments: Vec<_> = state
+            .environments
+            .iter()
+            .map(|env| {
+                (
+                    env.name.as_str(),
+                    env.display_name.as_deref(),
+                    env.production,
+                )
+            })
+            .collect();
+        assert_eq!(
+            environments,
+            vec![("test", Some("Testing"), false), ("production", None, true)]
+        );
+        assert_eq!(state.environments[0].uuid, "1234");
+        assert_eq!(state.webhooks.len(), 1);
+        assert_eq!(
+            state.webhooks[0]["request"]["url"],
+            json!("https://ci.example.com")
+        );
+    }
+}
@@ -0,0 +1,855 @@
+//! Backs up the resources of a Pact Broker to a local archive, and restores them.
+//!
+//! The archive is a gzipped tar file laid out as follows:
+//!
+//! ```text
+//! manifest.json
+//! resources/index.json
+//! resources/environments/<uuid>.json
+//! resources/pacticipants/<name>.json
+//! resources/pacticipants/<name>/versions/<number>.json
+//! resources/pacts/provider/<provider>/consumer/<consumer>/version/<number>.json
+//! ...
+//! ```
+//!
+//! Each resource is stored as the JSON the broker returned, at the path of its URL relative to
+//! the broker base URL. The manifest lists every resource in the order it must be restored in,
+//! with its kind, its URL in the original broker, the URL of the resource it belongs to (a
+//! pact's version, a verification result's pact, a deployment's environment) and the SHA-256 of
+//! the file.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::cli::pact_broker::main::{
+    PactBrokerError,
+    client::{CreateEnvironmentRequest, Environment, PactBrokerClient, RecordDeploymentRequest},
+    migrate::{
+        create_version, deployment_identity, fetch_all, fetch_all_from_index, link,
+        publish_verification_result, put_webhook, record_release, upsert_pacticipant,
+        verified_rows,
+    },
+    output::{self, OutputType},
+    types::BrokerDetails,
+    utils::{follow_broker_relation, get_broker_relation},
+};
+use crate::cli::utils;
+
+/// The version of the archive layout written by this version of the CLI
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResourceKind {
+    Index,
+    Environment,
+    Pacticipant,
+    Version,
+    Pact,
+    VerificationResult,
+    DeployedVersion,
+    ReleasedVersion,
+    Webhook,
+}
+
+impl ResourceKind {
+    fn plural(&self) -> &'static str {
+        match self {
+            ResourceKind::Index => "index",
+            ResourceKind::Environment => "environments",
+            ResourceKind::Pacticipant => "pacticipants",
+            ResourceKind::Version => "versions",
+            ResourceKind::Pact => "pacts",
+            ResourceKind::VerificationResult => "verification results",
+            ResourceKind::DeployedVersion => "deployed versions",
+            ResourceKind::ReleasedVersion => "released versions",
+            ResourceKind::Webhook => "webhooks",
+        }
+    }
+}
+
+/// An entry in the manifest of an archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedResource {
+    pub kind: ResourceKind,
+    /// The URL of the resource in the broker it was backed up from
+    pub href: String,
+    /// The path of the resource in the archive
+    pub file: String,
+    /// The URL of the resource this one belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub broker_url: String,
+    pub created_at: String,
+    pub cli_version: String,
+    pub resources: Vec<ArchivedResource>,
+}
+
+impl BackupManifest {
+    /// The number of resources of each kind, leaving out the index
+    pub fn counts(&self) -> BTreeMap<ResourceKind, usize> {
+        count(self.resources.iter().map(|resource| resource.kind))
+    }
+}
+
+fn count(kinds: impl Iterator<Item = ResourceKind>) -> BTreeMap<ResourceKind, usize> {
+    let mut counts = BTreeMap::new();
+    for kind in kinds.filter(|kind| *kind != ResourceKind::Index) {
+        *counts.entry(kind).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn describe(counts: &BTreeMap<ResourceKind, usize>) -> String {
+    if counts.is_empty() {
+        return "nothing".to_string();
+    }
+    counts
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind.plural()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn sha256(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+/// The path in the archive of a resource, from its URL relative to the broker base URL
+fn archive_file(broker_url: &str, href: &str) -> String {
+    let base_path = Url::parse(broker_url)
+        .map(|url| url.path().trim_end_matches('/').to_string())
+        .unwrap_or_default();
+    let path = Url::parse(href)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| href.to_string());
+    let path = path
+        .strip_prefix(&base_path)
+        .unwrap_or(&path)
+        .trim_matches('/');
+    if path.is_empty() {
+        "resources/index.json".to_string()
+    } else {
+        format!("resources/{}.json", path)
+    }
+}
+
+/// A backup of a broker: its manifest and the resources it lists
+#[derive(Debug, Clone)]
+pub struct BackupArchive {
+    pub manifest: BackupManifest,
+    /// The resources by their URL in the broker they were backed up from
+    resources: HashMap<String, Value>,
+}
+
+impl BackupArchive {
+    pub fn resource(&self, href: &str) -> Option<&Value> {
+        self.resources.get(href)
+    }
+
+    /// Writes the archive as a gzipped tar file
+    pub fn write(&self, path: &Path) -> Result<(), PactBrokerError> {
+        let io_error = |err: std::io::Error| {
+            PactBrokerError::IoError(format!(
+                "Failed to write the backup to {}: {}",
+                path.display(),
+                err
+            ))
+        };
+        let file = std::fs::File::create(path).map_err(io_error)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let mtime = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let mut append = |name: &str, contents: Vec<u8>| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            builder.append_data(&mut header, name, contents.as_slice())
+        };
+        append(
+            MANIFEST_FILE,
+            serde_json::to_vec_pretty(&self.manifest).unwrap(),
+        )
+        .map_err(io_error)?;
+        for entry in &self.manifest.resources {
+            let contents = serde_json::to_vec_pretty(&self.resources[&entry.href]).unwrap();
+            append(&entry.file, contents).map_err(io_error)?;
+        }
+        builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Reads an archive, checking that every resource in the manifest is present and matches its
+    /// checksum
+    pub fn read(path: &Path) -> Result<BackupArchive, PactBrokerError> {
+        let io_error = |err: std::io::Error| {
+            PactBrokerError::IoError(format!(
+                "Failed to read the backup {}: {}",
+                path.display(),
+                err
+            ))
+        };
+        let file = std::fs::File::open(path).map_err(io_error)?;
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+        let mut files = HashMap::new();
+        for entry in archive.entries().map_err(io_error)? {
+            let mut entry = entry.map_err(io_error)?;
+            let name = entry
+                .path()
+                .map_err(io_error)?
+                .to_string_lossy()
+                .to_string();
+            let mut contents = vec![];
+            entry.read_to_end(&mut contents).map_err(io_error)?;
+            files.insert(name, contents);
+        }
+
+        let manifest: BackupManifest = files
+            .get(MANIFEST_FILE)
+            .ok_or_else(|| {
+                PactBrokerError::ContentError(format!(
+                    "{} is not a Pact Broker backup, it has no {}",
+                    path.display(),
+                    MANIFEST_FILE
+                ))
+            })
+            .and_then(|contents| {
+                serde_json::from_slice(contents).map_err(|err| {
+                    PactBrokerError::ContentError(format!(
+                        "Failed to parse the manifest of {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })
+            })?;
+        if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+            return Err(PactBrokerError::ContentError(format!(
+                "{} uses archive format {}, but this version of the CLI only supports up to format {}",
+                path.display(),
+                manifest.format_version,
+                ARCHIVE_FORMAT_VERSION
+            )));
+        }
+
+        let mut resources = HashMap::new();
+        for entry in &manifest.resources {
+            let contents = files.get(&entry.file).ok_or_else(|| {
+                PactBrokerError::ContentError(format!(
+                    "{} is listed in the manifest but is missing from the backup",
+                    entry.file
+                ))
+            })?;
+            if sha256(contents) != entry.sha256 {
+                return Err(PactBrokerError::ContentError(format!(
+                    "The checksum of {} does not match the manifest, the backup is corrupt",
+                    entry.file
+                )));
+            }
+            let resource = serde_json::from_slice(contents).map_err(|err| {
+                PactBrokerError::ContentError(format!("Failed to parse {}: {}", entry.file, err))
+            })?;
+            resources.insert(entry.href.clone(), resource);
+        }
+        Ok(BackupArchive {
+            manifest,
+            resources,
+        })
+    }
+}
+
+async fn follow_index_relation(
+    client: &PactBrokerClient,
+    relation: &str,
+) -> Result<Value, PactBrokerError> {
+    let href = get_broker_relation(
+        client.hal_client().clone(),
+        relation.to_string(),
+        client.broker_url().to_string(),
+    )
+    .await?;
+    follow_broker_relation(client.hal_client().clone(), relation.to_string(), href).await
+}
+
+/// Walks the broker's resources from the index, collecting them in the order they are restored
+struct Walk {
+    client: PactBrokerClient,
+    resources: Vec<(ResourceKind, String, Option<String>, Value)>,
+    seen: HashSet<String>,
+}
+
+impl Walk {
+    fn add(&mut self, kind: ResourceKind, resource: Value, parent: Option<&str>) -> Option<String> {
+        let href = link(&resource, "self")?;
+        if !self.seen.insert(href.clone()) {
+            return None;
+        }
+        self.resources
+            .push((kind, href.clone(), parent.map(str::to_string), resource));
+        Some(href)
+    }
+
+    async fn walk(&mut self) -> Result<(), PactBrokerError> {
+        let index = self.client.hal_client().fetch("").await?;
+        self.add(ResourceKind::Index, index, None);
+
+        let environments = fetch_all_from_index(
+            self.client.hal_client(),
+            self.client.broker_url(),
+            "pb:environments",
+            "environments",
+        )
+        .await?;
+        let mut environment_hrefs = vec![];
+        for environment in environments {
+            environment_hrefs.extend(self.add(ResourceKind::Environment, environment, None));
+        }
+
+        let pacticipants = fetch_all_from_index(
+            self.client.hal_client(),
+            self.client.broker_url(),
+            "pb:pacticipants",
+            "pacticipants",
+        )
+        .await?;
+        for pacticipant in pacticipants {
+            let versions_href = link(&pacticipant, "pb:versions");
+            let name = pacticipant["name"].as_str().unwrap_or_default().to_string();
+            if let Some(href) = self.add(ResourceKind::Pacticipant, pacticipant, None)
+                && let Some(versions_href) = versions_href
+            {
+                self.versions(&href, &name, &versions_href).await?;
+            }
+        }
+
+        for href in &environment_hrefs {
+            self.deployments(href).await?;
+        }
+        self.webhooks().await
+    }
+
+    async fn versions(
+        &mut self,
+        pacticipant_href: &str,
+        pacticipant: &str,
+        versions_href: &str,
+    ) -> Result<(), PactBrokerError> {
+        let mut versions = fetch_all(self.client.hal_client(), versions_href, "versions").await?;
+        versions.sort_by(|a, b| a["createdAt"].as_str().cmp(&b["createdAt"].as_str()));
+        for summary in versions {
+            let version = match link(&summary, "self") {
+                Some(href) => self.client.hal_client().fetch(&href).await?,
+                None => continue,
+            };
+            let number = version["number"].as_str().unwrap_or_default().to_string();
+            let pact_hrefs: Vec<String> = version["_links"]["pb:pact-versions"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|pact| pact["href"].as_str().map(str::to_string))
+                .collect();
+            let Some(version_href) =
+                self.add(ResourceKind::Version, version, Some(pacticipant_href))
+            else {
+                continue;
+            };
+
+            let mut pacts = vec![];
+            for href in pact_hrefs {
+                let pact = self.client.hal_client().fetch(&href).await?;
+                let provider = pact["provider"]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                if let Some(href) = self.add(ResourceKind::Pact, pact, Some(&version_href)) {
+                    pacts.push((provider, href));
+                }
+            }
+            if pacts.is_empty() {
+                continue;
+            }
+            for (provider, _, result) in verified_rows(&self.client, pacticipant, &number).await? {
+                let Some(pact_href) = pacts
+                    .iter()
+                    .find(|(name, _)| *name == provider)
+                    .map(|(_, href)| href.clone())
+                else {
+                    continue;
+                };
+                let result = match link(&result, "self") {
+                    Some(href) => self.client.hal_client().fetch(&href).await?,
+                    None => continue,
+                };
+                self.add(ResourceKind::VerificationResult, result, Some(&pact_href));
+            }
+        }
+        Ok(())
+    }
+
+    async fn deployments(&mut self, environment_href: &str) -> Result<(), PactBrokerError> {
+        let environment = self.client.hal_client().fetch(environment_href).await?;
+        for (kind, relation, embedded) in [
+            (
+                ResourceKind::DeployedVersion,
+                "pb:currently-deployed-deployed-versions",
+                "deployedVersions",
+            ),
+            (
+                ResourceKind::ReleasedVersion,
+                "pb:currently-supported-released-versions",
+                "releasedVersions",
+            ),
+        ] {
+            let Some(href) = link(&environment, relation) else {
+                continue;
+            };
+            let res = self.client.hal_client().fetch(&href).await?;
+            for deployment in res["_embedded"][embedded]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+            {
+                self.add(kind, deployment, Some(environment_href));
+            }
+        }
+        Ok(())
+    }
+
+    async fn webhooks(&mut self) -> Result<(), PactBrokerError> {
+        let webhooks = self
+            .client
+            .hal_client()
+            .clone()
+            .navigate("pb:webhooks", &HashMap::new())
+            .await?;
+        for webhook_link in webhooks.iter_links("pb:webhooks")? {
+            if let Some(href) = webhook_link.href {
+                let webhook = self.client.hal_client().fetch(&href).await?;
+                self.add(ResourceKind::Webhook, webhook, None);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Backs up every resource reachable from the index of the broker
+pub async fn backup_broker(from: BrokerDetails) -> Result<BackupArchive, PactBrokerError> {
+    let broker_url = from.url().to_string();
+    let mut walk = Walk {
+        client: PactBrokerClient::new(from),
+        resources: vec![],
+        seen: HashSet::new(),
+    };
+    walk.walk().await?;
+
+    let mut entries = vec![];
+    let mut resources = HashMap::new();
+    for (kind, href, parent, resource) in walk.resources {
+        entries.push(ArchivedResource {
+            kind,
+            file: archive_file(&broker_url, &href),
+            sha256: sha256(&serde_json::to_vec_pretty(&resource).unwrap()),
+            href: href.clone(),
+            parent,
+        });
+        resources.insert(href, resource);
+    }
+    Ok(BackupArchive {
+        manifest: BackupManifest {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            broker_url,
+            created_at: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            cli_version: env!("CARGO_PKG_VERSION").to_string(),
+            resources: entries,
+        },
+        resources,
+    })
+}
+
+fn missing_parent(entry: &ArchivedResource, href: &str) -> PactBrokerError {
+    PactBrokerError::ContentError(format!(
+        "{} belongs to {}, which is not in the backup",
+        entry.file, href
+    ))
+}
+
+/// The URL and contents of the resource an archived resource belongs to
+fn parent_of<'a>(
+    archive: &'a BackupArchive,
+    entry: &'a ArchivedResource,
+) -> Result<(&'a str, &'a Value), PactBrokerError> {
+    let href = entry.parent.as_deref().unwrap_or_default();
+    archive
+        .resource(href)
+        .map(|resource| (href, resource))
+        .ok_or_else(|| missing_parent(entry, href))
+}
+
+/// Replays a backup against an empty broker, returning the number of resources of each kind
+/// that were restored
+pub async fn restore_broker(
+    to: BrokerDetails,
+    archive: &BackupArchive,
+) -> Result<BTreeMap<ResourceKind, usize>, PactBrokerError> {
+    let client = PactBrokerClient::new(to);
+    let res = follow_index_relation(&client, "pb:pacticipants").await?;
+    let has_pacticipants = [&res["_embedded"]["pacticipants"], &res["pacticipants"]]
+        .iter()
+        .any(|pacticipants| pacticipants.as_array().is_some_and(|p| !p.is_empty()));
+    if has_pacticipants {
+        return Err(PactBrokerError::ValidationError(vec![format!(
+            "The Pact Broker at {} already has pacticipants. Backups can only be restored to an empty Pact Broker",
+            client.broker_url()
+        )]));
+    }
+    let existing_environments = client.list_environments().await?;
+
+    let entries: HashMap<&str, &ArchivedResource> = archive
+        .manifest
+        .resources
+        .iter()
+        .map(|entry| (entry.href.as_str(), entry))
+        .collect();
+    let mut webhook_href = None;
+    let mut restored = vec![];
+    for entry in &archive.manifest.resources {
+        let resource = &archive.resources[&entry.href];
+        match entry.kind {
+            // Pacts are published along with the version they belong to
+            ResourceKind::Index | ResourceKind::Pact => continue,
+            ResourceKind::Environment => {
+                let environment: Environment =
+                    serde_json::from_value(resource.clone()).map_err(|err| {
+                        PactBrokerError::ContentError(format!(
+                            "Failed to read the environment in {}: {}",
+                            entry.file, err
+                        ))
+                    })?;
+                if existing_environments
+                    .iter()
+                    .any(|existing| existing.name == environment.name)
+                {
+                    continue;
+                }
+                client
+                    .create_environment(&CreateEnvironmentRequest {
+                        name: environment.name,
+                        display_name: environment.display_name,
+                        production: environment.production,
+                        contacts: environment.contacts.unwrap_or_default(),
+                    })
+                    .await?;
+            }
+            ResourceKind::Pacticipant => upsert_pacticipant(&client, resource).await?,
+            ResourceKind::Version => {
+                let (_, pacticipant) = parent_of(archive, entry)?;
+                let pacts: Vec<Value> = archive
+                    .manifest
+                    .resources
+                    .iter()
+                    .filter(|pact| {
+                        pact.kind == ResourceKind::Pact
+                            && pact.parent.as_deref() == Some(entry.href.as_str())
+                    })
+                    .map(|pact| archive.resources[&pact.href].clone())
+                    .collect();
+                create_version(
+                    &client,
+                    pacticipant["name"].as_str().unwrap_or_default(),
+                    resource["number"].as_str().unwrap_or_default(),
+                    resource,
+                    &pacts,
+                )
+                .await?;
+            }
+            ResourceKind::VerificationResult => {
+                let (pact_href, pact) = parent_of(archive, entry)?;
+                let pact_entry = entries
+                    .get(pact_href)
+                    .ok_or_else(|| missing_parent(entry, pact_href))?;
+                let (_, version) = parent_of(archive, pact_entry)?;
+                publish_verification_result(
+                    &client,
+                    pact["consumer"]["name"].as_str().unwrap_or_default(),
+                    version["number"].as_str().unwrap_or_default(),
+                    pact["provider"]["name"].as_str().unwrap_or_default(),
+                    resource["providerApplicationVersion"]
+                        .as_str()
+                        .unwrap_or_default(),
+                    resource,
+                )
+                .await?;
+            }
+            ResourceKind::DeployedVersion | ResourceKind::ReleasedVersion => {
+                let (_, environment) = parent_of(archive, entry)?;
+                let environment = environment["name"].as_str().unwrap_or_default();
+                let (pacticipant, number, instance) = deployment_identity(resource);
+                if entry.kind == ResourceKind::ReleasedVersion {
+                    record_release(&client, &pacticipant, &number, environment).await?;
+                } else {
+                    client
+                        .record_deployment(&RecordDeploymentRequest {
+                            pacticipant,
+                            version: number,
+                            environment: environment.to_string(),
+                            application_instance: instance,
+                        })
+                        .await?;
+                }
+            }
+            ResourceKind::Webhook => {
+                if webhook_href.is_none() {
+                    webhook_href = Some(
+                        get_broker_relation(
+                            client.hal_client().clone(),
+                            "pb:webhook".to_string(),
+                            client.broker_url().to_string(),
+                        )
+                        .await?,
+                    );
+                }
+                put_webhook(&client, webhook_href.as_deref().unwrap(), resource).await?;
+            }
+        }
+        restored.push(entry.kind);
+    }
+    let mut counts = count(restored.into_iter());
+    let pacts = archive
+        .manifest
+        .resources
+        .iter()
+        .filter(|entry| entry.kind == ResourceKind::Pact)
+        .count();
+    if pacts > 0 {
+        counts.insert(ResourceKind::Pact, pacts);
+    }
+    Ok(counts)
+}
+
+pub fn backup(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let details = BrokerDetails::from_args(args)?;
+    let broker_url = details.url().to_string();
+    let file = args.get_one::<String>("output-file").unwrap();
+    let output = OutputType::from_args(args);
+
+    let archive = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(backup_broker(details))?;
+    archive.write(Path::new(file))?;
+
+    let counts = archive.manifest.counts();
+    let backed_up = json!({ "file": file, "resources": counts });
+    if output.is_structured() {
+        return output::print(&backed_up, &output);
+    }
+    println!(
+        "✅ Backed up {} from {} to {}",
+        describe(&counts),
+        utils::GREEN.apply_to(&broker_url),
+        utils::GREEN.apply_to(file)
+    );
+    Ok(backed_up.to_string())
+}
+
+pub fn restore(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let details = BrokerDetails::from_args(args)?;
+    let broker_url = details.url().to_string();
+    let file = args.get_one::<String>("archive").unwrap();
+    let output = OutputType::from_args(args);
+
+    let archive = BackupArchive::read(Path::new(file))?;
+    let counts = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(restore_broker(details, &archive))?;
+
+    let restored = json!({ "restored": counts });
+    if output.is_structured() {
+        return output::print(&restored, &output);
+    }
+    println!(
+        "✅ Restored {} from {} (backed up from {} at {}) to {}",
+        describe(&counts),
+        file,
+        archive.manifest.broker_url,
+        archive.manifest.created_at,
+        utils::GREEN.apply_to(&broker_url)
+    );
+    Ok(restored.to_string())
+}
+
+#[cfg(test)]
+mod backup_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{BackupArchive, ResourceKind, archive_file, backup_broker, restore_broker};
+    use crate::cli::pact_broker::main::PactBrokerError;
+    use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+    use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+    use crate::cli::pact_broker::main::types::BrokerDetails;
+
+    fn temp_archive(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "pact-broker-cli-{}-{}.tar.gz",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn source_state() -> MockBrokerState {
+        serde_json::from_value(json!({
+            "environments": [{ "uuid": "1234", "name": "production", "production": true }],
+            "pacticipants": [{ "name": "Foo", "mainBranch": "main" }, { "name": "Bar" }],
+            "versions": [
+                { "pacticipant": "Foo", "number": "1", "branch": "main", "tags": ["prod"] },
+                { "pacticipant": "Bar", "number": "2" }
+            ],
+            "pacts": [{
+                "consumer": "Foo",
+                "consumerVersion": "1",
+                "provider": "Bar",
+                "content": { "consumer": { "name": "Foo" }, "provider": { "name": "Bar" }, "interactions": [] }
+            }],
+            "verificationResults": [{
+                "consumer": "Foo", "consumerVersion": "1", "provider": "Bar", "providerVersion": "2", "success": true
+            }],
+            "deployedVersions": [{ "pacticipant": "Bar", "version": "2", "environment": "1234" }],
+            "releasedVersions": [{ "pacticipant": "Foo", "version": "1", "environment": "1234" }],
+            "webhooks": [{
+                "uuid": "abcd",
+                "description": "Notify CI",
+                "events": [{ "name": "contract_published" }],
+                "request": { "method": "POST", "url": "https://ci.example.com" }
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn archive_files_are_relative_to_the_broker_url() {
+        assert_eq!(
+            archive_file(
+                "http://broker/ctx",
+                "http://broker/ctx/pacticipants/Foo%2FBar"
+            ),
+            "resources/pacticipants/Foo%2FBar.json"
+        );
+        assert_eq!(
+            archive_file("http://broker/ctx", "http://broker/ctx"),
+            "resources/index.json"
+        );
+    }
+
+    #[tokio::test]
+    async fn backs_up_and_restores_a_broker() {
+        let (_, from_url) = spawn_mock_broker(source_state()).await;
+        let (target, to_url) = spawn_mock_broker(MockBrokerState::default()).await;
+        let path = temp_archive("backup");
+
+        let archive = backup_broker(BrokerDetails::new(&from_url).with_retries(0))
+            .await
+            .unwrap();
+        archive.write(&path).unwrap();
+        let archive = BackupArchive::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let kinds: Vec<ResourceKind> = archive
+            .manifest
+            .resources
+            .iter()
+            .map(|entry| entry.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ResourceKind::Index,
+                ResourceKind::Environment,
+                ResourceKind::Pacticipant,
+                ResourceKind::Version,
+                ResourceKind::Pact,
+                ResourceKind::VerificationResult,
+                ResourceKind::Pacticipant,
+                ResourceKind::Version,
+                ResourceKind::DeployedVersion,
+                ResourceKind::ReleasedVersion,
+                ResourceKind::Webhook
+            ]
+        );
+        assert_eq!(
+            archive.manifest.resources[3].file,
+            "resources/pacticipants/Foo/versions/1.json"
+        );
+
+        let counts = restore_broker(BrokerDetails::new(&to_url).with_retries(0), &archive)
+            .await
+            .unwrap();
+        assert_eq!(counts[&ResourceKind::Pacticipant], 2);
+        assert_eq!(counts[&ResourceKind::Pact], 1);
+
+        let state = target.snapshot();
+        assert_eq!(state.environments[0].name, "production");
+        assert_eq!(
+            state.pacticipant("Foo").unwrap().main_branch.as_deref(),
+            Some("main")
+        );
+        assert_eq!(
+            state.version("Foo", "1").unwrap().tags,
+            vec!["prod".to_string()]
+        );
+        assert_eq!(state.pacts.len(), 1);
+        assert_eq!(state.verification_results[0].provider_version, "2");
+        assert_eq!(state.deployed_versions[0].pacticipant, "Bar");
+        assert_eq!(state.released_versions[0].pacticipant, "Foo");
+        assert_eq!(state.webhooks[0]["uuid"], "abcd");
+
+        let result = restore_broker(BrokerDetails::new(&to_url).with_retries(0), &archive).await;
+        assert!(matches!(result, Err(PactBrokerError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn rejects_an_archive_that_does_not_match_its_checksums() {
+        let (_, from_url) = spawn_mock_broker(source_state()).await;
+        let path = temp_archive("corrupt-backup");
+
+        let mut archive = backup_broker(BrokerDetails::new(&from_url).with_retries(0))
+            .await
+            .unwrap();
+        archive.manifest.resources[2].sha256 = "0".repeat(64);
+        archive.write(&path).unwrap();
+        let result = BackupArchive::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(PactBrokerError::ContentError(message)) => assert_eq!(
+                message,
+                "The checksum of resources/pacticipants/Foo.json does not match the manifest, the backup is corrupt"
+            ),
+            other => panic!("Expected a content error, got {:?}", other),
+        }
+    }
+}
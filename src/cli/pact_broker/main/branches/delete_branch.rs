@@ -1,10 +1,13 @@
 use maplit::hashmap;
 
+use serde_json::json;
+
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
+    output::{self, OutputType},
     utils::{
         delete_templated_broker_relation, get_auth, get_broker_relation, get_broker_url,
-        get_custom_headers, get_retries, get_ssl_options,
+        get_connection_options, get_custom_headers, get_retries,
     },
 };
 
@@ -12,7 +15,7 @@ pub fn delete_branch(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let branch_name = args.get_one::<String>("branch").unwrap();
     let pacticipant_name = args.get_one::<String>("pacticipant").unwrap();
@@ -21,12 +24,13 @@ pub fn delete_branch(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
         .unwrap_or(Some(&true))
         .copied()
         .unwrap_or(true);
+    let output = OutputType::from_args(args);
 
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -51,13 +55,18 @@ pub fn delete_branch(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
         .await
     });
 
+    let result = |deleted| json!({ "pacticipant": pacticipant_name, "name": branch_name, "deleted": deleted });
     match res {
         Ok(_) => {
             let message = format!(
                 "Successfully deleted branch '{}' of pacticipant '{}'",
                 branch_name, pacticipant_name
             );
-            println!("{}", message);
+            if output.is_structured() {
+                return output::print(&result(true), &output);
+            } else {
+                println!("{}", message);
+            }
             Ok(message)
         }
         Err(PactBrokerError::NotFound(_)) => {
@@ -68,7 +77,10 @@ pub fn delete_branch(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
             if error_when_not_found {
                 Err(PactBrokerError::NotFound(message.clone()))
             } else {
-                println!("{}", message);
+                output::status(&output, &message);
+                if output.is_structured() {
+                    return output::print(&result(false), &output);
+                }
                 Ok(message)
             }
         }
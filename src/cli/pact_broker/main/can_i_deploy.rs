@@ -1,107 +1,22 @@
 use clap::ArgMatches;
 use comfy_table::{Table, presets::UTF8_FULL};
-use serde::Deserialize;
 use tracing::debug;
 
-fn deserialize_optional_field<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-    T: Deserialize<'de>,
-{
-    Ok(Some(Option::deserialize(deserializer)?))
-}
-
 use crate::cli::{
     pact_broker::main::{
-        HALClient, Notice, PactBrokerError, process_notices,
-        utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+        PactBrokerError,
+        client::{
+            CanIDeployOutcome, CanIDeployRequest, IgnoreSelector, Matrix, MatrixPacticipant,
+            PactBrokerClient, PacticipantSelector, matrix_from_json,
+        },
+        output::{self, OutputType},
+        process_notices,
+        types::BrokerDetails,
     },
     utils,
 };
 
-#[derive(Debug, serde::Deserialize)]
-struct Summary {
-    deployable: Option<bool>,
-    // reason: String,
-    // success: u32,
-    // failed: u32,
-    // unknown: u32,
-}
-#[derive(Debug, serde::Deserialize)]
-struct Version {
-    number: String,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct Consumer {
-    name: String,
-    version: Option<Version>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct Provider {
-    name: String,
-    version: Option<Version>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct VerificationResult {
-    success: Option<bool>,
-    #[serde(rename = "_links")]
-    links: Option<VerificationLinks>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct VerificationLinks {
-    #[serde(rename = "self")]
-    self_link: Option<VerificationHref>,
-    #[serde(rename = "pfi:ui")]
-    _pfi_ui: Option<VerificationHref>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct VerificationHref {
-    href: Option<String>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct MatrixItem {
-    consumer: Consumer,
-    provider: Provider,
-    #[serde(rename = "verificationResult")]
-    verification_result: Option<VerificationResult>,
-    #[serde(
-        rename = "verificationType",
-        default,
-        deserialize_with = "deserialize_optional_field"
-    )]
-    verification_type: Option<Option<String>>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct Data {
-    summary: Option<Summary>,
-    notices: Option<Vec<Notice>>,
-    matrix: Vec<MatrixItem>,
-}
-
-#[derive(Debug, Default)]
-struct PacticipantArgs {
-    pacticipant: String,
-    version: Option<String>,
-    branch: Option<String>,
-    tags: Vec<String>,
-    latest: Option<Option<String>>,
-    main_branch: bool,
-}
-
-#[derive(Debug, Default)]
-struct IgnoreArgs {
-    pacticipant: String,
-    version: Option<String>,
-}
-
-fn parse_args_from_matches(raw_args: &[String]) -> Vec<PacticipantArgs> {
+fn parse_args_from_matches(raw_args: &[String]) -> Vec<PacticipantSelector> {
     // Get the raw arguments as they were passed on the command line
     let mut args = raw_args.iter().peekable();
     let mut result = Vec::new();
@@ -112,7 +27,7 @@ fn parse_args_from_matches(raw_args: &[String]) -> Vec<PacticipantArgs> {
                 .next()
                 .expect("Expected value after --pacticipant")
                 .to_string();
-            let mut pacticipant_args = PacticipantArgs {
+            let mut pacticipant_args = PacticipantSelector {
                 pacticipant,
                 ..Default::default()
             };
@@ -161,7 +76,7 @@ fn parse_args_from_matches(raw_args: &[String]) -> Vec<PacticipantArgs> {
     result
 }
 
-fn parse_ignore_args_from_matches(raw_args: &[String]) -> Vec<IgnoreArgs> {
+fn parse_ignore_args_from_matches(raw_args: &[String]) -> Vec<IgnoreSelector> {
     let mut args = raw_args.iter().peekable();
     let mut result = Vec::new();
 
@@ -169,7 +84,7 @@ fn parse_ignore_args_from_matches(raw_args: &[String]) -> Vec<IgnoreArgs> {
         if arg == "--ignore"
             && let Some(pacticipant) = args.next()
         {
-            let mut ignore_args = IgnoreArgs {
+            let mut ignore_args = IgnoreSelector {
                 pacticipant: pacticipant.to_string(),
                 ..Default::default()
             };
@@ -188,12 +103,18 @@ fn parse_ignore_args_from_matches(raw_args: &[String]) -> Vec<IgnoreArgs> {
     result
 }
 
-fn build_matrix_table(data: &Data) -> (String, Vec<(String, String)>) {
+/// The rows of the matrix as they are shown in each of the report formats
+struct MatrixReport {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+    verification_results: Vec<(String, String)>,
+}
+
+fn build_matrix_report(data: &Matrix) -> MatrixReport {
     let show_verification_type = data
         .matrix
         .iter()
         .any(|item| item.verification_type.is_some());
-    let mut table = Table::new();
     let mut header = vec![
         "CONSUMER",
         "C.VERSION",
@@ -205,7 +126,7 @@ fn build_matrix_table(data: &Data) -> (String, Vec<(String, String)>) {
     if show_verification_type {
         header.push("VERIFICATION TYPE");
     }
-    table.load_preset(UTF8_FULL).set_header(header);
+    let mut rows = Vec::new();
     let mut verification_results: Vec<(String, String)> = Vec::new();
     for matrix_item in &data.matrix {
         let success_str = matrix_item
@@ -220,16 +141,14 @@ fn build_matrix_table(data: &Data) -> (String, Vec<(String, String)>) {
             "0"
         };
         if let Some(verification_result) = &matrix_item.verification_result
-            && let Some(links) = &verification_result.links
-            && let Some(self_link) = &links.self_link
-            && let Some(href) = &self_link.href
+            && let Some(href) = verification_result.href()
         {
             let status = match verification_result.success {
                 Some(true) => "success",
                 Some(false) => "failure",
                 None => "unknown",
             };
-            verification_results.push((href.clone(), status.to_string()));
+            verification_results.push((href.to_string(), status.to_string()));
         }
         let mut row = vec![
             matrix_item.consumer.name.clone(),
@@ -259,16 +178,193 @@ fn build_matrix_table(data: &Data) -> (String, Vec<(String, String)>) {
                     .to_string(),
             );
         }
+        rows.push(row);
+    }
+    MatrixReport {
+        header,
+        rows,
+        verification_results,
+    }
+}
+
+fn build_matrix_table(data: &Matrix) -> (String, Vec<(String, String)>) {
+    let report = build_matrix_report(data);
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(report.header);
+    for row in report.rows {
         table.add_row(row);
     }
-    (format!("{table}"), verification_results)
+    (format!("{table}"), report.verification_results)
+}
+
+fn build_matrix_markdown(data: &Matrix) -> String {
+    let report = build_matrix_report(data);
+    let escape = |cell: &str| cell.replace('|', "\\|");
+    let mut markdown = if data.outcome() == CanIDeployOutcome::Deployable {
+        "### ✅ Computer says yes \\o/\n".to_string()
+    } else {
+        "### ❌ Computer says no ¯\\\\\\_(ツ)\\_/¯\n".to_string()
+    };
+    if let Some(reason) = data.reason() {
+        markdown.push_str(&format!("\n{}\n", reason));
+    }
+    if !report.rows.is_empty() {
+        markdown.push_str(&format!("\n| {} |\n", report.header.join(" | ")));
+        markdown.push_str(&format!(
+            "|{}\n",
+            report.header.iter().map(|_| " --- |").collect::<String>()
+        ));
+        for row in &report.rows {
+            let cells: Vec<String> = row.iter().map(|cell| escape(cell)).collect();
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+    }
+    if !report.verification_results.is_empty() {
+        markdown.push_str("\n#### Verification results\n\n");
+        for (i, (url, status)) in report.verification_results.iter().enumerate() {
+            markdown.push_str(&format!("{}. <{}> ({})\n", i + 1, url, status));
+        }
+    }
+    if let Some(notices) = data.notices.as_ref().filter(|notices| !notices.is_empty()) {
+        markdown.push('\n');
+        for notice in notices {
+            markdown.push_str(&format!("- {}\n", notice.text));
+        }
+    }
+    markdown
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Renders the matrix as a JUnit report, with a test case for each row and one for the overall
+/// can-i-deploy result, so CI servers can show which integration is blocking the deployment
+fn build_matrix_junit(data: &Matrix) -> String {
+    let outcome_name = |outcome: CanIDeployOutcome| {
+        serde_json::to_value(outcome)
+            .ok()
+            .and_then(|value| value.as_str().map(|s| s.to_string()))
+            .unwrap_or_default()
+    };
+    let version = |pacticipant: &MatrixPacticipant| {
+        pacticipant
+            .version
+            .as_ref()
+            .map(|version| version.number.clone())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    let outcome = data.outcome();
+    let mut testcases = Vec::new();
+    for row in &data.matrix {
+        let name = format!(
+            "{} {} → {} {}",
+            row.consumer.name,
+            version(&row.consumer),
+            row.provider.name,
+            version(&row.provider)
+        );
+        let message = match row.outcome() {
+            CanIDeployOutcome::VerificationFailed => Some("Verification failed"),
+            CanIDeployOutcome::MissingVerification => Some("Verification result is missing"),
+            CanIDeployOutcome::Unknown => {
+                Some("Verification result is unknown, the verification may still be pending")
+            }
+            CanIDeployOutcome::Deployable | CanIDeployOutcome::VersionNotFound => None,
+        };
+        testcases.push((
+            row.consumer.name.clone(),
+            name,
+            message.map(|message| (outcome_name(row.outcome()), message.to_string())),
+        ));
+    }
+    let overall_failure = if outcome == CanIDeployOutcome::Deployable {
+        None
+    } else {
+        Some((
+            outcome_name(outcome),
+            data.reason()
+                .unwrap_or("The selected versions can not be deployed")
+                .to_string(),
+        ))
+    };
+    testcases.push((
+        "can-i-deploy".to_string(),
+        "can-i-deploy".to_string(),
+        overall_failure,
+    ));
+
+    let failures = testcases
+        .iter()
+        .filter(|(_, _, failure)| failure.is_some())
+        .count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"can-i-deploy\" tests=\"{}\" failures=\"{}\">\n",
+        testcases.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"can-i-deploy\" tests=\"{}\" failures=\"{}\">\n",
+        testcases.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "    <properties>\n      <property name=\"outcome\" value=\"{}\"/>\n    </properties>\n",
+        outcome_name(outcome)
+    ));
+    for (classname, name, failure) in &testcases {
+        match failure {
+            Some((failure_type, message)) => {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\">\n      <failure type=\"{}\" message=\"{}\"/>\n    </testcase>\n",
+                    xml_escape(classname),
+                    xml_escape(name),
+                    xml_escape(failure_type),
+                    xml_escape(message)
+                ));
+            }
+            None => xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\"/>\n",
+                xml_escape(classname),
+                xml_escape(name)
+            )),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// The result of a can-i-deploy or can-i-merge check
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CanIDeployResult {
+    pub outcome: CanIDeployOutcome,
+    pub dry_run: bool,
+    /// The output that was printed
+    pub message: String,
+}
+
+impl CanIDeployResult {
+    /// The exit code for the process. Dry runs always succeed.
+    pub fn exit_code(&self) -> i32 {
+        if self.dry_run {
+            0
+        } else {
+            self.outcome.exit_code()
+        }
+    }
 }
 
 pub fn can_i_deploy(
     args: &ArgMatches,
     raw_args: Vec<String>,
     can_i_merge: bool,
-) -> Result<String, PactBrokerError> {
+) -> Result<CanIDeployResult, PactBrokerError> {
     debug!("Args: {:?}", args);
 
     let selectors = parse_args_from_matches(&raw_args);
@@ -276,240 +372,110 @@ pub fn can_i_deploy(
 
     debug!("Selectors: {:?}", selectors);
     debug!("Ignores: {:?}", ignores);
-    // Other arguments
-    let to_environment = args.try_get_one::<String>("to-environment").unwrap_or(None);
-    let to = args.try_get_one::<String>("to").unwrap_or(None);
-    let retry_while_unknown = args.get_one::<String>("retry-while-unknown");
-    let retry_interval = args.get_one::<String>("retry-interval");
+    let request = CanIDeployRequest {
+        pacticipants: selectors,
+        ignore: ignores,
+        to_environment: args
+            .try_get_one::<String>("to-environment")
+            .unwrap_or(None)
+            .cloned(),
+        to: args.try_get_one::<String>("to").unwrap_or(None).cloned(),
+        main_branch: can_i_merge,
+        retry_while_unknown: args
+            .get_one::<u32>("retry-while-unknown")
+            .copied()
+            .unwrap_or(0),
+        retry_interval: args.get_one::<u64>("retry-interval").copied().unwrap_or(5),
+    };
     let dry_run = args.get_flag("dry-run");
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let hal_client: HALClient = HALClient::with_url(
-            &broker_url,
-            Some(auth.clone()),
-            ssl_options.clone(),
-            custom_headers.clone(),
-        )
-        .with_retry_count(get_retries(args));
-
-        // Build matrix_href_path using selectors
-        let mut matrix_href_path = String::from("/matrix?");
-        for selector in &selectors {
-            matrix_href_path.push_str(&format!(
-                "q[][pacticipant]={}&",
-                urlencoding::encode(&selector.pacticipant)
-            ));
-            if let Some(version) = &selector.version {
-                matrix_href_path
-                    .push_str(&format!("q[][version]={}&", urlencoding::encode(version)));
-            }
-            if let Some(latest) = &selector.latest {
-                match latest {
-                    Some(tag) if !tag.is_empty() => {
-                        matrix_href_path.push_str("q[][latest]=true&");
-                        matrix_href_path
-                            .push_str(&format!("q[][tag]={}&", urlencoding::encode(tag)));
-                    }
-                    _ => {
-                        matrix_href_path.push_str("q[][latest]=true&");
-                    }
-                }
-            }
-            if let Some(branch) = &selector.branch {
-                matrix_href_path.push_str(&format!("q[][branch]={}&", urlencoding::encode(branch)));
-            }
-            for tag in &selector.tags {
-                matrix_href_path.push_str(&format!("q[][tag]={}&", urlencoding::encode(tag)));
-            }
-            if selector.main_branch {
-                matrix_href_path.push_str("q[][mainBranch]=true&");
-            }
-        }
-        for ignore in &ignores {
-            matrix_href_path.push_str(&format!(
-                "ignore[][pacticipant]={}&",
-                urlencoding::encode(&ignore.pacticipant)
-            ));
-            if let Some(version) = &ignore.version {
-                matrix_href_path.push_str(&format!(
-                    "ignore[][version]={}&",
-                    urlencoding::encode(version)
-                ));
-            }
-        }
-        if let Some(to) = to {
-            matrix_href_path.push_str(&format!("tag={}&", urlencoding::encode(to)));
-        }
-
-        // Use "cvpv" whenever an environment is targeted so that every version of an integrated
-        // application that is currently released/deployed to the environment is evaluated
-        // independently. "cvp" collapses these down to the latest provider version, hiding
-        // incompatible versions that are still live. See pact_broker issue #903.
-        if to_environment.is_some() || selectors.len() > 1 {
-            matrix_href_path.push_str("latestby=cvpv");
-        } else {
-            matrix_href_path.push_str("latestby=cvp");
-        }
-
-        if let Some(to_environment) = to_environment {
-            matrix_href_path.push_str(&format!(
-                "&environment={}",
-                urlencoding::encode(to_environment)
-            ));
-        }
-        if to_environment.is_none() && selectors.len() == 1 {
-            matrix_href_path.push_str("&latest=true");
-        }
-        if can_i_merge {
-            matrix_href_path.push_str("&mainBranch=true");
-        }
-        debug!(
-            "Querying broker at: {}",
-            broker_url.clone() + &matrix_href_path
-        );
-        let mut res;
-        let mut attempts = 0;
-        let max_attempts = retry_while_unknown
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(0);
-        let interval = retry_interval
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(5);
-
-        loop {
-            res = hal_client
-                .clone()
-                .fetch(&(broker_url.clone() + &matrix_href_path))
-                .await;
-
-            // If retry_while_unknown is set, poll if deployable is None (unknown)
-            if max_attempts > 0 {
-                if let Ok(ref response) = res
-                    && let Ok(data) = serde_json::from_str::<Data>(&response.to_string())
-                    && let Some(summary) = data.summary
-                    && summary.deployable.is_some()
-                {
-                    break;
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+
+    let res = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(client.can_i_deploy_json(&request))
+        .and_then(|res| Ok((matrix_from_json(res.clone())?, res)));
+    debug!("Response: {:?}", res);
+    match res {
+        Ok((data, res)) => {
+            let output = OutputType::from_args(args);
+            let outcome = data.outcome();
+
+            let message = match output {
+                ref output if output.is_structured() => output::print(&res, output)?,
+                OutputType::Junit => {
+                    let junit = build_matrix_junit(&data);
+                    print!("{}", junit);
+                    junit
                 }
-                attempts += 1;
-                if attempts > max_attempts {
-                    break;
+                OutputType::Markdown => {
+                    let markdown = build_matrix_markdown(&data);
+                    print!("{}", markdown);
+                    markdown
                 }
-                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
-            } else {
-                break;
-            }
-        }
-        debug!("Response: {:?}", res);
-        match res {
-            Ok(res) => {
-                let output: Result<Option<&String>, clap::parser::MatchesError> =
-                    args.try_get_one::<String>("output");
-
-                match output {
-                    Ok(Some(output)) => {
-                        if output == "json" {
-                            let json: String = serde_json::to_string(&res.clone()).unwrap();
-                            println!("{}", json);
-                            Ok(json)
-                        } else {
-                            let data: Data = match serde_json::from_str(&res.clone().to_string()) {
-                                Ok(data) => data,
-                                Err(err) => {
-                                    println!("❌ {}", utils::RED.apply_to(err.to_string()));
-                                    Data {
-                                        summary: Some(Summary {
-                                            deployable: Some(false),
-                                        }),
-                                        notices: Some(Vec::new()),
-                                        matrix: Vec::new(),
-                                    }
-                                }
-                            };
-
-                            if !data.matrix.is_empty() {
-                                let (table_str, verification_results) = build_matrix_table(&data);
-                                println!("{table_str}");
-                                if !verification_results.is_empty() {
-                                    println!("\nVERIFICATION RESULTS\n--------------------");
-                                    for (i, (url, status)) in
-                                        verification_results.iter().enumerate()
-                                    {
-                                        println!("{}. {} ({})", i + 1, url, status);
-                                    }
-                                    println!("\n");
-                                }
-                            }
-                            if let Some(notices) = data.notices {
-                                process_notices(&notices);
-                                println!("\n");
-                            }
-                            if data
-                                .summary
-                                .as_ref()
-                                .and_then(|s| s.deployable)
-                                .unwrap_or(false)
-                            {
-                                let computer_says_yes = utils::GREEN.apply_to("\\o/");
-                                let message = format!("✅ Computer says yes {}", computer_says_yes);
-                                println!("{}", message);
-                                Ok(message)
-                            } else {
-                                let computer_says_no = utils::RED.apply_to("¯\\_(ツ)_/¯");
-                                println!(r"❌ Computer says no {}", computer_says_no);
-                                if dry_run {
-                                    let message =
-                                        "📌 Dry run enabled, suppressing failing exit code"
-                                            .to_string();
-                                    println!("{}", utils::YELLOW.apply_to(message.clone()));
-                                    Ok(message)
-                                } else {
-                                    Err(PactBrokerError::NotFound(
-                                        "No deployable version found".to_string(),
-                                    ))
-                                }
+                _ => {
+                    if !data.matrix.is_empty() {
+                        let (table_str, verification_results) = build_matrix_table(&data);
+                        println!("{table_str}");
+                        if !verification_results.is_empty() {
+                            println!("\nVERIFICATION RESULTS\n--------------------");
+                            for (i, (url, status)) in verification_results.iter().enumerate() {
+                                println!("{}. {} ({})", i + 1, url, status);
                             }
+                            println!("\n");
                         }
                     }
-                    Err(res) => {
-                        let message = format!("❌ No output match provided for {}", res.clone());
-                        println!("{}", utils::RED.apply_to(message.clone()));
-                        Err(PactBrokerError::ValidationError([message].to_vec()))
+                    if let Some(notices) = &data.notices {
+                        process_notices(notices);
+                        println!("\n");
                     }
-                    _ => {
-                        let message = res.clone().to_string();
-                        println!("{}", message.clone());
-                        Ok(message)
-                    }
-                }
-            }
-            Err(err) => {
-                match &err {
-                    crate::cli::pact_broker::main::PactBrokerError::ValidationErrorWithNotices(
-                        messages,
-                        notices,
-                    ) => {
-                        println!("❌ Can-i-deploy command failed:");
-                        for message in messages {
-                            println!("   {}", message);
+                    if outcome == CanIDeployOutcome::Deployable {
+                        let computer_says_yes = utils::GREEN.apply_to("\\o/");
+                        let message = format!("✅ Computer says yes {}", computer_says_yes);
+                        println!("{}", message);
+                        message
+                    } else {
+                        let computer_says_no = utils::RED.apply_to("¯\\_(ツ)_/¯");
+                        let message = format!("❌ Computer says no {}", computer_says_no);
+                        println!("{}", message);
+                        if let Some(reason) = data.reason() {
+                            println!("{}", reason);
                         }
-                        if !notices.is_empty() {
-                            println!("\nDetails:");
-                            process_notices(notices);
+                        if dry_run {
+                            println!(
+                                "{}",
+                                utils::YELLOW
+                                    .apply_to("📌 Dry run enabled, suppressing failing exit code")
+                            );
                         }
+                        message
+                    }
+                }
+            };
+            Ok(CanIDeployResult {
+                outcome,
+                dry_run,
+                message,
+            })
+        }
+        Err(err) => {
+            match &err {
+                PactBrokerError::ValidationErrorWithNotices(messages, notices) => {
+                    eprintln!("❌ Can-i-deploy command failed:");
+                    for message in messages {
+                        eprintln!("   {}", message);
                     }
-                    _ => {
-                        println!("❌ {}", err);
+                    if !notices.is_empty() {
+                        println!("\nDetails:");
+                        process_notices(notices);
                     }
                 }
-                Err(err)
+                _ => {
+                    eprintln!("❌ {}", err);
+                }
             }
+            Err(err)
         }
-    })
+    }
 }
 
 #[cfg(test)]
@@ -543,7 +509,7 @@ mod can_i_deploy_tests {
     }
 
     fn table_from_json(matrix_json: &str) -> (String, Vec<(String, String)>) {
-        let data: super::Data = serde_json::from_str(matrix_json).unwrap();
+        let data: Matrix = serde_json::from_str(matrix_json).unwrap();
         super::build_matrix_table(&data)
     }
 
@@ -582,6 +548,88 @@ mod can_i_deploy_tests {
         assert!(results_output.contains("http://example.com/verification/123 (failure)"));
     }
 
+    #[test]
+    fn renders_junit_report_with_a_failure_per_broken_integration() {
+        let data: Matrix = serde_json::from_str(
+            r#"{
+            "summary": {"deployable": false, "reason": "The verification for Bar 2.0.0 failed"},
+            "matrix": [{
+                "consumer": {"name": "Foo", "version": {"number": "1.0.0"}},
+                "provider": {"name": "Bar", "version": {"number": "2.0.0"}},
+                "verificationResult": {"success": false}
+            }, {
+                "consumer": {"name": "Foo", "version": {"number": "1.0.0"}},
+                "provider": {"name": "Baz & Co", "version": {"number": "3.0.0"}},
+                "verificationResult": {"success": true}
+            }]
+        }"#,
+        )
+        .unwrap();
+
+        let junit = build_matrix_junit(&data);
+        assert!(junit.contains(r#"<testsuites name="can-i-deploy" tests="3" failures="2">"#));
+        assert!(junit.contains(r#"<property name="outcome" value="verification_failed"/>"#));
+        assert!(junit.contains(
+            r#"<testcase classname="Foo" name="Foo 1.0.0 → Bar 2.0.0">
+      <failure type="verification_failed" message="Verification failed"/>"#
+        ));
+        assert!(
+            junit.contains(r#"<testcase classname="Foo" name="Foo 1.0.0 → Baz &amp; Co 3.0.0"/>"#)
+        );
+        assert!(junit.contains(r#"message="The verification for Bar 2.0.0 failed""#));
+    }
+
+    #[test]
+    fn reports_pending_verifications_as_unknown_in_the_junit_report() {
+        let data: Matrix = serde_json::from_str(
+            r#"{
+            "summary": {"deployable": null, "reason": "Bar 2.0.0 has not finished verifying"},
+            "matrix": [{
+                "consumer": {"name": "Foo", "version": {"number": "1.0.0"}},
+                "provider": {"name": "Bar", "version": {"number": "2.0.0"}},
+                "verificationResult": {"success": null}
+            }, {
+                "consumer": {"name": "Foo", "version": {"number": "1.0.0"}},
+                "provider": {"name": "Baz"}
+            }]
+        }"#,
+        )
+        .unwrap();
+
+        let junit = build_matrix_junit(&data);
+        assert!(junit.contains(r#"<property name="outcome" value="unknown"/>"#));
+        assert!(junit.contains(
+            r#"<testcase classname="Foo" name="Foo 1.0.0 → Bar 2.0.0">
+      <failure type="unknown""#
+        ));
+        assert!(junit.contains(
+            r#"<testcase classname="Foo" name="Foo 1.0.0 → Baz unknown">
+      <failure type="missing_verification""#
+        ));
+    }
+
+    #[test]
+    fn renders_markdown_report() {
+        let data: Matrix = serde_json::from_str(
+            r#"{
+            "summary": {"deployable": true},
+            "matrix": [{
+                "consumer": {"name": "Foo", "version": {"number": "1.0.0"}},
+                "provider": {"name": "Bar", "version": {"number": "2.0.0"}},
+                "verificationResult": {"success": true, "_links": {"self": {"href": "http://result"}}}
+            }]
+        }"#,
+        )
+        .unwrap();
+
+        let markdown = build_matrix_markdown(&data);
+        assert!(markdown.starts_with("### ✅ Computer says yes"));
+        assert!(markdown.contains(
+            "| CONSUMER | C.VERSION | PROVIDER | P.VERSION | SUCCESS? | RESULT |\n| --- | --- | --- | --- | --- | --- |\n| Foo | 1.0.0 | Bar | 2.0.0 | true | 1 |"
+        ));
+        assert!(markdown.contains("1. <http://result> (success)"));
+    }
+
     #[test]
     fn shows_bdct_verification_type() {
         let matrix_json = r#"{
@@ -736,7 +784,7 @@ mod can_i_deploy_tests {
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
         assert!(result.is_ok());
-        let output = result.unwrap();
+        let output = result.unwrap().message;
         assert!(output.contains("Computer says"));
     }
 
@@ -876,7 +924,7 @@ mod can_i_deploy_tests {
         let matches = build_matches(raw_args.clone());
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
-        assert!(result.is_ok() || result.is_err());
+        assert_eq!(result.unwrap().outcome, CanIDeployOutcome::VersionNotFound);
     }
 
     #[test]
@@ -929,6 +977,53 @@ mod can_i_deploy_tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn unexpected_matrix_response_returns_error() {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..Default::default()
+        };
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction(
+                "a request for the compatibility matrix that gets an unexpected response",
+                "",
+                |mut i| {
+                    i.request
+                        .get()
+                        .path("/matrix")
+                        .query_param("q[][pacticipant]", "Foo")
+                        .query_param("q[][version]", "1.2.3")
+                        .query_param("latestby", "cvp")
+                        .query_param("latest", "true");
+                    i.response
+                        .status(200)
+                        .header("Content-Type", "application/hal+json;charset=utf-8")
+                        .json_body(json_pattern!({ "message": "Service temporarily degraded" }));
+                    i
+                },
+            )
+            .start_mock_server(None, Some(config));
+        let mock_server_url = pact_broker_service.url();
+
+        let raw_args = vec![
+            "can-i-deploy",
+            "-b",
+            mock_server_url.as_str(),
+            "--pacticipant",
+            "Foo",
+            "--version",
+            "1.2.3",
+        ];
+        let matches = build_matches(raw_args.clone());
+        let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
+        let result = can_i_deploy(&matches, raw_args, false);
+        assert!(
+            matches!(result, Err(PactBrokerError::ContentError(_))),
+            "{:?}",
+            result.map(|result| result.outcome)
+        );
+    }
+
     #[test]
     fn no_versions_specified_returns_multiple_rows() {
         let config = MockServerConfig {
@@ -1135,7 +1230,7 @@ mod can_i_deploy_tests {
 
     // False-positive scenario from issue #903: when multiple provider versions are released to an
     // environment and one fails, cvpv preserves the failing row. cvp would collapse it away,
-    // returning a false "deployable: true". The CLI must return Err when deployable is false.
+    // returning a false "deployable: true". The CLI must report the failed verification.
     #[test]
     fn to_environment_not_deployable_when_a_released_provider_version_fails() {
         let config = MockServerConfig {
@@ -1189,7 +1284,9 @@ mod can_i_deploy_tests {
         let matches = build_matches(raw_args.clone());
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
-        assert!(result.is_err());
+        let result = result.unwrap();
+        assert_eq!(result.outcome, CanIDeployOutcome::VerificationFailed);
+        assert_eq!(result.exit_code(), 2);
     }
 
     // When multiple provider versions are released/deployed to an environment and all are
@@ -1248,7 +1345,7 @@ mod can_i_deploy_tests {
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
         assert!(result.is_ok());
-        let output = result.unwrap();
+        let output = result.unwrap().message;
         assert!(output.contains("Computer says yes"));
     }
 
@@ -1305,7 +1402,7 @@ mod can_i_deploy_tests {
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
         assert!(result.is_ok());
-        let output = result.unwrap();
+        let output = result.unwrap().message;
         assert!(output.contains("Computer says"));
     }
 
@@ -1359,7 +1456,7 @@ mod can_i_deploy_tests {
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
         assert!(result.is_ok());
-        let output = result.unwrap();
+        let output = result.unwrap().message;
         assert!(output.contains("Computer says"));
     }
 
@@ -1516,7 +1613,7 @@ mod can_i_deploy_tests {
         let raw_args: Vec<String> = raw_args.into_iter().map(|s| s.to_string()).collect();
         let result = can_i_deploy(&matches, raw_args, false);
         assert!(result.is_ok());
-        let output = result.unwrap();
+        let output = result.unwrap().message;
         assert!(output.contains("Computer says"));
     }
 }
@@ -0,0 +1,1094 @@
+//! A typed client for the Pact Broker API, for use from Rust code without going through the CLI.
+//!
+//! ```no_run
+//! use pact_broker_cli::{BrokerDetails, PactBrokerClient};
+//! use pact_broker_cli::cli::pact_broker::main::client::{CanIDeployRequest, PacticipantSelector};
+//!
+//! # async fn example() -> Result<(), pact_broker_cli::cli::pact_broker::main::PactBrokerError> {
+//! let client = PactBrokerClient::new(BrokerDetails::new("http://localhost:9292"));
+//! let matrix = client
+//!     .can_i_deploy(&CanIDeployRequest {
+//!         pacticipants: vec![PacticipantSelector::version("Foo", "1.2.3")],
+//!         to_environment: Some("production".to_string()),
+//!         ..CanIDeployRequest::default()
+//!     })
+//!     .await?;
+//! println!("deployable: {:?}", matrix.deployable());
+//! # Ok(())
+//! # }
+//! ```
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::skip_serializing_none;
+use tracing::debug;
+
+use crate::cli::pact_broker::main::{
+    HALClient, Notice, PactBrokerError,
+    deployments::record_undeployment::{deployed_application_instance, relation_href},
+    types::{BrokerDetails, Pagination},
+    utils::get_broker_relation,
+};
+
+fn deserialize_optional_field<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(Some(Option::deserialize(deserializer)?))
+}
+
+/// Selects the version(s) of a pacticipant to check with can-i-deploy
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PacticipantSelector {
+    pub pacticipant: String,
+    pub version: Option<String>,
+    pub branch: Option<String>,
+    pub tags: Vec<String>,
+    /// `Some(None)` selects the latest version, `Some(Some(tag))` the latest version with the tag
+    pub latest: Option<Option<String>>,
+    pub main_branch: bool,
+}
+
+impl PacticipantSelector {
+    pub fn version(pacticipant: &str, version: &str) -> Self {
+        PacticipantSelector {
+            pacticipant: pacticipant.to_string(),
+            version: Some(version.to_string()),
+            ..PacticipantSelector::default()
+        }
+    }
+}
+
+/// A pacticipant (or version of a pacticipant) to leave out of the can-i-deploy result
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreSelector {
+    pub pacticipant: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CanIDeployRequest {
+    pub pacticipants: Vec<PacticipantSelector>,
+    pub ignore: Vec<IgnoreSelector>,
+    pub to_environment: Option<String>,
+    /// The tag of the versions being deployed to (deprecated in favour of environments)
+    pub to: Option<String>,
+    /// Only consider the main branch versions of the other pacticipants (can-i-merge)
+    pub main_branch: bool,
+    /// How many times to poll the matrix while the result is unknown
+    pub retry_while_unknown: u32,
+    /// Seconds to wait between polls
+    pub retry_interval: u64,
+}
+
+impl CanIDeployRequest {
+    /// Builds the path of the matrix resource to query
+    pub fn matrix_path(&self) -> String {
+        let mut path = String::from("/matrix?");
+        for selector in &self.pacticipants {
+            path.push_str(&format!(
+                "q[][pacticipant]={}&",
+                urlencoding::encode(&selector.pacticipant)
+            ));
+            if let Some(version) = &selector.version {
+                path.push_str(&format!("q[][version]={}&", urlencoding::encode(version)));
+            }
+            if let Some(latest) = &selector.latest {
+                path.push_str("q[][latest]=true&");
+                if let Some(tag) = latest.as_ref().filter(|tag| !tag.is_empty()) {
+                    path.push_str(&format!("q[][tag]={}&", urlencoding::encode(tag)));
+                }
+            }
+            if let Some(branch) = &selector.branch {
+                path.push_str(&format!("q[][branch]={}&", urlencoding::encode(branch)));
+            }
+            for tag in &selector.tags {
+                path.push_str(&format!("q[][tag]={}&", urlencoding::encode(tag)));
+            }
+            if selector.main_branch {
+                path.push_str("q[][mainBranch]=true&");
+            }
+        }
+        for ignore in &self.ignore {
+            path.push_str(&format!(
+                "ignore[][pacticipant]={}&",
+                urlencoding::encode(&ignore.pacticipant)
+            ));
+            if let Some(version) = &ignore.version {
+                path.push_str(&format!(
+                    "ignore[][version]={}&",
+                    urlencoding::encode(version)
+                ));
+            }
+        }
+        if let Some(to) = &self.to {
+            path.push_str(&format!("tag={}&", urlencoding::encode(to)));
+        }
+
+        // Use "cvpv" whenever an environment is targeted so that every version of an integrated
+        // application that is currently released/deployed to the environment is evaluated
+        // independently. "cvp" collapses these down to the latest provider version, hiding
+        // incompatible versions that are still live. See pact_broker issue #903.
+        if self.to_environment.is_some() || self.pacticipants.len() > 1 {
+            path.push_str("latestby=cvpv");
+        } else {
+            path.push_str("latestby=cvp");
+        }
+
+        if let Some(to_environment) = &self.to_environment {
+            path.push_str(&format!(
+                "&environment={}",
+                urlencoding::encode(to_environment)
+            ));
+        }
+        if self.to_environment.is_none() && self.pacticipants.len() == 1 {
+            path.push_str("&latest=true");
+        }
+        if self.main_branch {
+            path.push_str("&mainBranch=true");
+        }
+        path
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatrixSummary {
+    pub deployable: Option<bool>,
+    pub reason: Option<String>,
+    pub success: Option<u32>,
+    pub failed: Option<u32>,
+    pub unknown: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixVersion {
+    pub number: String,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixPacticipant {
+    pub name: String,
+    pub version: Option<MatrixVersion>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixHref {
+    pub href: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixVerificationLinks {
+    #[serde(rename = "self")]
+    pub self_link: Option<MatrixHref>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixVerificationResult {
+    pub success: Option<bool>,
+    #[serde(rename = "_links")]
+    pub links: Option<MatrixVerificationLinks>,
+}
+
+impl MatrixVerificationResult {
+    pub fn href(&self) -> Option<&str> {
+        self.links
+            .as_ref()
+            .and_then(|links| links.self_link.as_ref())
+            .and_then(|self_link| self_link.href.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixRow {
+    pub consumer: MatrixPacticipant,
+    pub provider: MatrixPacticipant,
+    #[serde(rename = "verificationResult", skip_serializing_if = "Option::is_none")]
+    pub verification_result: Option<MatrixVerificationResult>,
+    /// Only returned by PactFlow. `Some(None)` means the key was present but null.
+    #[serde(
+        rename = "verificationType",
+        default,
+        deserialize_with = "deserialize_optional_field",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub verification_type: Option<Option<String>>,
+}
+
+impl MatrixRow {
+    /// The outcome of the row on its own: deployable when its verification was successful
+    pub fn outcome(&self) -> CanIDeployOutcome {
+        match &self.verification_result {
+            None => CanIDeployOutcome::MissingVerification,
+            Some(result) => match result.success {
+                Some(true) => CanIDeployOutcome::Deployable,
+                Some(false) => CanIDeployOutcome::VerificationFailed,
+                None => CanIDeployOutcome::Unknown,
+            },
+        }
+    }
+}
+
+/// The result of a can-i-deploy query
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Matrix {
+    pub summary: Option<MatrixSummary>,
+    pub notices: Option<Vec<Notice>>,
+    #[serde(default)]
+    pub matrix: Vec<MatrixRow>,
+}
+
+impl Matrix {
+    pub fn deployable(&self) -> Option<bool> {
+        self.summary.as_ref().and_then(|summary| summary.deployable)
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.summary
+            .as_ref()
+            .and_then(|summary| summary.reason.as_deref())
+    }
+
+    /// Works out why the selected versions can or can not be deployed
+    pub fn outcome(&self) -> CanIDeployOutcome {
+        if self.deployable() == Some(true) {
+            CanIDeployOutcome::Deployable
+        } else if self.matrix.iter().any(|row| {
+            row.verification_result
+                .as_ref()
+                .and_then(|result| result.success)
+                == Some(false)
+        }) {
+            CanIDeployOutcome::VerificationFailed
+        } else if self.matrix.is_empty() {
+            CanIDeployOutcome::VersionNotFound
+        } else if self.deployable().is_none() {
+            CanIDeployOutcome::Unknown
+        } else {
+            CanIDeployOutcome::MissingVerification
+        }
+    }
+}
+
+/// Why a can-i-deploy check passed or failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanIDeployOutcome {
+    /// Every required verification was successful
+    Deployable,
+    /// At least one verification failed
+    VerificationFailed,
+    /// A required pact has not been verified
+    MissingVerification,
+    /// The broker can not tell yet, usually because a verification is still pending
+    Unknown,
+    /// No matrix rows were found for the selected versions
+    VersionNotFound,
+}
+
+impl CanIDeployOutcome {
+    /// The process exit code for the outcome. 1 is left for errors talking to the broker.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CanIDeployOutcome::Deployable => 0,
+            CanIDeployOutcome::VerificationFailed => 2,
+            CanIDeployOutcome::MissingVerification => 3,
+            CanIDeployOutcome::Unknown => 4,
+            CanIDeployOutcome::VersionNotFound => 5,
+        }
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: Option<String>,
+    pub details: Option<Value>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateEnvironmentRequest {
+    pub name: String,
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub production: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contacts: Vec<Contact>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    pub uuid: String,
+    pub name: String,
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub production: bool,
+    pub contacts: Option<Vec<Contact>>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordDeploymentRequest {
+    pub pacticipant: String,
+    pub version: String,
+    pub environment: String,
+    pub application_instance: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordReleaseRequest {
+    pub pacticipant: String,
+    pub version: String,
+    pub environment: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordUndeploymentRequest {
+    pub pacticipant: String,
+    pub environment: String,
+    /// Without an application instance, only the deployment that has none is undeployed
+    pub application_instance: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordSupportEndedRequest {
+    pub pacticipant: String,
+    pub version: String,
+    pub environment: String,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleasedVersion {
+    pub uuid: Option<String>,
+    pub currently_supported: Option<bool>,
+    pub created_at: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployedVersion {
+    pub uuid: Option<String>,
+    pub currently_deployed: Option<bool>,
+    pub target: Option<String>,
+    pub application_instance: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// A pacticipant version that is currently deployed to an environment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentDeployment {
+    pub pacticipant: String,
+    pub version: String,
+    pub application_instance: Option<String>,
+    /// The deployed version resource, which is patched to record the undeployment
+    #[serde(skip)]
+    pub href: String,
+}
+
+/// Publishes contracts for a version of their consumer, creating the version with its branch and
+/// tags if it does not exist
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishPactsRequest {
+    pub pacticipant_name: String,
+    pub pacticipant_version_number: String,
+    pub branch: Option<String>,
+    pub build_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+    pub contracts: Vec<PublishContract>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishContract {
+    pub consumer_name: String,
+    pub provider_name: String,
+    pub specification: String,
+    pub content_type: String,
+    /// The base64 encoded contract
+    pub content: String,
+    /// `overwrite` (the default) or `merge` to add the interactions to an existing pact
+    pub on_conflict: Option<String>,
+}
+
+impl PublishContract {
+    /// A pact between the consumer and provider, from its JSON
+    pub fn pact(consumer: &str, provider: &str, pact: &Value) -> Self {
+        PublishContract {
+            consumer_name: consumer.to_string(),
+            provider_name: provider.to_string(),
+            specification: "pact".to_string(),
+            content_type: "application/json".to_string(),
+            content: Base64.encode(pact.to_string()),
+            on_conflict: None,
+        }
+    }
+}
+
+/// The response to publishing contracts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishPactsResult {
+    /// What the broker did with the version and each contract
+    pub notices: Option<Vec<Notice>>,
+    /// The pacticipant and version the contracts were published for
+    #[serde(rename = "_embedded", default)]
+    pub embedded: Value,
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(json: Value) -> Result<T, PactBrokerError> {
+    serde_json::from_value(json).map_err(|err| {
+        PactBrokerError::ContentError(format!("Unexpected response from the Pact Broker: {}", err))
+    })
+}
+
+/// Deserialises a can-i-deploy response. When the broker cannot find a version it only sends the
+/// summary with the reason, so a response needs a summary or the matrix rows
+pub(crate) fn matrix_from_json(json: Value) -> Result<Matrix, PactBrokerError> {
+    if json.get("summary").is_none() && json.get("matrix").is_none() {
+        return Err(PactBrokerError::ContentError(
+            "Unexpected response from the Pact Broker: it has no matrix or summary".to_string(),
+        ));
+    }
+    from_json(json)
+}
+
+/// Client for the Pact Broker API. The CLI commands are built on top of this.
+#[derive(Clone)]
+pub struct PactBrokerClient {
+    broker_details: BrokerDetails,
+    hal_client: HALClient,
+}
+
+impl PactBrokerClient {
+    pub fn new(broker_details: BrokerDetails) -> Self {
+        let hal_client = HALClient::with_url(
+            &broker_details.url,
+            broker_details.auth.clone(),
+            broker_details.connection_options.clone(),
+            broker_details.custom_headers.clone(),
+        )
+        .with_retry_count(broker_details.retries);
+        PactBrokerClient {
+            broker_details,
+            hal_client,
+        }
+    }
+
+    pub fn broker_url(&self) -> &str {
+        &self.broker_details.url
+    }
+
+    /// The HAL client used for requests, for resources this client has no method for yet
+    pub(crate) fn hal_client(&self) -> &HALClient {
+        &self.hal_client
+    }
+
+    /// Queries the matrix, returning the response as the broker sent it
+    pub(crate) async fn can_i_deploy_json(
+        &self,
+        request: &CanIDeployRequest,
+    ) -> Result<Value, PactBrokerError> {
+        let matrix_url = format!("{}{}", self.broker_url(), request.matrix_path());
+        debug!("Querying broker at: {}", matrix_url);
+        let mut attempts = 0;
+        loop {
+            let res = self.hal_client.fetch(&matrix_url).await?;
+            // A response that is not a matrix is an error, rather than an unknown result
+            let matrix = matrix_from_json(res.clone())?;
+
+            // When retrying while unknown, poll until the broker can say whether it is deployable
+            if request.retry_while_unknown == 0 || matrix.deployable().is_some() {
+                return Ok(res);
+            }
+            attempts += 1;
+            if attempts > request.retry_while_unknown {
+                return Ok(res);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(request.retry_interval)).await;
+        }
+    }
+
+    /// Checks whether the selected pacticipant versions are compatible with each other, or with
+    /// the versions in the target environment
+    pub async fn can_i_deploy(
+        &self,
+        request: &CanIDeployRequest,
+    ) -> Result<Matrix, PactBrokerError> {
+        matrix_from_json(self.can_i_deploy_json(request).await?)
+    }
+
+    pub(crate) async fn list_environments_json(
+        &self,
+        pagination: &Pagination,
+    ) -> Result<Value, PactBrokerError> {
+        let href = get_broker_relation(
+            self.hal_client.clone(),
+            "pb:environments".to_string(),
+            self.broker_url().to_string(),
+        )
+        .await?;
+        self.hal_client
+            .fetch_collection(&href, &["/_embedded/environments"], pagination)
+            .await
+    }
+
+    /// Every environment, from all the pages of the collection
+    pub async fn list_environments(&self) -> Result<Vec<Environment>, PactBrokerError> {
+        let res = self.list_environments_json(&Pagination::all()).await?;
+        from_json(res["_embedded"]["environments"].clone())
+    }
+
+    pub(crate) async fn create_environment_json(
+        &self,
+        request: &CreateEnvironmentRequest,
+    ) -> Result<Value, PactBrokerError> {
+        if request.name.is_empty() {
+            return Err(PactBrokerError::ValidationError(vec![
+                "Environment name is required".to_string(),
+            ]));
+        }
+        let href = get_broker_relation(
+            self.hal_client.clone(),
+            "pb:environments".to_string(),
+            self.broker_url().to_string(),
+        )
+        .await?;
+        let body = serde_json::to_string(request).unwrap();
+        self.hal_client.post_json(&href, &body, None).await
+    }
+
+    pub async fn create_environment(
+        &self,
+        request: &CreateEnvironmentRequest,
+    ) -> Result<Environment, PactBrokerError> {
+        from_json(self.create_environment_json(request).await?)
+    }
+
+    /// Replaces the environment with the given UUID, returning the response as the broker sent it
+    pub(crate) async fn update_environment_json(
+        &self,
+        uuid: &str,
+        request: &CreateEnvironmentRequest,
+    ) -> Result<Value, PactBrokerError> {
+        if request.name.is_empty() {
+            return Err(PactBrokerError::ValidationError(vec![
+                "Environment name is required".to_string(),
+            ]));
+        }
+        let href = format!("{}/environments/{}", self.broker_url(), uuid);
+        let body = serde_json::to_string(request).unwrap();
+        self.hal_client.put_json(&href, &body, None).await
+    }
+
+    pub async fn update_environment(
+        &self,
+        uuid: &str,
+        request: &CreateEnvironmentRequest,
+    ) -> Result<Environment, PactBrokerError> {
+        from_json(self.update_environment_json(uuid, request).await?)
+    }
+
+    /// Finds the link of a version resource relation (pb:record-deployment or pb:record-release)
+    /// for an environment
+    async fn version_environment_href(
+        &self,
+        pacticipant: &str,
+        version: &str,
+        relation: &str,
+        environment: &str,
+    ) -> Result<String, PactBrokerError> {
+        let version = self
+            .hal_client
+            .fetch(&format!(
+                "{}/pacticipants/{}/versions/{}",
+                self.broker_url(),
+                pacticipant,
+                version
+            ))
+            .await?;
+
+        version["_links"][relation]
+            .as_array()
+            .ok_or_else(|| {
+                PactBrokerError::ContentError(format!(
+                    "Failed to {}: the version resource has no {} links",
+                    relation.trim_start_matches("pb:").replace('-', " "),
+                    relation
+                ))
+            })?
+            .iter()
+            .find(|link| link["name"].as_str() == Some(environment))
+            .and_then(|link| link["href"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                PactBrokerError::NotFound(format!("Environment {} does not exist", environment))
+            })
+    }
+
+    pub(crate) async fn record_deployment_json(
+        &self,
+        request: &RecordDeploymentRequest,
+    ) -> Result<Value, PactBrokerError> {
+        let href = self
+            .version_environment_href(
+                &request.pacticipant,
+                &request.version,
+                "pb:record-deployment",
+                &request.environment,
+            )
+            .await?;
+
+        let mut payload = serde_json::json!({});
+        if let Some(application_instance) = &request.application_instance {
+            payload["target"] = Value::String(application_instance.to_string());
+            payload["applicationInstance"] = Value::String(application_instance.to_string());
+        }
+        self.hal_client
+            .post_json(&href, &payload.to_string(), None)
+            .await
+    }
+
+    /// Records that a pacticipant version has been deployed to an environment
+    pub async fn record_deployment(
+        &self,
+        request: &RecordDeploymentRequest,
+    ) -> Result<DeployedVersion, PactBrokerError> {
+        from_json(self.record_deployment_json(request).await?)
+    }
+
+    pub(crate) async fn record_release_json(
+        &self,
+        request: &RecordReleaseRequest,
+    ) -> Result<Value, PactBrokerError> {
+        let href = self
+            .version_environment_href(
+                &request.pacticipant,
+                &request.version,
+                "pb:record-release",
+                &request.environment,
+            )
+            .await?;
+        self.hal_client.post_json(&href, "{}", None).await
+    }
+
+    /// Lists the pacticipant versions currently deployed to an environment, optionally only those
+    /// of one pacticipant
+    pub async fn currently_deployed_versions(
+        &self,
+        environment: &str,
+        pacticipant: Option<&str>,
+    ) -> Result<Vec<CurrentDeployment>, PactBrokerError> {
+        let environment = self.environment_resource(environment).await?;
+        let mut href = relation_href(
+            &environment,
+            "pb:currently-deployed-deployed-versions",
+            Some("pb:currently-deployed-versions"),
+        )
+        .ok_or_else(|| {
+            PactBrokerError::LinkError(
+                "This version of the Pact Broker does not support recording undeployments. Please upgrade to version 2.80.0 or later.".to_string(),
+            )
+        })?;
+        if let Some(pacticipant) = pacticipant {
+            href = format!("{}?pacticipant={}", href, urlencoding::encode(pacticipant));
+        }
+        let res = self.hal_client.fetch(&href).await?;
+        Ok(res["_embedded"]["deployedVersions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|deployed| {
+                Some(CurrentDeployment {
+                    pacticipant: deployed["_embedded"]["pacticipant"]["name"]
+                        .as_str()?
+                        .to_string(),
+                    // Older brokers do not embed the version
+                    version: deployed["_embedded"]["version"]["number"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    application_instance: deployed_application_instance(deployed),
+                    href: deployed["_links"]["self"]["href"].as_str()?.to_string(),
+                })
+            })
+            // Keep only exact matches of the pacticipant name
+            .filter(|deployed| pacticipant.is_none_or(|name| deployed.pacticipant == name))
+            .collect())
+    }
+
+    /// Fetches the resource of an environment, found by its name
+    async fn environment_resource(&self, environment: &str) -> Result<Value, PactBrokerError> {
+        let uuid = self
+            .list_environments()
+            .await?
+            .into_iter()
+            .find(|env| env.name == environment)
+            .map(|env| env.uuid)
+            .ok_or_else(|| {
+                PactBrokerError::NotFound(format!("Environment {} does not exist", environment))
+            })?;
+        self.hal_client
+            .fetch(&format!("{}/environments/{}", self.broker_url(), uuid))
+            .await
+    }
+
+    pub(crate) async fn end_deployment_json(
+        &self,
+        deployment: &CurrentDeployment,
+    ) -> Result<Value, PactBrokerError> {
+        self.hal_client
+            .patch_json(
+                &deployment.href,
+                &serde_json::json!({ "currentlyDeployed": false }).to_string(),
+                None,
+            )
+            .await
+    }
+
+    /// Records that a currently deployed version is no longer deployed
+    pub async fn end_deployment(
+        &self,
+        deployment: &CurrentDeployment,
+    ) -> Result<DeployedVersion, PactBrokerError> {
+        from_json(self.end_deployment_json(deployment).await?)
+    }
+
+    pub(crate) async fn record_undeployment_json(
+        &self,
+        request: &RecordUndeploymentRequest,
+    ) -> Result<Vec<Value>, PactBrokerError> {
+        let deployments = self
+            .currently_deployed_versions(&request.environment, Some(&request.pacticipant))
+            .await?;
+        if deployments.is_empty() {
+            return Err(PactBrokerError::NotFound(format!(
+                "{} is not currently deployed to {} environment. Cannot record undeployment.",
+                request.pacticipant, request.environment
+            )));
+        }
+
+        let (selected, others): (Vec<_>, Vec<_>) =
+            deployments.into_iter().partition(|deployment| {
+                deployment.application_instance == request.application_instance
+            });
+        if selected.is_empty() {
+            let known_instances: Vec<String> = others
+                .iter()
+                .filter_map(|deployment| deployment.application_instance.clone())
+                .collect();
+            let Some(instance) = &request.application_instance else {
+                return Err(PactBrokerError::NotFound(format!(
+                    "Please specify one of the following application instances to record the undeployment from: {}",
+                    known_instances.join(", ")
+                )));
+            };
+            let mut suggestions = vec![];
+            if others
+                .iter()
+                .any(|deployment| deployment.application_instance.is_none())
+            {
+                suggestions.push("omit the application instance".to_string());
+            }
+            if !known_instances.is_empty() {
+                suggestions.push(format!(
+                    "specify one of the following application instances to record the undeployment from: {}",
+                    known_instances.join(", ")
+                ));
+            }
+            return Err(PactBrokerError::NotFound(format!(
+                "{} is not currently deployed to application instance '{}' in {} environment.{}",
+                request.pacticipant,
+                instance,
+                request.environment,
+                if suggestions.is_empty() {
+                    String::new()
+                } else {
+                    format!(" Please {}.", suggestions.join(" or "))
+                }
+            )));
+        }
+
+        let mut undeployed = vec![];
+        for deployment in &selected {
+            undeployed.push(self.end_deployment_json(deployment).await?);
+        }
+        Ok(undeployed)
+    }
+
+    /// Records that a pacticipant is no longer deployed to an environment, or to one application
+    /// instance in it
+    pub async fn record_undeployment(
+        &self,
+        request: &RecordUndeploymentRequest,
+    ) -> Result<Vec<DeployedVersion>, PactBrokerError> {
+        from_json(Value::Array(self.record_undeployment_json(request).await?))
+    }
+
+    pub(crate) async fn record_support_ended_json(
+        &self,
+        request: &RecordSupportEndedRequest,
+    ) -> Result<Value, PactBrokerError> {
+        let environment = self.environment_resource(&request.environment).await?;
+        let href = relation_href(
+            &environment,
+            "pb:currently-supported-released-versions",
+            None,
+        )
+        .ok_or_else(|| {
+            PactBrokerError::LinkError(
+                "This version of the Pact Broker does not support recording the end of support. Please upgrade to version 2.80.0 or later.".to_string(),
+            )
+        })?;
+        let res = self
+            .hal_client
+            .fetch(&format!(
+                "{}?pacticipant={}",
+                href,
+                urlencoding::encode(&request.pacticipant)
+            ))
+            .await?;
+        let released_href = res["_embedded"]["releasedVersions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|released| {
+                released["_embedded"]["pacticipant"]["name"].as_str()
+                    == Some(request.pacticipant.as_str())
+                    && released["_embedded"]["version"]["number"].as_str()
+                        == Some(request.version.as_str())
+            })
+            .and_then(|released| released["_links"]["self"]["href"].as_str())
+            .ok_or_else(|| {
+                PactBrokerError::NotFound(format!(
+                    "No currently released versions found for {} in {} environment",
+                    request.pacticipant, request.environment
+                ))
+            })?;
+        self.hal_client
+            .patch_json(
+                released_href,
+                &serde_json::json!({ "currentlySupported": false }).to_string(),
+                None,
+            )
+            .await
+    }
+
+    /// Records that a released pacticipant version is no longer supported in an environment
+    pub async fn record_support_ended(
+        &self,
+        request: &RecordSupportEndedRequest,
+    ) -> Result<ReleasedVersion, PactBrokerError> {
+        from_json(self.record_support_ended_json(request).await?)
+    }
+
+    /// Records that a pacticipant version has been released to an environment
+    pub async fn record_release(
+        &self,
+        request: &RecordReleaseRequest,
+    ) -> Result<ReleasedVersion, PactBrokerError> {
+        from_json(self.record_release_json(request).await?)
+    }
+
+    pub(crate) async fn publish_pacts_json(
+        &self,
+        request: &PublishPactsRequest,
+    ) -> Result<Value, PactBrokerError> {
+        if request.pacticipant_version_number.is_empty() {
+            return Err(PactBrokerError::ValidationError(vec![
+                "Consumer app version is required to publish pacts".to_string(),
+            ]));
+        }
+        let href = get_broker_relation(
+            self.hal_client.clone(),
+            "pb:publish-contracts".to_string(),
+            self.broker_url().to_string(),
+        )
+        .await?;
+        let body = serde_json::to_string(request).unwrap();
+        self.hal_client.post_json(&href, &body, None).await
+    }
+
+    /// Publishes pacts for a consumer version
+    pub async fn publish_pacts(
+        &self,
+        request: &PublishPactsRequest,
+    ) -> Result<PublishPactsResult, PactBrokerError> {
+        from_json(self.publish_pacts_json(request).await?)
+    }
+}
+
+#[cfg(test)]
+mod client_tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn matrix_path_for_a_single_version_uses_cvp_and_latest() {
+        let request = CanIDeployRequest {
+            pacticipants: vec![PacticipantSelector::version("Foo Thing", "1.2.3")],
+            ..CanIDeployRequest::default()
+        };
+        assert_eq!(
+            request.matrix_path(),
+            "/matrix?q[][pacticipant]=Foo%20Thing&q[][version]=1.2.3&latestby=cvp&latest=true"
+        );
+    }
+
+    #[test]
+    fn matrix_path_for_an_environment_uses_cvpv() {
+        let request = CanIDeployRequest {
+            pacticipants: vec![PacticipantSelector {
+                pacticipant: "Foo".to_string(),
+                latest: Some(Some("prod".to_string())),
+                ..PacticipantSelector::default()
+            }],
+            ignore: vec![IgnoreSelector {
+                pacticipant: "Bar".to_string(),
+                version: None,
+            }],
+            to_environment: Some("production".to_string()),
+            main_branch: true,
+            ..CanIDeployRequest::default()
+        };
+        assert_eq!(
+            request.matrix_path(),
+            "/matrix?q[][pacticipant]=Foo&q[][latest]=true&q[][tag]=prod&ignore[][pacticipant]=Bar&latestby=cvpv&environment=production&mainBranch=true"
+        );
+    }
+
+    fn matrix_row(success: Option<bool>) -> MatrixRow {
+        MatrixRow {
+            consumer: MatrixPacticipant {
+                name: "Foo".to_string(),
+                version: None,
+            },
+            provider: MatrixPacticipant {
+                name: "Bar".to_string(),
+                version: None,
+            },
+            verification_result: success.map(|success| MatrixVerificationResult {
+                success: Some(success),
+                links: None,
+            }),
+            verification_type: None,
+        }
+    }
+
+    fn matrix(deployable: Option<bool>, rows: Vec<MatrixRow>) -> Matrix {
+        Matrix {
+            summary: Some(MatrixSummary {
+                deployable,
+                ..MatrixSummary::default()
+            }),
+            notices: None,
+            matrix: rows,
+        }
+    }
+
+    #[test]
+    fn matrix_outcome_separates_failed_missing_and_unknown_verifications() {
+        assert_eq!(
+            matrix(Some(true), vec![matrix_row(Some(true))]).outcome(),
+            CanIDeployOutcome::Deployable
+        );
+        assert_eq!(
+            matrix(
+                Some(false),
+                vec![matrix_row(Some(true)), matrix_row(Some(false))]
+            )
+            .outcome(),
+            CanIDeployOutcome::VerificationFailed
+        );
+        assert_eq!(
+            matrix(Some(false), vec![matrix_row(None)]).outcome(),
+            CanIDeployOutcome::MissingVerification
+        );
+        assert_eq!(
+            matrix(None, vec![matrix_row(None)]).outcome(),
+            CanIDeployOutcome::Unknown
+        );
+        assert_eq!(
+            matrix(Some(false), vec![]).outcome(),
+            CanIDeployOutcome::VersionNotFound
+        );
+    }
+
+    #[test]
+    fn create_environment_request_omits_unset_fields() {
+        let request = CreateEnvironmentRequest {
+            name: "test".to_string(),
+            ..CreateEnvironmentRequest::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({ "name": "test", "production": false })
+        );
+    }
+
+    #[tokio::test]
+    async fn publish_pacts_creates_the_version_and_its_pacts() {
+        use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+        use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+
+        let (broker, url) = spawn_mock_broker(MockBrokerState::default()).await;
+        let client = PactBrokerClient::new(BrokerDetails::new(&url).with_retries(0));
+        let pact = serde_json::json!({
+            "consumer": { "name": "Foo" },
+            "provider": { "name": "Bar" },
+            "interactions": []
+        });
+
+        let result = client
+            .publish_pacts(&PublishPactsRequest {
+                pacticipant_name: "Foo".to_string(),
+                pacticipant_version_number: "1.2.3".to_string(),
+                branch: Some("main".to_string()),
+                tags: vec!["dev".to_string()],
+                contracts: vec![PublishContract::pact("Foo", "Bar", &pact)],
+                ..PublishPactsRequest::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(
+            result.notices.unwrap_or_default().iter().any(
+                |notice| notice.text == "Pact published for Foo version 1.2.3 and provider Bar"
+            )
+        );
+        let state = broker.snapshot();
+        let version = state.version("Foo", "1.2.3").unwrap();
+        assert_eq!(version.branch.as_deref(), Some("main"));
+        assert_eq!(version.tags, vec!["dev".to_string()]);
+        assert_eq!(state.pacts[0].content, pact);
+
+        let missing_version = client
+            .publish_pacts(&PublishPactsRequest {
+                pacticipant_name: "Foo".to_string(),
+                ..PublishPactsRequest::default()
+            })
+            .await;
+        assert!(matches!(
+            missing_version,
+            Err(PactBrokerError::ValidationError(_))
+        ));
+    }
+}
@@ -0,0 +1,462 @@
+//! Named Pact Broker profiles, stored in `~/.config/pact-broker-cli/config.toml`
+//!
+//! ```toml
+//! [profiles.staging]
+//! url = "https://broker.staging.example.com"
+//! token = "..."
+//! retries = 3
+//!
+//! [profiles.staging.custom_headers]
+//! X-Tenant = "acme"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use comfy_table::{Table, presets::UTF8_FULL};
+use pact_models::http_utils::HttpAuth;
+use serde::{Deserialize, Serialize};
+
+use super::PactBrokerError;
+use super::output::{self, OutputType};
+
+/// Environment variable that overrides the location of the config file
+pub const CONFIG_FILE_ENV: &str = "PACT_BROKER_CLI_CONFIG";
+
+/// Connection details for a Pact Broker
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Base URL of the Pact Broker
+    pub url: String,
+    /// Bearer token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Basic auth username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Basic auth password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Headers to send with every request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_headers: BTreeMap<String, String>,
+    /// CA certificate used to verify the broker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// Skip SSL certificate verification
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_ssl_verification: bool,
+    /// Number of times to retry failed requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u8>,
+}
+
+impl Profile {
+    /// Authentication for the profile, preferring the token over basic auth
+    pub fn auth(&self) -> HttpAuth {
+        match (&self.token, &self.username) {
+            (Some(token), _) => HttpAuth::Token(token.clone()),
+            (None, Some(username)) => HttpAuth::User(username.clone(), self.password.clone()),
+            (None, None) => HttpAuth::None,
+        }
+    }
+
+    fn auth_description(&self) -> &'static str {
+        match self.auth() {
+            HttpAuth::Token(_) => "token",
+            HttpAuth::User(_, _) => "basic",
+            _ => "none",
+        }
+    }
+}
+
+/// Contents of the config file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Command used to store and look up tokens saved by `login`, instead of the credentials file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_helper: Option<String>,
+    /// Profiles, keyed by name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Location of the config file, `$PACT_BROKER_CLI_CONFIG` if set, otherwise
+/// `$XDG_CONFIG_HOME/pact-broker-cli/config.toml` or `~/.config/pact-broker-cli/config.toml`
+pub fn config_path() -> PathBuf {
+    if let Ok(path) = std::env::var(CONFIG_FILE_ENV) {
+        return PathBuf::from(path);
+    }
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| {
+            std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .unwrap_or_else(|_| PathBuf::from(".config"));
+    config_dir.join("pact-broker-cli").join("config.toml")
+}
+
+/// Writes a file that only the current user can read, creating its directory (only accessible
+/// by the current user) if needed. The file is created with those permissions, so its contents
+/// are never readable by others, and an existing file has its permissions narrowed.
+pub(crate) fn write_private_file(path: &Path, contents: &str) -> Result<(), PactBrokerError> {
+    use std::io::Write;
+
+    let io_error = |err: std::io::Error| {
+        PactBrokerError::IoError(format!("Failed to write {}: {}", path.display(), err))
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(dir).map_err(io_error)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(io_error)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .map_err(io_error)?;
+    }
+    file.write_all(contents.as_bytes()).map_err(io_error)
+}
+
+impl Config {
+    /// Load the config file, returning an empty config if it does not exist
+    pub fn load_from(path: &Path) -> Result<Config, PactBrokerError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            PactBrokerError::IoError(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        toml::from_str(&contents).map_err(|err| {
+            PactBrokerError::ValidationError(vec![format!(
+                "{} is not a valid config file: {}",
+                path.display(),
+                err
+            )])
+        })
+    }
+
+    /// Write the config file, creating its directory if needed. As profiles can hold
+    /// credentials, the file is only readable by the current user.
+    pub fn save_to(&self, path: &Path) -> Result<(), PactBrokerError> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|err| PactBrokerError::ContentError(err.to_string()))?;
+        write_private_file(path, &contents)
+    }
+
+    /// Look up a profile by name
+    pub fn profile(&self, name: &str) -> Result<&Profile, PactBrokerError> {
+        self.profiles.get(name).ok_or_else(|| {
+            PactBrokerError::NotFound(format!(
+                "Profile '{}' is not defined in {}",
+                name,
+                config_path().display()
+            ))
+        })
+    }
+}
+
+/// The profile selected with `--profile` or `PACT_BROKER_PROFILE`, if any
+pub(crate) fn selected_profile(args: &ArgMatches) -> Result<Option<Profile>, PactBrokerError> {
+    match args.try_get_one::<String>("profile").ok().flatten() {
+        Some(name) => {
+            let config = Config::load_from(&config_path())?;
+            config.profile(name).cloned().map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+fn profile_from_args(args: &ArgMatches) -> Profile {
+    Profile {
+        url: args
+            .get_one::<String>("url")
+            .unwrap()
+            .trim_end_matches('/')
+            .to_string(),
+        token: args.get_one::<String>("token").cloned(),
+        username: args.get_one::<String>("username").cloned(),
+        password: args.get_one::<String>("password").cloned(),
+        custom_headers: args
+            .get_many::<String>("custom-header")
+            .map(|headers| {
+                headers
+                    .filter_map(|header| header.split_once(':'))
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .filter(|(name, value)| !name.is_empty() && !value.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ca_cert: args.get_one::<String>("ca-cert").cloned(),
+        skip_ssl_verification: args.get_flag("skip-ssl-verification"),
+        retries: args.get_one::<u8>("retries").copied(),
+    }
+}
+
+pub(crate) fn add_profile_to(path: &Path, args: &ArgMatches) -> Result<String, PactBrokerError> {
+    let name = args.get_one::<String>("name").unwrap();
+    let mut config = Config::load_from(path)?;
+    let existing = config
+        .profiles
+        .insert(name.clone(), profile_from_args(args));
+    config.save_to(path)?;
+    Ok(format!(
+        "✅ {} profile {} in {}",
+        if existing.is_some() {
+            "Updated"
+        } else {
+            "Added"
+        },
+        name,
+        path.display()
+    ))
+}
+
+pub(crate) fn remove_profile_from(
+    path: &Path,
+    args: &ArgMatches,
+) -> Result<String, PactBrokerError> {
+    let name = args.get_one::<String>("name").unwrap();
+    let mut config = Config::load_from(path)?;
+    config.profile(name)?;
+    config.profiles.remove(name);
+    config.save_to(path)?;
+    Ok(format!(
+        "✅ Removed profile {} from {}",
+        name,
+        path.display()
+    ))
+}
+
+pub(crate) fn list_profiles_from(
+    path: &Path,
+    args: &ArgMatches,
+) -> Result<String, PactBrokerError> {
+    let config = Config::load_from(path)?;
+    let output = OutputType::from_args(args);
+    if output.is_structured() {
+        // Credentials are left out, the same as the table
+        let profiles: Vec<serde_json::Value> = config
+            .profiles
+            .iter()
+            .map(|(name, profile)| {
+                serde_json::json!({
+                    "name": name,
+                    "url": profile.url,
+                    "auth": profile.auth_description()
+                })
+            })
+            .collect();
+        return output::render(&serde_json::Value::Array(profiles), &output);
+    }
+    if config.profiles.is_empty() {
+        return Ok(format!("No profiles defined in {}", path.display()));
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_header(vec!["NAME", "URL", "AUTH"]);
+    for (name, profile) in &config.profiles {
+        table.add_row(vec![
+            name.as_str(),
+            profile.url.as_str(),
+            profile.auth_description(),
+        ]);
+    }
+    Ok(table.to_string())
+}
+
+/// Add or replace a profile in the config file
+pub fn add_profile(args: &ArgMatches) -> Result<String, PactBrokerError> {
+    add_profile_to(&config_path(), args)
+}
+
+/// Remove a profile from the config file
+pub fn remove_profile(args: &ArgMatches) -> Result<String, PactBrokerError> {
+    remove_profile_from(&config_path(), args)
+}
+
+/// List the profiles in the config file, without their credentials
+pub fn list_profiles(args: &ArgMatches) -> Result<String, PactBrokerError> {
+    list_profiles_from(&config_path(), args)
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::subcommands::add_config_subcommand;
+    use pretty_assertions::assert_eq;
+
+    fn temp_config(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("pact-broker-cli-{}-{}", name, std::process::id()))
+            .join("config.toml")
+    }
+
+    fn subcommand_args(args: Vec<&str>) -> ArgMatches {
+        let matches = add_config_subcommand().get_matches_from(args);
+        matches.subcommand().unwrap().1.clone()
+    }
+
+    #[test]
+    fn add_update_and_remove_profiles() {
+        let path = temp_config("add-remove");
+
+        let message = add_profile_to(
+            &path,
+            &subcommand_args(vec![
+                "config",
+                "add-profile",
+                "staging",
+                "--url",
+                "https://broker.staging.example.com/",
+                "--token",
+                "abc",
+                "--custom-header",
+                "X-Tenant: acme",
+                "--retries",
+                "3",
+            ]),
+        )
+        .unwrap();
+        assert!(message.starts_with("✅ Added profile staging"));
+
+        let message = add_profile_to(
+            &path,
+            &subcommand_args(vec![
+                "config",
+                "add-profile",
+                "staging",
+                "--url",
+                "https://broker.staging.example.com",
+                "--username",
+                "user",
+                "--password",
+                "pass",
+            ]),
+        )
+        .unwrap();
+        assert!(message.starts_with("✅ Updated profile staging"));
+
+        add_profile_to(
+            &path,
+            &subcommand_args(vec![
+                "config",
+                "add-profile",
+                "production",
+                "--url",
+                "https://broker.example.com",
+            ]),
+        )
+        .unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(
+            config.profiles.keys().collect::<Vec<_>>(),
+            vec!["production", "staging"]
+        );
+        assert!(matches!(
+            config.profile("staging").unwrap().auth(),
+            HttpAuth::User(username, Some(password)) if username == "user" && password == "pass"
+        ));
+        assert_eq!(config.profile("staging").unwrap().retries, None);
+
+        let listing = list_profiles_from(
+            &path,
+            &subcommand_args(vec!["config", "list-profiles", "--output", "json"]),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&listing).unwrap(),
+            serde_json::json!([
+                { "name": "production", "url": "https://broker.example.com", "auth": "none" },
+                { "name": "staging", "url": "https://broker.staging.example.com", "auth": "basic" }
+            ])
+        );
+
+        remove_profile_from(
+            &path,
+            &subcommand_args(vec!["config", "remove-profile", "staging"]),
+        )
+        .unwrap();
+        let result = remove_profile_from(
+            &path,
+            &subcommand_args(vec!["config", "remove-profile", "staging"]),
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(matches!(result, Err(PactBrokerError::NotFound(_))));
+    }
+
+    #[test]
+    fn config_file_round_trips() {
+        let path = temp_config("round-trip");
+        let mut config = Config::default();
+        config.profiles.insert(
+            "pactflow".to_string(),
+            Profile {
+                url: "https://acme.pactflow.io".to_string(),
+                token: Some("abc".to_string()),
+                custom_headers: BTreeMap::from([("X-Team".to_string(), "payments".to_string())]),
+                ca_cert: Some("/etc/ssl/ca.pem".to_string()),
+                skip_ssl_verification: true,
+                retries: Some(2),
+                ..Profile::default()
+            },
+        );
+
+        config.save_to(&path).unwrap();
+        let loaded = Config::load_from(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, config);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn config_file_is_only_accessible_by_the_current_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_config("private");
+        Config::default().save_to(&path).unwrap();
+        // An existing file that others could read is narrowed when it is rewritten
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        Config::default().save_to(&path).unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let (file_mode, dir_mode) = (mode(&path), mode(path.parent().unwrap()));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(file_mode, 0o600);
+        assert_eq!(dir_mode, 0o700);
+    }
+
+    #[test]
+    fn missing_config_file_has_no_profiles() {
+        let config = Config::load_from(&temp_config("missing")).unwrap();
+
+        assert!(config.profiles.is_empty());
+        assert!(matches!(
+            config.profile("staging"),
+            Err(PactBrokerError::NotFound(_))
+        ));
+    }
+}
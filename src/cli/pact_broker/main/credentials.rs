@@ -0,0 +1,426 @@
+//! Tokens saved by `login`, keyed by Pact Broker URL.
+//!
+//! Tokens are kept in `credentials.toml` next to the config file, or handed to an external
+//! credential helper when one is configured with `credential_helper` in the config file or
+//! `PACT_BROKER_CREDENTIAL_HELPER`. Like a git credential helper, the helper is run with `get`,
+//! `store` or `erase` as its argument and is sent `key=value` lines on stdin:
+//!
+//! ```text
+//! url=https://broker.example.com
+//! token=abc123
+//! ```
+//!
+//! The `token` line is only sent to `store`. For `get`, the helper prints a `token=<token>` line
+//! if it has a token for the URL.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use clap::ArgMatches;
+use pact_models::http_utils::HttpAuth;
+use serde::{Deserialize, Serialize};
+
+use super::config::{Config, config_path, write_private_file};
+use super::utils::{get_connection_options, get_custom_headers, get_retries};
+use super::{HALClient, PactBrokerError};
+
+/// Environment variable naming a credential helper command
+pub const CREDENTIAL_HELPER_ENV: &str = "PACT_BROKER_CREDENTIAL_HELPER";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct StoredCredential {
+    token: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Credentials {
+    #[serde(default)]
+    brokers: BTreeMap<String, StoredCredential>,
+}
+
+/// Where `login` keeps tokens
+#[derive(Debug, Clone, PartialEq)]
+pub enum CredentialStore {
+    /// A TOML file, only readable by the current user
+    File(PathBuf),
+    /// An external credential helper command
+    Helper(String),
+}
+
+fn normalise_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+impl CredentialStore {
+    /// The helper from `--credential-helper`, `PACT_BROKER_CREDENTIAL_HELPER` or the config
+    /// file, falling back to `credentials.toml` next to the config file
+    pub fn configured(args: Option<&ArgMatches>) -> CredentialStore {
+        let helper = args
+            .and_then(|args| {
+                args.try_get_one::<String>("credential-helper")
+                    .ok()
+                    .flatten()
+            })
+            .cloned()
+            .or_else(|| std::env::var(CREDENTIAL_HELPER_ENV).ok())
+            .or_else(|| {
+                Config::load_from(&config_path())
+                    .ok()
+                    .and_then(|config| config.credential_helper)
+            })
+            .filter(|helper| !helper.trim().is_empty());
+        match helper {
+            Some(helper) => CredentialStore::Helper(helper),
+            None => CredentialStore::File(config_path().with_file_name("credentials.toml")),
+        }
+    }
+
+    /// The token saved for a Pact Broker, if there is one
+    pub fn get(&self, url: &str) -> Result<Option<String>, PactBrokerError> {
+        let url = normalise_url(url);
+        match self {
+            CredentialStore::File(path) => Ok(load_credentials(path)?
+                .brokers
+                .remove(&url)
+                .map(|credential| credential.token)),
+            CredentialStore::Helper(helper) => {
+                let output = run_helper(helper, "get", &url, None)?;
+                Ok(output
+                    .lines()
+                    .find_map(|line| line.strip_prefix("token="))
+                    .map(|token| token.trim().to_string())
+                    .filter(|token| !token.is_empty()))
+            }
+        }
+    }
+
+    /// Save the token for a Pact Broker, replacing any existing one
+    pub fn store(&self, url: &str, token: &str) -> Result<(), PactBrokerError> {
+        let url = normalise_url(url);
+        match self {
+            CredentialStore::File(path) => {
+                let mut credentials = load_credentials(path)?;
+                credentials.brokers.insert(
+                    url,
+                    StoredCredential {
+                        token: token.to_string(),
+                    },
+                );
+                save_credentials(path, &credentials)
+            }
+            CredentialStore::Helper(helper) => {
+                run_helper(helper, "store", &url, Some(token)).map(|_| ())
+            }
+        }
+    }
+
+    /// Remove the token for a Pact Broker, returning if there was one to remove. Helpers don't
+    /// report this, so are assumed to have removed one.
+    pub fn erase(&self, url: &str) -> Result<bool, PactBrokerError> {
+        let url = normalise_url(url);
+        match self {
+            CredentialStore::File(path) => {
+                let mut credentials = load_credentials(path)?;
+                let removed = credentials.brokers.remove(&url).is_some();
+                if removed {
+                    save_credentials(path, &credentials)?;
+                }
+                Ok(removed)
+            }
+            CredentialStore::Helper(helper) => {
+                run_helper(helper, "erase", &url, None).map(|_| true)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for CredentialStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialStore::File(path) => write!(f, "{}", path.display()),
+            CredentialStore::Helper(helper) => write!(f, "credential helper '{}'", helper),
+        }
+    }
+}
+
+fn load_credentials(path: &Path) -> Result<Credentials, PactBrokerError> {
+    if !path.exists() {
+        return Ok(Credentials::default());
+    }
+    let contents = std::fs::read_to_string(path).map_err(|err| {
+        PactBrokerError::IoError(format!("Failed to read {}: {}", path.display(), err))
+    })?;
+    toml::from_str(&contents).map_err(|err| {
+        PactBrokerError::ValidationError(vec![format!(
+            "{} is not a valid credentials file: {}",
+            path.display(),
+            err
+        )])
+    })
+}
+
+fn save_credentials(path: &Path, credentials: &Credentials) -> Result<(), PactBrokerError> {
+    let contents = toml::to_string_pretty(credentials)
+        .map_err(|err| PactBrokerError::ContentError(err.to_string()))?;
+    write_private_file(path, &contents)
+}
+
+fn run_helper(
+    helper: &str,
+    action: &str,
+    url: &str,
+    token: Option<&str>,
+) -> Result<String, PactBrokerError> {
+    let command_line = format!("{} {}", helper, action);
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", &command_line]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", &command_line]);
+        command
+    };
+    let helper_error = |err: std::io::Error| {
+        PactBrokerError::IoError(format!(
+            "Failed to run credential helper '{}': {}",
+            helper, err
+        ))
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(helper_error)?;
+
+    let mut input = format!("url={}\n", url);
+    if let Some(token) = token {
+        input.push_str(&format!("token={}\n", token));
+    }
+    input.push('\n');
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(helper_error)?;
+    }
+
+    let output = child.wait_with_output().map_err(helper_error)?;
+    if !output.status.success() {
+        return Err(PactBrokerError::IoError(format!(
+            "Credential helper '{}' failed to {} the token ({})",
+            helper, action, output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The token saved by `login` for a Pact Broker. Errors from the store are logged and ignored, so
+/// a broken helper does not stop commands that don't need a token.
+pub(crate) fn saved_token(url: &str) -> Option<String> {
+    CredentialStore::configured(None)
+        .get(url)
+        .map_err(|err| tracing::warn!("Could not read saved credentials: {}", err))
+        .ok()
+        .flatten()
+}
+
+fn read_token(args: &ArgMatches, broker_url: &str) -> Result<String, PactBrokerError> {
+    let token = if let Some(token) = args.get_one::<String>("broker-token") {
+        token.clone()
+    } else if args.get_flag("token-stdin") {
+        let mut token = String::new();
+        std::io::stdin()
+            .read_line(&mut token)
+            .map_err(|err| PactBrokerError::IoError(err.to_string()))?;
+        token
+    } else {
+        let term = console::Term::stderr();
+        term.write_str(&format!("Token for {}: ", broker_url))
+            .and_then(|_| term.read_secure_line())
+            .map_err(|err| PactBrokerError::IoError(format!("Failed to read token: {}", err)))?
+    };
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        Err(PactBrokerError::ValidationError(vec![
+            "No token was provided".to_string(),
+        ]))
+    } else {
+        Ok(token)
+    }
+}
+
+pub(crate) fn login_with_store(
+    args: &ArgMatches,
+    store: &CredentialStore,
+) -> Result<String, PactBrokerError> {
+    let broker_url = normalise_url(&super::utils::get_broker_url(args));
+    let token = read_token(args, &broker_url)?;
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let hal_client = HALClient::with_url(
+            &broker_url,
+            Some(HttpAuth::Token(token.clone())),
+            get_connection_options(args),
+            get_custom_headers(args),
+        )
+        .with_retry_count(get_retries(args));
+        hal_client.fetch("/").await.map_err(|err| {
+            PactBrokerError::ValidationError(vec![format!(
+                "The token could not be used to access {} - {}",
+                broker_url, err
+            )])
+        })
+    })?;
+
+    store.store(&broker_url, &token)?;
+    Ok(format!(
+        "✅ Logged in to {}, token saved to {}",
+        broker_url, store
+    ))
+}
+
+pub(crate) fn logout_with_store(
+    args: &ArgMatches,
+    store: &CredentialStore,
+) -> Result<String, PactBrokerError> {
+    let broker_url = normalise_url(&super::utils::get_broker_url(args));
+    if store.erase(&broker_url)? {
+        Ok(format!("✅ Logged out of {}", broker_url))
+    } else {
+        Ok(format!("ℹ️  No token was saved for {}", broker_url))
+    }
+}
+
+/// Check a token against the Pact Broker index, then save it for later commands
+pub fn login(args: &ArgMatches) -> Result<String, PactBrokerError> {
+    login_with_store(args, &CredentialStore::configured(Some(args)))
+}
+
+/// Remove the saved token for a Pact Broker
+pub fn logout(args: &ArgMatches) -> Result<String, PactBrokerError> {
+    logout_with_store(args, &CredentialStore::configured(Some(args)))
+}
+
+#[cfg(test)]
+mod credentials_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::subcommands::{add_login_subcommand, add_logout_subcommand};
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+    use pretty_assertions::assert_eq;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pact-broker-cli-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn file_store_saves_tokens_by_broker_url() {
+        let dir = temp_dir("credentials-file");
+        let store = CredentialStore::File(dir.join("credentials.toml"));
+
+        store.store("https://broker.example.com/", "abc").unwrap();
+        store.store("https://other.example.com", "def").unwrap();
+
+        assert_eq!(
+            store.get("https://broker.example.com").unwrap(),
+            Some("abc".to_string())
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("credentials.toml"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(store.erase("https://broker.example.com").unwrap());
+        assert!(!store.erase("https://broker.example.com").unwrap());
+        assert_eq!(store.get("https://broker.example.com").unwrap(), None);
+        assert_eq!(
+            store.get("https://other.example.com").unwrap(),
+            Some("def".to_string())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn helper_store_runs_the_helper_command() {
+        let dir = temp_dir("credentials-helper");
+        std::fs::create_dir_all(&dir).unwrap();
+        let helper = dir.join("helper.sh");
+        std::fs::write(
+            &helper,
+            format!(
+                "#!/bin/sh\ncat > {dir}/$1.input\nif [ \"$1\" = get ]; then echo token=from-helper; fi\n",
+                dir = dir.display()
+            ),
+        )
+        .unwrap();
+        let store = CredentialStore::Helper(format!("sh {}", helper.display()));
+
+        store.store("https://broker.example.com/", "abc").unwrap();
+        let token = store.get("https://broker.example.com").unwrap();
+        let stored = std::fs::read_to_string(dir.join("store.input")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(token, Some("from-helper".to_string()));
+        assert_eq!(stored, "url=https://broker.example.com\ntoken=abc\n\n");
+    }
+
+    #[test]
+    fn login_checks_the_token_and_saves_it() {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction("a request for the index with a token", "", |mut i| {
+                i.request
+                    .get()
+                    .path("/")
+                    .header("Authorization", "Bearer abc123");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "_links": {
+                            "pb:environments": { "href": term!("http:\\/\\/.*", "http://localhost/environments") }
+                        }
+                    }));
+                i
+            })
+            .start_mock_server(None, Some(config));
+        let mock_server_url = pact_broker_service.url().to_string();
+        let dir = temp_dir("login");
+        let store = CredentialStore::File(dir.join("credentials.toml"));
+
+        let matches = add_login_subcommand().get_matches_from(vec![
+            "login",
+            "-b",
+            mock_server_url.as_str(),
+            "--broker-token",
+            "abc123",
+        ]);
+        let message = login_with_store(&matches, &store).unwrap();
+        assert!(message.starts_with("✅ Logged in to"));
+        assert_eq!(
+            store.get(&mock_server_url).unwrap(),
+            Some("abc123".to_string())
+        );
+
+        let matches = add_logout_subcommand().get_matches_from(vec![
+            "logout",
+            "-b",
+            mock_server_url.as_str(),
+        ]);
+        logout_with_store(&matches, &store).unwrap();
+        let token = store.get(&mock_server_url).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(token, None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod record_deployment;
+pub mod record_deployments_from_manifest;
 pub mod record_release;
 pub mod record_support_ended;
 pub mod record_undeployment;
@@ -1,115 +1,65 @@
-use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
-
 use crate::cli::{
     pact_broker::main::{
-        HALClient, PactBrokerError,
-        utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+        PactBrokerError,
+        client::{PactBrokerClient, RecordDeploymentRequest},
+        output::{self, OutputType},
+        types::BrokerDetails,
     },
     utils,
 };
 
 pub fn record_deployment(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
-    let version = args.get_one::<String>("version");
-    let pacticipant = args.get_one::<String>("pacticipant");
-    let environment = args.get_one::<String>("environment");
-    let application_instance = args.get_one::<String>("application-instance");
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-                let hal_client: HALClient = HALClient::with_url(&broker_url, Some(auth.clone()), ssl_options.clone(), custom_headers.clone())
-                    .with_retry_count(get_retries(args));
-
-                let res = hal_client.clone()
-                    .fetch(
-                        &(broker_url.clone()
-                            + "/pacticipants/"
-                            + pacticipant.unwrap()
-                            + "/versions/"
-                            + version.unwrap()),
-                    )
-                    .await;
-
-                #[derive(Debug, Deserialize, Serialize)]
-                struct PacticipantVersions {
-                    _links: Links,
-                }
-
-                #[derive(Debug, Deserialize, Serialize)]
-                struct Links {
-                    #[serde(rename = "pb:record-deployment")]
-                    record_deployment: Vec<Link>,
-                }
-
-                #[derive(Debug, Deserialize, Serialize)]
-                struct Link {
-                    href: String,
-                    name: Option<String>,
-                    title: Option<String>,
-                    templated: Option<bool>,
-                }
-
-                match res {
-                    Ok(res) => {
-
-                        let result: Result<PacticipantVersions, serde_json::Error> = serde_json::from_value(res);
-                        match result {
-                            Ok(data) => {
-                            match data._links.record_deployment.iter().find(|x| x.name == Some(environment.unwrap().to_string())) {
-                                Some(link) => {
-                                    let link_record_deployment_href = &link.href;
-
-                                    // <- "POST /pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/deployed-versions/environment/c540ce64-5493-48c5-ab7c-28dae27b166b HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nContent-Type: application/json\r\nHost: localhost:9292\r\nContent-Length: 44\r\n\r\n"
-                                    // <- "{\"applicationInstance\":\"foo\",\"target\":\"foo\"}"
-
-
-                                    let mut payload = json!({});
-                                    if let Some(application_instance) = application_instance {
-                                        payload["target"] = serde_json::Value::String(application_instance.to_string());
-                                        payload["applicationInstance"] = serde_json::Value::String(application_instance.to_string());
-                                    }
-                                    let res: Result<Value, PactBrokerError> = hal_client.clone().post_json(&(link_record_deployment_href.clone()), &payload.to_string(), None).await;
-                                    let default_output = "text".to_string();
-                                    let output = args.get_one::<String>("output").unwrap_or(&default_output);
-                                    match res {
-                                        Ok(res) => {
-                                            let message = format!("✅ Recorded deployment of {} version {} to {} environment{} in the Pact Broker.", utils::GREEN.apply_to(pacticipant.unwrap()), utils::GREEN.apply_to(version.unwrap()),utils::GREEN.apply_to(environment.unwrap()), application_instance.map(|instance| format!(" (application instance {})", utils::GREEN.apply_to(instance))).unwrap_or_default());
-
-                                                if output == "pretty" {
-                                                    let json = serde_json::to_string_pretty(&res).unwrap();
-                                                    println!("{}", json);
-                                                    return Ok(json);
-                                                } else if output == "json" {
-                                                    let json = serde_json::to_string(&res).unwrap();
-                                                    println!("{}", json);
-                                                    return Ok(json);
-                                                } else if output == "id" {
-                                                    println!("{}", res["uuid"].to_string().trim_matches('"'));
-                                                }
-                                                else {
-                                                    println!("{}", message);
-                                                }
-                                            Ok(message)
-                                        }
-            Err(err) => Err(err),
-                                    }
-                                            }
-                                None => {
-                                    let message = format!("❌ Environment {} does not exist", utils::RED.apply_to(environment.unwrap()));
-                                    println!("{}", message);
-                                    Err(PactBrokerError::NotFound(message))
-                                }}
-                            }
-                            Err(err) => {
-                                let message = format!("❌ Failed to record deployment: {}", err);
-                                Err(PactBrokerError::ContentError(message))
-                            }
-                        }
-                    }
-            Err(err) => Err(err),
-            }})
+    let request = RecordDeploymentRequest {
+        pacticipant: args.get_one::<String>("pacticipant").unwrap().to_string(),
+        version: args.get_one::<String>("version").unwrap().to_string(),
+        environment: args.get_one::<String>("environment").unwrap().to_string(),
+        application_instance: args.get_one::<String>("application-instance").cloned(),
+    };
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let res = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(client.record_deployment_json(&request));
+
+    let res = match res {
+        Ok(res) => res,
+        Err(PactBrokerError::NotFound(message))
+            if message == format!("Environment {} does not exist", request.environment) =>
+        {
+            let message = format!(
+                "❌ Environment {} does not exist",
+                utils::RED.apply_to(&request.environment)
+            );
+            eprintln!("{}", message);
+            return Err(PactBrokerError::NotFound(message));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let output = OutputType::from_args(args);
+    let message = format!(
+        "✅ Recorded deployment of {} version {} to {} environment{} in the Pact Broker.",
+        utils::GREEN.apply_to(&request.pacticipant),
+        utils::GREEN.apply_to(&request.version),
+        utils::GREEN.apply_to(&request.environment),
+        request
+            .application_instance
+            .as_ref()
+            .map(|instance| format!(
+                " (application instance {})",
+                utils::GREEN.apply_to(instance)
+            ))
+            .unwrap_or_default()
+    );
+    if output == OutputType::Pretty {
+        let json = serde_json::to_string_pretty(&res).unwrap();
+        println!("{}", json);
+        return Ok(json);
+    } else if output.is_structured() {
+        return output::print(&res, &output);
+    } else {
+        println!("{}", message);
+    }
+    Ok(message)
 }
 
 #[cfg(test)]
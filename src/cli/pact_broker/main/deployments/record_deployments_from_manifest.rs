@@ -0,0 +1,639 @@
+//! Records the deployments described by Kubernetes manifests, such as the output of
+//! `helm template` or `kubectl get -o yaml`. Workloads are opted in with the
+//! `pact.io/pacticipant` annotation, and the version is taken from the container image.
+
+use std::collections::HashSet;
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cli::{
+    pact_broker::main::{
+        PactBrokerError,
+        client::{CurrentDeployment, PactBrokerClient, RecordDeploymentRequest},
+        output::{self, OutputType},
+        types::BrokerDetails,
+    },
+    utils,
+};
+
+/// Names the pacticipant a workload deploys
+pub const PACTICIPANT_ANNOTATION: &str = "pact.io/pacticipant";
+/// Selects the container whose image holds the version, for workloads with more than one
+pub const CONTAINER_ANNOTATION: &str = "pact.io/container";
+
+/// Where the application instance of a deployment comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstanceSource {
+    Namespace,
+    Cluster,
+    None,
+}
+
+/// Which part of the container image is used as the pacticipant version
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VersionSource {
+    Tag,
+    Digest,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestOptions {
+    pub version_from: VersionSource,
+    pub instance_from: InstanceSource,
+    /// The namespace of workloads that don't declare one
+    pub default_namespace: String,
+    pub cluster: Option<String>,
+}
+
+impl Default for ManifestOptions {
+    fn default() -> Self {
+        ManifestOptions {
+            version_from: VersionSource::Tag,
+            instance_from: InstanceSource::Namespace,
+            default_namespace: "default".to_string(),
+            cluster: None,
+        }
+    }
+}
+
+/// An annotated workload found in a manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestWorkload {
+    pub pacticipant: String,
+    pub version: String,
+    pub application_instance: Option<String>,
+    /// The kind and name of the workload, e.g. `Deployment/order-api`
+    pub workload: String,
+    pub image: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestRecordingSummary {
+    pub environment: String,
+    pub recorded: Vec<ManifestWorkload>,
+    /// Workloads that were already recorded as deployed
+    pub unchanged: Vec<ManifestWorkload>,
+    pub undeployed: Vec<CurrentDeployment>,
+}
+
+/// Extracts the version from a container image reference. The other part of the reference is
+/// used when the image has no tag (or digest).
+fn image_version(image: &str, version_from: VersionSource) -> Option<String> {
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image, None),
+    };
+    // A colon before the last slash separates the registry host from its port
+    let tag = name
+        .rsplit_once(':')
+        .map(|(_, tag)| tag)
+        .filter(|tag| !tag.contains('/'));
+    match version_from {
+        VersionSource::Tag => tag.or(digest),
+        VersionSource::Digest => digest.or(tag),
+    }
+    .map(str::to_string)
+}
+
+/// The pod template of a workload, if it has one. A Pod is its own template.
+fn pod_template(resource: &Value) -> Option<&Value> {
+    let template = match resource["kind"].as_str()? {
+        "Pod" => resource,
+        "CronJob" => &resource["spec"]["jobTemplate"]["spec"]["template"],
+        _ => &resource["spec"]["template"],
+    };
+    Some(template).filter(|template| template["spec"].is_object())
+}
+
+/// Annotations can be on the workload or its pod template
+fn annotation<'a>(resource: &'a Value, template: &'a Value, name: &str) -> Option<&'a str> {
+    resource["metadata"]["annotations"][name]
+        .as_str()
+        .or_else(|| template["metadata"]["annotations"][name].as_str())
+}
+
+fn workload(
+    resource: &Value,
+    options: &ManifestOptions,
+) -> Result<Option<ManifestWorkload>, PactBrokerError> {
+    // Other annotated resources, such as the Service in front of the workload, are skipped
+    let Some(template) = pod_template(resource) else {
+        return Ok(None);
+    };
+    let Some(pacticipant) = annotation(resource, template, PACTICIPANT_ANNOTATION) else {
+        return Ok(None);
+    };
+    let name = format!(
+        "{}/{}",
+        resource["kind"].as_str().unwrap_or_default(),
+        resource["metadata"]["name"].as_str().unwrap_or_default()
+    );
+    let containers = template["spec"]["containers"]
+        .as_array()
+        .ok_or_else(|| PactBrokerError::ContentError(format!("{} has no containers", name)))?;
+    let container = match annotation(resource, template, CONTAINER_ANNOTATION) {
+        Some(container) => containers
+            .iter()
+            .find(|c| c["name"] == container)
+            .ok_or_else(|| {
+                PactBrokerError::ContentError(format!(
+                    "{} has no container named {}",
+                    name, container
+                ))
+            })?,
+        None if containers.len() == 1 => &containers[0],
+        None => {
+            return Err(PactBrokerError::ContentError(format!(
+                "{} has {} containers, use the {} annotation to select the one to take the version from",
+                name,
+                containers.len(),
+                CONTAINER_ANNOTATION
+            )));
+        }
+    };
+    let image = container["image"].as_str().unwrap_or_default();
+    let version = image_version(image, options.version_from).ok_or_else(|| {
+        PactBrokerError::ContentError(format!(
+            "The image {} of {} has neither a tag nor a digest to use as the version",
+            image, name
+        ))
+    })?;
+    let application_instance = match options.instance_from {
+        InstanceSource::Namespace => Some(
+            resource["metadata"]["namespace"]
+                .as_str()
+                .unwrap_or(&options.default_namespace)
+                .to_string(),
+        ),
+        InstanceSource::Cluster => options.cluster.clone(),
+        InstanceSource::None => None,
+    };
+    Ok(Some(ManifestWorkload {
+        pacticipant: pacticipant.to_string(),
+        version,
+        application_instance,
+        workload: name,
+        image: image.to_string(),
+    }))
+}
+
+fn collect_workloads(
+    resource: &Value,
+    options: &ManifestOptions,
+    workloads: &mut Vec<ManifestWorkload>,
+) -> Result<(), PactBrokerError> {
+    if resource["kind"] == "List" {
+        for item in resource["items"].as_array().into_iter().flatten() {
+            collect_workloads(item, options, workloads)?;
+        }
+    } else if let Some(workload) = workload(resource, options)? {
+        workloads.push(workload);
+    }
+    Ok(())
+}
+
+/// Finds the annotated workloads in a stream of YAML documents
+pub fn workloads_from_manifest(
+    manifest: &str,
+    options: &ManifestOptions,
+) -> Result<Vec<ManifestWorkload>, PactBrokerError> {
+    workloads_from_manifests([manifest], options)
+}
+
+/// Finds the annotated workloads in several streams of YAML documents, such as one per file,
+/// checked together so that a pacticipant is only deployed at one version to an application
+/// instance across all of them
+pub fn workloads_from_manifests<'a>(
+    manifests: impl IntoIterator<Item = &'a str>,
+    options: &ManifestOptions,
+) -> Result<Vec<ManifestWorkload>, PactBrokerError> {
+    let mut workloads = vec![];
+    for manifest in manifests {
+        for document in serde_norway::Deserializer::from_str(manifest) {
+            let resource = Value::deserialize(document).map_err(|err| {
+                PactBrokerError::ContentError(format!("Failed to parse the manifest: {}", err))
+            })?;
+            collect_workloads(&resource, options, &mut workloads)?;
+        }
+    }
+
+    // The same pacticipant can only be deployed once to an application instance
+    let mut unique: Vec<ManifestWorkload> = vec![];
+    for workload in workloads {
+        match unique.iter().find(|w| {
+            w.pacticipant == workload.pacticipant
+                && w.application_instance == workload.application_instance
+        }) {
+            Some(existing) if existing.version != workload.version => {
+                return Err(PactBrokerError::ValidationError(vec![format!(
+                    "{} is deployed as version {} by {} and version {} by {}",
+                    workload.pacticipant,
+                    existing.version,
+                    existing.workload,
+                    workload.version,
+                    workload.workload
+                )]));
+            }
+            Some(_) => {}
+            None => unique.push(workload),
+        }
+    }
+    Ok(unique)
+}
+
+/// Records the deployment of each workload not already recorded as deployed. Unless
+/// `undeploy_missing` is false, the deployments of pacticipants absent from the manifest are
+/// recorded as undeployed, but only for the application instances the manifest covers.
+pub async fn record_manifest_deployments(
+    client: &PactBrokerClient,
+    environment: &str,
+    workloads: Vec<ManifestWorkload>,
+    undeploy_missing: bool,
+) -> Result<ManifestRecordingSummary, PactBrokerError> {
+    let current = client
+        .currently_deployed_versions(environment, None)
+        .await?;
+    let mut summary = ManifestRecordingSummary {
+        environment: environment.to_string(),
+        ..ManifestRecordingSummary::default()
+    };
+
+    let instances: HashSet<Option<String>> = workloads
+        .iter()
+        .map(|w| w.application_instance.clone())
+        .collect();
+    let deployed: HashSet<(String, Option<String>)> = workloads
+        .iter()
+        .map(|w| (w.pacticipant.clone(), w.application_instance.clone()))
+        .collect();
+
+    for workload in workloads {
+        if current.iter().any(|c| {
+            c.pacticipant == workload.pacticipant
+                && c.version == workload.version
+                && c.application_instance == workload.application_instance
+        }) {
+            summary.unchanged.push(workload);
+            continue;
+        }
+        client
+            .record_deployment(&RecordDeploymentRequest {
+                pacticipant: workload.pacticipant.clone(),
+                version: workload.version.clone(),
+                environment: environment.to_string(),
+                application_instance: workload.application_instance.clone(),
+            })
+            .await?;
+        summary.recorded.push(workload);
+    }
+
+    if undeploy_missing {
+        for deployment in current {
+            let key = (
+                deployment.pacticipant.clone(),
+                deployment.application_instance.clone(),
+            );
+            if instances.contains(&deployment.application_instance) && !deployed.contains(&key) {
+                client.end_deployment(&deployment).await?;
+                summary.undeployed.push(deployment);
+            }
+        }
+    }
+    Ok(summary)
+}
+
+fn read_manifest(path: &str) -> Result<String, PactBrokerError> {
+    let mut manifest = String::new();
+    let res = if path == "-" {
+        std::io::stdin().read_to_string(&mut manifest).map(|_| ())
+    } else {
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut manifest).map(|_| ()))
+    };
+    res.map_err(|err| {
+        PactBrokerError::IoError(format!("Failed to read manifest {}: {}", path, err))
+    })?;
+    Ok(manifest)
+}
+
+fn describe(pacticipant: &str, version: &str, application_instance: &Option<String>) -> String {
+    let mut description = format!(
+        "{} version {}",
+        utils::GREEN.apply_to(pacticipant),
+        utils::GREEN.apply_to(version)
+    );
+    if let Some(application_instance) = application_instance {
+        description.push_str(&format!(
+            " (application instance {})",
+            utils::GREEN.apply_to(application_instance)
+        ));
+    }
+    description
+}
+
+pub fn record_deployments_from_manifest(
+    args: &clap::ArgMatches,
+) -> Result<ManifestRecordingSummary, PactBrokerError> {
+    let environment = args.get_one::<String>("environment").unwrap();
+    let options = ManifestOptions {
+        version_from: match args.get_one::<String>("version-from").map(String::as_str) {
+            Some("digest") => VersionSource::Digest,
+            _ => VersionSource::Tag,
+        },
+        instance_from: match args.get_one::<String>("instance-from").map(String::as_str) {
+            Some("cluster") => InstanceSource::Cluster,
+            Some("none") => InstanceSource::None,
+            _ => InstanceSource::Namespace,
+        },
+        default_namespace: args
+            .get_one::<String>("namespace")
+            .cloned()
+            .unwrap_or_else(|| "default".to_string()),
+        cluster: args.get_one::<String>("cluster").cloned(),
+    };
+    let manifests = args
+        .get_many::<String>("file")
+        .unwrap()
+        .map(|path| read_manifest(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let workloads = workloads_from_manifests(manifests.iter().map(String::as_str), &options)?;
+    if workloads.is_empty() {
+        return Err(PactBrokerError::ValidationError(vec![format!(
+            "No workloads with the {} annotation were found",
+            PACTICIPANT_ANNOTATION
+        )]));
+    }
+    let output = OutputType::from_args(args);
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+
+    let summary = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(record_manifest_deployments(
+            &client,
+            environment,
+            workloads,
+            !args.get_flag("no-undeploy"),
+        ))?;
+
+    if output.is_structured() {
+        output::print(&serde_json::to_value(&summary).unwrap(), &output)?;
+        return Ok(summary);
+    }
+    for workload in &summary.recorded {
+        println!(
+            "✅ Recorded deployment of {} to {} environment from {}",
+            describe(
+                &workload.pacticipant,
+                &workload.version,
+                &workload.application_instance
+            ),
+            utils::GREEN.apply_to(environment),
+            workload.workload
+        );
+    }
+    for workload in &summary.unchanged {
+        println!(
+            "{} is already recorded as deployed",
+            describe(
+                &workload.pacticipant,
+                &workload.version,
+                &workload.application_instance
+            )
+        );
+    }
+    for deployment in &summary.undeployed {
+        println!(
+            "✅ Recorded undeployment of {} from {} environment",
+            describe(
+                &deployment.pacticipant,
+                &deployment.version,
+                &deployment.application_instance
+            ),
+            utils::GREEN.apply_to(environment)
+        );
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod record_deployments_from_manifest_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{
+        InstanceSource, ManifestOptions, ManifestWorkload, VersionSource, image_version,
+        record_manifest_deployments, workloads_from_manifest, workloads_from_manifests,
+    };
+    use crate::cli::pact_broker::main::client::PactBrokerClient;
+    use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+    use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+    use crate::cli::pact_broker::main::types::BrokerDetails;
+
+    const MANIFEST: &str = r#"
+# Source: orders/templates/deployment.yaml
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: order-api
+  namespace: shop
+  annotations:
+    pact.io/pacticipant: order-api
+spec:
+  template:
+    spec:
+      containers:
+        - name: order-api
+          image: registry.example.com:5000/shop/order-api:1.4.2
+---
+apiVersion: batch/v1
+kind: CronJob
+metadata:
+  name: billing
+spec:
+  jobTemplate:
+    spec:
+      template:
+        metadata:
+          annotations:
+            pact.io/pacticipant: billing
+            pact.io/container: billing
+        spec:
+          containers:
+            - name: istio-proxy
+              image: istio/proxyv2:1.20.0
+            - name: billing
+              image: billing@sha256:abc123
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: order-api
+  annotations:
+    pact.io/pacticipant: order-api
+---
+"#;
+
+    fn workload(pacticipant: &str, version: &str, instance: Option<&str>) -> ManifestWorkload {
+        ManifestWorkload {
+            pacticipant: pacticipant.to_string(),
+            version: version.to_string(),
+            application_instance: instance.map(str::to_string),
+            workload: format!("Deployment/{}", pacticipant),
+            image: format!("{}:{}", pacticipant, version),
+        }
+    }
+
+    #[test]
+    fn takes_the_version_from_the_image_tag_or_digest() {
+        assert_eq!(
+            image_version("registry:5000/foo:1.2", VersionSource::Tag),
+            Some("1.2".to_string())
+        );
+        assert_eq!(image_version("registry:5000/foo", VersionSource::Tag), None);
+        assert_eq!(
+            image_version("foo:1.2@sha256:abc", VersionSource::Digest),
+            Some("sha256:abc".to_string())
+        );
+        assert_eq!(
+            image_version("foo@sha256:abc", VersionSource::Tag),
+            Some("sha256:abc".to_string())
+        );
+    }
+
+    #[test]
+    fn finds_the_annotated_workloads() {
+        let workloads = workloads_from_manifest(MANIFEST, &ManifestOptions::default()).unwrap();
+
+        assert_eq!(
+            workloads
+                .iter()
+                .map(|w| (
+                    w.pacticipant.as_str(),
+                    w.version.as_str(),
+                    w.application_instance.as_deref(),
+                    w.workload.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("order-api", "1.4.2", Some("shop"), "Deployment/order-api"),
+                (
+                    "billing",
+                    "sha256:abc123",
+                    Some("default"),
+                    "CronJob/billing"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn uses_the_cluster_as_the_application_instance() {
+        let options = ManifestOptions {
+            instance_from: InstanceSource::Cluster,
+            cluster: Some("eu-west".to_string()),
+            ..ManifestOptions::default()
+        };
+        let workloads = workloads_from_manifest(MANIFEST, &options).unwrap();
+
+        assert!(
+            workloads
+                .iter()
+                .all(|w| w.application_instance.as_deref() == Some("eu-west"))
+        );
+    }
+
+    #[test]
+    fn requires_the_container_annotation_for_workloads_with_several_containers() {
+        let manifest = MANIFEST.replace("pact.io/container: billing", "team: billing");
+
+        let error = workloads_from_manifest(&manifest, &ManifestOptions::default()).unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("CronJob/billing has 2 containers")
+        );
+    }
+
+    #[test]
+    fn checks_the_versions_of_a_pacticipant_across_manifests() {
+        let other = MANIFEST.replace("order-api:1.4.2", "order-api:1.5.0");
+
+        let error =
+            workloads_from_manifests([MANIFEST, other.as_str()], &ManifestOptions::default())
+                .unwrap_err();
+        assert!(error.to_string().contains(
+            "order-api is deployed as version 1.4.2 by Deployment/order-api and version 1.5.0 by Deployment/order-api"
+        ));
+
+        let workloads =
+            workloads_from_manifests([MANIFEST, MANIFEST], &ManifestOptions::default()).unwrap();
+        assert_eq!(workloads.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn records_new_deployments_and_undeploys_missing_pacticipants() {
+        let state: MockBrokerState = serde_json::from_value(json!({
+            "environments": [{ "uuid": "1", "name": "production", "production": true }],
+            "pacticipants": [{ "name": "order-api" }, { "name": "billing" }, { "name": "legacy" }, { "name": "search" }],
+            "versions": [
+                { "pacticipant": "order-api", "number": "1.4.1" },
+                { "pacticipant": "order-api", "number": "1.4.2" },
+                { "pacticipant": "billing", "number": "7" },
+                { "pacticipant": "legacy", "number": "1" },
+                { "pacticipant": "search", "number": "3" }
+            ],
+            "deployedVersions": [
+                { "pacticipant": "order-api", "version": "1.4.1", "environment": "1", "applicationInstance": "shop" },
+                { "pacticipant": "billing", "version": "7", "environment": "1", "applicationInstance": "shop" },
+                { "pacticipant": "legacy", "version": "1", "environment": "1", "applicationInstance": "shop" },
+                { "pacticipant": "search", "version": "3", "environment": "1", "applicationInstance": "search" }
+            ]
+        }))
+        .unwrap();
+        let (broker, url) = spawn_mock_broker(state).await;
+        let client = PactBrokerClient::new(BrokerDetails::new(&url).with_retries(0));
+
+        let summary = record_manifest_deployments(
+            &client,
+            "production",
+            vec![
+                workload("order-api", "1.4.2", Some("shop")),
+                workload("billing", "7", Some("shop")),
+            ],
+            true,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            summary.recorded,
+            vec![workload("order-api", "1.4.2", Some("shop"))]
+        );
+        assert_eq!(
+            summary.unchanged,
+            vec![workload("billing", "7", Some("shop"))]
+        );
+        assert_eq!(
+            summary
+                .undeployed
+                .iter()
+                .map(|d| d.pacticipant.as_str())
+                .collect::<Vec<_>>(),
+            vec!["legacy"]
+        );
+        let mut current = broker
+            .snapshot()
+            .deployed_versions
+            .into_iter()
+            .filter(|d| d.current)
+            .map(|d| format!("{} {}", d.pacticipant, d.version))
+            .collect::<Vec<_>>();
+        current.sort();
+        assert_eq!(current, vec!["billing 7", "order-api 1.4.2", "search 3"]);
+    }
+}
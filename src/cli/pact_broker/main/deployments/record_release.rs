@@ -1,116 +1,55 @@
-use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
-
 use crate::cli::{
     pact_broker::main::{
-        HALClient, PactBrokerError,
-        utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+        PactBrokerError,
+        client::{PactBrokerClient, RecordReleaseRequest},
+        output::{self, OutputType},
+        types::BrokerDetails,
     },
     utils,
 };
 
 pub fn record_release(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
-    // 1. Check broker index link for connection
-    // 2, Check version exists "GET /pacticipants/{pacticipant}/versions/{versions}?
-    // "{\"number\":\"5556b8149bf8bac76bc30f50a8a2dd4c22c85f30\",\"createdAt\":\"2024-03-17T07:11:23+00:00\",\"_embedded\":{\"branchVersions\":[{\"name\":\"main\",\"latest\":true,\"_links\":{\"self\":{\"title\":\"Branch version\",\"name\":\"main\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/branches/main/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30\"}}}],\"tags\":[{\"name\":\"main\",\"_links\":{\"self\":{\"title\":\"Tag\",\"name\":\"main\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/tags/main\"}}}]},\"_links\":{\"self\":{\"title\":\"Version\",\"name\":\"5556b8149bf8bac76bc30f50a8a2dd4c22c85f30\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30\"},\"pb:pacticipant\":{\"title\":\"Pacticipant\",\"name\":\"Example App\",\"href\":\"http://localhost:9292/pacticipants/Example%20App\"},\"pb:tag\":{\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/tags/{tag}\",\"title\":\"Get, create or delete a tag for this pacticipant version\",\"templated\":true},\"pb:latest-verification-results-where-pacticipant-is-consumer\":{\"title\":\"Latest verification results for consumer version\",\"href\":\"http://localhost:9292/verification-results/consumer/Example%20App/version/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/latest\"},\"pb:pact-versions\":[{\"title\":\"Pact\",\"name\":\"Pact between Example App (5556b8149bf8bac76bc30f50a8a2dd4c22c85f30) and Example API\",\"href\":\"http://localhost:9292/pacts/provider/Example%20API/consumer/Example%20App/version/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30\"}],\"pb:record-deployment\":[{\"title\":\"Record deployment to Production\",\"name\":\"production\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/deployed-versions/environment/c540ce64-5493-48c5-ab7c-28dae27b166b\"},{\"title\":\"Record deployment to Test\",\"name\":\"test\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/deployed-versions/environment/cf7dcfdb-3645-4b16-b2f7-7ecb4b6045e0\"}],\"pb:record-release\":[{\"title\":\"Record release to Production\",\"name\":\"production\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/released-versions/environment/c540ce64-5493-48c5-ab7c-28dae27b166b\"},{\"title\":\"Record release to Test\",\"name\":\"test\",\"href\":\"http://localhost:9292/pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/released-versions/environment/cf7dcfdb-3645-4b16-b2f7-7ecb4b6045e0\"}],\"curies\":[{\"name\":\"pb\",\"href\":\"http://localhost:9292/doc/{rel}?context=version\",\"templated\":true}]}}"
-    // 3. Find the pb:record-release link for the specified environment
-    // 4. Send a POST request to the pb:record-release link with an empty payload
-    // 5. Handle the response
-
-    let version = args.get_one::<String>("version");
-    let pacticipant = args.get_one::<String>("pacticipant");
-    let environment = args.get_one::<String>("environment");
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-            let hal_client: HALClient = HALClient::with_url(&broker_url, Some(auth.clone()),ssl_options.clone(), custom_headers.clone())
-                .with_retry_count(get_retries(args));
-            // todo add trim_end_matches to broker url arg parse
-            let res = hal_client.clone()
-                .fetch(
-                    &(broker_url.clone()
-                        + "/pacticipants/"
-                        + pacticipant.unwrap()
-                        + "/versions/"
-                        + version.unwrap()),
-                )
-                .await;
-            #[derive(Debug, Deserialize, Serialize)]
-            struct PacticipantVersions {
-                _links: Links,
-            }
-
-            #[derive(Debug, Deserialize, Serialize)]
-            struct Links {
-                #[serde(rename = "pb:record-release")]
-                record_release: Vec<Link>,
-            }
-
-            #[derive(Debug, Deserialize, Serialize)]
-            struct Link {
-                href: String,
-                name: Option<String>,
-                title: Option<String>,
-                templated: Option<bool>,
-            }
-
-            match res {
-                Ok(res) => {
-
-                    let result: Result<PacticipantVersions, serde_json::Error> = serde_json::from_value(res);
-                    match result {
-                        Ok(data) => {
-                        match data._links.record_release.iter().find(|x| x.name == Some(environment.unwrap().to_string())) {
-                            Some(link) => {
-                                let record_release_href = &link.href;
-
-                                // println!("✅ Found environment {} with {}", utils::GREEN.apply_to(environment.unwrap()), utils::GREEN.apply_to(link_record_deployment_href.clone()));
-
-                                // <- "POST /pacticipants/Example%20App/versions/5556b8149bf8bac76bc30f50a8a2dd4c22c85f30/deployed-versions/environment/c540ce64-5493-48c5-ab7c-28dae27b166b HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nContent-Type: application/json\r\nHost: localhost:9292\r\nContent-Length: 44\r\n\r\n"
-                                // <- "{\"applicationInstance\":\"foo\",\"target\":\"foo\"}"
-
-                                let payload = json!({});
-                                let res: Result<Value, PactBrokerError> = hal_client.clone().post_json(&(record_release_href.clone()), &payload.to_string(), None).await;
-                                let default_output = "text".to_string();
-                                let output = args.get_one::<String>("output").unwrap_or(&default_output);
-                                match res {
-                                    Ok(res) => {
-                                        let message = format!("✅ Recorded release of {} version {} to {} environment in the Pact Broker.", utils::GREEN.apply_to(pacticipant.unwrap()), utils::GREEN.apply_to(version.unwrap()),utils::GREEN.apply_to(environment.unwrap()));
-                                            if output == "pretty" {
-                                                let json = serde_json::to_string_pretty(&res).unwrap();
-                                                println!("{}", json);
-                                            } else if output == "json" {
-                                                println!("{}", serde_json::to_string(&res).unwrap());
-                                            } else if output == "id" {
-                                                println!("{}", res["uuid"].to_string().trim_matches('"'));
-                                            }
-                                            else {
-                                                println!("{}", message);
-
-                                            }
-                                        Ok(message.to_string())
-                                    }
-            Err(err) => Err(err),
-                                }
-                                        }
-                            None => {
-                                let message = format!("❌ Environment {} does not exist", utils::RED.apply_to(environment.unwrap()));
-                                println!("{}", message);
-                                Err(PactBrokerError::NotFound(message))
-                            }}
-                        }
-                        Err(err) => {
-                            let message = format!("❌ Failed to record release: {}", err);
-                            Err(PactBrokerError::ContentError(message))
-                        }
-                    }
-                }
-            Err(err) => {
-                Err(err)
-            }
-        }})
+    let request = RecordReleaseRequest {
+        pacticipant: args.get_one::<String>("pacticipant").unwrap().to_string(),
+        version: args.get_one::<String>("version").unwrap().to_string(),
+        environment: args.get_one::<String>("environment").unwrap().to_string(),
+    };
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let res = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(client.record_release_json(&request));
+
+    let res = match res {
+        Ok(res) => res,
+        Err(PactBrokerError::NotFound(message))
+            if message == format!("Environment {} does not exist", request.environment) =>
+        {
+            let message = format!(
+                "❌ Environment {} does not exist",
+                utils::RED.apply_to(&request.environment)
+            );
+            println!("{}", message);
+            return Err(PactBrokerError::NotFound(message));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let output = OutputType::from_args(args);
+    let message = format!(
+        "✅ Recorded release of {} version {} to {} environment in the Pact Broker.",
+        utils::GREEN.apply_to(&request.pacticipant),
+        utils::GREEN.apply_to(&request.version),
+        utils::GREEN.apply_to(&request.environment)
+    );
+    if output == OutputType::Pretty {
+        let json = serde_json::to_string_pretty(&res).unwrap();
+        println!("{}", json);
+    } else if output.is_structured() {
+        output::print(&res, &output)?;
+    } else {
+        println!("{}", message);
+    }
+    Ok(message)
 }
 
 #[cfg(test)]
@@ -1,229 +1,52 @@
-use serde_json::{Value, json};
+use serde_json::Value;
 
 use crate::cli::{
     pact_broker::main::{
-        HALClient, PactBrokerError,
-        utils::{
-            follow_broker_relation, get_auth, get_broker_relation, get_broker_url,
-            get_custom_headers, get_retries, get_ssl_options,
-        },
+        PactBrokerError,
+        client::{PactBrokerClient, RecordUndeploymentRequest},
+        output::{self, OutputType},
+        types::BrokerDetails,
     },
     utils,
 };
 
 pub fn record_undeployment(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
-    // 1. Check broker index link for connection
-    // <- "GET /? HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nHost: localhost:9292\r\n\r\n"
-    // -> "HTTP/1.1 200 OK\r\n"
-    // 2. Call environments and check the specified enviroment exists, get the environment link
-    // <- "GET /environments? HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nHost: localhost:9292\r\n\r\n"
-    // -> "HTTP/1.1 200 OK\r\n"
-    // 3. Call the environment link and check the specified version exists, get the version link
-    // <- "GET /environments/c540ce64-5493-48c5-ab7c-28dae27b166b? HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nHost: localhost:9292\r\n\r\n"
-    // -> "HTTP/1.1 200 OK\r\n"
-    // 4. Call the /environments/c540ce64-5493-48c5-ab7c-28dae27b166b/deployed-versions/currently-deployed?pacticipant=Example+App link, and check our app is currently deployed
-    // <- "GET /environments/c540ce64-5493-48c5-ab7c-28dae27b166b/deployed-versions/currently-deployed?pacticipant=Example+App HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nHost: localhost:9292\r\n\r\n"
-    // -> "HTTP/1.1 200 OK\r\n"
-    // 5. perform a patch request to the /environments/c540ce64-5493-48c5-ab7c-28dae27b166b/deployed-versions/9b756f93-19a2-4ca7-ae36-c0b917ac1f21 link to set currentlyDeployed to false
-    // <- "PATCH /deployed-versions/9b756f93-19a2-4ca7-ae36-c0b917ac1f21 HTTP/1.1\r\nAccept: application/hal+json\r\nUser-Agent: Ruby\r\nContent-Type: application/merge-patch+json\r\nHost: localhost:9292\r\nContent-Length: 27\r\n\r\n"
-    // <- "{\"currentlyDeployed\":false}"
-
-    let pacticipant = args.get_one::<String>("pacticipant").unwrap();
-    let environment = args.get_one::<String>("environment").unwrap();
-    let application_instance = args.get_one::<String>("application-instance");
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let hal_client: HALClient = HALClient::with_url(&broker_url, Some(auth.clone()), ssl_options.clone(), custom_headers.clone())
-            .with_retry_count(get_retries(args));
-
-        #[derive(Debug, serde::Deserialize)]
-        struct Environment {
-            uuid: String,
-            name: String,
-        }
-
-        let pb_environments_href_path = get_broker_relation(
-            hal_client.clone(),
-            "pb:environments".to_string(),
-            broker_url.to_string(),
-        )
-        .await?;
-
-        let environments_res = follow_broker_relation(
-            hal_client.clone(),
-            "pb:environments".to_string(),
-            pb_environments_href_path,
-        )
-        .await;
-
-        let environments_response = match environments_res {
-            Ok(response) => response,
-            Err(err) => return Err(err),
-        };
-
-        let environments: Vec<Environment> = environments_response["_embedded"]["environments"]
-            .as_array()
-            .ok_or_else(|| PactBrokerError::ContentError("Missing environments in response".to_string()))?
-            .iter()
-            .map(|env| serde_json::from_value(env.clone()))
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|err| {
-                PactBrokerError::ContentError(format!("Failed to parse environment response: {err}"))
-            })?;
-
-        let environment_uuid = environments
-            .iter()
-            .find(|env| env.name == *environment)
-            .map(|env| env.uuid.clone())
-            .ok_or_else(|| {
-                PactBrokerError::NotFound(format!("Environment {} not found", environment))
-            })?;
-
-        let environment_result = hal_client
-            .clone()
-            .fetch(&(broker_url.clone() + "/environments/" + &environment_uuid))
-            .await?;
-
-        let currently_deployed_link = relation_href(
-            &environment_result,
-            "pb:currently-deployed-deployed-versions",
-            Some("pb:currently-deployed-versions"),
-        )
-        .ok_or_else(|| {
-            PactBrokerError::LinkError(
-                "This version of the Pact Broker does not support recording undeployments. Please upgrade to version 2.80.0 or later.".to_string(),
-            )
-        })?;
-
-        let pacticipant_query = format!("?pacticipant={}", urlencoding::encode(pacticipant));
-        let deployed_versions_response = hal_client
-            .clone()
-            .fetch(&(currently_deployed_link + &pacticipant_query))
-            .await?;
-
-        let deployed_versions = deployed_versions_response["_embedded"]["deployedVersions"]
-            .as_array()
-            .ok_or_else(|| {
-                PactBrokerError::ContentError("No deployed versions could be found in response".to_string())
-            })?;
-
-        let pacticipant_deployments: Vec<&Value> = deployed_versions
-            .iter()
-            .filter(|deployed_version| {
-                deployed_version["_embedded"]["pacticipant"]["name"].as_str() == Some(pacticipant)
-            })
-            .collect();
-
-        if pacticipant_deployments.is_empty() {
-            return Err(PactBrokerError::NotFound(format!(
-                "{} is not currently deployed to {} environment. Cannot record undeployment.",
-                pacticipant, environment
-            )));
-        }
-
-        let deployments_for_instance: Vec<&Value> = pacticipant_deployments
-            .iter()
-            .copied()
-            .filter(|deployment| match application_instance {
-                Some(instance) => deployed_application_instance(deployment)
-                    .map(|deployed_instance| deployed_instance == *instance)
-                    .unwrap_or(false),
-                None => {
-                    deployment["applicationInstance"].is_null() && deployment["target"].is_null()
-                }
-            })
-            .collect();
-
-        if deployments_for_instance.is_empty() {
-            let potential_application_instances: Vec<Option<String>> = pacticipant_deployments
-                .iter()
-                .map(|deployment| deployed_application_instance(deployment))
-                .collect();
-
-            if let Some(instance) = application_instance {
-                let should_omit_instance = potential_application_instances.iter().any(|value| value.is_none());
-                let known_instances: Vec<String> = potential_application_instances
-                    .iter()
-                    .flatten()
-                    .cloned()
-                    .collect();
-                let mut suggestions = Vec::new();
-                if should_omit_instance {
-                    suggestions.push("omit the application instance".to_string());
-                }
-                if !known_instances.is_empty() {
-                    suggestions.push(format!(
-                        "specify one of the following application instances to record the undeployment from: {}",
-                        known_instances.join(", ")
-                    ));
-                }
-
-                return Err(PactBrokerError::NotFound(format!(
-                    "{} is not currently deployed to application instance '{}' in {} environment.{}",
-                    pacticipant,
-                    instance,
-                    environment,
-                    if suggestions.is_empty() {
-                        String::new()
-                    } else {
-                        format!(" Please {}.", suggestions.join(" or "))
-                    }
-                )));
-            }
-
-            let known_instances: Vec<String> = potential_application_instances
-                .iter()
-                .flatten()
-                .cloned()
-                .collect();
-            if !known_instances.is_empty() {
-                return Err(PactBrokerError::NotFound(format!(
-                    "Please specify one of the following application instances to record the undeployment from: {}",
-                    known_instances.join(", ")
-                )));
-            }
-
-            return Err(PactBrokerError::NotFound(format!(
-                "{} is not currently deployed to {} environment. Cannot record undeployment.",
-                pacticipant, environment
-            )));
-        }
-
-        for deployed_version in deployments_for_instance {
-            let self_href = deployed_version["_links"]["self"]["href"]
-                .as_str()
-                .ok_or_else(|| {
-                    PactBrokerError::ContentError(
-                        "No self link found for currently deployed version".to_string(),
-                    )
-                })?;
-
-            let mut payload = json!({});
-            payload["currentlyDeployed"] = serde_json::Value::Bool(false);
-            hal_client
-                .clone()
-                .patch_json(self_href, &payload.to_string(), None)
-                .await?;
-        }
-
-        println!(
-            "✅ ♻️ Undeployed {} from {} environment{}",
-            utils::GREEN.apply_to(pacticipant),
-            utils::GREEN.apply_to(environment),
-            application_instance
-                .map(|instance| format!(" (application instance {})", utils::GREEN.apply_to(instance)))
-                .unwrap_or_default()
-        );
-
-        Ok("Undeployment recorded successfully".to_string())
-    })
+    let request = RecordUndeploymentRequest {
+        pacticipant: args.get_one::<String>("pacticipant").unwrap().to_string(),
+        environment: args.get_one::<String>("environment").unwrap().to_string(),
+        application_instance: args.get_one::<String>("application-instance").cloned(),
+    };
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let undeployed = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(client.record_undeployment_json(&request))?;
+
+    let output = OutputType::from_args(args);
+    if output.is_structured() {
+        return output::print(&Value::Array(undeployed), &output);
+    }
+    println!(
+        "✅ ♻️ Undeployed {} from {} environment{}",
+        utils::GREEN.apply_to(&request.pacticipant),
+        utils::GREEN.apply_to(&request.environment),
+        request
+            .application_instance
+            .as_ref()
+            .map(|instance| format!(
+                " (application instance {})",
+                utils::GREEN.apply_to(instance)
+            ))
+            .unwrap_or_default()
+    );
+
+    Ok("Undeployment recorded successfully".to_string())
 }
 
-fn relation_href(resource: &Value, primary: &str, fallback: Option<&str>) -> Option<String> {
+pub(crate) fn relation_href(
+    resource: &Value,
+    primary: &str,
+    fallback: Option<&str>,
+) -> Option<String> {
     let primary_link = resource["_links"][primary]["href"]
         .as_str()
         .map(str::to_string);
@@ -238,7 +61,7 @@ fn relation_href(resource: &Value, primary: &str, fallback: Option<&str>) -> Opt
     })
 }
 
-fn deployed_application_instance(deployed_version: &Value) -> Option<String> {
+pub(crate) fn deployed_application_instance(deployed_version: &Value) -> Option<String> {
     deployed_version["applicationInstance"]
         .as_str()
         .map(str::to_string)
@@ -2,126 +2,59 @@ use serde_json::json;
 
 use crate::cli::{
     pact_broker::main::{
-        HALClient, PactBrokerError,
-        utils::{
-            get_auth, get_broker_relation, get_broker_url, get_custom_headers, get_retries,
-            get_ssl_options,
-        },
+        PactBrokerError,
+        client::{Contact, CreateEnvironmentRequest, PactBrokerClient},
+        output::{self, OutputType},
+        types::BrokerDetails,
     },
     utils,
 };
 
 pub fn create_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
-    let name = args.get_one::<String>("name");
-    let display_name = args.get_one::<String>("display-name");
-    let production = args.get_flag("production");
-    let contact_name = args.get_one::<String>("contact-name");
-    let contact_email_address = args.get_one::<String>("contact-email-address");
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let hal_client: HALClient = HALClient::with_url(
-            &broker_url,
-            Some(auth.clone()),
-            ssl_options.clone(),
-            custom_headers.clone(),
-        )
-        .with_retry_count(get_retries(args));
-        let pb_environments_href_path = get_broker_relation(
-            hal_client.clone(),
-            "pb:environments".to_string(),
-            broker_url.to_string(),
-        )
-        .await?;
-
-        let mut payload = json!({});
-        payload["production"] = serde_json::Value::Bool(production);
-        if let Some(name) = name {
-            payload["name"] = serde_json::Value::String(name.to_string());
-        } else {
+    let name = match args.get_one::<String>("name") {
+        Some(name) => name.to_string(),
+        None => {
             let message = "Environment name is required";
             println!("❌ {}", utils::RED.apply_to(message));
             return Err(PactBrokerError::ValidationError(vec![message.to_string()]));
         }
-        if let Some(contact_name) = contact_name {
-            payload["contacts"] = serde_json::Value::Array(vec![{
-                let mut map = serde_json::Map::new();
-                map.insert(
-                    "name".to_string(),
-                    serde_json::Value::String(contact_name.to_string()),
-                );
-                serde_json::Value::Object(map)
-            }]);
-        }
-        if let Some(display_name) = display_name {
-            payload["displayName"] = serde_json::Value::String(display_name.to_string());
-        }
-        if let Some(contact_email_address) = contact_email_address {
-            if payload["contacts"].is_array() {
-                let contacts = payload["contacts"].as_array_mut().unwrap();
-                let contact = contacts.get_mut(0).unwrap();
-                let contact_map = contact.as_object_mut().unwrap();
-                let details = contact_map
-                    .entry("details")
-                    .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
-                if let serde_json::Value::Object(details_map) = details {
-                    details_map.insert(
-                        "emailAddress".to_string(),
-                        serde_json::Value::String(contact_email_address.to_string()),
-                    );
-                }
-            } else {
-                payload["contacts"] = serde_json::Value::Array(vec![{
-                    let mut map = serde_json::Map::new();
-                    let mut details_map = serde_json::Map::new();
-                    details_map.insert(
-                        "emailAddress".to_string(),
-                        serde_json::Value::String(contact_email_address.to_string()),
-                    );
-                    map.insert(
-                        "details".to_string(),
-                        serde_json::Value::Object(details_map),
-                    );
-                    serde_json::Value::Object(map)
-                }]);
-            }
-        }
-
-        let res = hal_client
-            .post_json(
-                &pb_environments_href_path,
-                &json!(payload).to_string(),
-                None,
-            )
-            .await;
+    };
+    let contact_name = args.get_one::<String>("contact-name");
+    let contact_email_address = args.get_one::<String>("contact-email-address");
+    let contacts = if contact_name.is_some() || contact_email_address.is_some() {
+        vec![Contact {
+            name: contact_name.cloned(),
+            details: contact_email_address.map(|email| json!({ "emailAddress": email })),
+        }]
+    } else {
+        vec![]
+    };
+    let request = CreateEnvironmentRequest {
+        name: name.clone(),
+        display_name: args.get_one::<String>("display-name").cloned(),
+        production: args.get_flag("production"),
+        contacts,
+    };
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let res = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(client.create_environment_json(&request))?;
 
-        let default_output: String = "text".to_string();
-        let output: &String = args.get_one::<String>("output").unwrap_or(&default_output);
-        match res {
-            Ok(res) => {
-                if output == "pretty" {
-                    let json = serde_json::to_string_pretty(&res).unwrap();
-                    println!("{}", json);
-                } else if output == "json" {
-                    println!("{}", serde_json::to_string(&res).unwrap());
-                } else if output == "id" {
-                    println!("{}", res["uuid"].to_string().trim_matches('"'));
-                } else {
-                    let uuid = res["uuid"].to_string();
-                    println!(
-                        "✅ Created {} environment in the Pact Broker with UUID {}",
-                        utils::GREEN.apply_to(name.unwrap()),
-                        utils::GREEN.apply_to(uuid.trim_matches('"'))
-                    );
-                }
-                Ok("Successfully created environment".to_string())
-            }
-            Err(err) => Err(err),
-        }
-    })
+    let output = OutputType::from_args(args);
+    if output == OutputType::Pretty {
+        let json = serde_json::to_string_pretty(&res).unwrap();
+        println!("{}", json);
+    } else if output.is_structured() {
+        output::print(&res, &output)?;
+    } else {
+        let uuid = res["uuid"].to_string();
+        println!(
+            "✅ Created {} environment in the Pact Broker with UUID {}",
+            utils::GREEN.apply_to(&name),
+            utils::GREEN.apply_to(uuid.trim_matches('"'))
+        );
+    }
+    Ok("Successfully created environment".to_string())
 }
 
 #[cfg(test)]
@@ -1,7 +1,12 @@
+use serde_json::json;
+
 use crate::cli::{
     pact_broker::main::{
         HALClient, PactBrokerError,
-        utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+        output::{self, OutputType},
+        utils::{
+            get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries,
+        },
     },
     utils,
 };
@@ -11,12 +16,13 @@ pub fn delete_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerE
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
+    let output = OutputType::from_args(args);
     tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -26,7 +32,7 @@ pub fn delete_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerE
             .await;
         match res {
             Ok(_) => {
-                let name = res.clone().unwrap()["name"].to_string();
+                let name = res.clone().unwrap()["name"].clone();
                 let res = hal_client
                     .clone()
                     .delete(&(broker_url.clone() + "/environments/" + &uuid))
@@ -35,10 +41,15 @@ pub fn delete_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerE
                     Ok(_) => {
                         let message = format!(
                             "✅ Environment {} with UUID {} deleted successfully",
-                            utils::GREEN.apply_to(name.trim_matches('"')),
+                            utils::GREEN.apply_to(name.as_str().unwrap_or_default()),
                             utils::GREEN.apply_to(uuid.trim_matches('"'))
                         );
-                        println!("{}", message);
+                        if output.is_structured() {
+                            let result = json!({ "uuid": uuid, "name": name, "deleted": true });
+                            return output::print(&result, &output);
+                        } else {
+                            println!("{}", message);
+                        }
                         Ok(message)
                     }
                     Err(err) => Err(err),
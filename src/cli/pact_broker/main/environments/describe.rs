@@ -1,7 +1,10 @@
 use crate::cli::{
     pact_broker::main::{
         HALClient, PactBrokerError,
-        utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+        output::{self, OutputType},
+        utils::{
+            get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries,
+        },
     },
     utils,
 };
@@ -11,13 +14,13 @@ pub fn describe_environment(args: &clap::ArgMatches) -> Result<String, PactBroke
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -25,15 +28,14 @@ pub fn describe_environment(args: &clap::ArgMatches) -> Result<String, PactBroke
             .fetch(&(broker_url + "/environments/" + &uuid))
             .await;
 
-        let default_output = "text".to_string();
-        let output = args.get_one::<String>("output").unwrap_or(&default_output);
+        let output = OutputType::from_args(args);
         match res {
             Ok(res) => {
-                if output == "pretty" {
+                if output == OutputType::Pretty {
                     let json = serde_json::to_string_pretty(&res).unwrap();
                     println!("{}", json);
-                } else if output == "json" {
-                    println!("{}", serde_json::to_string(&res).unwrap());
+                } else if output.is_structured() {
+                    output::print(&res, &output)?;
                 } else {
                     let res_uuid = res["uuid"].to_string();
                     let res_name = res["name"].to_string();
@@ -1,91 +1,52 @@
 use crate::cli::pact_broker::main::{
-    HALClient, PactBrokerError,
-    utils::{
-        follow_broker_relation, get_auth, get_broker_relation, get_broker_url, get_custom_headers,
-        get_retries, get_ssl_options,
-    },
+    PactBrokerError,
+    client::{Environment, PactBrokerClient},
+    output::{self, OutputType},
+    types::{BrokerDetails, Pagination},
 };
 use comfy_table::Table;
 use comfy_table::presets::UTF8_FULL;
 
 pub fn list_environments(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-    tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let hal_client: HALClient = HALClient::with_url(
-            &broker_url,
-            Some(auth.clone()),
-            ssl_options.clone(),
-            custom_headers.clone(),
-        )
-        .with_retry_count(get_retries(args));
-        let pb_environments_href_path = get_broker_relation(
-            hal_client.clone(),
-            "pb:environments".to_string(),
-            broker_url.to_string(),
-        )
-        .await?;
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let res = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(client.list_environments_json(&Pagination::from_args(args)))?;
 
-        let res = follow_broker_relation(
-            hal_client,
-            "pb:environments".to_string(),
-            pb_environments_href_path,
-        )
-        .await;
+    let output = OutputType::from_args(args);
+    if output == OutputType::Pretty {
+        let json = serde_json::to_string_pretty(&res).unwrap();
+        println!("{}", json);
+    } else if output.is_structured() {
+        output::print(&res, &output)?;
+    } else {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .set_header(vec!["UUID", "NAME", "DISPLAY NAME", "PRODUCTION"]);
 
-        let default_output = "text".to_string();
-        let output = args.get_one::<String>("output").unwrap_or(&default_output);
-        match res {
-            Ok(res) => {
-                if output == "pretty" {
-                    let json = serde_json::to_string_pretty(&res).unwrap();
-                    println!("{}", json);
-                } else if output == "json" {
-                    println!("{}", serde_json::to_string(&res).unwrap());
-                } else {
-                    let mut table = Table::new();
-
-                    #[derive(Debug, serde::Deserialize)]
-                    struct Environment {
-                        uuid: String,
-                        name: String,
-                        #[serde(rename = "displayName")]
-                        display_name: String,
-                        production: bool,
-                    }
-
-                    table.load_preset(UTF8_FULL).set_header(vec![
-                        "UUID",
-                        "NAME",
-                        "DISPLAY NAME",
-                        "PRODUCTION",
-                    ]);
-
-                    if let Some(embedded) = res["_embedded"].as_object()
-                        && let Some(environments) = embedded["environments"].as_array()
-                    {
-                        for environment in environments {
-                            let environment: Environment =
-                                serde_json::from_value(environment.clone()).unwrap();
-                            table.add_row(vec![
-                                environment.uuid,
-                                environment.name,
-                                environment.display_name,
-                                environment.production.to_string(),
-                            ]);
-                        }
-                    }
-
-                    println!("{table}");
-                }
-
-                Ok("".to_string())
+        if let Some(environments) = res["_embedded"]["environments"].as_array() {
+            for environment in environments {
+                let environment: Environment = serde_json::from_value(environment.clone())
+                    .map_err(|err| {
+                        PactBrokerError::ContentError(format!(
+                            "Unexpected environment in the response from the Pact Broker: {}",
+                            err
+                        ))
+                    })?;
+                table.add_row(vec![
+                    environment.uuid,
+                    environment.name,
+                    environment.display_name.unwrap_or_default(),
+                    environment.production.to_string(),
+                ]);
             }
-            Err(err) => Err(err),
         }
-    })
+
+        println!("{table}");
+    }
+
+    Ok("".to_string())
 }
 
 #[cfg(test)]
@@ -2,4 +2,5 @@ pub mod create;
 pub mod delete;
 pub mod describe;
 pub mod list;
+pub mod status;
 pub mod update;
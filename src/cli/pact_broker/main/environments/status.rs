@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::{Cell, Color, Table};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::cli::{
+    pact_broker::main::{
+        PactBrokerError,
+        client::{Environment, PactBrokerClient},
+        deployments::record_undeployment::{deployed_application_instance, relation_href},
+        output::{self, OutputType},
+        types::BrokerDetails,
+    },
+    utils,
+};
+
+/// A version that is currently deployed to, or released and supported in, an environment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentVersion {
+    pub pacticipant: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentStatus {
+    pub name: String,
+    pub production: bool,
+    pub deployed_versions: Vec<CurrentVersion>,
+    pub released_versions: Vec<CurrentVersion>,
+}
+
+impl EnvironmentStatus {
+    /// The distinct versions of each pacticipant in the environment, deployed or released
+    pub fn versions_by_pacticipant(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut versions: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for current in self.deployed_versions.iter().chain(&self.released_versions) {
+            versions
+                .entry(current.pacticipant.clone())
+                .or_default()
+                .insert(current.version.clone());
+        }
+        versions
+    }
+}
+
+/// The versions of a pacticipant in each of the compared environments
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDrift {
+    pub pacticipant: String,
+    pub versions: BTreeMap<String, BTreeSet<String>>,
+    pub drift: bool,
+}
+
+fn current_versions(res: &Value, embedded: &str) -> Vec<CurrentVersion> {
+    let mut versions: Vec<CurrentVersion> = res["_embedded"][embedded]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|current| CurrentVersion {
+            pacticipant: current["_embedded"]["pacticipant"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            version: current["_embedded"]["version"]["number"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            application_instance: deployed_application_instance(current),
+            since: current["createdAt"].as_str().map(str::to_string),
+        })
+        .collect();
+    versions.sort_by(|a, b| {
+        (&a.pacticipant, &a.application_instance).cmp(&(&b.pacticipant, &b.application_instance))
+    });
+    versions
+}
+
+async fn fetch_status(
+    client: &PactBrokerClient,
+    environment: &Environment,
+) -> Result<EnvironmentStatus, PactBrokerError> {
+    let res = client
+        .hal_client()
+        .fetch(&format!(
+            "{}/environments/{}",
+            client.broker_url(),
+            environment.uuid
+        ))
+        .await?;
+    let deployed_href = relation_href(
+        &res,
+        "pb:currently-deployed-deployed-versions",
+        Some("pb:currently-deployed-versions"),
+    )
+    .ok_or_else(|| {
+        PactBrokerError::LinkError(
+            "This version of the Pact Broker does not support deployed versions. Please upgrade to version 2.80.0 or later.".to_string(),
+        )
+    })?;
+    let deployed_versions = current_versions(
+        &client.hal_client().fetch(&deployed_href).await?,
+        "deployedVersions",
+    );
+    let released_versions =
+        match relation_href(&res, "pb:currently-supported-released-versions", None) {
+            Some(href) => {
+                current_versions(&client.hal_client().fetch(&href).await?, "releasedVersions")
+            }
+            None => vec![],
+        };
+    Ok(EnvironmentStatus {
+        name: environment.name.clone(),
+        production: environment.production,
+        deployed_versions,
+        released_versions,
+    })
+}
+
+/// The status of the named environments, in the order given, or of every environment when no
+/// names are given
+pub async fn environment_statuses(
+    client: &PactBrokerClient,
+    names: &[String],
+) -> Result<Vec<EnvironmentStatus>, PactBrokerError> {
+    let environments = client.list_environments().await?;
+    let selected = if names.is_empty() {
+        environments.iter().collect()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                environments
+                    .iter()
+                    .find(|environment| environment.name == *name)
+                    .ok_or_else(|| {
+                        PactBrokerError::NotFound(format!("Environment {} not found", name))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let mut statuses = vec![];
+    for environment in selected {
+        statuses.push(fetch_status(client, environment).await?);
+    }
+    Ok(statuses)
+}
+
+/// Compares the versions of every pacticipant found in either environment
+pub fn compare_environments(
+    left: &EnvironmentStatus,
+    right: &EnvironmentStatus,
+) -> Vec<VersionDrift> {
+    let left_versions = left.versions_by_pacticipant();
+    let right_versions = right.versions_by_pacticipant();
+    let pacticipants: BTreeSet<&String> =
+        left_versions.keys().chain(right_versions.keys()).collect();
+    pacticipants
+        .into_iter()
+        .map(|pacticipant| {
+            let in_left = left_versions.get(pacticipant).cloned().unwrap_or_default();
+            let in_right = right_versions.get(pacticipant).cloned().unwrap_or_default();
+            VersionDrift {
+                pacticipant: pacticipant.clone(),
+                drift: in_left != in_right,
+                versions: BTreeMap::from([
+                    (left.name.clone(), in_left),
+                    (right.name.clone(), in_right),
+                ]),
+            }
+        })
+        .collect()
+}
+
+fn status_table(statuses: &[EnvironmentStatus]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(vec![
+        "ENVIRONMENT",
+        "PACTICIPANT",
+        "VERSION",
+        "STATUS",
+        "APPLICATION INSTANCE",
+        "SINCE",
+    ]);
+    for status in statuses {
+        for (label, versions) in [
+            ("deployed", &status.deployed_versions),
+            ("released", &status.released_versions),
+        ] {
+            for current in versions {
+                table.add_row(vec![
+                    status.name.clone(),
+                    current.pacticipant.clone(),
+                    current.version.clone(),
+                    label.to_string(),
+                    current.application_instance.clone().unwrap_or_default(),
+                    current.since.clone().unwrap_or_default(),
+                ]);
+            }
+        }
+    }
+    table
+}
+
+fn drift_table(left: &str, right: &str, drift: &[VersionDrift]) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_header(vec!["PACTICIPANT", left, right, "STATUS"]);
+    for row in drift {
+        let versions = |environment: &str| {
+            let versions = &row.versions[environment];
+            if versions.is_empty() {
+                "-".to_string()
+            } else {
+                versions.iter().cloned().collect::<Vec<_>>().join(", ")
+            }
+        };
+        let color = if row.drift {
+            Color::Yellow
+        } else {
+            Color::Reset
+        };
+        table.add_row(vec![
+            Cell::new(&row.pacticipant).fg(color),
+            Cell::new(versions(left)).fg(color),
+            Cell::new(versions(right)).fg(color),
+            Cell::new(if row.drift { "drift" } else { "" }).fg(color),
+        ]);
+    }
+    table
+}
+
+pub fn environment_status(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let output = OutputType::from_args(args);
+    let compare: Vec<String> = args
+        .get_many::<String>("compare")
+        .map(|names| names.cloned().collect())
+        .unwrap_or_default();
+    let names: Vec<String> = if compare.is_empty() {
+        args.get_many::<String>("environment")
+            .map(|names| names.cloned().collect())
+            .unwrap_or_default()
+    } else {
+        compare.clone()
+    };
+
+    let statuses = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(environment_statuses(&client, &names))?;
+
+    if let [left, right] = &statuses[..]
+        && !compare.is_empty()
+    {
+        let drift = compare_environments(left, right);
+        let drifted = drift.iter().filter(|row| row.drift).count();
+        let json = json!({
+            "environments": [left.name, right.name],
+            "pacticipants": drift
+        });
+        if output.is_structured() {
+            output::print(&serde_json::to_value(&json).unwrap(), &output)?;
+        } else {
+            println!("{}", drift_table(&left.name, &right.name, &drift));
+            if drifted == 0 {
+                println!("✅ {} and {} have the same versions", left.name, right.name);
+            } else {
+                println!(
+                    "{}",
+                    utils::YELLOW.apply_to(format!(
+                        "⚠️  {} of {} pacticipants have different versions in {} and {}",
+                        drifted,
+                        drift.len(),
+                        left.name,
+                        right.name
+                    ))
+                );
+            }
+        }
+        return Ok(json.to_string());
+    }
+
+    let json = json!({ "environments": statuses });
+    if output.is_structured() {
+        output::print(&serde_json::to_value(&json).unwrap(), &output)?;
+    } else {
+        println!("{}", status_table(&statuses));
+    }
+    Ok(json.to_string())
+}
+
+#[cfg(test)]
+mod environment_status_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{CurrentVersion, compare_environments, environment_statuses};
+    use crate::cli::pact_broker::main::PactBrokerError;
+    use crate::cli::pact_broker::main::client::PactBrokerClient;
+    use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+    use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+    use crate::cli::pact_broker::main::types::BrokerDetails;
+
+    async fn client() -> PactBrokerClient {
+        let state: MockBrokerState = serde_json::from_value(json!({
+            "environments": [
+                { "uuid": "1", "name": "staging" },
+                { "uuid": "2", "name": "production", "production": true }
+            ],
+            "pacticipants": [{ "name": "Foo" }, { "name": "Bar" }, { "name": "Baz" }],
+            "versions": [
+                { "pacticipant": "Foo", "number": "1" },
+                { "pacticipant": "Foo", "number": "2" },
+                { "pacticipant": "Bar", "number": "5" },
+                { "pacticipant": "Baz", "number": "7" }
+            ],
+            "deployedVersions": [
+                { "pacticipant": "Foo", "version": "2", "environment": "1", "applicationInstance": "blue" },
+                { "pacticipant": "Foo", "version": "1", "environment": "2" },
+                { "pacticipant": "Bar", "version": "5", "environment": "1" },
+                { "pacticipant": "Bar", "version": "5", "environment": "2" }
+            ],
+            "releasedVersions": [{ "pacticipant": "Baz", "version": "7", "environment": "1" }]
+        }))
+        .unwrap();
+        let (_, url) = spawn_mock_broker(state).await;
+        PactBrokerClient::new(BrokerDetails::new(&url).with_retries(0))
+    }
+
+    #[tokio::test]
+    async fn shows_the_current_versions_of_every_environment() {
+        let client = client().await;
+
+        let statuses = environment_statuses(&client, &[]).await.unwrap();
+
+        assert_eq!(
+            statuses.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["staging", "production"]
+        );
+        let staging = &statuses[0];
+        assert_eq!(
+            staging
+                .deployed_versions
+                .iter()
+                .map(|current| CurrentVersion {
+                    since: None,
+                    ..current.clone()
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                CurrentVersion {
+                    pacticipant: "Bar".to_string(),
+                    version: "5".to_string(),
+                    application_instance: None,
+                    since: None
+                },
+                CurrentVersion {
+                    pacticipant: "Foo".to_string(),
+                    version: "2".to_string(),
+                    application_instance: Some("blue".to_string()),
+                    since: None
+                }
+            ]
+        );
+        assert_eq!(staging.released_versions[0].pacticipant, "Baz");
+        assert!(statuses[1].production);
+
+        let result = environment_statuses(&client, &["test".to_string()]).await;
+        assert!(matches!(result, Err(PactBrokerError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn compares_the_versions_in_two_environments() {
+        let client = client().await;
+
+        let statuses =
+            environment_statuses(&client, &["staging".to_string(), "production".to_string()])
+                .await
+                .unwrap();
+        let drift = compare_environments(&statuses[0], &statuses[1]);
+
+        assert_eq!(
+            serde_json::to_value(&drift).unwrap(),
+            json!([
+                { "pacticipant": "Bar", "versions": { "production": ["5"], "staging": ["5"] }, "drift": false },
+                { "pacticipant": "Baz", "versions": { "production": [], "staging": ["7"] }, "drift": true },
+                { "pacticipant": "Foo", "versions": { "production": ["1"], "staging": ["2"] }, "drift": true }
+            ])
+        );
+    }
+}
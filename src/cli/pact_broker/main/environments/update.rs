@@ -1,38 +1,28 @@
 use crate::cli::{
     pact_broker::main::{
-        HALClient, PactBrokerError,
-        utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+        PactBrokerError,
+        client::{Contact, CreateEnvironmentRequest, PactBrokerClient},
+        output::{self, OutputType},
+        types::BrokerDetails,
     },
     utils,
 };
 use serde_json::json;
 
 pub fn update_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
-    let default_output = "text".to_string();
-    let output = args.get_one::<String>("output").unwrap_or(&default_output);
+    let output = OutputType::from_args(args);
     let uuid = args.get_one::<String>("uuid").unwrap().to_string();
     let name = args.get_one::<String>("name");
     let display_name = args.get_one::<String>("display-name");
     let production = args.get_flag("production");
     let contact_name = args.get_one::<String>("contact-name");
     let contact_email_address = args.get_one::<String>("contact-email-address");
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-    let environments_href = format!("{}/environments/{}", broker_url, uuid.clone());
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+    let environments_href = format!("{}/environments/{}", client.broker_url(), uuid);
     tokio::runtime::Runtime::new().unwrap().block_on(async {
-        let hal_client: HALClient = HALClient::with_url(
-            &broker_url,
-            Some(auth.clone()),
-            ssl_options.clone(),
-            custom_headers.clone(),
-        )
-        .with_retry_count(get_retries(args));
-
         // check if the uuid url exists, if not return an error, otherwise continue
 
-        let get_uuid_result = hal_client.fetch(&environments_href);
+        let get_uuid_result = client.hal_client().fetch(&environments_href);
 
         match get_uuid_result.await {
             Ok(_) => {}
@@ -40,66 +30,40 @@ pub fn update_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerE
                 return Err(match err {
                     PactBrokerError::NotFound(_error) => {
                         let message = format!("Environment with UUID {} not found", uuid);
-                        println!("❌ {}", utils::RED.apply_to(message.clone()));
-                        if output == "json" {
+                        eprintln!("❌ {}", utils::RED.apply_to(message.clone()));
+                        if output.is_structured() {
                             return Err(PactBrokerError::NotFound("{}".to_string()));
                         }
                         PactBrokerError::NotFound(message)
                     }
                     other => {
-                        println!("❌ {}", utils::RED.apply_to(other.to_string()));
+                        eprintln!("❌ {}", utils::RED.apply_to(other.to_string()));
                         other
                     }
                 });
             }
         }
 
-        let mut payload = json!({});
-        // payload["uuid"] = serde_json::Value::String(uuid.clone());
-        payload["production"] = serde_json::Value::Bool(production);
-        if let Some(name) = name {
-            payload["name"] = serde_json::Value::String(name.to_string());
-        } else {
+        let Some(name) = name else {
             let message = "❌ Name is required".to_string();
-            println!("{}", message.clone());
+            eprintln!("{}", message.clone());
             return Err(PactBrokerError::ValidationError(vec![message]));
-        }
-        if let Some(contact_name) = contact_name {
-            payload["contacts"] = serde_json::Value::Array(vec![{
-                let mut map = serde_json::Map::new();
-                map.insert(
-                    "name".to_string(),
-                    serde_json::Value::String(contact_name.to_string()),
-                );
-                serde_json::Value::Object(map)
-            }]);
-        }
-        if let Some(display_name) = display_name {
-            payload["displayName"] = serde_json::Value::String(display_name.to_string());
-        }
-        if let Some(contact_email_address) = contact_email_address {
-            if payload["contacts"].is_array() {
-                let contacts = payload["contacts"].as_array_mut().unwrap();
-                let contact = contacts.get_mut(0).unwrap();
-                let contact_map = contact.as_object_mut().unwrap();
-                contact_map.insert(
-                    "email".to_string(),
-                    serde_json::Value::String(contact_email_address.to_string()),
-                );
-            } else {
-                payload["contacts"] = serde_json::Value::Array(vec![{
-                    let mut map = serde_json::Map::new();
-                    map.insert(
-                        "email".to_string(),
-                        serde_json::Value::String(contact_email_address.to_string()),
-                    );
-                    serde_json::Value::Object(map)
-                }]);
-            }
-        }
-        let res = hal_client
-            .put_json(&(environments_href), &payload.to_string(), None)
-            .await;
+        };
+        let contacts = if contact_name.is_some() || contact_email_address.is_some() {
+            vec![Contact {
+                name: contact_name.cloned(),
+                details: contact_email_address.map(|email| json!({ "emailAddress": email })),
+            }]
+        } else {
+            vec![]
+        };
+        let request = CreateEnvironmentRequest {
+            name: name.clone(),
+            display_name: display_name.cloned(),
+            production,
+            contacts,
+        };
+        let res = client.update_environment_json(&uuid, &request).await;
 
         let columns = vec![
             "ID",
@@ -122,21 +86,16 @@ pub fn update_environment(args: &clap::ArgMatches) -> Result<String, PactBrokerE
                 let uuid: String = res["uuid"].to_string();
                 let message = format!(
                     "✅ Updated {} environment in the Pact Broker with UUID {}",
-                    utils::GREEN.apply_to(name.unwrap()),
+                    utils::GREEN.apply_to(name),
                     utils::GREEN.apply_to(uuid.trim_matches('"'))
                 );
-                if output == "pretty" {
+                if output == OutputType::Pretty {
                     let json = serde_json::to_string_pretty(&res).unwrap();
                     println!("{}", json);
-                } else if output == "json" {
-                    let json = serde_json::to_string(&res).unwrap();
-                    println!("{}", json);
-                    return Ok(json);
-                } else if output == "id" {
-                    println!("{}", res["uuid"].to_string().trim_matches('"'));
-                } else if output == "table" {
-                    let table =
-                        crate::cli::pact_broker::main::utils::generate_table(&res, columns, names);
+                } else if output.is_structured() {
+                    return output::print(&res, &output);
+                } else if output == OutputType::Table {
+                    let table = output::generate_table(&res, columns, names);
                     println!("{table}");
                 } else {
                     println!("{}", message);
@@ -0,0 +1,727 @@
+//! Recording the HTTP traffic with the broker to a HAR file, and replaying it without a network.
+//!
+//! With `--record-http`, every request sent by the client (including each retry) and the
+//! response to it is written to an HTTP archive ([HAR 1.2](http://www.softwareishard.com/blog/har-12-spec/)),
+//! with credentials replaced by `[REDACTED]`: the values of credential and custom headers, of
+//! credential fields in JSON bodies and of credential query parameters, and any password in the
+//! URL. The file is only readable by the current user, and is rewritten after each response, so
+//! it is complete even when a command fails part way through.
+//!
+//! With `--replay-http`, responses are served from an archive instead. A request is matched to
+//! the first entry with the same method, path and query that has not been replayed yet, so the
+//! host of the broker can differ from the one recorded (credentials in the query are redacted
+//! before matching). When every matching entry has been
+//! replayed, the last one is served again.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Instant, SystemTime};
+
+use anyhow::anyhow;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use http::Extensions;
+use reqwest::{Request, Response, ResponseBuilderExt, Url};
+use reqwest_middleware::{Middleware, Next};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use super::CustomHeaders;
+use super::config::write_private_file;
+use crate::cli::utils::{is_credential_field, mask_json_credentials};
+
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HttpArchive {
+    /// Record the requests and responses to the HAR file
+    Record(String),
+    /// Serve the responses from the HAR file
+    Replay(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct HarLog {
+    version: String,
+    creator: HarCreator,
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct HarCreator {
+    name: String,
+    version: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    #[serde(default)]
+    started_date_time: String,
+    #[serde(default)]
+    time: f64,
+    request: HarRequest,
+    response: HarResponse,
+    #[serde(default)]
+    cache: Value,
+    #[serde(default)]
+    timings: HarTimings,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    http_version: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    #[serde(default)]
+    query_string: Vec<HarHeader>,
+    #[serde(default)]
+    cookies: Vec<Value>,
+    #[serde(default)]
+    headers_size: i64,
+    #[serde(default)]
+    body_size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    #[serde(default)]
+    status_text: String,
+    #[serde(default)]
+    http_version: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    #[serde(default)]
+    cookies: Vec<Value>,
+    content: HarContent,
+    #[serde(default, rename = "redirectURL")]
+    redirect_url: String,
+    #[serde(default)]
+    headers_size: i64,
+    #[serde(default)]
+    body_size: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    #[serde(default)]
+    size: i64,
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct HarTimings {
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+impl Har {
+    fn new() -> Self {
+        Har {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: env!("CARGO_PKG_NAME").to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries: vec![],
+            },
+        }
+    }
+}
+
+/// The headers, with the values of credential headers and the `--custom-header` headers (which
+/// are often API keys) redacted
+fn har_headers(
+    headers: &reqwest::header::HeaderMap,
+    custom_headers: Option<&CustomHeaders>,
+) -> Vec<HarHeader> {
+    let is_custom = |name: &str| {
+        custom_headers
+            .map(|custom| {
+                custom
+                    .headers
+                    .keys()
+                    .any(|custom| custom.eq_ignore_ascii_case(name))
+            })
+            .unwrap_or(false)
+    };
+    headers
+        .iter()
+        .map(|(name, value)| HarHeader {
+            name: name.to_string(),
+            value: if is_credential_field(name.as_str()) || is_custom(name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).to_string()
+            },
+        })
+        .collect()
+}
+
+fn mime_type(headers: &reqwest::header::HeaderMap) -> String {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The URL with any password and the values of credential query parameters redacted
+fn redacted_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    if url.password().is_some() {
+        let _ = url.set_password(Some(REDACTED));
+    }
+    if url
+        .query_pairs()
+        .any(|(name, _)| is_credential_field(&name))
+    {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| match is_credential_field(&name) {
+                true => (name.to_string(), REDACTED.to_string()),
+                false => (name.to_string(), value.to_string()),
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url
+}
+
+fn har_request(req: &Request, custom_headers: Option<&CustomHeaders>) -> HarRequest {
+    let body = req.body().and_then(|body| body.as_bytes());
+    let url = redacted_url(req.url());
+    HarRequest {
+        method: req.method().to_string(),
+        url: url.to_string(),
+        http_version: format!("{:?}", req.version()),
+        headers: har_headers(req.headers(), custom_headers),
+        query_string: url
+            .query_pairs()
+            .map(|(name, value)| HarHeader {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+        cookies: vec![],
+        headers_size: -1,
+        body_size: body.map(|body| body.len() as i64).unwrap_or(0),
+        post_data: body.map(|body| HarPostData {
+            mime_type: mime_type(req.headers()),
+            text: {
+                let text = String::from_utf8_lossy(body);
+                mask_json_credentials(&text, REDACTED).unwrap_or_else(|| text.to_string())
+            },
+        }),
+    }
+}
+
+/// The body as text, or base64 encoded if it is not UTF-8
+fn har_content(headers: &reqwest::header::HeaderMap, body: &[u8]) -> HarContent {
+    let (text, encoding) = match std::str::from_utf8(body) {
+        Ok(text) => (text.to_string(), None),
+        Err(_) => (BASE64.encode(body), Some("base64".to_string())),
+    };
+    HarContent {
+        size: body.len() as i64,
+        mime_type: mime_type(headers),
+        text: Some(text),
+        encoding,
+    }
+}
+
+/// The path and query of a URL, which is what requests are matched on when replaying
+fn request_target(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    Some(match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    })
+}
+
+/// Archives being recorded by this process. An archive is started afresh the first time it is
+/// used, and shared by every client after that
+static RECORDINGS: LazyLock<Mutex<HashMap<String, Har>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn record(path: &str, entry: HarEntry) -> anyhow::Result<()> {
+    let mut recordings = RECORDINGS.lock().unwrap();
+    let har = recordings.entry(path.to_string()).or_insert_with(Har::new);
+    har.log.entries.push(entry);
+    write_private_file(Path::new(path), &serde_json::to_string_pretty(har)?)?;
+    Ok(())
+}
+
+/// Middleware that records each request and its response to a HAR file
+pub(crate) struct HttpRecordMiddleware {
+    pub(crate) path: String,
+}
+
+#[async_trait::async_trait]
+impl Middleware for HttpRecordMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let started = SystemTime::now();
+        let start = Instant::now();
+        let request = har_request(&req, extensions.get::<CustomHeaders>());
+        let url = req.url().clone();
+
+        let response = next.run(req, extensions).await?;
+        let wait = start.elapsed();
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let receive = start.elapsed() - wait;
+
+        let entry = HarEntry {
+            started_date_time: humantime::format_rfc3339_millis(started).to_string(),
+            time: start.elapsed().as_secs_f64() * 1000.0,
+            request,
+            response: HarResponse {
+                status: status.as_u16(),
+                status_text: status.canonical_reason().unwrap_or_default().to_string(),
+                http_version: format!("{:?}", version),
+                headers: har_headers(&headers, None),
+                cookies: vec![],
+                content: har_content(&headers, &body),
+                redirect_url: headers
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string(),
+                headers_size: -1,
+                body_size: body.len() as i64,
+            },
+            cache: serde_json::json!({}),
+            timings: HarTimings {
+                send: 0.0,
+                wait: wait.as_secs_f64() * 1000.0,
+                receive: receive.as_secs_f64() * 1000.0,
+            },
+        };
+        if let Err(err) = record(&self.path, entry) {
+            warn!("Could not write the HTTP archive '{}' - {}", self.path, err);
+        }
+
+        let mut builder = http::Response::builder()
+            .status(status)
+            .version(version)
+            .url(url);
+        if let Some(response_headers) = builder.headers_mut() {
+            *response_headers = headers;
+        }
+        Ok(builder
+            .body(body)
+            .map_err(|err| reqwest_middleware::Error::Middleware(anyhow!(err)))?
+            .into())
+    }
+}
+
+/// An archive being replayed, with the entries that have been served
+struct Replay {
+    entries: Vec<HarEntry>,
+    replayed: Vec<bool>,
+}
+
+impl Replay {
+    fn load(path: &str) -> anyhow::Result<Replay> {
+        let json = std::fs::read_to_string(path)?;
+        let har: Har = serde_json::from_str(&json)?;
+        let replayed = vec![false; har.log.entries.len()];
+        Ok(Replay {
+            entries: har.log.entries,
+            replayed,
+        })
+    }
+
+    fn next_response(&mut self, method: &str, target: &str) -> Option<HarResponse> {
+        let matching: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.request.method.eq_ignore_ascii_case(method)
+                    && request_target(&entry.request.url).as_deref() == Some(target)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let index = matching
+            .iter()
+            .find(|index| !self.replayed[**index])
+            .or(matching.last())
+            .copied()?;
+        self.replayed[index] = true;
+        Some(self.entries[index].response.clone())
+    }
+}
+
+/// Archives being replayed by this process, shared by every client
+static REPLAYS: LazyLock<Mutex<HashMap<String, Replay>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn replayed_response(path: &str, method: &str, target: &str) -> anyhow::Result<HarResponse> {
+    let mut replays = REPLAYS.lock().unwrap();
+    if !replays.contains_key(path) {
+        let replay = Replay::load(path)
+            .map_err(|err| anyhow!("Could not read the HTTP archive '{}' - {}", path, err))?;
+        replays.insert(path.to_string(), replay);
+    }
+    replays
+        .get_mut(path)
+        .and_then(|replay| replay.next_response(method, target))
+        .ok_or_else(|| {
+            anyhow!(
+                "There is no response in the HTTP archive '{}' for {} {}",
+                path,
+                method,
+                target
+            )
+        })
+}
+
+/// Middleware that serves responses from a HAR file instead of sending the requests
+pub(crate) struct HttpReplayMiddleware {
+    pub(crate) path: String,
+}
+
+#[async_trait::async_trait]
+impl Middleware for HttpReplayMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        _extensions: &mut Extensions,
+        _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let target = request_target(redacted_url(req.url()).as_str()).unwrap_or_default();
+        let response = replayed_response(&self.path, req.method().as_str(), &target)
+            .map_err(reqwest_middleware::Error::Middleware)?;
+        debug!(
+            "Replaying {} {} from '{}' with status {}",
+            req.method(),
+            target,
+            self.path,
+            response.status
+        );
+
+        let body = match (response.content.text, response.content.encoding.as_deref()) {
+            (Some(text), Some("base64")) => BASE64
+                .decode(text)
+                .map_err(|err| reqwest_middleware::Error::Middleware(anyhow!(err)))?,
+            (Some(text), _) => text.into_bytes(),
+            (None, _) => vec![],
+        };
+        let mut builder = http::Response::builder()
+            .status(response.status)
+            .url(req.url().clone());
+        for header in response.headers {
+            // The body is stored decoded, so its original length and encoding no longer apply
+            let name = header.name.to_lowercase();
+            if name != "content-length" && name != "transfer-encoding" {
+                builder = builder.header(header.name, header.value);
+            }
+        }
+        Ok(builder
+            .body(body)
+            .map_err(|err| reqwest_middleware::Error::Middleware(anyhow!(err)))?
+            .into())
+    }
+}
+
+#[cfg(test)]
+mod http_archive_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::{CustomHeaders, HALClient, types::ConnectionOptions};
+    use axum::Router;
+    use axum::http::{StatusCode, header::CONTENT_TYPE};
+    use axum::routing::{get, post};
+    use pact_models::http_utils::HttpAuth;
+    use pretty_assertions::assert_eq;
+    use reqwest::Method;
+    use tokio::net::TcpListener;
+
+    async fn spawn_broker() -> String {
+        let router = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    (
+                        StatusCode::OK,
+                        [(CONTENT_TYPE, "application/hal+json")],
+                        "{\"_links\":{\"pb:pacticipants\":{\"href\":\"/pacticipants\"}}}",
+                    )
+                }),
+            )
+            .route(
+                "/pacticipants",
+                get(|| async {
+                    (
+                        StatusCode::OK,
+                        [(CONTENT_TYPE, "application/hal+json")],
+                        "{\"pacticipants\":[{\"name\":\"Foo\"}]}",
+                    )
+                }),
+            )
+            .route(
+                "/webhooks",
+                post(|| async {
+                    (
+                        StatusCode::CREATED,
+                        [(CONTENT_TYPE, "application/hal+json")],
+                        "{\"description\":\"Trigger CI\"}",
+                    )
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn archive_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!(
+                "pact-broker-cli-{}-{}.har",
+                name,
+                std::process::id()
+            ))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn hal_client(base_url: &str, archive: HttpArchive) -> HALClient {
+        let connection_options = ConnectionOptions {
+            http_archive: Some(archive),
+            ..ConnectionOptions::default()
+        };
+        HALClient::with_url(
+            base_url,
+            Some(HttpAuth::Token("s3cr3t".to_string())),
+            connection_options,
+            None,
+        )
+        .with_retry_count(0)
+    }
+
+    #[tokio::test]
+    async fn records_and_replays_the_responses() {
+        let path = archive_path("record-replay");
+        let base_url = spawn_broker().await;
+
+        let client = hal_client(&base_url, HttpArchive::Record(path.clone()));
+        let recorded = client.fetch("/pacticipants").await.unwrap();
+        client.fetch("/").await.unwrap();
+
+        let har = std::fs::read_to_string(&path).unwrap();
+        assert!(!har.contains("s3cr3t"));
+        let har: Value = serde_json::from_str(&har).unwrap();
+        assert_eq!(har["log"]["entries"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            har["log"]["entries"][0]["request"]["url"],
+            format!("{}/pacticipants", base_url)
+        );
+        assert_eq!(har["log"]["entries"][0]["response"]["status"], 200);
+
+        // Nothing is listening on this port, so the responses must come from the archive
+        let client = hal_client("http://127.0.0.1:9", HttpArchive::Replay(path.clone()));
+        let replayed = client.fetch("/pacticipants").await.unwrap();
+        assert_eq!(replayed, recorded);
+        assert_eq!(replayed["pacticipants"][0]["name"], "Foo");
+
+        let missing = client.fetch("/environments").await.unwrap_err();
+        assert!(
+            missing
+                .to_string()
+                .contains("There is no response in the HTTP archive"),
+            "{}",
+            missing
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn redacts_credentials_in_headers_bodies_and_urls() {
+        let path = archive_path("redact");
+        let base_url = spawn_broker().await;
+        let connection_options = ConnectionOptions {
+            http_archive: Some(HttpArchive::Record(path.clone())),
+            ..ConnectionOptions::default()
+        };
+        let client = HALClient::with_url(
+            &base_url,
+            Some(HttpAuth::Token("s3cr3t".to_string())),
+            connection_options,
+            Some(CustomHeaders {
+                headers: HashMap::from([("X-Client-Id".to_string(), "c1ient-id".to_string())]),
+            }),
+        )
+        .with_retry_count(0);
+
+        let body = serde_json::json!({
+            "description": "Trigger CI",
+            "request": {
+                "method": "POST",
+                "url": "https://ci.example.org/build",
+                "username": "ci",
+                "password": "webh00k-pa55word",
+                "headers": { "Authorization": "Bearer webh00k-t0ken" }
+            }
+        });
+        let query = [
+            ("access_token".to_string(), "qu3ry-t0ken".to_string()),
+            ("page".to_string(), "1".to_string()),
+        ];
+        client
+            .send_request(Method::POST, "/webhooks", &query, Some(&body.to_string()))
+            .await
+            .unwrap();
+
+        let har = std::fs::read_to_string(&path).unwrap();
+        for secret in [
+            "s3cr3t",
+            "c1ient-id",
+            "webh00k-pa55word",
+            "webh00k-t0ken",
+            "qu3ry-t0ken",
+        ] {
+            assert!(!har.contains(secret), "{} was recorded", secret);
+        }
+        let har: Value = serde_json::from_str(&har).unwrap();
+        let request = &har["log"]["entries"][0]["request"];
+        assert_eq!(
+            request["url"],
+            format!("{}/webhooks?access_token=%5BREDACTED%5D&page=1", base_url)
+        );
+        assert_eq!(
+            request["queryString"],
+            serde_json::json!([
+                { "name": "access_token", "value": "[REDACTED]" },
+                { "name": "page", "value": "1" }
+            ])
+        );
+        let recorded_body: Value =
+            serde_json::from_str(request["postData"]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(recorded_body["request"]["username"], "ci");
+        assert_eq!(recorded_body["request"]["password"], "[REDACTED]");
+        assert_eq!(
+            recorded_body["request"]["headers"]["Authorization"],
+            "[REDACTED]"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Requests with credentials in the query still match the redacted entries
+        let client = hal_client("http://127.0.0.1:9", HttpArchive::Replay(path.clone()));
+        let replayed = client
+            .send_request(Method::POST, "/webhooks", &query, Some(&body.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(replayed["description"], "Trigger CI");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replays_matching_entries_in_order_then_repeats_the_last() {
+        let entry = |url: &str, status: u16| HarEntry {
+            started_date_time: String::new(),
+            time: 0.0,
+            request: HarRequest {
+                method: "GET".to_string(),
+                url: url.to_string(),
+                http_version: String::new(),
+                headers: vec![],
+                query_string: vec![],
+                cookies: vec![],
+                headers_size: -1,
+                body_size: 0,
+                post_data: None,
+            },
+            response: HarResponse {
+                status,
+                status_text: String::new(),
+                http_version: String::new(),
+                headers: vec![],
+                cookies: vec![],
+                content: HarContent {
+                    size: 0,
+                    mime_type: String::new(),
+                    text: None,
+                    encoding: None,
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: 0,
+            },
+            cache: Value::Null,
+            timings: HarTimings::default(),
+        };
+        let mut replay = Replay {
+            entries: vec![
+                entry("http://localhost/matrix?latest=true", 503),
+                entry("http://localhost/", 200),
+                entry("http://localhost/matrix?latest=true", 200),
+            ],
+            replayed: vec![false; 3],
+        };
+
+        let mut status = || {
+            replay
+                .next_response("GET", "/matrix?latest=true")
+                .map(|response| response.status)
+        };
+        assert_eq!(status(), Some(503));
+        assert_eq!(status(), Some(200));
+        assert_eq!(status(), Some(200));
+        assert!(replay.next_response("POST", "/").is_none());
+    }
+}
@@ -0,0 +1,667 @@
+//! Offline linting of pact files against a configurable set of rules.
+//!
+//! Rules are enabled with their default severity and can be tuned with a `.pactlint.toml`:
+//!
+//! ```toml
+//! [rules]
+//! missing-provider-state = "error"
+//! body-without-matchers = "off"
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use clap::ArgMatches;
+use pact_models::pact;
+use pact_models::verify_json::{PactFileVerificationResult, ResultLevel};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::PactBrokerError;
+use super::output::OutputType;
+use super::pact_publish::{almost_duplicate_message, load_files, same_description_and_state};
+use super::verification::{VerificationResult, display_results, verify_json};
+
+/// Config file picked up from the current directory when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = ".pactlint.toml";
+
+/// Severity of a lint rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The rule is disabled
+    Off,
+    /// Reported for information only
+    Notice,
+    /// Reported, but does not fail the lint
+    Warning,
+    /// Reported and fails the lint
+    Error,
+}
+
+impl Severity {
+    fn result_level(self) -> ResultLevel {
+        match self {
+            Severity::Error => ResultLevel::ERROR,
+            Severity::Warning => ResultLevel::WARNING,
+            Severity::Notice | Severity::Off => ResultLevel::NOTICE,
+        }
+    }
+
+    fn sarif_level(self) -> &'static str {
+        match self {
+            Severity::Off => "none",
+            Severity::Notice => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A lint rule
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    /// Identifier used in the config file and in reports
+    pub id: &'static str,
+    /// Short description of what the rule checks
+    pub description: &'static str,
+    /// Severity used when the config file does not mention the rule
+    pub default_severity: Severity,
+}
+
+/// All the rules known to the linter
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "pact-spec",
+        description: "Pact files must be valid for their Pact specification version",
+        default_severity: Severity::Error,
+    },
+    Rule {
+        id: "duplicate-interactions",
+        description: "Interactions with the same description and provider state must be identical",
+        default_severity: Severity::Error,
+    },
+    Rule {
+        id: "missing-provider-state",
+        description: "Interactions should declare the provider state they depend on",
+        default_severity: Severity::Warning,
+    },
+    Rule {
+        id: "hardcoded-host",
+        description: "Request paths should not include a scheme or host",
+        default_severity: Severity::Error,
+    },
+    Rule {
+        id: "body-without-matchers",
+        description: "Request, response and message bodies should use matching rules",
+        default_severity: Severity::Warning,
+    },
+];
+
+/// Contents of a `.pactlint.toml` file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// Severity overrides, keyed by rule id
+    pub rules: HashMap<String, Severity>,
+}
+
+impl LintConfig {
+    /// Parse a config file, rejecting rule ids the linter does not know about
+    pub fn parse(contents: &str) -> Result<LintConfig, PactBrokerError> {
+        let config: LintConfig = toml::from_str(contents)
+            .map_err(|err| PactBrokerError::ValidationError(vec![err.to_string()]))?;
+        let unknown: Vec<String> = config
+            .rules
+            .keys()
+            .filter(|id| !RULES.iter().any(|rule| rule.id == id.as_str()))
+            .map(|id| format!("Unknown lint rule '{}'", id))
+            .collect();
+        if unknown.is_empty() {
+            Ok(config)
+        } else {
+            Err(PactBrokerError::ValidationError(unknown))
+        }
+    }
+
+    /// Load the config file given on the command line, or `.pactlint.toml` if it exists
+    pub fn load(path: Option<&String>) -> Result<LintConfig, PactBrokerError> {
+        let path = match path {
+            Some(path) => Path::new(path),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Path::new(DEFAULT_CONFIG_FILE),
+            None => return Ok(LintConfig::default()),
+        };
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            PactBrokerError::IoError(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        LintConfig::parse(&contents)
+    }
+
+    /// The severity to report a rule with
+    pub fn severity(&self, rule: &Rule) -> Severity {
+        self.rules
+            .get(rule.id)
+            .copied()
+            .unwrap_or(rule.default_severity)
+    }
+}
+
+/// A single problem found by the linter
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintFinding {
+    /// Id of the rule that was broken
+    pub rule: String,
+    /// Severity the finding is reported with
+    pub severity: Severity,
+    /// The pact file
+    pub source: String,
+    /// JSON path in the pact file
+    pub path: String,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Findings for all the linted files
+#[derive(Debug, Clone, Serialize)]
+pub struct LintReport {
+    /// The pact files that were linted
+    pub files: Vec<String>,
+    /// Problems found, in file order
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// If any finding has error severity
+    pub fn has_errors(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Error)
+    }
+}
+
+/// A rule check, returning the JSON path and message of each problem found
+type Check = fn(&[(String, &Value)]) -> Vec<(String, String)>;
+
+fn rule(id: &str) -> &'static Rule {
+    RULES.iter().find(|rule| rule.id == id).unwrap()
+}
+
+fn interactions(pact_json: &Value) -> Vec<(String, &Value)> {
+    ["interactions", "messages"]
+        .iter()
+        .filter_map(|key| {
+            pact_json
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| (key, items))
+        })
+        .flat_map(|(key, items)| {
+            items
+                .iter()
+                .enumerate()
+                .map(move |(index, item)| (format!("/{}/{}", key, index), item))
+        })
+        .collect()
+}
+
+fn description(interaction: &Value) -> &str {
+    interaction
+        .get("description")
+        .and_then(|d| d.as_str())
+        .unwrap_or("unknown")
+}
+
+fn check_duplicate_interactions(interactions: &[(String, &Value)]) -> Vec<(String, String)> {
+    let mut problems = vec![];
+    for (index, (path, interaction)) in interactions.iter().enumerate() {
+        if let Some((_, original)) = interactions[..index].iter().find(|(_, original)| {
+            same_description_and_state(original, interaction) && original != interaction
+        }) {
+            problems.push((
+                path.clone(),
+                almost_duplicate_message(original, interaction),
+            ));
+        }
+    }
+    problems
+}
+
+fn check_missing_provider_state(interactions: &[(String, &Value)]) -> Vec<(String, String)> {
+    interactions
+        .iter()
+        .filter(|(_, interaction)| {
+            let has_state = interaction
+                .get("providerState")
+                .and_then(|s| s.as_str())
+                .map(|s| !s.is_empty())
+                .unwrap_or(false);
+            let has_states = interaction
+                .get("providerStates")
+                .and_then(|s| s.as_array())
+                .map(|s| !s.is_empty())
+                .unwrap_or(false);
+            !has_state && !has_states
+        })
+        .map(|(path, interaction)| {
+            (
+                path.clone(),
+                format!(
+                    "Interaction \"{}\" does not declare a provider state",
+                    description(interaction)
+                ),
+            )
+        })
+        .collect()
+}
+
+fn check_hardcoded_host(interactions: &[(String, &Value)]) -> Vec<(String, String)> {
+    let host = Regex::new(r"^([a-zA-Z][a-zA-Z0-9+.-]*:)?//").unwrap();
+    interactions
+        .iter()
+        .filter_map(|(path, interaction)| {
+            let request_path = interaction.get("request")?.get("path")?.as_str()?;
+            host.is_match(request_path).then(|| {
+                (
+                    format!("{}/request/path", path),
+                    format!(
+                        "Request path \"{}\" of interaction \"{}\" includes a host, use a path relative to the provider base URL",
+                        request_path,
+                        description(interaction)
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+fn body_content(body: &Value) -> &Value {
+    // V4 bodies are wrapped with their content type and encoding
+    match body {
+        Value::Object(map) if map.contains_key("content") && map.contains_key("contentType") => {
+            &map["content"]
+        }
+        _ => body,
+    }
+}
+
+fn has_body_matchers(matching_rules: Option<&Value>) -> bool {
+    match matching_rules.and_then(|rules| rules.as_object()) {
+        Some(rules) => rules.iter().any(|(key, value)| {
+            let is_body_key = key.starts_with("$.body")
+                || key.starts_with("$.contents")
+                || key == "body"
+                || key == "content";
+            let has_rules = value.as_object().map(|v| !v.is_empty()).unwrap_or(true);
+            is_body_key && has_rules
+        }),
+        None => false,
+    }
+}
+
+fn check_body_without_matchers(interactions: &[(String, &Value)]) -> Vec<(String, String)> {
+    let mut problems = vec![];
+    for (path, interaction) in interactions {
+        let parts = [
+            ("Request", "request", interaction.get("request")),
+            ("Response", "response", interaction.get("response")),
+            ("Message", "", Some(*interaction)),
+        ];
+        for (label, part_path, part) in parts {
+            let Some(part) = part.filter(|p| p.is_object()) else {
+                continue;
+            };
+            let body_key = if part.get("body").is_some() {
+                "body"
+            } else {
+                "contents"
+            };
+            let has_structured_body = part
+                .get(body_key)
+                .map(body_content)
+                .map(|body| match body {
+                    Value::Object(map) => !map.is_empty(),
+                    Value::Array(items) => !items.is_empty(),
+                    _ => false,
+                })
+                .unwrap_or(false);
+            if has_structured_body && !has_body_matchers(part.get("matchingRules")) {
+                let json_path = if part_path.is_empty() {
+                    format!("{}/{}", path, body_key)
+                } else {
+                    format!("{}/{}/{}", path, part_path, body_key)
+                };
+                problems.push((
+                    json_path,
+                    format!(
+                        "{} body of interaction \"{}\" has no matching rules, so the pact will only match these exact values",
+                        label,
+                        description(interaction)
+                    ),
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// Lint a single pact file
+pub fn lint_pact(
+    source: &str,
+    pact_json: &Value,
+    config: &LintConfig,
+    strict: bool,
+) -> Vec<LintFinding> {
+    let mut findings = vec![];
+    let finding = |id: &str, severity: Severity, path: String, message: String| LintFinding {
+        rule: id.to_string(),
+        severity,
+        source: source.to_string(),
+        path,
+        message,
+    };
+
+    let spec_severity = config.severity(rule("pact-spec"));
+    if spec_severity != Severity::Off {
+        let spec_version = pact::determine_spec_version(source, &pact::parse_meta_data(pact_json));
+        for result in verify_json(pact_json, spec_version, source, strict) {
+            let severity = match result.level {
+                ResultLevel::ERROR => spec_severity,
+                ResultLevel::WARNING => Severity::Warning,
+                ResultLevel::NOTICE => Severity::Notice,
+            };
+            findings.push(finding("pact-spec", severity, result.path, result.message));
+        }
+    }
+
+    let interactions = interactions(pact_json);
+    let checks: [(&str, Check); 4] = [
+        ("duplicate-interactions", check_duplicate_interactions),
+        ("missing-provider-state", check_missing_provider_state),
+        ("hardcoded-host", check_hardcoded_host),
+        ("body-without-matchers", check_body_without_matchers),
+    ];
+    for (id, check) in checks {
+        let severity = config.severity(rule(id));
+        if severity == Severity::Off {
+            continue;
+        }
+        for (path, message) in check(&interactions) {
+            findings.push(finding(id, severity, path, message));
+        }
+    }
+
+    findings
+}
+
+/// Group findings per file in the shape used by `publish --validate`
+pub fn to_verification_results(report: &LintReport) -> Vec<VerificationResult> {
+    report
+        .files
+        .iter()
+        .map(|source| {
+            let results = report
+                .findings
+                .iter()
+                .filter(|finding| &finding.source == source)
+                .map(|finding| {
+                    PactFileVerificationResult::new(
+                        finding.path.clone(),
+                        finding.severity.result_level(),
+                        format!("[{}] {}", finding.rule, finding.message),
+                    )
+                })
+                .collect();
+            VerificationResult::new(source, results)
+        })
+        .collect()
+}
+
+/// Build a SARIF 2.1.0 log of the findings, as accepted by GitHub code scanning
+pub fn build_sarif(report: &LintReport, config: &LintConfig) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": config.severity(rule).sarif_level() }
+            })
+        })
+        .collect();
+    let results: Vec<Value> = report
+        .findings
+        .iter()
+        .map(|finding| {
+            json!({
+                "ruleId": finding.rule,
+                "ruleIndex": RULES.iter().position(|rule| rule.id == finding.rule),
+                "level": finding.severity.sarif_level(),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": finding.source.replace('\\', "/").trim_start_matches("./")
+                        },
+                        "region": { "startLine": 1 }
+                    },
+                    "logicalLocations": [{ "fullyQualifiedName": finding.path }]
+                }]
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "pact-broker-cli",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/pact-foundation/pact-broker-cli",
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    })
+}
+
+/// Lint the pact files given on the command line and print the findings
+pub fn lint(args: &ArgMatches) -> Result<LintReport, PactBrokerError> {
+    let config = LintConfig::load(args.get_one::<String>("config"))?;
+    let strict = args.get_flag("strict");
+    let files = load_files(args).map_err(|err| PactBrokerError::IoError(err.to_string()))?;
+
+    let report = LintReport {
+        files: files.iter().map(|(source, _)| source.clone()).collect(),
+        findings: files
+            .iter()
+            .flat_map(|(source, pact_json)| lint_pact(source, pact_json, &config, strict))
+            .collect(),
+    };
+
+    let output_type = args
+        .get_one::<String>("output")
+        .map(|s| s.as_str())
+        .unwrap_or("text");
+    if output_type == "sarif" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&build_sarif(&report, &config)).unwrap()
+        );
+    } else {
+        display_results(
+            &to_verification_results(&report),
+            &OutputType::from_args(args),
+        )
+        .map_err(|err| PactBrokerError::ContentError(err.to_string()))?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::subcommands::add_lint_subcommand;
+    use pretty_assertions::assert_eq;
+
+    fn pact() -> Value {
+        json!({
+            "consumer": { "name": "Consumer" },
+            "provider": { "name": "Provider" },
+            "interactions": [
+                {
+                    "description": "a request for an order",
+                    "providerState": "an order exists",
+                    "request": { "method": "GET", "path": "/orders/1" },
+                    "response": {
+                        "status": 200,
+                        "body": { "id": 1 },
+                        "matchingRules": { "$.body.id": { "match": "type" } }
+                    }
+                },
+                {
+                    "description": "a request for an order",
+                    "providerState": "an order exists",
+                    "request": { "method": "GET", "path": "/orders/2" },
+                    "response": { "status": 200 }
+                },
+                {
+                    "description": "a request for all orders",
+                    "request": { "method": "GET", "path": "http://localhost:8080/orders" },
+                    "response": { "status": 200, "body": [{ "id": 1 }] }
+                }
+            ],
+            "metadata": { "pactSpecification": { "version": "2.0.0" } }
+        })
+    }
+
+    fn rules_found(findings: &[LintFinding]) -> Vec<(String, String)> {
+        findings
+            .iter()
+            .filter(|f| f.rule != "pact-spec")
+            .map(|f| (f.rule.clone(), f.path.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_rule_with_default_severity() {
+        let findings = lint_pact("pact.json", &pact(), &LintConfig::default(), false);
+
+        assert_eq!(
+            rules_found(&findings),
+            vec![
+                (
+                    "duplicate-interactions".to_string(),
+                    "/interactions/1".to_string()
+                ),
+                (
+                    "missing-provider-state".to_string(),
+                    "/interactions/2".to_string()
+                ),
+                (
+                    "hardcoded-host".to_string(),
+                    "/interactions/2/request/path".to_string()
+                ),
+                (
+                    "body-without-matchers".to_string(),
+                    "/interactions/2/response/body".to_string()
+                ),
+            ]
+        );
+        let missing_state = findings
+            .iter()
+            .find(|f| f.rule == "missing-provider-state")
+            .unwrap();
+        assert_eq!(missing_state.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn config_overrides_severity_and_disables_rules() {
+        let config = LintConfig::parse(
+            r#"
+            [rules]
+            missing-provider-state = "error"
+            hardcoded-host = "off"
+            body-without-matchers = "off"
+            duplicate-interactions = "off"
+            "#,
+        )
+        .unwrap();
+        let findings = lint_pact("pact.json", &pact(), &config, false);
+
+        assert_eq!(
+            rules_found(&findings),
+            vec![(
+                "missing-provider-state".to_string(),
+                "/interactions/2".to_string()
+            )]
+        );
+        assert_eq!(findings.last().unwrap().severity, Severity::Error);
+    }
+
+    #[test]
+    fn config_rejects_unknown_rules() {
+        let result = LintConfig::parse("[rules]\nno-such-rule = \"error\"\n");
+
+        assert_eq!(
+            result.unwrap_err(),
+            "Unknown lint rule 'no-such-rule'".to_string()
+        );
+    }
+
+    #[test]
+    fn sarif_output_lists_rules_and_results() {
+        let report = LintReport {
+            files: vec!["./pacts/pact.json".to_string()],
+            findings: lint_pact("./pacts/pact.json", &pact(), &LintConfig::default(), false)
+                .into_iter()
+                .filter(|f| f.rule == "hardcoded-host")
+                .collect(),
+        };
+        let sarif = build_sarif(&report, &LintConfig::default());
+
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            RULES.len()
+        );
+        assert_eq!(run["results"][0]["ruleId"], "hardcoded-host");
+        assert_eq!(run["results"][0]["ruleIndex"], 3);
+        assert_eq!(run["results"][0]["level"], "error");
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "pacts/pact.json"
+        );
+    }
+
+    #[test]
+    fn lint_reads_files_and_config_from_the_command_line() {
+        let dir = std::env::temp_dir().join(format!("pactlint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pact_file = dir.join("pact.json");
+        std::fs::write(&pact_file, pact().to_string()).unwrap();
+        let config_file = dir.join(".pactlint.toml");
+        std::fs::write(
+            &config_file,
+            "[rules]\nduplicate-interactions = \"warning\"\nhardcoded-host = \"warning\"\n",
+        )
+        .unwrap();
+
+        let matches = add_lint_subcommand().get_matches_from(vec![
+            "lint",
+            pact_file.to_str().unwrap(),
+            "--config",
+            config_file.to_str().unwrap(),
+        ]);
+        let report = lint(&matches).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.files.len(), 1);
+        assert!(!report.has_errors());
+        assert_eq!(report.findings.len(), 4);
+    }
+}
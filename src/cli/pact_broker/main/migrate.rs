@@ -0,0 +1,1070 @@
+//! Copies the history held by one Pact Broker into another.
+//!
+//! Everything is copied in dependency order: environments, pacticipants, versions with their
+//! branches, tags and pacts, verification results, deployed and released versions, and finally
+//! webhooks. Resources that already exist in the destination are left alone, and each copied
+//! resource is written to a progress file so an interrupted migration carries on where it
+//! stopped.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64;
+use futures::TryStreamExt;
+use pact_models::http_utils::HttpAuth;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::cli::pact_broker::main::{
+    HALClient, PactBrokerError,
+    client::{CreateEnvironmentRequest, Environment, PactBrokerClient, RecordDeploymentRequest},
+    output::{self, OutputType},
+    types::{BrokerDetails, Pagination},
+    utils::{get_broker_relation, get_connection_options, get_retries},
+    webhooks::{
+        export::{exportable_webhook, masked_headers},
+        list::{fetch_webhooks, webhook_uuid},
+    },
+};
+use crate::cli::utils;
+
+/// The keys of the resources copied so far. Only kept in memory when there is no file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MigrationProgress {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    completed: BTreeSet<String>,
+}
+
+impl MigrationProgress {
+    /// Loads the progress of an earlier run, or starts afresh if the file does not exist
+    pub fn load(path: &Path) -> Result<MigrationProgress, PactBrokerError> {
+        let mut progress = if path.exists() {
+            let contents = std::fs::read_to_string(path).map_err(|err| {
+                PactBrokerError::IoError(format!(
+                    "Failed to read the progress file {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            serde_json::from_str(&contents).map_err(|err| {
+                PactBrokerError::ContentError(format!(
+                    "Failed to parse the progress file {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?
+        } else {
+            MigrationProgress::default()
+        };
+        progress.path = Some(path.to_path_buf());
+        Ok(progress)
+    }
+
+    pub fn is_done(&self, key: &str) -> bool {
+        self.completed.contains(key)
+    }
+
+    fn mark_done(&mut self, key: String) -> Result<(), PactBrokerError> {
+        if !self.completed.insert(key) {
+            return Ok(());
+        }
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_string_pretty(self).unwrap()).map_err(|err| {
+                PactBrokerError::IoError(format!(
+                    "Failed to write the progress file {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// How many of each kind of resource were copied
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationSummary {
+    pub environments: usize,
+    pub pacticipants: usize,
+    pub versions: usize,
+    pub pacts: usize,
+    pub verification_results: usize,
+    pub deployed_versions: usize,
+    pub released_versions: usize,
+    pub webhooks: usize,
+    /// Resources that were copied by an earlier run, or already existed in the destination
+    pub skipped: usize,
+    /// The UUIDs of the copied webhooks that use basic auth. The broker never returns webhook
+    /// passwords, so these are copied without one and need it set again in the destination.
+    pub webhooks_without_password: Vec<String>,
+    /// The UUIDs of the copied webhooks with headers, such as `Authorization`, whose values the
+    /// broker returns masked. These are copied without those headers, which need to be set again.
+    pub webhooks_with_masked_headers: Vec<String>,
+}
+
+impl std::fmt::Display for MigrationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} environments, {} pacticipants, {} versions, {} pacts, {} verification results, {} deployed versions, {} released versions and {} webhooks ({} skipped)",
+            self.environments,
+            self.pacticipants,
+            self.versions,
+            self.pacts,
+            self.verification_results,
+            self.deployed_versions,
+            self.released_versions,
+            self.webhooks,
+            self.skipped
+        )
+    }
+}
+
+pub(crate) fn link(resource: &Value, relation: &str) -> Option<String> {
+    resource["_links"][relation]["href"]
+        .as_str()
+        .map(str::to_string)
+}
+
+fn names(values: &Value) -> Vec<String> {
+    values
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|value| value["name"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// Fetches every embedded resource of a collection, following `next` links through the pages
+pub(crate) async fn fetch_all(
+    hal_client: &HALClient,
+    href: &str,
+    embedded: &str,
+) -> Result<Vec<Value>, PactBrokerError> {
+    hal_client
+        .fetch_pages(href)
+        .map_ok(|page| {
+            page["_embedded"][embedded]
+                .as_array()
+                .or_else(|| page[embedded].as_array())
+                .cloned()
+                .unwrap_or_default()
+        })
+        .try_concat()
+        .await
+}
+
+/// Fetches every embedded resource of the collection linked from the index with the relation
+pub(crate) async fn fetch_all_from_index(
+    hal_client: &HALClient,
+    broker_url: &str,
+    relation: &str,
+    embedded: &str,
+) -> Result<Vec<Value>, PactBrokerError> {
+    let href = get_broker_relation(
+        hal_client.clone(),
+        relation.to_string(),
+        broker_url.to_string(),
+    )
+    .await?;
+    fetch_all(hal_client, &href, embedded).await
+}
+
+struct Migration<'a> {
+    from: PactBrokerClient,
+    to: PactBrokerClient,
+    progress: &'a mut MigrationProgress,
+    summary: MigrationSummary,
+    print_progress: bool,
+}
+
+impl Migration<'_> {
+    /// Whether an earlier run has already copied the resource
+    fn skip(&mut self, key: &str) -> bool {
+        let done = self.progress.is_done(key);
+        if done {
+            self.summary.skipped += 1;
+        }
+        done
+    }
+
+    fn copied(&mut self, key: String) -> Result<(), PactBrokerError> {
+        if self.print_progress {
+            println!("  ✅ {}", key);
+        }
+        self.progress.mark_done(key)
+    }
+
+    async fn environments(&mut self) -> Result<Vec<Environment>, PactBrokerError> {
+        let source = self.from.list_environments().await?;
+        let existing = self.to.list_environments().await?;
+        for environment in &source {
+            let key = format!("environment:{}", environment.name);
+            if self.skip(&key) {
+                continue;
+            }
+            if existing.iter().any(|e| e.name == environment.name) {
+                self.summary.skipped += 1;
+                self.progress.mark_done(key)?;
+                continue;
+            }
+            self.to
+                .create_environment(&CreateEnvironmentRequest {
+                    name: environment.name.clone(),
+                    display_name: environment.display_name.clone(),
+                    production: environment.production,
+                    contacts: environment.contacts.clone().unwrap_or_default(),
+                })
+                .await?;
+            self.summary.environments += 1;
+            self.copied(key)?;
+        }
+        Ok(source)
+    }
+
+    async fn pacticipants(&mut self) -> Result<Vec<Value>, PactBrokerError> {
+        let source = fetch_all_from_index(
+            self.from.hal_client(),
+            self.from.broker_url(),
+            "pb:pacticipants",
+            "pacticipants",
+        )
+        .await?;
+
+        for pacticipant in &source {
+            let Some(name) = pacticipant["name"].as_str() else {
+                continue;
+            };
+            let key = format!("pacticipant:{}", name);
+            if self.skip(&key) {
+                continue;
+            }
+            upsert_pacticipant(&self.to, pacticipant).await?;
+            self.summary.pacticipants += 1;
+            self.copied(key)?;
+        }
+        Ok(source)
+    }
+
+    /// Copies the versions of each pacticipant, oldest first, and returns the consumer versions
+    /// that have pacts
+    async fn versions(
+        &mut self,
+        pacticipants: &[Value],
+    ) -> Result<Vec<(String, String)>, PactBrokerError> {
+        let mut with_pacts = vec![];
+        for pacticipant in pacticipants {
+            let Some(name) = pacticipant["name"].as_str() else {
+                continue;
+            };
+            let versions_href = link(pacticipant, "pb:versions")
+                .unwrap_or_else(|| resource_url(&self.from, &["pacticipants", name, "versions"]));
+            let mut versions =
+                fetch_all(self.from.hal_client(), &versions_href, "versions").await?;
+            versions.sort_by(|a, b| a["createdAt"].as_str().cmp(&b["createdAt"].as_str()));
+
+            for summary in versions {
+                let Some(number) = summary["number"].as_str() else {
+                    continue;
+                };
+                let version = match link(&summary, "self") {
+                    Some(href) => self.from.hal_client().fetch(&href).await?,
+                    None => summary.clone(),
+                };
+                let pacts: Vec<String> = version["_links"]["pb:pact-versions"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|pact| pact["href"].as_str().map(str::to_string))
+                    .collect();
+                if !pacts.is_empty() {
+                    with_pacts.push((name.to_string(), number.to_string()));
+                }
+                let key = format!("version:{}:{}", name, number);
+                if self.skip(&key) {
+                    continue;
+                }
+                let mut contents = vec![];
+                for href in &pacts {
+                    contents.push(self.from.hal_client().fetch(href).await?);
+                }
+                create_version(&self.to, name, number, &version, &contents).await?;
+                self.summary.versions += 1;
+                self.summary.pacts += contents.len();
+                self.copied(key)?;
+            }
+        }
+        Ok(with_pacts)
+    }
+
+    async fn verification_results(
+        &mut self,
+        consumer_versions: &[(String, String)],
+    ) -> Result<(), PactBrokerError> {
+        for (consumer, number) in consumer_versions {
+            let mut source = vec![];
+            for (provider, provider_version, result) in
+                verified_rows(&self.from, consumer, number).await?
+            {
+                let key = format!(
+                    "verification-result:{}:{}:{}:{}",
+                    consumer, number, provider, provider_version
+                );
+                if !self.skip(&key) {
+                    source.push((key, provider, provider_version, result));
+                }
+            }
+            if source.is_empty() {
+                continue;
+            }
+            let existing = verified_rows(&self.to, consumer, number).await?;
+            for (key, provider, provider_version, result) in source {
+                if existing
+                    .iter()
+                    .any(|(p, v, _)| *p == provider && *v == provider_version)
+                {
+                    self.summary.skipped += 1;
+                    self.progress.mark_done(key)?;
+                    continue;
+                }
+                let result = match link(&result, "self") {
+                    Some(href) => self.from.hal_client().fetch(&href).await?,
+                    None => result,
+                };
+                publish_verification_result(
+                    &self.to,
+                    consumer,
+                    number,
+                    &provider,
+                    &provider_version,
+                    &result,
+                )
+                .await?;
+                self.summary.verification_results += 1;
+                self.copied(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the versions currently deployed to, or released and supported in, each environment
+    async fn deployments(&mut self, environments: &[Environment]) -> Result<(), PactBrokerError> {
+        let targets = self.to.list_environments().await?;
+        for environment in environments {
+            let Some(target) = targets.iter().find(|t| t.name == environment.name) else {
+                continue;
+            };
+            let source_env = self
+                .from
+                .hal_client()
+                .fetch(&resource_url(
+                    &self.from,
+                    &["environments", &environment.uuid],
+                ))
+                .await?;
+            let target_env = self
+                .to
+                .hal_client()
+                .fetch(&resource_url(&self.to, &["environments", &target.uuid]))
+                .await?;
+            for (release, relation, embedded) in [
+                (
+                    false,
+                    "pb:currently-deployed-deployed-versions",
+                    "deployedVersions",
+                ),
+                (
+                    true,
+                    "pb:currently-supported-released-versions",
+                    "releasedVersions",
+                ),
+            ] {
+                let (Some(source_href), Some(target_href)) =
+                    (link(&source_env, relation), link(&target_env, relation))
+                else {
+                    continue;
+                };
+                let source = self.from.hal_client().fetch(&source_href).await?;
+                let existing: Vec<(String, String, Option<String>)> =
+                    self.to.hal_client().fetch(&target_href).await?["_embedded"][embedded]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(deployment_identity)
+                        .collect();
+                for deployment in source["_embedded"][embedded]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                {
+                    let identity = deployment_identity(deployment);
+                    let (pacticipant, number, instance) = &identity;
+                    let key = format!(
+                        "{}:{}:{}:{}:{}",
+                        if release {
+                            "released-version"
+                        } else {
+                            "deployed-version"
+                        },
+                        environment.name,
+                        pacticipant,
+                        number,
+                        instance.as_deref().unwrap_or_default()
+                    );
+                    if self.skip(&key) {
+                        continue;
+                    }
+                    if existing.contains(&identity) {
+                        self.summary.skipped += 1;
+                        self.progress.mark_done(key)?;
+                        continue;
+                    }
+                    if release {
+                        record_release(&self.to, pacticipant, number, &environment.name).await?;
+                        self.summary.released_versions += 1;
+                    } else {
+                        self.to
+                            .record_deployment(&RecordDeploymentRequest {
+                                pacticipant: pacticipant.clone(),
+                                version: number.clone(),
+                                environment: environment.name.clone(),
+                                application_instance: instance.clone(),
+                            })
+                            .await?;
+                        self.summary.deployed_versions += 1;
+                    }
+                    self.copied(key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn webhooks(&mut self) -> Result<(), PactBrokerError> {
+        let webhooks = fetch_webhooks(
+            self.from.hal_client(),
+            self.from.broker_url(),
+            &Pagination::all(),
+        )
+        .await?;
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+        let webhook_href = get_broker_relation(
+            self.to.hal_client().clone(),
+            "pb:webhook".to_string(),
+            self.to.broker_url().to_string(),
+        )
+        .await?;
+        for webhook in &webhooks {
+            let key = format!("webhook:{}", webhook_uuid(webhook));
+            if self.skip(&key) {
+                continue;
+            }
+            put_webhook(&self.to, &webhook_href, webhook).await?;
+            self.summary.webhooks += 1;
+            if webhook["request"]["username"].is_string() {
+                self.summary
+                    .webhooks_without_password
+                    .push(webhook_uuid(webhook));
+            }
+            if !masked_headers(webhook).is_empty() {
+                self.summary
+                    .webhooks_with_masked_headers
+                    .push(webhook_uuid(webhook));
+            }
+            self.copied(key)?;
+        }
+        Ok(())
+    }
+}
+
+/// The pacticipant, version and application instance of a deployed or released version
+pub(crate) fn deployment_identity(deployment: &Value) -> (String, String, Option<String>) {
+    (
+        deployment["_embedded"]["pacticipant"]["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        deployment["_embedded"]["version"]["number"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        deployment["applicationInstance"]
+            .as_str()
+            .or_else(|| deployment["target"].as_str())
+            .map(str::to_string),
+    )
+}
+
+/// The verified integrations of a consumer version, as (provider, provider version, result)
+pub(crate) async fn verified_rows(
+    client: &PactBrokerClient,
+    consumer: &str,
+    number: &str,
+) -> Result<Vec<(String, String, Value)>, PactBrokerError> {
+    let matrix = client
+        .hal_client()
+        .fetch(&format!(
+            "{}/matrix?q[][pacticipant]={}&q[][version]={}",
+            client.broker_url(),
+            urlencoding::encode(consumer),
+            urlencoding::encode(number)
+        ))
+        .await?;
+    Ok(matrix["matrix"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|row| {
+            row["consumer"]["name"] == consumer
+                && row["consumer"]["version"]["number"] == number
+                && row["verificationResult"].is_object()
+        })
+        .filter_map(|row| {
+            Some((
+                row["provider"]["name"].as_str()?.to_string(),
+                row["provider"]["version"]["number"].as_str()?.to_string(),
+                row["verificationResult"].clone(),
+            ))
+        })
+        .collect())
+}
+
+/// The URL of a resource on a broker, from its percent-encoded path segments
+pub(crate) fn resource_url(client: &PactBrokerClient, segments: &[&str]) -> String {
+    let mut url = client.broker_url().to_string();
+    for segment in segments {
+        url.push('/');
+        url.push_str(&urlencoding::encode(segment));
+    }
+    url
+}
+
+/// Creates the pacticipant, or updates it if it already exists
+pub(crate) async fn upsert_pacticipant(
+    to: &PactBrokerClient,
+    pacticipant: &Value,
+) -> Result<(), PactBrokerError> {
+    let name = pacticipant["name"].as_str().unwrap_or_default();
+    let mut body = json!({ "name": name });
+    for field in ["displayName", "mainBranch", "repositoryUrl"] {
+        if let Some(value) = pacticipant.get(field).filter(|value| !value.is_null()) {
+            body[field] = value.clone();
+        }
+    }
+    let href = resource_url(to, &["pacticipants", name]);
+    match to.hal_client().fetch(&href).await {
+        Ok(_) => {
+            to.hal_client()
+                .patch_json(&href, &body.to_string(), None)
+                .await?;
+        }
+        Err(PactBrokerError::NotFound(_)) => {
+            let pacticipants_href = get_broker_relation(
+                to.hal_client().clone(),
+                "pb:pacticipants".to_string(),
+                to.broker_url().to_string(),
+            )
+            .await?;
+            to.hal_client()
+                .post_json(&pacticipants_href, &body.to_string(), None)
+                .await?;
+        }
+        Err(err) => return Err(err),
+    }
+    Ok(())
+}
+
+/// Creates a version with its branches and tags. When the version has pacts they are published
+/// with it, as the broker only accepts pacts through publishing.
+pub(crate) async fn create_version(
+    to: &PactBrokerClient,
+    pacticipant: &str,
+    number: &str,
+    version: &Value,
+    pacts: &[Value],
+) -> Result<(), PactBrokerError> {
+    let mut branches = names(&version["_embedded"]["branchVersions"]);
+    if branches.is_empty()
+        && let Some(branch) = version["branch"].as_str()
+    {
+        branches.push(branch.to_string());
+    }
+    let tags = names(&version["_embedded"]["tags"]);
+    let build_url = version["buildUrl"].as_str().filter(|url| !url.is_empty());
+
+    let remaining_branches = if pacts.is_empty() {
+        let mut body = json!({});
+        if let Some(build_url) = build_url {
+            body["buildUrl"] = json!(build_url);
+        }
+        let href = resource_url(to, &["pacticipants", pacticipant, "versions", number]);
+        to.hal_client()
+            .put_json(&href, &body.to_string(), None)
+            .await?;
+        for tag in &tags {
+            let href = resource_url(
+                to,
+                &["pacticipants", pacticipant, "versions", number, "tags", tag],
+            );
+            to.hal_client()
+                .put_json(&href, &json!({ "name": tag }).to_string(), None)
+                .await?;
+        }
+        &branches[..]
+    } else {
+        let contracts: Vec<Value> = pacts
+            .iter()
+            .map(|pact| {
+                let mut pact = pact.clone();
+                if let Some(pact) = pact.as_object_mut() {
+                    for key in ["_links", "_embedded", "createdAt"] {
+                        pact.remove(key);
+                    }
+                }
+                json!({
+                    "consumerName": pact["consumer"]["name"],
+                    "providerName": pact["provider"]["name"],
+                    "specification": "pact",
+                    "contentType": "application/json",
+                    "content": Base64.encode(pact.to_string()),
+                    "onConflict": "overwrite"
+                })
+            })
+            .collect();
+        let mut payload = json!({
+            "pacticipantName": pacticipant,
+            "pacticipantVersionNumber": number,
+            "tags": tags,
+            "contracts": contracts
+        });
+        if let Some(branch) = branches.first() {
+            payload["branch"] = json!(branch);
+        }
+        if let Some(build_url) = build_url {
+            payload["buildUrl"] = json!(build_url);
+        }
+        let publish_href = get_broker_relation(
+            to.hal_client().clone(),
+            "pb:publish-contracts".to_string(),
+            to.broker_url().to_string(),
+        )
+        .await?;
+        to.hal_client()
+            .post_json(&publish_href, &payload.to_string(), None)
+            .await?;
+        branches.get(1..).unwrap_or_default()
+    };
+
+    for branch in remaining_branches {
+        let href = resource_url(
+            to,
+            &[
+                "pacticipants",
+                pacticipant,
+                "branches",
+                branch,
+                "versions",
+                number,
+            ],
+        );
+        to.hal_client()
+            .put_json(&href, &json!({ "name": branch }).to_string(), None)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Publishes a verification result for the pact of a consumer version
+pub(crate) async fn publish_verification_result(
+    to: &PactBrokerClient,
+    consumer: &str,
+    number: &str,
+    provider: &str,
+    provider_version: &str,
+    result: &Value,
+) -> Result<(), PactBrokerError> {
+    let mut body = json!({
+        "success": result["success"],
+        "providerApplicationVersion": provider_version
+    });
+    for field in ["buildUrl", "testResults", "verifiedBy"] {
+        if let Some(value) = result.get(field).filter(|value| !value.is_null()) {
+            body[field] = value.clone();
+        }
+    }
+    let pact_href = resource_url(
+        to,
+        &[
+            "pacts", "provider", provider, "consumer", consumer, "version", number,
+        ],
+    );
+    let pact = to.hal_client().fetch(&pact_href).await?;
+    let publish_href = link(&pact, "pb:publish-verification-results").ok_or_else(|| {
+        PactBrokerError::LinkError(format!(
+            "The pact between {} ({}) and {} has no pb:publish-verification-results link",
+            consumer, number, provider
+        ))
+    })?;
+    to.hal_client()
+        .post_json(&publish_href, &body.to_string(), None)
+        .await?;
+    Ok(())
+}
+
+/// Records a release of a version to an environment, found by name
+pub(crate) async fn record_release(
+    to: &PactBrokerClient,
+    pacticipant: &str,
+    number: &str,
+    environment: &str,
+) -> Result<(), PactBrokerError> {
+    let version = to
+        .hal_client()
+        .fetch(&resource_url(
+            to,
+            &["pacticipants", pacticipant, "versions", number],
+        ))
+        .await?;
+    let href = version["_links"]["pb:record-release"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|link| link["name"] == environment)
+        .and_then(|link| link["href"].as_str())
+        .ok_or_else(|| {
+            PactBrokerError::NotFound(format!("Environment {} does not exist", environment))
+        })?;
+    to.hal_client().post_json(href, "{}", None).await?;
+    Ok(())
+}
+
+/// Creates or replaces a webhook, keeping its UUID. `webhook_href` is the templated pb:webhook
+/// relation of the destination.
+pub(crate) async fn put_webhook(
+    to: &PactBrokerClient,
+    webhook_href: &str,
+    webhook: &Value,
+) -> Result<(), PactBrokerError> {
+    let mut body = exportable_webhook(webhook);
+    let uuid = body["uuid"].as_str().unwrap_or_default().to_string();
+    if let Some(body) = body.as_object_mut() {
+        body.remove("uuid");
+    }
+    to.hal_client()
+        .put_json(
+            &webhook_href.replace("{uuid}", &uuid),
+            &body.to_string(),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Copies everything from one broker to another, skipping what the progress says is done
+pub async fn migrate_broker(
+    from: BrokerDetails,
+    to: BrokerDetails,
+    progress: &mut MigrationProgress,
+    print_progress: bool,
+) -> Result<MigrationSummary, PactBrokerError> {
+    let mut migration = Migration {
+        from: PactBrokerClient::new(from),
+        to: PactBrokerClient::new(to),
+        progress,
+        summary: MigrationSummary::default(),
+        print_progress,
+    };
+    let environments = migration.environments().await?;
+    let pacticipants = migration.pacticipants().await?;
+    let consumer_versions = migration.versions(&pacticipants).await?;
+    migration.verification_results(&consumer_versions).await?;
+    migration.deployments(&environments).await?;
+    migration.webhooks().await?;
+    Ok(migration.summary)
+}
+
+/// The details of one side of the migration, from its URL and credential arguments
+fn broker_details(args: &clap::ArgMatches, names: [&str; 4]) -> BrokerDetails {
+    let [url, username, password, token] = names.map(|name| args.get_one::<String>(name));
+    let details = BrokerDetails::new(url.unwrap())
+        .with_connection_options(get_connection_options(args))
+        .with_retries(get_retries(args));
+    match (token, username) {
+        (Some(token), _) => details.with_auth(HttpAuth::Token(token.clone())),
+        (None, Some(username)) => {
+            details.with_auth(HttpAuth::User(username.clone(), password.cloned()))
+        }
+        (None, None) => details,
+    }
+}
+
+pub fn migrate(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let from = broker_details(
+        args,
+        ["from", "from-username", "from-password", "from-token"],
+    );
+    let to = broker_details(args, ["to", "to-username", "to-password", "to-token"]);
+    if from.url() == to.url() {
+        return Err(PactBrokerError::ValidationError(vec![
+            "The source and destination Pact Brokers must be different".to_string(),
+        ]));
+    }
+    let progress_file = args.get_one::<String>("progress-file").unwrap();
+    let output = OutputType::from_args(args);
+    let mut progress = MigrationProgress::load(Path::new(progress_file))?;
+
+    if !output.is_structured() {
+        println!(
+            "🚚 Migrating {} to {}",
+            utils::GREEN.apply_to(from.url()),
+            utils::GREEN.apply_to(to.url())
+        );
+    }
+    let from_url = from.url().to_string();
+    let result = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(migrate_broker(
+            from,
+            to,
+            &mut progress,
+            !output.is_structured(),
+        ));
+    match result {
+        Ok(summary) => {
+            if !summary.webhooks_without_password.is_empty() {
+                eprintln!(
+                    "⚠️ The Pact Broker does not return webhook passwords, so these webhooks were copied without one. Set their passwords again in {}: {}",
+                    args.get_one::<String>("to").unwrap(),
+                    summary.webhooks_without_password.join(", ")
+                );
+            }
+            if !summary.webhooks_with_masked_headers.is_empty() {
+                eprintln!(
+                    "⚠️ The Pact Broker masks sensitive webhook header values, such as Authorization, so these webhooks were copied without those headers. Set them again in {}: {}",
+                    args.get_one::<String>("to").unwrap(),
+                    summary.webhooks_with_masked_headers.join(", ")
+                );
+            }
+            if output.is_structured() {
+                output::print(&serde_json::to_value(&summary).unwrap(), &output)
+            } else {
+                println!("✅ Migrated {}", summary);
+                Ok(serde_json::to_string(&summary).unwrap())
+            }
+        }
+        Err(err @ PactBrokerError::DryRun(_)) => Err(err),
+        Err(err) => {
+            eprintln!(
+                "❌ Migration from {} stopped. Run the command again to carry on from {}",
+                from_url, progress_file
+            );
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod migrate_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{MigrationProgress, MigrationSummary, fetch_all_from_index, migrate_broker};
+    use crate::cli::pact_broker::main::HALClient;
+    use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+    use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+    use crate::cli::pact_broker::main::types::{BrokerDetails, ConnectionOptions};
+
+    fn source_state() -> MockBrokerState {
+        serde_json::from_value::<MockBrokerState>(json!({
+            "environments": [{ "uuid": "1234", "name": "production", "production": true }],
+            "pacticipants": [{ "name": "Foo", "mainBranch": "main" }, { "name": "Bar" }],
+            "versions": [
+                { "pacticipant": "Foo", "number": "1", "branch": "main", "tags": ["prod"] },
+                { "pacticipant": "Bar", "number": "2", "branch": "main" }
+            ],
+            "pacts": [{
+                "consumer": "Foo",
+                "consumerVersion": "1",
+                "provider": "Bar",
+                "content": { "consumer": { "name": "Foo" }, "provider": { "name": "Bar" }, "interactions": [] }
+            }],
+            "verificationResults": [{
+                "consumer": "Foo", "consumerVersion": "1", "provider": "Bar", "providerVersion": "2", "success": true
+            }],
+            "deployedVersions": [{ "pacticipant": "Bar", "version": "2", "environment": "1234" }],
+            "releasedVersions": [{ "pacticipant": "Foo", "version": "1", "environment": "1234" }],
+            "webhooks": [{
+                "uuid": "abcd",
+                "description": "Notify CI",
+                "events": [{ "name": "contract_published" }],
+                "request": {
+                    "method": "POST",
+                    "url": "https://ci.example.com",
+                    "username": "ci",
+                    "headers": { "Authorization": "**********", "Accept": "application/json" }
+                }
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn copies_everything_and_skips_it_the_second_time() {
+        let (_, from_url) = spawn_mock_broker(source_state()).await;
+        let (target, to_url) = spawn_mock_broker(MockBrokerState::default()).await;
+        let from = BrokerDetails::new(&from_url).with_retries(0);
+        let to = BrokerDetails::new(&to_url).with_retries(0);
+
+        let summary = migrate_broker(
+            from.clone(),
+            to.clone(),
+            &mut MigrationProgress::default(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            MigrationSummary {
+                environments: 1,
+                pacticipants: 2,
+                versions: 2,
+                pacts: 1,
+                verification_results: 1,
+                deployed_versions: 1,
+                released_versions: 1,
+                webhooks: 1,
+                skipped: 0,
+                webhooks_without_password: vec!["abcd".to_string()],
+                webhooks_with_masked_headers: vec!["abcd".to_string()]
+            }
+        );
+
+        let state = target.snapshot();
+        assert_eq!(state.environments[0].name, "production");
+        assert_eq!(
+            state.pacticipant("Foo").unwrap().main_branch.as_deref(),
+            Some("main")
+        );
+        let version = state.version("Foo", "1").unwrap();
+        assert_eq!(version.branch.as_deref(), Some("main"));
+        assert_eq!(version.tags, vec!["prod".to_string()]);
+        assert_eq!(state.pacts[0].content["interactions"], json!([]));
+        assert_eq!(state.verification_results[0].provider_version, "2");
+        assert_eq!(state.deployed_versions[0].pacticipant, "Bar");
+        assert_eq!(state.released_versions[0].pacticipant, "Foo");
+        assert_eq!(state.webhooks[0]["uuid"], "abcd");
+        assert_eq!(
+            state.webhooks[0]["request"]["headers"],
+            json!({ "Accept": "application/json" })
+        );
+
+        // Without a progress file, existing resources in the destination are still left alone
+        let summary = migrate_broker(from, to, &mut MigrationProgress::default(), false)
+            .await
+            .unwrap();
+        assert_eq!(summary.environments, 0);
+        assert_eq!(summary.verification_results, 0);
+        assert_eq!(summary.deployed_versions, 0);
+        let again = target.snapshot();
+        assert_eq!(again.verification_results.len(), 1);
+        assert_eq!(again.deployed_versions.len(), 1);
+        assert_eq!(again.released_versions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fetches_every_page_of_a_collection_from_the_index() {
+        use axum::Router;
+        use axum::extract::{Query, State};
+        use axum::routing::get;
+        use std::collections::HashMap;
+
+        async fn index(State(base_url): State<String>) -> axum::Json<serde_json::Value> {
+            axum::Json(json!({
+                "_links": { "pb:pacticipants": { "href": format!("{}/pacticipants", base_url) } }
+            }))
+        }
+        async fn pacticipants(
+            State(base_url): State<String>,
+            Query(query): Query<HashMap<String, String>>,
+        ) -> axum::Json<serde_json::Value> {
+            axum::Json(match query.get("page").map(String::as_str) {
+                Some("2") => json!({ "_embedded": { "pacticipants": [{ "name": "Bar" }] } }),
+                _ => json!({
+                    "_embedded": { "pacticipants": [{ "name": "Foo" }] },
+                    "_links": { "next": { "href": format!("{}/pacticipants?page=2", base_url) } }
+                }),
+            })
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/", get(index))
+            .route("/pacticipants", get(pacticipants))
+            .with_state(base_url.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        let hal_client = HALClient::with_url(&base_url, None, ConnectionOptions::default(), None)
+            .with_retry_count(0);
+        let pacticipants =
+            fetch_all_from_index(&hal_client, &base_url, "pb:pacticipants", "pacticipants")
+                .await
+                .unwrap();
+
+        assert_eq!(
+            pacticipants,
+            vec![json!({ "name": "Foo" }), json!({ "name": "Bar" })]
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_progress_file() {
+        let path = std::env::temp_dir().join(format!(
+            "pact-broker-cli-migrate-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let (_, from_url) = spawn_mock_broker(source_state()).await;
+        let (target, to_url) = spawn_mock_broker(MockBrokerState::default()).await;
+
+        let mut progress = MigrationProgress::load(&path).unwrap();
+        migrate_broker(
+            BrokerDetails::new(&from_url).with_retries(0),
+            BrokerDetails::new(&to_url).with_retries(0),
+            &mut progress,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let progress = MigrationProgress::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(progress.is_done("environment:production"));
+        assert!(progress.is_done("version:Foo:1"));
+        assert!(progress.is_done("webhook:abcd"));
+
+        // A fresh destination shows that nothing recorded in the progress is copied again
+        let (fresh, fresh_url) = spawn_mock_broker(MockBrokerState::default()).await;
+        let mut progress = progress;
+        let summary = migrate_broker(
+            BrokerDetails::new(&from_url).with_retries(0),
+            BrokerDetails::new(&fresh_url).with_retries(0),
+            &mut progress,
+            false,
+        )
+        .await
+        .unwrap();
+        // Deployments are not counted, as the environments they belong to were never created
+        assert_eq!(summary.skipped, 7);
+        assert!(fresh.snapshot().environments.is_empty());
+        assert_eq!(target.snapshot().pacts.len(), 1);
+    }
+}
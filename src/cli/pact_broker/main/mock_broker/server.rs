@@ -0,0 +1,1020 @@
+//! A local HAL+JSON server implementing the parts of the Pact Broker API used by this CLI, so
+//! pipeline scripts can be run without a real broker
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::Response;
+use axum::routing::{get, post, put};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64;
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+use super::state::{
+    MockBrokerState, MockDeployment, MockEnvironment, MockPact, MockPacticipant,
+    MockVerificationResult, href, new_uuid, now, pact_href,
+};
+use crate::cli::pact_broker::main::PactBrokerError;
+use crate::cli::utils;
+
+/// The state of a running mock broker, shared between the request handlers
+pub struct MockBroker {
+    base_url: String,
+    state: Mutex<MockBrokerState>,
+}
+
+impl MockBroker {
+    pub fn new(base_url: &str, state: MockBrokerState) -> Self {
+        MockBroker {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            state: Mutex::new(state),
+        }
+    }
+
+    /// A copy of the current state
+    pub fn snapshot(&self) -> MockBrokerState {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+type Shared = Arc<MockBroker>;
+type Params = UrlPath<Vec<(String, String)>>;
+
+fn hal(status: StatusCode, body: Value) -> Response {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/hal+json;charset=utf-8")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn ok(body: Value) -> Response {
+    hal(StatusCode::OK, body)
+}
+
+fn no_content() -> Response {
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn not_found(message: String) -> Response {
+    hal(StatusCode::NOT_FOUND, json!({ "error": message }))
+}
+
+fn invalid(field: &str, message: String) -> Response {
+    hal(
+        StatusCode::BAD_REQUEST,
+        json!({ "errors": { field: [message] } }),
+    )
+}
+
+fn parse_body(body: &str) -> Result<Value, String> {
+    if body.trim().is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_str(body).map_err(|err| format!("Invalid JSON in request body - {}", err))
+}
+
+/// Looks up a path parameter. Route parameters are always present, so a missing one is a bug.
+fn param<'a>(params: &'a [(String, String)], name: &str) -> &'a str {
+    params
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default()
+}
+
+fn string_field(body: &Value, field: &str) -> Option<String> {
+    body[field].as_str().map(str::to_string)
+}
+
+/// Builds the router for the mock broker
+pub fn router(broker: Shared) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/contracts/publish", post(publish_contracts))
+        .route("/pacticipants", get(list_pacticipants).post(create_pacticipant))
+        .route(
+            "/pacticipants/{pacticipant}",
+            get(get_pacticipant)
+                .put(update_pacticipant)
+                .patch(update_pacticipant)
+                .delete(delete_pacticipant),
+        )
+        .route("/pacticipants/{pacticipant}/versions", get(list_versions))
+        .route(
+            "/pacticipants/{pacticipant}/versions/{version}",
+            get(get_version).put(put_version),
+        )
+        .route(
+            "/pacticipants/{pacticipant}/versions/{version}/tags/{tag}",
+            put(put_tag),
+        )
+        .route(
+            "/pacticipants/{pacticipant}/branches/{branch}/versions/{version}",
+            put(put_branch_version),
+        )
+        .route(
+            "/pacticipants/{pacticipant}/versions/{version}/deployed-versions/environment/{environment}",
+            post(record_deployment),
+        )
+        .route(
+            "/pacticipants/{pacticipant}/versions/{version}/released-versions/environment/{environment}",
+            post(record_release),
+        )
+        .route(
+            "/pacts/provider/{provider}/consumer/{consumer}/version/{version}",
+            get(get_pact),
+        )
+        .route(
+            "/pacts/provider/{provider}/consumer/{consumer}/version/{version}/verification-results",
+            post(publish_verification_result),
+        )
+        .route(
+            "/pacts/provider/{provider}/consumer/{consumer}/version/{version}/verification-results/{provider_version}",
+            get(get_verification_result),
+        )
+        .route("/environments", get(list_environments).post(create_environment))
+        .route(
+            "/environments/{uuid}",
+            get(get_environment)
+                .put(update_environment)
+                .delete(delete_environment),
+        )
+        .route(
+            "/environments/{uuid}/deployed-versions/currently-deployed",
+            get(currently_deployed),
+        )
+        .route(
+            "/environments/{uuid}/released-versions/currently-supported",
+            get(currently_supported),
+        )
+        .route(
+            "/deployed-versions/{uuid}",
+            get(get_deployed_version).patch(update_deployed_version),
+        )
+        .route(
+            "/released-versions/{uuid}",
+            get(get_released_version).patch(update_released_version),
+        )
+        .route("/matrix", get(matrix))
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/{uuid}",
+            get(get_webhook).put(put_webhook).delete(delete_webhook),
+        )
+        .fallback(|| async { not_found("No such resource in the mock Pact Broker".to_string()) })
+        .with_state(broker)
+}
+
+async fn index(State(broker): State<Shared>) -> Response {
+    let state = broker.state.lock().unwrap();
+    ok(state.index_json(&broker.base_url))
+}
+
+async fn publish_contracts(State(broker): State<Shared>, body: String) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let (Some(pacticipant), Some(number)) = (
+        string_field(&body, "pacticipantName"),
+        string_field(&body, "pacticipantVersionNumber"),
+    ) else {
+        return invalid(
+            "pacticipantVersionNumber",
+            "pacticipantName and pacticipantVersionNumber are required".to_string(),
+        );
+    };
+
+    let mut pacts = vec![];
+    for contract in body["contracts"].as_array().cloned().unwrap_or_default() {
+        let content = contract["content"]
+            .as_str()
+            .and_then(|content| Base64.decode(content).ok())
+            .and_then(|content| serde_json::from_slice::<Value>(&content).ok());
+        let (Some(consumer), Some(provider), Some(content)) = (
+            string_field(&contract, "consumerName"),
+            string_field(&contract, "providerName"),
+            content,
+        ) else {
+            return invalid(
+                "contracts",
+                "Each contract needs a consumerName, providerName and base64 encoded JSON content"
+                    .to_string(),
+            );
+        };
+        pacts.push(MockPact {
+            consumer,
+            consumer_version: number.clone(),
+            provider,
+            content,
+            created_at: now(),
+        });
+    }
+
+    let mut state = broker.state.lock().unwrap();
+    let version = state.ensure_version(&pacticipant, &number);
+    if let Some(branch) = string_field(&body, "branch") {
+        version.branch = Some(branch);
+    }
+    if let Some(build_url) = string_field(&body, "buildUrl") {
+        version.build_url = Some(build_url);
+    }
+    for tag in body["tags"].as_array().into_iter().flatten() {
+        if let Some(tag) = tag.as_str()
+            && !version.tags.iter().any(|t| t == tag)
+        {
+            version.tags.push(tag.to_string());
+        }
+    }
+    let version = version.clone();
+
+    let mut notices = vec![json!({
+        "type": "success",
+        "text": format!("Created {} version {}", pacticipant, number)
+    })];
+    let mut contract_links = vec![];
+    for pact in pacts {
+        notices.push(json!({
+            "type": "success",
+            "text": format!("Pact published for {} version {} and provider {}", pact.consumer, number, pact.provider)
+        }));
+        contract_links.push(json!({
+            "title": "Pact",
+            "name": format!("Pact between {} ({}) and {}", pact.consumer, number, pact.provider),
+            "href": pact_href(&broker.base_url, &pact)
+        }));
+        state.publish_pact(pact);
+    }
+
+    let version_json = state.version_json(&broker.base_url, &version);
+    let pacticipant_href = href(&broker.base_url, &["pacticipants", &pacticipant]);
+    ok(json!({
+        "_embedded": {
+            "pacticipant": { "name": pacticipant, "_links": { "self": { "href": pacticipant_href } } },
+            "version": { "number": number, "_links": { "self": version_json["_links"]["self"] } }
+        },
+        "logs": [],
+        "notices": notices,
+        "_links": {
+            "pb:pacticipant": { "title": "Pacticipant", "name": pacticipant, "href": pacticipant_href },
+            "pb:pacticipant-version": version_json["_links"]["self"],
+            "pb:pacticipant-version-tags": version.tags.iter().map(|tag| json!({ "name": tag })).collect::<Vec<_>>(),
+            "pb:contracts": contract_links
+        }
+    }))
+}
+
+async fn list_pacticipants(State(broker): State<Shared>) -> Response {
+    let state = broker.state.lock().unwrap();
+    ok(state.pacticipants_json(&broker.base_url))
+}
+
+fn apply_pacticipant_fields(pacticipant: &mut MockPacticipant, body: &Value) {
+    if let Some(display_name) = string_field(body, "displayName") {
+        pacticipant.display_name = Some(display_name);
+    }
+    if let Some(main_branch) = string_field(body, "mainBranch") {
+        pacticipant.main_branch = Some(main_branch);
+    }
+    if let Some(repository_url) = string_field(body, "repositoryUrl") {
+        pacticipant.repository_url = Some(repository_url);
+    }
+}
+
+async fn create_pacticipant(State(broker): State<Shared>, body: String) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let Some(name) = string_field(&body, "name") else {
+        return invalid("name", "Missing required parameter 'name'".to_string());
+    };
+    let mut state = broker.state.lock().unwrap();
+    let pacticipant = state.ensure_pacticipant(&name);
+    apply_pacticipant_fields(pacticipant, &body);
+    let pacticipant = pacticipant.clone();
+    hal(
+        StatusCode::CREATED,
+        state.pacticipant_json(&broker.base_url, &pacticipant),
+    )
+}
+
+async fn get_pacticipant(State(broker): State<Shared>, UrlPath(name): UrlPath<String>) -> Response {
+    let state = broker.state.lock().unwrap();
+    match state.pacticipant(&name) {
+        Some(pacticipant) => ok(state.pacticipant_json(&broker.base_url, pacticipant)),
+        None => not_found(format!("Pacticipant {} not found", name)),
+    }
+}
+
+async fn update_pacticipant(
+    State(broker): State<Shared>,
+    UrlPath(name): UrlPath<String>,
+    body: String,
+) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let mut state = broker.state.lock().unwrap();
+    let pacticipant = state.ensure_pacticipant(&name);
+    apply_pacticipant_fields(pacticipant, &body);
+    let pacticipant = pacticipant.clone();
+    ok(state.pacticipant_json(&broker.base_url, &pacticipant))
+}
+
+async fn delete_pacticipant(
+    State(broker): State<Shared>,
+    UrlPath(name): UrlPath<String>,
+) -> Response {
+    let mut state = broker.state.lock().unwrap();
+    if state.pacticipant(&name).is_none() {
+        return not_found(format!("Pacticipant {} not found", name));
+    }
+    state.remove_pacticipant(&name);
+    no_content()
+}
+
+async fn list_versions(State(broker): State<Shared>, UrlPath(name): UrlPath<String>) -> Response {
+    let state = broker.state.lock().unwrap();
+    if state.pacticipant(&name).is_none() {
+        return not_found(format!("Pacticipant {} not found", name));
+    }
+    ok(state.versions_json(&broker.base_url, &name))
+}
+
+async fn get_version(State(broker): State<Shared>, UrlPath(params): Params) -> Response {
+    let (pacticipant, number) = (param(&params, "pacticipant"), param(&params, "version"));
+    let state = broker.state.lock().unwrap();
+    match state.version(pacticipant, number) {
+        Some(version) => ok(state.version_json(&broker.base_url, version)),
+        None => not_found(format!(
+            "Version {} of pacticipant {} not found",
+            number, pacticipant
+        )),
+    }
+}
+
+async fn put_version(
+    State(broker): State<Shared>,
+    UrlPath(params): Params,
+    body: String,
+) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let mut state = broker.state.lock().unwrap();
+    let version = state.ensure_version(param(&params, "pacticipant"), param(&params, "version"));
+    if let Some(branch) = string_field(&body, "branch") {
+        version.branch = Some(branch);
+    }
+    if let Some(build_url) = string_field(&body, "buildUrl") {
+        version.build_url = Some(build_url);
+    }
+    let version = version.clone();
+    ok(state.version_json(&broker.base_url, &version))
+}
+
+async fn put_tag(State(broker): State<Shared>, UrlPath(params): Params) -> Response {
+    let tag = param(&params, "tag");
+    let mut state = broker.state.lock().unwrap();
+    let version = state.ensure_version(param(&params, "pacticipant"), param(&params, "version"));
+    if !version.tags.iter().any(|t| t == tag) {
+        version.tags.push(tag.to_string());
+    }
+    let self_href = href(
+        &broker.base_url,
+        &[
+            "pacticipants",
+            &version.pacticipant,
+            "versions",
+            &version.number,
+            "tags",
+            tag,
+        ],
+    );
+    hal(
+        StatusCode::CREATED,
+        json!({ "name": tag, "_links": { "self": { "href": self_href } } }),
+    )
+}
+
+async fn put_branch_version(State(broker): State<Shared>, UrlPath(params): Params) -> Response {
+    let branch = param(&params, "branch");
+    let mut state = broker.state.lock().unwrap();
+    let version = state.ensure_version(param(&params, "pacticipant"), param(&params, "version"));
+    version.branch = Some(branch.to_string());
+    let self_href = href(
+        &broker.base_url,
+        &[
+            "pacticipants",
+            &version.pacticipant,
+            "branches",
+            branch,
+            "versions",
+            &version.number,
+        ],
+    );
+    ok(json!({ "name": branch, "_links": { "self": { "href": self_href } } }))
+}
+
+async fn record_deployment(
+    State(broker): State<Shared>,
+    UrlPath(params): Params,
+    body: String,
+) -> Response {
+    record(broker, params, body, false)
+}
+
+async fn record_release(
+    State(broker): State<Shared>,
+    UrlPath(params): Params,
+    body: String,
+) -> Response {
+    record(broker, params, body, true)
+}
+
+fn record(broker: Shared, params: Vec<(String, String)>, body: String, release: bool) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let (pacticipant, number, environment) = (
+        param(&params, "pacticipant"),
+        param(&params, "version"),
+        param(&params, "environment"),
+    );
+    let mut state = broker.state.lock().unwrap();
+    if state.version(pacticipant, number).is_none() {
+        return not_found(format!(
+            "Version {} of pacticipant {} not found",
+            number, pacticipant
+        ));
+    }
+    if state.environment(environment).is_none() {
+        return not_found(format!("Environment {} not found", environment));
+    }
+    let deployment = state.record(
+        MockDeployment {
+            uuid: new_uuid(),
+            pacticipant: pacticipant.to_string(),
+            version: number.to_string(),
+            environment: environment.to_string(),
+            application_instance: string_field(&body, "applicationInstance")
+                .or_else(|| string_field(&body, "target")),
+            current: true,
+            created_at: now(),
+        },
+        release,
+    );
+    hal(
+        StatusCode::CREATED,
+        state.deployment_json(&broker.base_url, &deployment, release),
+    )
+}
+
+async fn get_pact(State(broker): State<Shared>, UrlPath(params): Params) -> Response {
+    let state = broker.state.lock().unwrap();
+    match find_pact(&state, &params) {
+        Some(pact) => ok(state.pact_json(&broker.base_url, pact)),
+        None => not_found("Pact not found".to_string()),
+    }
+}
+
+fn find_pact<'a>(state: &'a MockBrokerState, params: &[(String, String)]) -> Option<&'a MockPact> {
+    state.pacts.iter().find(|pact| {
+        pact.provider == param(params, "provider")
+            && pact.consumer == param(params, "consumer")
+            && pact.consumer_version == param(params, "version")
+    })
+}
+
+async fn publish_verification_result(
+    State(broker): State<Shared>,
+    UrlPath(params): Params,
+    body: String,
+) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let (Some(success), Some(provider_version)) = (
+        body["success"].as_bool(),
+        string_field(&body, "providerApplicationVersion"),
+    ) else {
+        return invalid(
+            "success",
+            "success and providerApplicationVersion are required".to_string(),
+        );
+    };
+    let mut state = broker.state.lock().unwrap();
+    let Some(pact) = find_pact(&state, &params).cloned() else {
+        return not_found("Pact not found".to_string());
+    };
+    state.ensure_version(&pact.provider, &provider_version);
+    let result = MockVerificationResult {
+        consumer: pact.consumer.clone(),
+        consumer_version: pact.consumer_version.clone(),
+        provider: pact.provider.clone(),
+        provider_version,
+        success,
+        verified_at: now(),
+    };
+    state.verification_results.push(result.clone());
+    hal(StatusCode::CREATED, result.to_json(&broker.base_url, &pact))
+}
+
+async fn get_verification_result(
+    State(broker): State<Shared>,
+    UrlPath(params): Params,
+) -> Response {
+    let state = broker.state.lock().unwrap();
+    let provider_version = param(&params, "provider_version");
+    let result = find_pact(&state, &params).and_then(|pact| {
+        state
+            .verification_results
+            .iter()
+            .rev()
+            .find(|r| r.is_for(pact) && r.provider_version == provider_version)
+            .map(|result| result.to_json(&broker.base_url, pact))
+    });
+    match result {
+        Some(result) => ok(result),
+        None => not_found("Verification result not found".to_string()),
+    }
+}
+
+async fn list_environments(State(broker): State<Shared>) -> Response {
+    let state = broker.state.lock().unwrap();
+    ok(state.environments_json(&broker.base_url))
+}
+
+fn apply_environment_fields(environment: &mut MockEnvironment, body: &Value) {
+    if let Some(name) = string_field(body, "name") {
+        environment.name = name;
+    }
+    environment.display_name = string_field(body, "displayName");
+    environment.production = body["production"].as_bool().unwrap_or_default();
+    environment.contacts = body["contacts"].as_array().cloned().unwrap_or_default();
+}
+
+async fn create_environment(State(broker): State<Shared>, body: String) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let Some(name) = string_field(&body, "name") else {
+        return invalid("name", "Missing required parameter 'name'".to_string());
+    };
+    let mut state = broker.state.lock().unwrap();
+    if state.environment_named(&name).is_some() {
+        return invalid(
+            "name",
+            format!("Another environment with name '{}' already exists.", name),
+        );
+    }
+    let mut environment = MockEnvironment {
+        uuid: new_uuid(),
+        created_at: now(),
+        ..MockEnvironment::default()
+    };
+    apply_environment_fields(&mut environment, &body);
+    state.environments.push(environment.clone());
+    hal(
+        StatusCode::CREATED,
+        state.environment_json(&broker.base_url, &environment),
+    )
+}
+
+async fn get_environment(State(broker): State<Shared>, UrlPath(uuid): UrlPath<String>) -> Response {
+    let state = broker.state.lock().unwrap();
+    match state.environment(&uuid) {
+        Some(environment) => ok(state.environment_json(&broker.base_url, environment)),
+        None => not_found(format!("Environment {} not found", uuid)),
+    }
+}
+
+async fn update_environment(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+    body: String,
+) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let mut state = broker.state.lock().unwrap();
+    let Some(environment) = state.environments.iter_mut().find(|env| env.uuid == uuid) else {
+        return not_found(format!("Environment {} not found", uuid));
+    };
+    apply_environment_fields(environment, &body);
+    let environment = environment.clone();
+    ok(state.environment_json(&broker.base_url, &environment))
+}
+
+async fn delete_environment(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+) -> Response {
+    let mut state = broker.state.lock().unwrap();
+    if state.environment(&uuid).is_none() {
+        return not_found(format!("Environment {} not found", uuid));
+    }
+    state.environments.retain(|env| env.uuid != uuid);
+    state.deployed_versions.retain(|d| d.environment != uuid);
+    state.released_versions.retain(|d| d.environment != uuid);
+    no_content()
+}
+
+async fn currently_deployed(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Response {
+    current_deployments(broker, uuid, query, false)
+}
+
+async fn currently_supported(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Response {
+    current_deployments(broker, uuid, query, true)
+}
+
+fn current_deployments(
+    broker: Shared,
+    uuid: String,
+    query: Vec<(String, String)>,
+    release: bool,
+) -> Response {
+    let state = broker.state.lock().unwrap();
+    if state.environment(&uuid).is_none() {
+        return not_found(format!("Environment {} not found", uuid));
+    }
+    let pacticipant = query
+        .iter()
+        .find(|(key, _)| key == "pacticipant")
+        .map(|(_, value)| value.as_str());
+    ok(state.current_deployments_json(&broker.base_url, &uuid, pacticipant, release))
+}
+
+async fn get_deployed_version(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+) -> Response {
+    get_deployment(broker, uuid, false)
+}
+
+async fn get_released_version(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+) -> Response {
+    get_deployment(broker, uuid, true)
+}
+
+fn get_deployment(broker: Shared, uuid: String, release: bool) -> Response {
+    let state = broker.state.lock().unwrap();
+    let deployments = if release {
+        &state.released_versions
+    } else {
+        &state.deployed_versions
+    };
+    match deployments.iter().find(|d| d.uuid == uuid) {
+        Some(deployment) => ok(state.deployment_json(&broker.base_url, deployment, release)),
+        None => not_found(format!("Deployment {} not found", uuid)),
+    }
+}
+
+async fn update_deployed_version(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+    body: String,
+) -> Response {
+    update_deployment(broker, uuid, body, false)
+}
+
+async fn update_released_version(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+    body: String,
+) -> Response {
+    update_deployment(broker, uuid, body, true)
+}
+
+/// Handles `currentlyDeployed: false` and `currentlySupported: false`
+fn update_deployment(broker: Shared, uuid: String, body: String, release: bool) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    let current_key = if release {
+        "currentlySupported"
+    } else {
+        "currentlyDeployed"
+    };
+    let mut state = broker.state.lock().unwrap();
+    let deployments = if release {
+        &mut state.released_versions
+    } else {
+        &mut state.deployed_versions
+    };
+    let Some(deployment) = deployments.iter_mut().find(|d| d.uuid == uuid) else {
+        return not_found(format!("Deployment {} not found", uuid));
+    };
+    if let Some(current) = body[current_key].as_bool() {
+        deployment.current = current;
+    }
+    let deployment = deployment.clone();
+    ok(state.deployment_json(&broker.base_url, &deployment, release))
+}
+
+async fn matrix(
+    State(broker): State<Shared>,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Response {
+    let state = broker.state.lock().unwrap();
+    match state.matrix_json(&broker.base_url, &query) {
+        Ok(matrix) => ok(matrix),
+        Err(message) => invalid("environment", message),
+    }
+}
+
+async fn list_webhooks(State(broker): State<Shared>) -> Response {
+    let state = broker.state.lock().unwrap();
+    ok(state.webhooks_json(&broker.base_url))
+}
+
+async fn create_webhook(State(broker): State<Shared>, body: String) -> Response {
+    save_webhook(broker, new_uuid(), body, StatusCode::CREATED)
+}
+
+async fn put_webhook(
+    State(broker): State<Shared>,
+    UrlPath(uuid): UrlPath<String>,
+    body: String,
+) -> Response {
+    let exists = broker.state.lock().unwrap().webhook(&uuid).is_some();
+    let status = if exists {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    save_webhook(broker, uuid, body, status)
+}
+
+fn save_webhook(broker: Shared, uuid: String, body: String, status: StatusCode) -> Response {
+    let body = match parse_body(&body) {
+        Ok(body) => body,
+        Err(message) => return invalid("body", message),
+    };
+    if body["request"]["url"].as_str().is_none() {
+        return invalid("request", "The webhook request needs a url".to_string());
+    }
+    let mut state = broker.state.lock().unwrap();
+    let webhook = state.put_webhook(&uuid, body);
+    hal(status, state.webhook_json(&broker.base_url, &webhook))
+}
+
+async fn get_webhook(State(broker): State<Shared>, UrlPath(uuid): UrlPath<String>) -> Response {
+    let state = broker.state.lock().unwrap();
+    match state.webhook(&uuid) {
+        Some(webhook) => ok(state.webhook_json(&broker.base_url, webhook)),
+        None => not_found(format!("Webhook {} not found", uuid)),
+    }
+}
+
+async fn delete_webhook(State(broker): State<Shared>, UrlPath(uuid): UrlPath<String>) -> Response {
+    let mut state = broker.state.lock().unwrap();
+    if state.webhook(&uuid).is_none() {
+        return not_found(format!("Webhook {} not found", uuid));
+    }
+    state
+        .webhooks
+        .retain(|webhook| webhook["uuid"].as_str() != Some(uuid.as_str()));
+    no_content()
+}
+
+/// Binds the listener and returns the base URL the mock broker is served from
+pub(crate) async fn bind(host: &str, port: u16) -> Result<(TcpListener, String), PactBrokerError> {
+    let listener = TcpListener::bind((host, port)).await.map_err(|err| {
+        PactBrokerError::IoError(format!("Failed to listen on {}:{} - {}", host, port, err))
+    })?;
+    let address = listener
+        .local_addr()
+        .map_err(|err| PactBrokerError::IoError(err.to_string()))?;
+    Ok((listener, format!("http://{}", address)))
+}
+
+pub fn mock_broker(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let host = args.get_one::<String>("host").unwrap();
+    let port = *args.get_one::<u16>("port").unwrap();
+    let state = match args.get_one::<String>("fixture") {
+        Some(fixture) => MockBrokerState::load_fixture(Path::new(fixture))?,
+        None => MockBrokerState::default(),
+    };
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let (listener, base_url) = bind(host, port).await?;
+        let broker = Arc::new(MockBroker::new(&base_url, state));
+        println!(
+            "🚀 Mock Pact Broker listening on {}",
+            utils::GREEN.apply_to(&base_url)
+        );
+        println!("   Press Ctrl+C to stop");
+        axum::serve(listener, router(broker))
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+            .map_err(|err| {
+                PactBrokerError::IoError(format!("Mock Pact Broker failed - {}", err))
+            })?;
+        Ok("Mock Pact Broker stopped".to_string())
+    })
+}
+
+#[cfg(test)]
+mod mock_broker_tests {
+    use std::sync::Arc;
+
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD as Base64;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::MockBroker;
+    use crate::cli::pact_broker::main::client::{
+        CanIDeployOutcome, CanIDeployRequest, CreateEnvironmentRequest, PactBrokerClient,
+        PacticipantSelector, RecordDeploymentRequest,
+    };
+    use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+    use crate::cli::pact_broker::main::types::{BrokerDetails, ConnectionOptions};
+    use crate::cli::pact_broker::main::{HALClient, test_utils};
+
+    async fn spawn_mock_broker(
+        state: MockBrokerState,
+    ) -> (Arc<MockBroker>, PactBrokerClient, HALClient) {
+        let (broker, base_url) = test_utils::spawn_mock_broker(state).await;
+        let client = PactBrokerClient::new(BrokerDetails::new(&base_url).with_retries(0));
+        let hal_client = HALClient::with_url(&base_url, None, ConnectionOptions::default(), None)
+            .with_retry_count(0);
+        (broker, client, hal_client)
+    }
+
+    fn can_i_deploy_request(pacticipant: &str, version: &str) -> CanIDeployRequest {
+        CanIDeployRequest {
+            pacticipants: vec![PacticipantSelector::version(pacticipant, version)],
+            to_environment: Some("production".to_string()),
+            ..CanIDeployRequest::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_contracts_and_records_deployments() {
+        let (broker, client, hal_client) = spawn_mock_broker(MockBrokerState::default()).await;
+        client
+            .create_environment(&CreateEnvironmentRequest {
+                name: "production".to_string(),
+                production: true,
+                ..CreateEnvironmentRequest::default()
+            })
+            .await
+            .unwrap();
+
+        let pact = json!({ "consumer": { "name": "Foo" }, "provider": { "name": "Bar" }, "interactions": [] });
+        let payload = json!({
+            "pacticipantName": "Foo",
+            "pacticipantVersionNumber": "1",
+            "branch": "main",
+            "contracts": [{
+                "consumerName": "Foo",
+                "providerName": "Bar",
+                "specification": "pact",
+                "contentType": "application/json",
+                "content": Base64.encode(pact.to_string())
+            }]
+        });
+        let published = hal_client
+            .post_json("/contracts/publish", &payload.to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(published["_embedded"]["version"]["number"], "1");
+        assert_eq!(
+            published["_links"]["pb:contracts"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Bar has not been deployed, so nothing has verified the pact in production
+        let matrix = client
+            .can_i_deploy(&can_i_deploy_request("Foo", "1"))
+            .await
+            .unwrap();
+        assert_eq!(matrix.outcome(), CanIDeployOutcome::MissingVerification);
+
+        let deployed = client
+            .record_deployment(&RecordDeploymentRequest {
+                pacticipant: "Foo".to_string(),
+                version: "1".to_string(),
+                environment: "production".to_string(),
+                application_instance: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(deployed.currently_deployed, Some(true));
+
+        let state = broker.snapshot();
+        assert_eq!(state.pacts.len(), 1);
+        assert_eq!(state.versions[0].branch.as_deref(), Some("main"));
+        assert_eq!(state.deployed_versions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn seeds_the_state_from_a_fixture() {
+        let fixture = std::env::temp_dir().join(format!(
+            "pact-broker-cli-mock-broker-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &fixture,
+            json!({
+                "environments": [{ "name": "production", "production": true }],
+                "pacts": [{ "consumer": "Foo", "consumerVersion": "1", "provider": "Bar" }],
+                "verificationResults": [{
+                    "consumer": "Foo", "consumerVersion": "1", "provider": "Bar", "providerVersion": "2", "success": true
+                }],
+                "deployedVersions": [{ "pacticipant": "Bar", "version": "2", "environment": "production" }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let state = MockBrokerState::load_fixture(&fixture).unwrap();
+        std::fs::remove_file(&fixture).unwrap();
+        assert_eq!(
+            state.deployed_versions[0].environment,
+            state.environments[0].uuid
+        );
+
+        let (_, client, _) = spawn_mock_broker(state).await;
+        let matrix = client
+            .can_i_deploy(&can_i_deploy_request("Foo", "1"))
+            .await
+            .unwrap();
+        assert_eq!(matrix.outcome(), CanIDeployOutcome::Deployable);
+        assert_eq!(matrix.matrix.len(), 1);
+        assert_eq!(
+            matrix.matrix[0].provider.version.as_ref().unwrap().number,
+            "2"
+        );
+
+        let matrix = client
+            .can_i_deploy(&can_i_deploy_request("Foo", "9"))
+            .await
+            .unwrap();
+        assert_eq!(matrix.outcome(), CanIDeployOutcome::VersionNotFound);
+    }
+
+    #[tokio::test]
+    async fn manages_webhooks() {
+        let (broker, _, hal_client) = spawn_mock_broker(MockBrokerState::default()).await;
+        let webhook = json!({
+            "description": "Notify CI",
+            "events": [{ "name": "contract_published" }],
+            "request": { "method": "POST", "url": "https://ci.example.com" }
+        });
+        let created = hal_client
+            .post_json("/webhooks", &webhook.to_string(), None)
+            .await
+            .unwrap();
+        let uuid = created["uuid"].as_str().unwrap().to_string();
+
+        let webhooks = hal_client.fetch("/webhooks").await.unwrap();
+        assert_eq!(webhooks["_links"]["pb:webhooks"][0]["name"], uuid.as_str());
+
+        hal_client
+            .clone()
+            .delete(&format!("/webhooks/{}", uuid))
+            .await
+            .unwrap();
+        assert!(broker.snapshot().webhooks.is_empty());
+        assert!(
+            hal_client
+                .fetch(&format!("/webhooks/{}", uuid))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,986 @@
+//! The resources held by the mock Pact Broker, and their HAL+JSON representations.
+//!
+//! The state can be seeded from a JSON fixture with the same shape as [`MockBrokerState`].
+//! Every field of a fixture is optional. Pacticipants and versions that are referenced by
+//! pacts, verification results or deployments are created when the fixture is loaded, and
+//! deployments may refer to an environment by its name or UUID.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::cli::pact_broker::main::PactBrokerError;
+
+pub(crate) fn new_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+pub(crate) fn now() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Builds an absolute link to a broker resource, encoding each path segment
+pub fn href(base_url: &str, segments: &[&str]) -> String {
+    let mut href = base_url.trim_end_matches('/').to_string();
+    for segment in segments {
+        href.push('/');
+        href.push_str(&urlencoding::encode(segment));
+    }
+    href
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockPacticipant {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub main_branch: Option<String>,
+    pub repository_url: Option<String>,
+    #[serde(default = "now")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockVersion {
+    pub pacticipant: String,
+    pub number: String,
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub build_url: Option<String>,
+    #[serde(default = "now")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockPact {
+    pub consumer: String,
+    pub consumer_version: String,
+    pub provider: String,
+    #[serde(default)]
+    pub content: Value,
+    #[serde(default = "now")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockVerificationResult {
+    pub consumer: String,
+    pub consumer_version: String,
+    pub provider: String,
+    pub provider_version: String,
+    pub success: bool,
+    #[serde(default = "now")]
+    pub verified_at: String,
+}
+
+impl MockVerificationResult {
+    pub fn is_for(&self, pact: &MockPact) -> bool {
+        self.consumer == pact.consumer
+            && self.consumer_version == pact.consumer_version
+            && self.provider == pact.provider
+    }
+
+    pub fn to_json(&self, base_url: &str, pact: &MockPact) -> Value {
+        json!({
+            "success": self.success,
+            "providerName": self.provider,
+            "providerApplicationVersion": self.provider_version,
+            "verifiedAt": self.verified_at,
+            "_links": { "self": { "href": verification_href(base_url, pact, &self.provider_version) } }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockEnvironment {
+    #[serde(default = "new_uuid")]
+    pub uuid: String,
+    pub name: String,
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub production: bool,
+    #[serde(default)]
+    pub contacts: Vec<Value>,
+    #[serde(default = "now")]
+    pub created_at: String,
+}
+
+/// A deployed version when `release` is false, otherwise a released version
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockDeployment {
+    #[serde(default = "new_uuid")]
+    pub uuid: String,
+    pub pacticipant: String,
+    pub version: String,
+    /// The UUID of the environment. Fixtures may also use the environment name.
+    pub environment: String,
+    pub application_instance: Option<String>,
+    /// Whether the version is still deployed, or for a release, still supported
+    #[serde(default = "default_true")]
+    pub current: bool,
+    #[serde(default = "now")]
+    pub created_at: String,
+}
+
+/// Everything the mock broker knows about
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockBrokerState {
+    pub pacticipants: Vec<MockPacticipant>,
+    pub versions: Vec<MockVersion>,
+    pub pacts: Vec<MockPact>,
+    pub verification_results: Vec<MockVerificationResult>,
+    pub environments: Vec<MockEnvironment>,
+    pub deployed_versions: Vec<MockDeployment>,
+    pub released_versions: Vec<MockDeployment>,
+    /// Webhooks are stored as they were sent, with their UUID added
+    pub webhooks: Vec<Value>,
+}
+
+impl MockBrokerState {
+    /// Loads the state from a JSON fixture file
+    pub fn load_fixture(path: &Path) -> Result<MockBrokerState, PactBrokerError> {
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            PactBrokerError::IoError(format!(
+                "Failed to read the fixture {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        let state: MockBrokerState = serde_json::from_str(&contents).map_err(|err| {
+            PactBrokerError::ContentError(format!(
+                "Failed to parse the fixture {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        state.normalised()
+    }
+
+    /// Creates any pacticipants and versions that are only referred to, and replaces
+    /// environment names in deployments with UUIDs
+    fn normalised(mut self) -> Result<MockBrokerState, PactBrokerError> {
+        let mut referenced = vec![];
+        for pact in &self.pacts {
+            referenced.push((pact.consumer.clone(), Some(pact.consumer_version.clone())));
+            referenced.push((pact.provider.clone(), None));
+        }
+        for result in &self.verification_results {
+            referenced.push((
+                result.consumer.clone(),
+                Some(result.consumer_version.clone()),
+            ));
+            referenced.push((
+                result.provider.clone(),
+                Some(result.provider_version.clone()),
+            ));
+        }
+        for version in &self.versions {
+            referenced.push((version.pacticipant.clone(), None));
+        }
+        for deployment in self.deployed_versions.iter().chain(&self.released_versions) {
+            referenced.push((
+                deployment.pacticipant.clone(),
+                Some(deployment.version.clone()),
+            ));
+        }
+        for (pacticipant, version) in referenced {
+            match version {
+                Some(number) => {
+                    self.ensure_version(&pacticipant, &number);
+                }
+                None => {
+                    self.ensure_pacticipant(&pacticipant);
+                }
+            }
+        }
+
+        for deployment in self
+            .deployed_versions
+            .iter_mut()
+            .chain(self.released_versions.iter_mut())
+        {
+            let environment = self
+                .environments
+                .iter()
+                .find(|env| {
+                    env.uuid == deployment.environment || env.name == deployment.environment
+                })
+                .ok_or_else(|| {
+                    PactBrokerError::ValidationError(vec![format!(
+                        "The fixture refers to an unknown environment '{}'",
+                        deployment.environment
+                    )])
+                })?;
+            deployment.environment = environment.uuid.clone();
+        }
+        Ok(self)
+    }
+
+    pub fn pacticipant(&self, name: &str) -> Option<&MockPacticipant> {
+        self.pacticipants.iter().find(|p| p.name == name)
+    }
+
+    pub fn ensure_pacticipant(&mut self, name: &str) -> &mut MockPacticipant {
+        let index = match self.pacticipants.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                self.pacticipants.push(MockPacticipant {
+                    name: name.to_string(),
+                    created_at: now(),
+                    ..MockPacticipant::default()
+                });
+                self.pacticipants.len() - 1
+            }
+        };
+        &mut self.pacticipants[index]
+    }
+
+    pub fn version(&self, pacticipant: &str, number: &str) -> Option<&MockVersion> {
+        self.versions
+            .iter()
+            .find(|v| v.pacticipant == pacticipant && v.number == number)
+    }
+
+    pub fn ensure_version(&mut self, pacticipant: &str, number: &str) -> &mut MockVersion {
+        self.ensure_pacticipant(pacticipant);
+        let index = match self
+            .versions
+            .iter()
+            .position(|v| v.pacticipant == pacticipant && v.number == number)
+        {
+            Some(index) => index,
+            None => {
+                self.versions.push(MockVersion {
+                    pacticipant: pacticipant.to_string(),
+                    number: number.to_string(),
+                    created_at: now(),
+                    ..MockVersion::default()
+                });
+                self.versions.len() - 1
+            }
+        };
+        &mut self.versions[index]
+    }
+
+    /// Removes a pacticipant along with everything that belongs to it
+    pub fn remove_pacticipant(&mut self, name: &str) {
+        self.pacticipants.retain(|p| p.name != name);
+        self.versions.retain(|v| v.pacticipant != name);
+        self.pacts
+            .retain(|p| p.consumer != name && p.provider != name);
+        self.verification_results
+            .retain(|r| r.consumer != name && r.provider != name);
+        self.deployed_versions.retain(|d| d.pacticipant != name);
+        self.released_versions.retain(|d| d.pacticipant != name);
+    }
+
+    /// Adds or replaces the pact for a consumer version and provider
+    pub fn publish_pact(&mut self, pact: MockPact) {
+        self.ensure_version(&pact.consumer, &pact.consumer_version);
+        self.ensure_pacticipant(&pact.provider);
+        self.pacts.retain(|p| {
+            !(p.consumer == pact.consumer
+                && p.consumer_version == pact.consumer_version
+                && p.provider == pact.provider)
+        });
+        self.pacts.push(pact);
+    }
+
+    pub fn environment(&self, uuid: &str) -> Option<&MockEnvironment> {
+        self.environments.iter().find(|env| env.uuid == uuid)
+    }
+
+    pub fn environment_named(&self, name: &str) -> Option<&MockEnvironment> {
+        self.environments.iter().find(|env| env.name == name)
+    }
+
+    /// Records a deployment or release of a version, replacing any previous deployment of the
+    /// pacticipant to the same application instance of the environment
+    pub fn record(&mut self, mut deployment: MockDeployment, release: bool) -> MockDeployment {
+        deployment.current = true;
+        if release {
+            self.released_versions.push(deployment.clone());
+        } else {
+            for previous in self.deployed_versions.iter_mut().filter(|d| {
+                d.pacticipant == deployment.pacticipant
+                    && d.environment == deployment.environment
+                    && d.application_instance == deployment.application_instance
+            }) {
+                previous.current = false;
+            }
+            self.deployed_versions.push(deployment.clone());
+        }
+        deployment
+    }
+
+    pub fn webhook(&self, uuid: &str) -> Option<&Value> {
+        self.webhooks
+            .iter()
+            .find(|webhook| webhook["uuid"].as_str() == Some(uuid))
+    }
+
+    /// Adds a webhook, or replaces the one with the same UUID
+    pub fn put_webhook(&mut self, uuid: &str, mut webhook: Value) -> Value {
+        webhook["uuid"] = json!(uuid);
+        if webhook.get("createdAt").is_none() {
+            webhook["createdAt"] = json!(now());
+        }
+        match self
+            .webhooks
+            .iter_mut()
+            .find(|existing| existing["uuid"].as_str() == Some(uuid))
+        {
+            Some(existing) => *existing = webhook.clone(),
+            None => self.webhooks.push(webhook.clone()),
+        }
+        webhook
+    }
+
+    /// The most recently created version of a pacticipant that matches
+    fn latest_version(
+        &self,
+        pacticipant: &str,
+        matches: impl Fn(&MockVersion) -> bool,
+    ) -> Option<&MockVersion> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|v| v.pacticipant == pacticipant && matches(v))
+    }
+
+    /// The versions of a pacticipant currently deployed or supported in an environment
+    fn versions_in_environment(&self, pacticipant: &str, environment: &str) -> Vec<&MockVersion> {
+        self.versions
+            .iter()
+            .filter(|version| version.pacticipant == pacticipant)
+            .filter(|version| {
+                self.deployed_versions
+                    .iter()
+                    .chain(&self.released_versions)
+                    .any(|d| {
+                        d.current
+                            && d.environment == environment
+                            && d.pacticipant == pacticipant
+                            && d.version == version.number
+                    })
+            })
+            .collect()
+    }
+
+    pub fn index_json(&self, base_url: &str) -> Value {
+        let link = |title: &str, path: &str| json!({ "title": title, "href": format!("{}{}", base_url, path) });
+        let templated = |title: &str, path: &str| json!({ "title": title, "href": format!("{}{}", base_url, path), "templated": true });
+        json!({
+            "_links": {
+                "self": link("Index", ""),
+                "pb:publish-contracts": link("Publish contracts", "/contracts/publish"),
+                "pb:pacticipants": link("Pacticipants", "/pacticipants"),
+                "pb:pacticipant": templated("Fetch pacticipant by name", "/pacticipants/{pacticipant}"),
+                "pb:pacticipant-version": templated("Fetch pacticipant version", "/pacticipants/{pacticipant}/versions/{version}"),
+                "pb:environments": link("Environments", "/environments"),
+                "pb:environment": templated("Environment", "/environments/{uuid}"),
+                "pb:webhooks": link("Webhooks", "/webhooks"),
+                "pb:webhook": templated("Webhook", "/webhooks/{uuid}"),
+                "pb:matrix": link("Matrix", "/matrix")
+            }
+        })
+    }
+
+    pub fn pacticipant_json(&self, base_url: &str, pacticipant: &MockPacticipant) -> Value {
+        let self_href = href(base_url, &["pacticipants", &pacticipant.name]);
+        json!({
+            "name": pacticipant.name,
+            "displayName": pacticipant.display_name.clone().unwrap_or_else(|| pacticipant.name.clone()),
+            "mainBranch": pacticipant.main_branch,
+            "repositoryUrl": pacticipant.repository_url,
+            "createdAt": pacticipant.created_at,
+            "_links": {
+                "self": { "href": self_href, "name": pacticipant.name },
+                "pb:versions": { "href": format!("{}/versions", self_href) }
+            }
+        })
+    }
+
+    pub fn pacticipants_json(&self, base_url: &str) -> Value {
+        let pacticipants: Vec<Value> = self
+            .pacticipants
+            .iter()
+            .map(|p| self.pacticipant_json(base_url, p))
+            .collect();
+        json!({
+            "pacticipants": pacticipants,
+            "_embedded": { "pacticipants": pacticipants },
+            "_links": { "self": { "href": href(base_url, &["pacticipants"]) } }
+        })
+    }
+
+    pub fn version_json(&self, base_url: &str, version: &MockVersion) -> Value {
+        let self_href = href(
+            base_url,
+            &[
+                "pacticipants",
+                &version.pacticipant,
+                "versions",
+                &version.number,
+            ],
+        );
+        let environment_links = |kind: &str, action: &str| -> Vec<Value> {
+            self.environments
+                .iter()
+                .map(|env| {
+                    json!({
+                        "title": format!("Record {} to {}", action, env.display_name.as_ref().unwrap_or(&env.name)),
+                        "name": env.name,
+                        "href": format!("{}/{}/environment/{}", self_href, kind, env.uuid)
+                    })
+                })
+                .collect()
+        };
+        let branch_versions: Vec<Value> = version
+            .branch
+            .iter()
+            .map(|branch| {
+                let latest = self
+                    .latest_version(&version.pacticipant, |v| v.branch.as_ref() == Some(branch));
+                json!({ "name": branch, "latest": latest == Some(version) })
+            })
+            .collect();
+        let tags: Vec<Value> = version
+            .tags
+            .iter()
+            .map(|tag| json!({ "name": tag }))
+            .collect();
+        let pacts: Vec<Value> = self
+            .pacts
+            .iter()
+            .filter(|p| p.consumer == version.pacticipant && p.consumer_version == version.number)
+            .map(|p| {
+                json!({
+                    "title": "Pact",
+                    "name": format!("Pact between {} ({}) and {}", p.consumer, p.consumer_version, p.provider),
+                    "href": pact_href(base_url, p)
+                })
+            })
+            .collect();
+        json!({
+            "number": version.number,
+            "branch": version.branch,
+            "buildUrl": version.build_url,
+            "createdAt": version.created_at,
+            "_embedded": { "branchVersions": branch_versions, "tags": tags },
+            "_links": {
+                "self": { "title": "Version", "name": version.number, "href": self_href },
+                "pb:pacticipant": {
+                    "title": "Pacticipant",
+                    "name": version.pacticipant,
+                    "href": href(base_url, &["pacticipants", &version.pacticipant])
+                },
+                "pb:pact-versions": pacts,
+                "pb:record-deployment": environment_links("deployed-versions", "deployment"),
+                "pb:record-release": environment_links("released-versions", "release")
+            }
+        })
+    }
+
+    pub fn versions_json(&self, base_url: &str, pacticipant: &str) -> Value {
+        let versions: Vec<Value> = self
+            .versions
+            .iter()
+            .filter(|v| v.pacticipant == pacticipant)
+            .map(|v| self.version_json(base_url, v))
+            .collect();
+        json!({
+            "_embedded": { "versions": versions },
+            "_links": { "self": { "href": href(base_url, &["pacticipants", pacticipant, "versions"]) } }
+        })
+    }
+
+    pub fn pact_json(&self, base_url: &str, pact: &MockPact) -> Value {
+        let mut json = if pact.content.is_object() {
+            pact.content.clone()
+        } else {
+            json!({})
+        };
+        if json.get("consumer").is_none() {
+            json["consumer"] = json!({ "name": pact.consumer });
+        }
+        if json.get("provider").is_none() {
+            json["provider"] = json!({ "name": pact.provider });
+        }
+        let self_href = pact_href(base_url, pact);
+        json["createdAt"] = json!(pact.created_at);
+        json["_links"] = json!({
+            "self": { "href": self_href },
+            "pb:publish-verification-results": {
+                "title": "Publish verification results",
+                "href": format!("{}/verification-results", self_href)
+            },
+            "pb:consumer": { "name": pact.consumer, "href": href(base_url, &["pacticipants", &pact.consumer]) },
+            "pb:provider": { "name": pact.provider, "href": href(base_url, &["pacticipants", &pact.provider]) }
+        });
+        json
+    }
+
+    pub fn environment_json(&self, base_url: &str, environment: &MockEnvironment) -> Value {
+        let self_href = href(base_url, &["environments", &environment.uuid]);
+        json!({
+            "uuid": environment.uuid,
+            "name": environment.name,
+            "displayName": environment.display_name,
+            "production": environment.production,
+            "contacts": environment.contacts,
+            "createdAt": environment.created_at,
+            "_links": {
+                "self": { "title": "Environment", "name": environment.name, "href": self_href },
+                "pb:currently-deployed-deployed-versions": {
+                    "title": "Versions currently deployed to this environment",
+                    "href": format!("{}/deployed-versions/currently-deployed", self_href)
+                },
+                "pb:currently-supported-released-versions": {
+                    "title": "Versions released and supported in this environment",
+                    "href": format!("{}/released-versions/currently-supported", self_href)
+                }
+            }
+        })
+    }
+
+    pub fn environments_json(&self, base_url: &str) -> Value {
+        let environments: Vec<Value> = self
+            .environments
+            .iter()
+            .map(|env| self.environment_json(base_url, env))
+            .collect();
+        json!({
+            "_embedded": { "environments": environments },
+            "_links": { "self": { "href": href(base_url, &["environments"]) } }
+        })
+    }
+
+    pub fn deployment_json(
+        &self,
+        base_url: &str,
+        deployment: &MockDeployment,
+        release: bool,
+    ) -> Value {
+        let (collection, current_key) = if release {
+            ("released-versions", "currentlySupported")
+        } else {
+            ("deployed-versions", "currentlyDeployed")
+        };
+        let mut json = json!({
+            "uuid": deployment.uuid,
+            "createdAt": deployment.created_at,
+            "_embedded": {
+                "pacticipant": { "name": deployment.pacticipant },
+                "version": { "number": deployment.version },
+                "environment": self.environment(&deployment.environment).map(|env| json!({ "uuid": env.uuid, "name": env.name }))
+            },
+            "_links": { "self": { "href": href(base_url, &[collection, &deployment.uuid]) } }
+        });
+        json[current_key] = json!(deployment.current);
+        if !release {
+            json["applicationInstance"] = json!(deployment.application_instance);
+            json["target"] = json!(deployment.application_instance);
+        }
+        json
+    }
+
+    /// The deployed (or released) versions that are current in an environment
+    pub fn current_deployments_json(
+        &self,
+        base_url: &str,
+        environment: &str,
+        pacticipant: Option<&str>,
+        release: bool,
+    ) -> Value {
+        let (deployments, key) = if release {
+            (&self.released_versions, "releasedVersions")
+        } else {
+            (&self.deployed_versions, "deployedVersions")
+        };
+        let current: Vec<Value> = deployments
+            .iter()
+            .filter(|d| d.current && d.environment == environment)
+            .filter(|d| pacticipant.is_none_or(|name| d.pacticipant == name))
+            .map(|d| self.deployment_json(base_url, d, release))
+            .collect();
+        let mut json = json!({ "_embedded": {} });
+        json["_embedded"][key] = json!(current);
+        json
+    }
+
+    pub fn webhook_json(&self, base_url: &str, webhook: &Value) -> Value {
+        let uuid = webhook["uuid"].as_str().unwrap_or_default();
+        let mut json = webhook.clone();
+        json["_links"] = json!({
+            "self": {
+                "title": webhook["description"].as_str().unwrap_or("Webhook"),
+                "href": href(base_url, &["webhooks", uuid])
+            }
+        });
+        json
+    }
+
+    pub fn webhooks_json(&self, base_url: &str) -> Value {
+        let links: Vec<Value> = self
+            .webhooks
+            .iter()
+            .map(|webhook| {
+                let uuid = webhook["uuid"].as_str().unwrap_or_default();
+                json!({
+                    "title": webhook["description"].as_str().unwrap_or("Webhook"),
+                    "name": uuid,
+                    "href": href(base_url, &["webhooks", uuid])
+                })
+            })
+            .collect();
+        json!({
+            "_links": {
+                "self": { "href": href(base_url, &["webhooks"]) },
+                "pb:webhooks": links
+            }
+        })
+    }
+
+    /// Answers a can-i-deploy query. The query parameters are the ones sent by
+    /// `CanIDeployRequest::matrix_path`.
+    pub fn matrix_json(&self, base_url: &str, query: &[(String, String)]) -> Result<Value, String> {
+        let query = MatrixQuery::parse(query);
+        if let Some(name) = &query.environment
+            && self.environment_named(name).is_none()
+        {
+            return Err(format!("Environment with name '{}' does not exist", name));
+        }
+        let environment = query
+            .environment
+            .as_ref()
+            .and_then(|name| self.environment_named(name))
+            .map(|env| env.uuid.clone());
+
+        let mut selected = vec![];
+        let mut missing = vec![];
+        for selector in &query.selectors {
+            match self.select_version(selector) {
+                Some(version) => selected.push(version),
+                None => missing.push(match &selector.version {
+                    Some(number) => format!(
+                        "No pacticipant version found for {} with number {}",
+                        selector.pacticipant, number
+                    ),
+                    None => format!("No matching version found for {}", selector.pacticipant),
+                }),
+            }
+        }
+        if !missing.is_empty() {
+            return Ok(json!({
+                "summary": { "deployable": null, "reason": missing.join(", "), "success": 0, "failed": 0, "unknown": 1 },
+                "notices": missing.iter().map(|text| json!({ "type": "warning", "text": text })).collect::<Vec<_>>(),
+                "matrix": []
+            }));
+        }
+
+        let ignored = |name: &str, number: &str| {
+            query
+                .ignore
+                .iter()
+                .any(|(p, v)| p == name && v.as_ref().is_none_or(|v| v == number))
+        };
+        // Versions of the other side of an integration that the selected versions must work with
+        let counterparts = |pacticipant: &str| -> Vec<&MockVersion> {
+            if let Some(version) = selected.iter().find(|v| v.pacticipant == pacticipant) {
+                return vec![version];
+            }
+            if let Some(environment) = &environment {
+                return self.versions_in_environment(pacticipant, environment);
+            }
+            let latest = if let Some(tag) = &query.tag {
+                self.latest_version(pacticipant, |v| v.tags.contains(tag))
+            } else if query.main_branch {
+                let main_branch = self
+                    .pacticipant(pacticipant)
+                    .and_then(|p| p.main_branch.clone());
+                self.latest_version(pacticipant, |v| {
+                    main_branch.is_some() && v.branch == main_branch
+                })
+            } else {
+                None
+            };
+            latest.into_iter().collect()
+        };
+
+        let mut rows = vec![];
+        for version in &selected {
+            for pact in self.pacts.iter().filter(|p| {
+                p.consumer == version.pacticipant && p.consumer_version == version.number
+            }) {
+                let providers = counterparts(&pact.provider);
+                // With nothing to compare against, use the verifications of the pact, or only the
+                // latest one with `latestby`. When the provider is missing from the target
+                // environment, the pact is unverified.
+                if providers.is_empty() {
+                    let mut verifications: Vec<&MockVerificationResult> =
+                        if environment.is_none() && query.tag.is_none() && !query.main_branch {
+                            self.verification_results
+                                .iter()
+                                .filter(|r| r.is_for(pact))
+                                .collect()
+                        } else {
+                            vec![]
+                        };
+                    if query.latestby.is_some() && verifications.len() > 1 {
+                        verifications.drain(..verifications.len() - 1);
+                    }
+                    if verifications.is_empty() {
+                        rows.push(self.matrix_row(base_url, pact, None, None));
+                    }
+                    for verification in verifications {
+                        rows.push(self.matrix_row(
+                            base_url,
+                            pact,
+                            Some(&verification.provider_version),
+                            Some(verification),
+                        ));
+                    }
+                }
+                for provider_version in providers {
+                    if ignored(&pact.provider, &provider_version.number) {
+                        continue;
+                    }
+                    let verification =
+                        self.latest_verification(pact, Some(&provider_version.number));
+                    rows.push(self.matrix_row(
+                        base_url,
+                        pact,
+                        Some(&provider_version.number),
+                        verification,
+                    ));
+                }
+            }
+            // Integrations where the selected version is the provider. Consumers that were
+            // also selected have already been covered above.
+            if query.selectors.len() > 1 {
+                continue;
+            }
+            let consumers: Vec<&str> = self
+                .pacts
+                .iter()
+                .filter(|p| p.provider == version.pacticipant)
+                .map(|p| p.consumer.as_str())
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            for consumer in consumers {
+                for consumer_version in counterparts(consumer) {
+                    if ignored(consumer, &consumer_version.number) {
+                        continue;
+                    }
+                    if let Some(pact) = self.pacts.iter().find(|p| {
+                        p.consumer == consumer
+                            && p.consumer_version == consumer_version.number
+                            && p.provider == version.pacticipant
+                    }) {
+                        let verification = self.latest_verification(pact, Some(&version.number));
+                        rows.push(self.matrix_row(
+                            base_url,
+                            pact,
+                            Some(&version.number),
+                            verification,
+                        ));
+                    }
+                }
+            }
+        }
+
+        let success = rows
+            .iter()
+            .filter(|row| row["verificationResult"]["success"] == json!(true))
+            .count();
+        let failed = rows
+            .iter()
+            .filter(|row| row["verificationResult"]["success"] == json!(false))
+            .count();
+        let unknown = rows.len() - success - failed;
+        let (deployable, reason) = if rows.is_empty() {
+            (true, "There are no missing dependencies".to_string())
+        } else if failed > 0 {
+            (
+                false,
+                "One or more of the verification results failed".to_string(),
+            )
+        } else if unknown > 0 {
+            let missing: Vec<String> = rows
+                .iter()
+                .filter(|row| row["verificationResult"].is_null())
+                .map(|row| {
+                    format!(
+                        "There is no verified pact between {} ({}) and {}",
+                        row["consumer"]["name"].as_str().unwrap_or_default(),
+                        row["consumer"]["version"]["number"]
+                            .as_str()
+                            .unwrap_or_default(),
+                        row["provider"]["name"].as_str().unwrap_or_default()
+                    )
+                })
+                .collect();
+            (false, missing.join(", "))
+        } else {
+            (
+                true,
+                "All required verification results are published and successful".to_string(),
+            )
+        };
+        Ok(json!({
+            "summary": {
+                "deployable": deployable,
+                "reason": reason,
+                "success": success,
+                "failed": failed,
+                "unknown": unknown
+            },
+            "notices": [{ "type": if deployable { "success" } else { "warning" }, "text": reason }],
+            "matrix": rows
+        }))
+    }
+
+    fn select_version(&self, selector: &MatrixSelector) -> Option<&MockVersion> {
+        let pacticipant = &selector.pacticipant;
+        if let Some(number) = &selector.version {
+            self.version(pacticipant, number)
+        } else if let Some(branch) = &selector.branch {
+            self.latest_version(pacticipant, |v| v.branch.as_ref() == Some(branch))
+        } else if selector.main_branch {
+            let main_branch = self.pacticipant(pacticipant)?.main_branch.clone();
+            self.latest_version(pacticipant, |v| {
+                main_branch.is_some() && v.branch == main_branch
+            })
+        } else if !selector.tags.is_empty() {
+            self.latest_version(pacticipant, |v| {
+                selector.tags.iter().all(|tag| v.tags.contains(tag))
+            })
+        } else {
+            self.latest_version(pacticipant, |_| true)
+        }
+    }
+
+    fn latest_verification(
+        &self,
+        pact: &MockPact,
+        provider_version: Option<&str>,
+    ) -> Option<&MockVerificationResult> {
+        self.verification_results.iter().rev().find(|r| {
+            r.is_for(pact) && provider_version.is_none_or(|number| r.provider_version == number)
+        })
+    }
+
+    fn matrix_row(
+        &self,
+        base_url: &str,
+        pact: &MockPact,
+        provider_version: Option<&str>,
+        verification: Option<&MockVerificationResult>,
+    ) -> Value {
+        json!({
+            "consumer": { "name": pact.consumer, "version": { "number": pact.consumer_version } },
+            "provider": {
+                "name": pact.provider,
+                "version": provider_version.map(|number| json!({ "number": number }))
+            },
+            "pact": {
+                "createdAt": pact.created_at,
+                "_links": { "self": { "href": pact_href(base_url, pact) } }
+            },
+            "verificationResult": verification.map(|result| result.to_json(base_url, pact))
+        })
+    }
+}
+
+pub fn pact_href(base_url: &str, pact: &MockPact) -> String {
+    href(
+        base_url,
+        &[
+            "pacts",
+            "provider",
+            &pact.provider,
+            "consumer",
+            &pact.consumer,
+            "version",
+            &pact.consumer_version,
+        ],
+    )
+}
+
+pub fn verification_href(base_url: &str, pact: &MockPact, provider_version: &str) -> String {
+    format!(
+        "{}/verification-results/{}",
+        pact_href(base_url, pact),
+        urlencoding::encode(provider_version)
+    )
+}
+
+#[derive(Debug, Default)]
+struct MatrixSelector {
+    pacticipant: String,
+    version: Option<String>,
+    branch: Option<String>,
+    tags: Vec<String>,
+    main_branch: bool,
+}
+
+#[derive(Debug, Default)]
+struct MatrixQuery {
+    selectors: Vec<MatrixSelector>,
+    ignore: Vec<(String, Option<String>)>,
+    environment: Option<String>,
+    tag: Option<String>,
+    main_branch: bool,
+    latestby: Option<String>,
+}
+
+impl MatrixQuery {
+    fn parse(query: &[(String, String)]) -> MatrixQuery {
+        let mut matrix_query = MatrixQuery::default();
+        for (key, value) in query {
+            match key.as_str() {
+                "q[][pacticipant]" => matrix_query.selectors.push(MatrixSelector {
+                    pacticipant: value.clone(),
+                    ..MatrixSelector::default()
+                }),
+                "q[][version]" | "q[][branch]" | "q[][tag]" | "q[][mainBranch]" => {
+                    if let Some(selector) = matrix_query.selectors.last_mut() {
+                        match key.as_str() {
+                            "q[][version]" => selector.version = Some(value.clone()),
+                            "q[][branch]" => selector.branch = Some(value.clone()),
+                            "q[][tag]" => selector.tags.push(value.clone()),
+                            _ => selector.main_branch = value == "true",
+                        }
+                    }
+                }
+                "ignore[][pacticipant]" => matrix_query.ignore.push((value.clone(), None)),
+                "ignore[][version]" => {
+                    if let Some(ignore) = matrix_query.ignore.last_mut() {
+                        ignore.1 = Some(value.clone());
+                    }
+                }
+                "environment" => matrix_query.environment = Some(value.clone()),
+                "tag" => matrix_query.tag = Some(value.clone()),
+                "mainBranch" => matrix_query.main_branch = value == "true",
+                "latestby" => matrix_query.latestby = Some(value.clone()),
+                _ => {}
+            }
+        }
+        matrix_query
+    }
+}
@@ -0,0 +1,433 @@
+//! OAuth2 bearer tokens for brokers behind an identity-aware proxy.
+//!
+//! A token is requested from the token endpoint with the client credentials grant or, when a
+//! subject token file is given, by exchanging the token in the file (such as the OIDC token a CI
+//! system issues to a job) with the token exchange grant of RFC 8693. Tokens are cached for the
+//! life of the process until they expire, and fetched again when the broker responds with a 401.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use http::Extensions;
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use serde::Deserialize;
+use tracing::{debug, info};
+
+const TOKEN_EXCHANGE_GRANT: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+const JWT_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:jwt";
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+/// Tokens are treated as expired this long before their actual expiry, so a token does not
+/// expire while a request is in flight
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Default, PartialEq)]
+pub struct OAuthOptions {
+    /// The token endpoint of the authorization server
+    pub token_url: String,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Space separated scopes to request
+    pub scope: Option<String>,
+    /// The audience to request the token for, which some authorization servers require
+    pub audience: Option<String>,
+    /// A file with a token to exchange for a broker token, instead of using the client
+    /// credentials grant. The file is read for each token request, so it can be rotated
+    pub subject_token_file: Option<String>,
+}
+
+impl fmt::Debug for OAuthOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuthOptions")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &self.client_secret.as_ref().map(|_| "***"))
+            .field("scope", &self.scope)
+            .field("audience", &self.audience)
+            .field("subject_token_file", &self.subject_token_file)
+            .finish()
+    }
+}
+
+impl OAuthOptions {
+    /// The form parameters of the token request
+    fn token_request(&self) -> anyhow::Result<Vec<(&'static str, String)>> {
+        let mut params = match self.subject_token_file {
+            Some(ref path) => {
+                let subject_token = std::fs::read_to_string(path).map_err(|err| {
+                    anyhow!("could not read the subject token file '{}' - {}", path, err)
+                })?;
+                let mut params = vec![
+                    ("grant_type", TOKEN_EXCHANGE_GRANT.to_string()),
+                    ("subject_token", subject_token.trim().to_string()),
+                    ("subject_token_type", JWT_TOKEN_TYPE.to_string()),
+                    ("requested_token_type", ACCESS_TOKEN_TYPE.to_string()),
+                ];
+                if self.client_secret.is_none()
+                    && let Some(ref client_id) = self.client_id
+                {
+                    params.push(("client_id", client_id.clone()));
+                }
+                params
+            }
+            None => {
+                if self.client_id.is_none() || self.client_secret.is_none() {
+                    return Err(anyhow!(
+                        "the client credentials grant needs --oauth-client-id and --oauth-client-secret, or give --oauth-subject-token-file to exchange a token"
+                    ));
+                }
+                vec![("grant_type", "client_credentials".to_string())]
+            }
+        };
+        if let Some(ref scope) = self.scope {
+            params.push(("scope", scope.clone()));
+        }
+        if let Some(ref audience) = self.audience {
+            params.push(("audience", audience.clone()));
+        }
+        Ok(params)
+    }
+
+    fn cache_key(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            self.token_url,
+            self.client_id.as_deref().unwrap_or_default(),
+            self.scope.as_deref().unwrap_or_default(),
+            self.audience.as_deref().unwrap_or_default(),
+            self.subject_token_file.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_valid(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| Instant::now() + EXPIRY_MARGIN < expires_at)
+            .unwrap_or(true)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+/// Tokens shared by all the clients of the process, as a command can create several clients
+static TOKENS: LazyLock<Mutex<HashMap<String, CachedToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn cached_token(options: &OAuthOptions) -> Option<String> {
+    TOKENS
+        .lock()
+        .unwrap()
+        .get(&options.cache_key())
+        .filter(|token| token.is_valid())
+        .map(|token| token.access_token.clone())
+}
+
+fn cache_token(options: &OAuthOptions, token: CachedToken) {
+    TOKENS.lock().unwrap().insert(options.cache_key(), token);
+}
+
+fn forget_token(options: &OAuthOptions) {
+    TOKENS.lock().unwrap().remove(&options.cache_key());
+}
+
+/// Requests a new token from the token endpoint
+async fn fetch_token(
+    client: &reqwest::Client,
+    options: &OAuthOptions,
+) -> anyhow::Result<CachedToken> {
+    info!("Fetching an OAuth2 token from {}", options.token_url);
+    let form = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(options.token_request()?)
+        .finish();
+    let mut request = client
+        .post(&options.token_url)
+        .header("accept", "application/json")
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(form);
+    if let (Some(client_id), Some(client_secret)) = (&options.client_id, &options.client_secret) {
+        request = request.basic_auth(client_id, Some(client_secret));
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "the token endpoint responded with {} {}",
+            status,
+            body
+        ));
+    }
+    let token: TokenResponse = response.json().await?;
+    debug!(
+        "Received an OAuth2 token that expires in {:?}s",
+        token.expires_in
+    );
+    Ok(CachedToken {
+        access_token: token.access_token,
+        expires_at: token
+            .expires_in
+            .map(|expires_in| Instant::now() + Duration::from_secs(expires_in)),
+    })
+}
+
+/// Middleware that sends a bearer token from the authorization server with each request. When
+/// the broker rejects the token with a 401, a new token is fetched and the request is sent again
+pub(crate) struct OAuthMiddleware {
+    pub(crate) options: OAuthOptions,
+    /// Client for the token endpoint, with the same TLS and proxy settings as the broker client
+    pub(crate) client: reqwest::Client,
+}
+
+impl OAuthMiddleware {
+    async fn token(&self, refresh: bool) -> reqwest_middleware::Result<String> {
+        if refresh {
+            forget_token(&self.options);
+        } else if let Some(token) = cached_token(&self.options) {
+            return Ok(token);
+        }
+        let token = fetch_token(&self.client, &self.options)
+            .await
+            .map_err(|err| {
+                reqwest_middleware::Error::Middleware(anyhow!(
+                    "Failed to get an OAuth2 token from {} - {}",
+                    self.options.token_url,
+                    err
+                ))
+            })?;
+        cache_token(&self.options, token.clone());
+        Ok(token.access_token)
+    }
+}
+
+fn with_bearer_token(mut req: Request, token: &str) -> reqwest_middleware::Result<Request> {
+    let value = format!("Bearer {}", token).parse().map_err(|_| {
+        reqwest_middleware::Error::Middleware(anyhow!(
+            "The OAuth2 token is not a valid header value"
+        ))
+    })?;
+    req.headers_mut()
+        .insert(reqwest::header::AUTHORIZATION, value);
+    Ok(req)
+}
+
+#[async_trait::async_trait]
+impl Middleware for OAuthMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let retry = req.try_clone();
+        let token = self.token(false).await?;
+        let response = next
+            .clone()
+            .run(with_bearer_token(req, &token)?, extensions)
+            .await?;
+
+        match retry {
+            Some(retry) if response.status() == StatusCode::UNAUTHORIZED => {
+                debug!("The broker rejected the OAuth2 token, fetching a new one");
+                let token = self.token(true).await?;
+                next.run(with_bearer_token(retry, &token)?, extensions)
+                    .await
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod oauth_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::{HALClient, types::ConnectionOptions};
+    use axum::Router;
+    use axum::http::{HeaderMap, StatusCode, header::CONTENT_TYPE};
+    use axum::routing::{get, post};
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    /// A broker that only accepts the tokens in `accepted`, and a token endpoint that issues
+    /// `token-1`, `token-2`, ... Returns the base URL and the number of tokens issued
+    async fn spawn_broker(accepted: &'static [&'static str]) -> (String, Arc<AtomicUsize>) {
+        let issued = Arc::new(AtomicUsize::new(0));
+        let count = issued.clone();
+        let router = Router::new()
+            .route(
+                "/oauth2/token",
+                post(move |headers: HeaderMap, body: String| {
+                    let count = count.clone();
+                    async move {
+                        if !body.contains("grant_type=client_credentials")
+                            || !headers.contains_key("authorization")
+                        {
+                            return (StatusCode::BAD_REQUEST, String::new());
+                        }
+                        let n = count.fetch_add(1, Ordering::SeqCst) + 1;
+                        (
+                            StatusCode::OK,
+                            format!(
+                                "{{\"access_token\":\"token-{}\",\"token_type\":\"Bearer\",\"expires_in\":3600}}",
+                                n
+                            ),
+                        )
+                    }
+                }),
+            )
+            .route(
+                "/",
+                get(move |headers: HeaderMap| async move {
+                    let authorization = headers
+                        .get("authorization")
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default();
+                    if accepted
+                        .iter()
+                        .any(|token| authorization == format!("Bearer {}", token))
+                    {
+                        (StatusCode::OK, [(CONTENT_TYPE, "application/hal+json")], "{\"_links\":{}}")
+                    } else {
+                        (StatusCode::UNAUTHORIZED, [(CONTENT_TYPE, "application/json")], "{}")
+                    }
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        (format!("http://{}", addr), issued)
+    }
+
+    fn hal_client(base_url: &str) -> HALClient {
+        let connection_options = ConnectionOptions {
+            oauth: Some(OAuthOptions {
+                token_url: format!("{}/oauth2/token", base_url),
+                ..client_credentials()
+            }),
+            ..ConnectionOptions::default()
+        };
+        HALClient::with_url(base_url, None, connection_options, None).with_retry_count(0)
+    }
+
+    fn client_credentials() -> OAuthOptions {
+        OAuthOptions {
+            token_url: "https://auth.example.com/oauth2/token".to_string(),
+            client_id: Some("pact-broker-cli".to_string()),
+            client_secret: Some("s3cr3t".to_string()),
+            scope: Some("broker:read broker:write".to_string()),
+            ..OAuthOptions::default()
+        }
+    }
+
+    #[test]
+    fn client_credentials_request() {
+        assert_eq!(
+            client_credentials().token_request().unwrap(),
+            vec![
+                ("grant_type", "client_credentials".to_string()),
+                ("scope", "broker:read broker:write".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn client_credentials_need_the_client_id_and_secret() {
+        let options = OAuthOptions {
+            client_secret: None,
+            ..client_credentials()
+        };
+
+        assert!(
+            options
+                .token_request()
+                .unwrap_err()
+                .to_string()
+                .contains("--oauth-client-secret")
+        );
+    }
+
+    #[test]
+    fn token_exchange_request() {
+        let file = std::env::temp_dir().join(format!(
+            "pact-broker-cli-oauth-subject-token-{}",
+            std::process::id()
+        ));
+        std::fs::write(&file, "ci.oidc.token\n").unwrap();
+        let options = OAuthOptions {
+            token_url: "https://auth.example.com/oauth2/token".to_string(),
+            client_id: Some("pact-broker-cli".to_string()),
+            audience: Some("https://broker.example.com".to_string()),
+            subject_token_file: Some(file.to_string_lossy().to_string()),
+            ..OAuthOptions::default()
+        };
+
+        let request = options.token_request();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(
+            request.unwrap(),
+            vec![
+                ("grant_type", TOKEN_EXCHANGE_GRANT.to_string()),
+                ("subject_token", "ci.oidc.token".to_string()),
+                ("subject_token_type", JWT_TOKEN_TYPE.to_string()),
+                ("requested_token_type", ACCESS_TOKEN_TYPE.to_string()),
+                ("client_id", "pact-broker-cli".to_string()),
+                ("audience", "https://broker.example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_expire_early() {
+        let token = |expires_in: u64| CachedToken {
+            access_token: "token".to_string(),
+            expires_at: Some(Instant::now() + Duration::from_secs(expires_in)),
+        };
+
+        assert!(token(300).is_valid());
+        assert!(!token(10).is_valid());
+    }
+
+    #[tokio::test]
+    async fn tokens_are_fetched_once_and_shared_by_clients() {
+        let (base_url, issued) = spawn_broker(&["token-1"]).await;
+
+        assert!(hal_client(&base_url).fetch("/").await.is_ok());
+        assert!(hal_client(&base_url).fetch("/").await.is_ok());
+        assert_eq!(issued.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn a_new_token_is_fetched_when_the_broker_rejects_the_token() {
+        let (base_url, issued) = spawn_broker(&["token-2"]).await;
+
+        let result = hal_client(&base_url).fetch("/").await;
+
+        assert!(result.is_ok(), "expected OK but got: {:?}", result.err());
+        assert_eq!(issued.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn the_client_secret_is_not_logged() {
+        assert!(!format!("{:?}", client_credentials()).contains("s3cr3t"));
+    }
+}
@@ -0,0 +1,413 @@
+//! Output handling shared by the commands.
+//!
+//! A command builds the JSON document for its result, and this renders it in the format chosen
+//! with `--output` (or `--template`). Structured output (`json`, `yaml`, `csv`, `id` and
+//! templates) is the only thing written to stdout; anything a command prints alongside it, such
+//! as progress or notices, goes to stderr so that the output can be piped into other tools.
+//!
+//! The JSON document of a command is the resource the Pact Broker returned for it, or for the
+//! commands that combine several requests, the serialised result type of the command.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use clap::Arg;
+use comfy_table::{Table, presets::UTF8_FULL};
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::cli::pact_broker::main::PactBrokerError;
+use crate::cli::pact_broker::main::query::Query;
+
+/// The formats every command with JSON output also supports
+pub const STRUCTURED_OUTPUTS: [&str; 4] = ["json", "yaml", "csv", "id"];
+
+/// The fields tried, in order, for the identifier of a record with `--output id`
+const ID_FIELDS: [&str; 4] = ["uuid", "id", "number", "name"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputType {
+    Json,
+    Table,
+    Text,
+    Pretty,
+    Yaml,
+    Csv,
+    /// The identifier of each record, one per line
+    Id,
+    Junit,
+    Markdown,
+    /// Each record rendered with a `{{field}}` template, one per line
+    Template(String),
+    /// The result of a `--query`, rendered in a structured format
+    Query(Query, Box<OutputType>),
+}
+
+impl FromStr for OutputType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "json" => Ok(OutputType::Json),
+            "table" => Ok(OutputType::Table),
+            "text" => Ok(OutputType::Text),
+            "pretty" => Ok(OutputType::Pretty),
+            "yaml" => Ok(OutputType::Yaml),
+            "csv" => Ok(OutputType::Csv),
+            "id" => Ok(OutputType::Id),
+            "junit" => Ok(OutputType::Junit),
+            "markdown" => Ok(OutputType::Markdown),
+            _ => Err(()),
+        }
+    }
+}
+
+impl OutputType {
+    /// The output chosen on the command line. `--template` takes precedence over `--output`, and
+    /// commands without either print text.
+    pub fn from_args(args: &clap::ArgMatches) -> OutputType {
+        let output = match args.try_get_one::<String>("template").ok().flatten() {
+            Some(template) => OutputType::Template(template.clone()),
+            None => args
+                .try_get_one::<String>("output")
+                .ok()
+                .flatten()
+                .and_then(|output| output.parse().ok())
+                .unwrap_or(OutputType::Text),
+        };
+        match args.try_get_one::<Query>("query").ok().flatten() {
+            // A query result has no text form, so is printed as JSON unless another
+            // structured format was asked for
+            Some(query) if output.is_structured() => {
+                OutputType::Query(query.clone(), Box::new(output))
+            }
+            Some(query) => OutputType::Query(query.clone(), Box::new(OutputType::Json)),
+            None => output,
+        }
+    }
+
+    /// If the output is meant for other programs rather than people
+    pub fn is_structured(&self) -> bool {
+        matches!(
+            self,
+            OutputType::Json
+                | OutputType::Yaml
+                | OutputType::Csv
+                | OutputType::Id
+                | OutputType::Template(_)
+                | OutputType::Query(..)
+        )
+    }
+}
+
+/// The `--template` argument, added to every command that supports JSON output
+pub fn template_argument() -> Arg {
+    Arg::new("template")
+        .long("template")
+        .value_name("TEMPLATE")
+        .help("Print each record with a template instead, for example '{{name}} {{uuid}}'. Fields of nested objects are separated with dots")
+}
+
+/// The global `--query` argument
+pub fn query_argument() -> Arg {
+    Arg::new("query")
+        .long("query")
+        .global(true)
+        .value_name("QUERY")
+        .value_parser(|query: &str| query.parse::<Query>())
+        .help("Filter the JSON output of the command with a JMESPath expression, for example '[].name'. Implies --output json unless another structured output is selected")
+}
+
+/// The records of a document: the items of an array or collection resource, or otherwise the
+/// document itself. An object is a collection when it has no fields of its own besides its
+/// links, and exactly one array, either directly or under `_embedded`.
+pub fn records(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(fields) => {
+            let has_own_fields = fields
+                .iter()
+                .any(|(name, value)| name != "_links" && !value.is_array() && !value.is_object());
+            let arrays: Vec<&Vec<Value>> = fields
+                .iter()
+                .filter(|(name, _)| *name != "_links")
+                .flat_map(|(name, value)| {
+                    if name == "_embedded" {
+                        value
+                            .as_object()
+                            .map(|embedded| embedded.values().collect())
+                            .unwrap_or_default()
+                    } else {
+                        vec![value]
+                    }
+                })
+                .filter_map(Value::as_array)
+                .collect();
+            match arrays.as_slice() {
+                [items] if !has_own_fields => items.iter().collect(),
+                _ => vec![value],
+            }
+        }
+        _ => vec![value],
+    }
+}
+
+/// Looks up a field by its dotted path, e.g. `_embedded.version.number`
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, name| match value {
+        Value::Array(items) => name.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => value.get(name),
+    })
+}
+
+/// A value as it appears in text output: strings without quotes, and nothing for null
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn id(record: &Value) -> Option<String> {
+    if !record.is_object() {
+        return Some(scalar(record)).filter(|id| !id.is_empty());
+    }
+    ID_FIELDS
+        .iter()
+        .find_map(|field| record.get(field).filter(|v| !v.is_null()))
+        .map(scalar)
+        .or_else(|| {
+            record["_links"]["self"]["href"]
+                .as_str()
+                .and_then(|href| href.trim_end_matches('/').rsplit('/').next())
+                .map(str::to_string)
+        })
+}
+
+fn flatten(value: &Value, prefix: &str, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(object) => {
+            for (name, value) in object {
+                if name == "_links" {
+                    continue;
+                }
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(value, &path, fields);
+            }
+        }
+        _ => fields.push((prefix.to_string(), scalar(value))),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv(records: &[&Value]) -> String {
+    let rows: Vec<Vec<(String, String)>> = records
+        .iter()
+        .map(|record| {
+            let mut fields = vec![];
+            flatten(
+                record,
+                if record.is_object() { "" } else { "value" },
+                &mut fields,
+            );
+            fields
+        })
+        .collect();
+    // Sorted, so the columns are the same whichever record has an optional field
+    let mut columns: Vec<&str> = rows.iter().flatten().map(|(c, _)| c.as_str()).collect();
+    columns.sort_unstable();
+    columns.dedup();
+    let mut lines = vec![
+        columns
+            .iter()
+            .map(|c| csv_field(c))
+            .collect::<Vec<_>>()
+            .join(","),
+    ];
+    for row in &rows {
+        lines.push(
+            columns
+                .iter()
+                .map(|column| {
+                    row.iter()
+                        .find(|(name, _)| name == column)
+                        .map(|(_, value)| csv_field(value))
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    lines.join("\n")
+}
+
+fn template(records: &[&Value], template: &str) -> String {
+    let placeholder = Regex::new(r"\{\{\s*([^{}\s]+)\s*\}\}").unwrap();
+    records
+        .iter()
+        .map(|record| {
+            placeholder
+                .replace_all(template, |captures: &Captures| {
+                    lookup(record, &captures[1]).map(scalar).unwrap_or_default()
+                })
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A table of the records of a document, with a column for each of the paths in `fields`
+pub(crate) fn generate_table(value: &Value, columns: Vec<&str>, fields: Vec<Vec<&str>>) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(columns);
+    for record in records(value) {
+        table.add_row(
+            fields
+                .iter()
+                .map(|path| {
+                    lookup(record, &path.join("."))
+                        .map(Value::to_string)
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+    table
+}
+
+/// Renders a document in a structured output format. Other formats are rendered as pretty JSON,
+/// as they are the responsibility of the command.
+pub fn render(value: &Value, output: &OutputType) -> Result<String, PactBrokerError> {
+    match output {
+        OutputType::Json => Ok(value.to_string()),
+        OutputType::Yaml => serde_norway::to_string(value)
+            .map(|yaml| yaml.trim_end().to_string())
+            .map_err(|err| {
+                PactBrokerError::ContentError(format!("Failed to render YAML: {}", err))
+            }),
+        OutputType::Csv => Ok(csv(&records(value))),
+        OutputType::Id => Ok(records(value)
+            .into_iter()
+            .filter_map(id)
+            .collect::<Vec<_>>()
+            .join("\n")),
+        OutputType::Template(t) => Ok(template(&records(value), t)),
+        OutputType::Query(query, output) => render(&query.search(value)?, output),
+        _ => Ok(serde_json::to_string_pretty(value).unwrap()),
+    }
+}
+
+/// Renders a document and prints it to stdout, returning what was printed
+pub fn print(value: &Value, output: &OutputType) -> Result<String, PactBrokerError> {
+    let rendered = render(value, output)?;
+    if !rendered.is_empty() {
+        println!("{}", rendered);
+    }
+    Ok(rendered)
+}
+
+/// Prints a message that accompanies structured output, such as progress, to stderr. With other
+/// outputs the message is part of what the command prints, so goes to stdout.
+pub fn status(output: &OutputType, message: impl Display) {
+    if output.is_structured() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{OutputType, records, render};
+
+    fn environments() -> serde_json::Value {
+        json!({
+            "_links": { "self": { "href": "http://localhost/environments" } },
+            "_embedded": {
+                "environments": [
+                    { "uuid": "1234", "name": "test", "production": false, "contacts": [] },
+                    { "uuid": "5678", "name": "prod, eu", "production": true, "displayName": "Production \"EU\"" }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn finds_the_records_of_collections() {
+        assert_eq!(records(&environments()).len(), 2);
+        assert_eq!(records(&json!({ "pacticipants": [{}, {}, {}] })).len(), 3);
+        assert_eq!(records(&json!([1, 2])).len(), 2);
+
+        let pacticipant = json!({ "name": "Foo", "_embedded": { "labels": [{ "name": "a" }] } });
+        assert_eq!(records(&pacticipant), vec![&pacticipant]);
+    }
+
+    #[test]
+    fn renders_ids() {
+        assert_eq!(
+            render(&environments(), &OutputType::Id).unwrap(),
+            "1234\n5678"
+        );
+        assert_eq!(
+            render(
+                &json!({ "description": "x", "_links": { "self": { "href": "http://localhost/webhooks/abc" } } }),
+                &OutputType::Id
+            )
+            .unwrap(),
+            "abc"
+        );
+    }
+
+    #[test]
+    fn renders_csv_with_a_column_for_every_field() {
+        assert_eq!(
+            render(&environments(), &OutputType::Csv).unwrap(),
+            "contacts,displayName,name,production,uuid\n\
+             [],,test,false,1234\n\
+             ,\"Production \"\"EU\"\"\",\"prod, eu\",true,5678"
+        );
+    }
+
+    #[test]
+    fn renders_templates() {
+        assert_eq!(
+            render(
+                &environments(),
+                &OutputType::Template("{{name}}: {{ uuid }} {{missing}}".to_string())
+            )
+            .unwrap(),
+            "test: 1234 \nprod, eu: 5678 "
+        );
+        assert_eq!(
+            render(
+                &json!({ "_embedded": { "version": { "number": "1.0" } } }),
+                &OutputType::Template("{{_embedded.version.number}}".to_string())
+            )
+            .unwrap(),
+            "1.0"
+        );
+    }
+
+    #[test]
+    fn renders_yaml() {
+        assert_eq!(
+            render(&json!({ "name": "Foo", "tags": ["a"] }), &OutputType::Yaml).unwrap(),
+            "name: Foo\ntags:\n- a"
+        );
+    }
+}
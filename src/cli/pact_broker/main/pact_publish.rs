@@ -5,25 +5,24 @@
 use std::fs::File;
 
 use anyhow::{Context, anyhow};
-use base64::Engine;
-use base64::engine::general_purpose::STANDARD as Base64;
 use clap::ArgMatches;
 use log::*;
 
 use glob::glob;
 use pact_models::pact;
-use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
-use crate::cli::pact_broker::main::utils::{
-    get_auth, get_broker_relation, get_broker_url, get_custom_headers,
+use crate::cli::pact_broker::main::client::{
+    PactBrokerClient, PublishContract, PublishPactsRequest, PublishPactsResult,
 };
-use crate::cli::pact_broker::main::utils::{get_retries, get_ssl_options, handle_error};
-use crate::cli::pact_broker::main::{HALClient, Notice, process_notices};
+use crate::cli::pact_broker::main::output::{self, OutputType};
+use crate::cli::pact_broker::main::process_notices;
+use crate::cli::pact_broker::main::types::BrokerDetails;
+use crate::cli::pact_broker::main::utils::handle_error;
 use crate::cli::utils::git_info;
 use std::collections::HashMap;
 
-use super::verification::{VerificationResult, display_results, verify_json};
+use super::verification::{VerificationResult, verify_json};
 
 /// Error type for pact merging conflicts
 #[derive(Debug)]
@@ -40,115 +39,7 @@ impl std::fmt::Display for PactMergeError {
 
 impl std::error::Error for PactMergeError {}
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Root {
-    #[serde(rename = "_embedded")]
-    pub embedded: Embedded,
-    #[serde(rename = "_links")]
-    pub links: Links3,
-    pub logs: Vec<Log>,
-    pub notices: Option<Vec<Notice>>,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Embedded {
-    pub pacticipant: Pacticipant,
-    pub version: Version,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Pacticipant {
-    #[serde(rename = "_links")]
-    pub links: Links,
-    pub name: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Links {
-    #[serde(rename = "self")]
-    pub self_field: SelfField,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SelfField {
-    pub href: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Version {
-    #[serde(rename = "_links")]
-    pub links: Links2,
-    pub number: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Links2 {
-    #[serde(rename = "self")]
-    pub self_field: SelfField2,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SelfField2 {
-    pub href: String,
-    pub name: String,
-    pub title: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Links3 {
-    #[serde(rename = "pb:contracts")]
-    pub pb_contracts: Vec<Contract>,
-    #[serde(rename = "pb:pacticipant")]
-    pub pb_pacticipant: PbPacticipant,
-    #[serde(rename = "pb:pacticipant-version")]
-    pub pb_pacticipant_version: PbPacticipantVersion,
-    #[serde(rename = "pb:pacticipant-version-tags")]
-    pub pb_pacticipant_version_tags: Vec<Value>,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Contract {
-    pub href: String,
-    pub name: String,
-    pub title: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PbPacticipant {
-    pub href: String,
-    pub name: String,
-    pub title: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PbPacticipantVersion {
-    pub href: String,
-    pub name: String,
-    pub title: String,
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Log {
-    pub deprecation_warning: String,
-    pub level: String,
-    pub message: String,
-}
-
-/// Check if two interactions have the same description and provider state
-fn same_description_and_state(original: &Value, additional: &Value) -> bool {
+pub(crate) fn same_description_and_state(original: &Value, additional: &Value) -> bool {
     let same_description = original.get("description") == additional.get("description");
 
     let same_state = match (
@@ -173,7 +64,7 @@ fn same_description_and_state(original: &Value, additional: &Value) -> bool {
     same_description && same_state
 }
 
-fn almost_duplicate_message(original: &Value, new_interaction: &Value) -> String {
+pub(crate) fn almost_duplicate_message(original: &Value, new_interaction: &Value) -> String {
     let description = new_interaction
         .get("description")
         .and_then(|d| d.as_str())
@@ -243,7 +134,9 @@ pub fn handle_matches(args: &ArgMatches) -> Result<Vec<VerificationResult>, i32>
         println!("❌ No pact files found to publish");
         return Err(1);
     }
-    let display_result = display_results(&results, "json");
+    // The validation results accompany the published pacts, so are not part of the output
+    let display_result = serde_json::to_string_pretty(&results)
+        .map(|json| output::status(&OutputType::from_args(args), json));
 
     if display_result.is_err() {
         Err(3)
@@ -265,284 +158,251 @@ pub fn publish_pacts(args: &ArgMatches) -> Result<Value, i32> {
     }
     let files = files.map_err(|_| 1)?;
 
-    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
-    let auth = get_auth(args);
-    let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
-    let hal_client: HALClient = HALClient::with_url(
-        &broker_url,
-        Some(auth.clone()),
-        ssl_options.clone(),
-        custom_headers.clone(),
-    )
-    .with_retry_count(get_retries(args));
-
-    let publish_pact_href_path = tokio::runtime::Runtime::new().unwrap().block_on(async {
-        get_broker_relation(
-            hal_client.clone(),
-            "pb:publish-contracts".to_string(),
-            broker_url.to_string(),
-        )
-        .await
-    });
-
-    match publish_pact_href_path {
-        Ok(publish_pact_href) => {
-            let mut consumer_app_version = args.get_one::<String>("consumer-app-version");
-            let mut branch = args.get_one::<String>("branch");
-            let auto_detect_version_properties: bool =
-                args.get_flag("auto-detect-version-properties");
-            let tag_with_git_branch = args.get_flag("tag-with-git-branch");
-            let build_url = args.get_one::<String>("build-url");
-            let (git_commit, git_branch);
-            if auto_detect_version_properties {
-                git_commit = git_info::commit(false);
-                git_branch = git_info::branch(false);
-            } else {
-                git_commit = Some("".to_string());
-                git_branch = Some("".to_string());
+    let client = match BrokerDetails::from_args(args) {
+        Ok(broker_details) => PactBrokerClient::new(broker_details),
+        Err(err) => {
+            handle_error(err);
+            return Err(1);
+        }
+    };
+
+    let output_type = OutputType::from_args(args);
+    let mut consumer_app_version = args.get_one::<String>("consumer-app-version");
+    let mut branch = args.get_one::<String>("branch");
+    let auto_detect_version_properties: bool = args.get_flag("auto-detect-version-properties");
+    let tag_with_git_branch = args.get_flag("tag-with-git-branch");
+    let build_url = args.get_one::<String>("build-url");
+    let (git_commit, git_branch);
+    if auto_detect_version_properties {
+        git_commit = git_info::commit(false);
+        git_branch = git_info::branch(false);
+    } else {
+        git_commit = Some("".to_string());
+        git_branch = Some("".to_string());
+    }
+    if auto_detect_version_properties {
+        if consumer_app_version.is_none() {
+            consumer_app_version = git_commit.as_ref();
+            if let Some(v) = consumer_app_version {
+                output::status(&output_type, format!("🔍 Auto detected git commit: {}", v));
             }
-            if auto_detect_version_properties {
-                if consumer_app_version.is_none() {
-                    consumer_app_version = git_commit.as_ref();
-                    if let Some(v) = consumer_app_version {
-                        println!("🔍 Auto detected git commit: {}", v);
-                    }
-                } else if let Some(v) = consumer_app_version {
-                    println!(
-                        "🔍 auto_detect_version_properties set to {}, but consumer_app_version provided {}",
-                        auto_detect_version_properties, v
-                    );
-                }
-                if branch.is_none() {
-                    branch = git_branch.as_ref();
-                    if let Some(b) = branch {
-                        println!("🔍 Auto detected git branch: {}", b);
-                    }
-                } else if let Some(b) = branch {
-                    println!(
-                        "🔍 auto_detect_version_properties set to {}, but branch provided {}",
-                        auto_detect_version_properties, b
-                    );
-                }
+        } else if let Some(v) = consumer_app_version {
+            output::status(
+                &output_type,
+                format!(
+                    "🔍 auto_detect_version_properties set to {}, but consumer_app_version provided {}",
+                    auto_detect_version_properties, v
+                ),
+            );
+        }
+        if branch.is_none() {
+            branch = git_branch.as_ref();
+            if let Some(b) = branch {
+                output::status(&output_type, format!("🔍 Auto detected git branch: {}", b));
             }
+        } else if let Some(b) = branch {
+            output::status(
+                &output_type,
+                format!(
+                    "🔍 auto_detect_version_properties set to {}, but branch provided {}",
+                    auto_detect_version_properties, b
+                ),
+            );
+        }
+    }
 
-            let on_conflict = if args.get_flag("merge") {
-                "merge"
-            } else {
-                "overwrite"
-            };
-            let output: Result<Option<&String>, clap::parser::MatchesError> =
-                args.try_get_one::<String>("output");
-            // publish the pacts
-            // Group pacts by (consumer, provider) pair and merge their interactions
-            let mut merged_pacts: HashMap<(String, String), Value> = HashMap::new();
-            for (source, pact_json) in files.iter() {
-                tracing::debug!("Processing pact file: {}", source);
-
-                // Load pact and extract consumer/provider names
-                let pact_res = pact::load_pact_from_json(source, pact_json);
-                if let Ok(pact) = &pact_res {
-                    let consumer_name = pact.consumer().name.clone();
-                    let provider_name = pact.provider().name.clone();
-                    let key = (consumer_name.clone(), provider_name.clone());
+    let on_conflict = if args.get_flag("merge") {
+        "merge"
+    } else {
+        "overwrite"
+    };
+    let mut published = vec![];
+    // publish the pacts
+    // Group pacts by (consumer, provider) pair and merge their interactions
+    let mut merged_pacts: HashMap<(String, String), Value> = HashMap::new();
+    for (source, pact_json) in files.iter() {
+        tracing::debug!("Processing pact file: {}", source);
+
+        // Load pact and extract consumer/provider names
+        let pact_res = pact::load_pact_from_json(source, pact_json);
+        if let Ok(pact) = &pact_res {
+            let consumer_name = pact.consumer().name.clone();
+            let provider_name = pact.provider().name.clone();
+            let key = (consumer_name.clone(), provider_name.clone());
+
+            tracing::debug!(
+                "Loaded pact for consumer: '{}' and provider: '{}'",
+                consumer_name,
+                provider_name
+            );
 
+            // If already present, merge interactions
+            if let Some(existing_json) = merged_pacts.get_mut(&key) {
+                tracing::debug!(
+                    "Merging interactions for consumer: '{}' and provider: '{}'",
+                    consumer_name,
+                    provider_name
+                );
+                // Merge interactions arrays with duplicate detection
+                if let (Some(existing_interactions), Some(new_interactions)) = (
+                    existing_json.get_mut("interactions"),
+                    pact_json.get("interactions"),
+                ) && let (Some(existing_arr), Some(new_arr)) = (
+                    existing_interactions.as_array_mut(),
+                    new_interactions.as_array(),
+                ) {
                     tracing::debug!(
-                        "Loaded pact for consumer: '{}' and provider: '{}'",
-                        consumer_name,
-                        provider_name
+                        "Existing interactions: {}, New interactions: {}",
+                        existing_arr.len(),
+                        new_arr.len()
                     );
 
-                    // If already present, merge interactions
-                    if let Some(existing_json) = merged_pacts.get_mut(&key) {
-                        tracing::debug!(
-                            "Merging interactions for consumer: '{}' and provider: '{}'",
-                            consumer_name,
-                            provider_name
-                        );
-                        // Merge interactions arrays with duplicate detection
-                        if let (Some(existing_interactions), Some(new_interactions)) = (
-                            existing_json.get_mut("interactions"),
-                            pact_json.get("interactions"),
-                        ) && let (Some(existing_arr), Some(new_arr)) = (
-                            existing_interactions.as_array_mut(),
-                            new_interactions.as_array(),
-                        ) {
+                    match merge_interactions_or_messages(existing_arr, new_arr) {
+                        Ok(()) => {
                             tracing::debug!(
-                                "Existing interactions: {}, New interactions: {}",
-                                existing_arr.len(),
-                                new_arr.len()
+                                "Total interactions after merge: {}",
+                                existing_arr.len()
                             );
-
-                            match merge_interactions_or_messages(existing_arr, new_arr) {
-                                Ok(()) => {
-                                    tracing::debug!(
-                                        "Total interactions after merge: {}",
-                                        existing_arr.len()
-                                    );
-                                }
-                                Err(merge_error) => {
-                                    println!("❌ {}", merge_error);
-                                    error!("Pact merge error: {}", merge_error);
-                                    return Err(1);
-                                }
-                            }
                         }
-                    } else {
-                        tracing::debug!(
-                            "Inserting new pact for consumer: '{}' and provider: '{}'",
-                            consumer_name,
-                            provider_name
-                        );
-                        // Insert new pact
-                        merged_pacts.insert(key, pact_json.clone());
+                        Err(merge_error) => {
+                            eprintln!("❌ {}", merge_error);
+                            error!("Pact merge error: {}", merge_error);
+                            return Err(1);
+                        }
                     }
-                } else {
-                    println!("❌ Failed to load pact from JSON: {:?}", pact_res);
-                    error!("Failed to load pact from JSON: {:?}", pact_res);
-                    return Err(1);
                 }
+            } else {
+                tracing::debug!(
+                    "Inserting new pact for consumer: '{}' and provider: '{}'",
+                    consumer_name,
+                    provider_name
+                );
+                // Insert new pact
+                merged_pacts.insert(key, pact_json.clone());
             }
+        } else {
+            eprintln!("❌ Failed to load pact from JSON: {:?}", pact_res);
+            error!("Failed to load pact from JSON: {:?}", pact_res);
+            return Err(1);
+        }
+    }
 
-            // Publish merged pacts
-            for ((consumer_name, provider_name), pact_json) in merged_pacts.iter() {
-                let pact_res = pact::load_pact_from_json(
-                    &format!("{}-{}", consumer_name, provider_name),
-                    pact_json,
+    // Publish merged pacts
+    for ((consumer_name, provider_name), pact_json) in merged_pacts.iter() {
+        let pact_res =
+            pact::load_pact_from_json(&format!("{}-{}", consumer_name, provider_name), pact_json);
+        match pact_res {
+            Ok(pact) => {
+                let consumer_name = pact.consumer().name.clone();
+                let provider_name = pact.provider().name.clone();
+                let pact_spec = pact.specification_version();
+                let pact_json_data = pact.to_json(pact_spec).unwrap();
+                let Some(consumer_app_version) = consumer_app_version else {
+                    eprintln!("❌ Error: Consumer app version is required to publish pact");
+                    return Err(1);
+                };
+                let mut tags: Vec<String> = args
+                    .get_many::<String>("tag")
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect();
+                if tag_with_git_branch {
+                    tags.push(git_info::commit(false).unwrap_or_default());
+                }
+                let request = PublishPactsRequest {
+                    pacticipant_name: consumer_name.clone(),
+                    pacticipant_version_number: consumer_app_version.to_string(),
+                    branch: branch.cloned(),
+                    build_url: build_url.cloned(),
+                    tags,
+                    contracts: vec![PublishContract {
+                        on_conflict: Some(on_conflict.to_string()),
+                        ..PublishContract::pact(&consumer_name, &provider_name, &pact_json_data)
+                    }],
+                };
+                output::status(
+                    &output_type,
+                    format!(
+                        "\n📨 Attempting to publish pact for consumer: {} against provider: {}",
+                        consumer_name, provider_name
+                    ),
                 );
-                match pact_res {
-                    Ok(pact) => {
-                        let consumer_name = pact.consumer().name.clone();
-                        let provider_name = pact.provider().name.clone();
-                        let pact_spec = pact.specification_version();
-                        let pact_json_data = pact.to_json(pact_spec).unwrap();
-                        let mut payload = json!({});
-                        payload["pacticipantName"] = Value::String(consumer_name.clone());
-                        if let Some(v) = consumer_app_version {
-                            payload["pacticipantVersionNumber"] = Value::String(v.to_string());
+                let res = tokio::runtime::Runtime::new()
+                    .unwrap()
+                    .block_on(client.publish_pacts_json(&request));
+                match res {
+                    Ok(res) => {
+                        if output_type.is_structured() {
+                            published.push(res);
+                        } else if output_type == OutputType::Pretty {
+                            let json = serde_json::to_string_pretty(&res).unwrap();
+                            println!("{}", json);
                         } else {
-                            println!("❌ Error: Consumer app version is required to publish pact");
-                            return Err(1);
-                        }
-                        if let Some(b) = branch {
-                            payload["branch"] = Value::String(b.to_string());
-                        }
-                        if let Some(url) = build_url {
-                            payload["buildUrl"] = Value::String(url.to_string());
-                        }
-                        if let Some(tags) = args.get_many::<String>("tag") {
-                            payload["tags"] = serde_json::Value::Array(vec![]);
-                            for tag in tags {
-                                payload["tags"]
-                                    .as_array_mut()
-                                    .unwrap()
-                                    .push(serde_json::Value::String(tag.to_string()));
-                            }
-                        };
-                        if tag_with_git_branch {
-                            if !payload.get("tags").is_some_and(|v| v.is_array()) {
-                                payload["tags"] = serde_json::Value::Array(vec![]);
+                            let parsed_res = serde_json::from_value::<PublishPactsResult>(res);
+                            match parsed_res {
+                                Ok(parsed_res) => {
+                                    print!("✅ ");
+                                    if let Some(notices) = parsed_res.notices {
+                                        process_notices(&notices);
+                                    } else {
+                                        println!(
+                                            "Pact published successfully for consumer: {} against provider: {}",
+                                            consumer_name, provider_name
+                                        );
+                                    }
+                                }
+                                Err(err) => {
+                                    println!(
+                                        "✅ Pact published successfully for consumer: {} against provider: {}",
+                                        consumer_name, provider_name
+                                    );
+                                    println!(
+                                        "⚠️ Warning: Failed to process response notices - Error: {:?}",
+                                        err
+                                    );
+                                }
                             }
-                            payload["tags"].as_array_mut().unwrap().push(
-                                serde_json::Value::String(
-                                    git_info::commit(false).unwrap_or_default(),
-                                ),
-                            );
                         }
-
-                        payload["contracts"] = serde_json::Value::Array(vec![json!({
-                          "consumerName": consumer_name,
-                          "providerName": provider_name,
-                          "specification": "pact",
-                          "contentType": "application/json",
-                          "content": Base64.encode(pact_json_data.to_string()),
-                          "onConflict": on_conflict
-                        })]);
-                        println!();
+                    }
+                    // The pacts are independent, so a dry run previews each of them
+                    Err(crate::cli::pact_broker::main::PactBrokerError::DryRun(_)) => {
                         println!(
-                            "📨 Attempting to publish pact for consumer: {} against provider: {}",
+                            "⚠️ Dry run, the pact for consumer: {} against provider: {} was not published",
                             consumer_name, provider_name
                         );
-                        let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
-                            hal_client
-                                .clone()
-                                .post_json(&(publish_pact_href), &payload.to_string(), None)
-                                .await
-                        });
-                        match res {
-                            Ok(res) => match output {
-                                Ok(Some(output)) => {
-                                    if output == "pretty" {
-                                        let json = serde_json::to_string_pretty(&res).unwrap();
-                                        println!("{}", json);
-                                    } else if output == "json" {
-                                        let json: String =
-                                            serde_json::to_string(&res.clone()).unwrap();
-                                        println!("{}", json);
-                                    } else {
-                                        let parsed_res = serde_json::from_value::<Root>(res);
-                                        match parsed_res {
-                                            Ok(parsed_res) => {
-                                                print!("✅ ");
-                                                if let Some(notices) = parsed_res.notices {
-                                                    process_notices(&notices);
-                                                } else {
-                                                    println!(
-                                                        "Pact published successfully for consumer: {} against provider: {}",
-                                                        consumer_name, provider_name
-                                                    );
-                                                }
-                                            }
-                                            Err(err) => {
-                                                println!(
-                                                    "✅ Pact published successfully for consumer: {} against provider: {}",
-                                                    consumer_name, provider_name
-                                                );
-                                                println!(
-                                                    "⚠️ Warning: Failed to process response notices - Error: {:?}",
-                                                    err
-                                                );
-                                            }
-                                        }
-                                    }
+                    }
+                    Err(err) => {
+                        match &err {
+                            crate::cli::pact_broker::main::PactBrokerError::ValidationErrorWithNotices(messages, notices) => {
+                                eprintln!("❌ Pact publication failed:");
+                                for message in messages {
+                                    eprintln!("   {}", message);
                                 }
-                                _ => {
-                                    println!("{:?}", res.clone());
+                                if !notices.is_empty() {
+                                    println!("\nDetails:");
+                                    process_notices(notices);
                                 }
                             },
-                            Err(err) => {
-                                match &err {
-                                    crate::cli::pact_broker::main::PactBrokerError::ValidationErrorWithNotices(messages, notices) => {
-                                        println!("❌ Pact publication failed:");
-                                        for message in messages {
-                                            println!("   {}", message);
-                                        }
-                                        if !notices.is_empty() {
-                                            println!("\nDetails:");
-                                            process_notices(notices);
-                                        }
-                                    },
-                                    _ => {
-                                        println!("❌ {}", err);
-                                    }
-                                }
-                                return Err(1);
+                            _ => {
+                                eprintln!("❌ {}", err);
                             }
                         }
-                    }
-                    _ => {
-                        println!("❌ Failed to load pact from JSON: {:?}", pact_res);
                         return Err(1);
                     }
                 }
             }
-            Ok(json!({}))
-        }
-        Err(err) => {
-            handle_error(err);
-            Err(1)
+            _ => {
+                eprintln!("❌ Failed to load pact from JSON: {:?}", pact_res);
+                return Err(1);
+            }
         }
     }
+    // The JSON output is the publication response for each consumer and provider pair
+    if output_type.is_structured() {
+        output::print(&Value::Array(published), &output_type).map_err(|_| 1)?;
+    }
+    Ok(json!({}))
 }
 
 /// Load pact files specified by the command-line arguments into memory.
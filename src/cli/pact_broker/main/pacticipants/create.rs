@@ -1,8 +1,9 @@
 use crate::cli::pact_broker::main::{
     HALClient, Link, PactBrokerError,
+    output::{self, OutputType},
     utils::{
-        get_auth, get_broker_relation, get_broker_url, get_custom_headers, get_retries,
-        get_ssl_options,
+        get_auth, get_broker_relation, get_broker_url, get_connection_options, get_custom_headers,
+        get_retries,
     },
 };
 use maplit::hashmap;
@@ -12,18 +13,19 @@ pub fn create_or_update_pacticipant(args: &clap::ArgMatches) -> Result<String, P
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let pacticipant_name = args.get_one::<String>("name").unwrap();
     let display_name = args.try_get_one::<String>("display-name").unwrap();
     let main_branch = args.try_get_one::<String>("main-branch").unwrap();
     let repository_url = args.try_get_one::<String>("repository-url").unwrap();
+    let output = OutputType::from_args(args);
 
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -78,22 +80,27 @@ pub fn create_or_update_pacticipant(args: &clap::ArgMatches) -> Result<String, P
                 }
 
                 let pacticipant_data_str = pacticipant_data.to_string();
-                hal_client
+                let resource = hal_client
                     .patch_json(&pacticipant_href, &pacticipant_data_str, None)
                     .await
                     .map_err(|e| {
-                        PactBrokerError::IoError(format!(
-                            "Failed to update pacticipant '{}': {}",
-                            pacticipant_name, e
-                        ))
+                        e.map_unless_dry_run(|e| {
+                            PactBrokerError::IoError(format!(
+                                "Failed to update pacticipant '{}': {}",
+                                pacticipant_name, e
+                            ))
+                        })
                     })?;
-                Ok(format!(
-                    "Pacticipant '{}' updated successfully",
-                    pacticipant_name
+                Ok((
+                    format!("Pacticipant '{}' updated successfully", pacticipant_name),
+                    resource,
                 ))
             }
             Err(PactBrokerError::NotFound(_)) => {
-                println!("Pacticipant does not exist, creating it at: {}", broker_url);
+                output::status(
+                    &output,
+                    format!("Pacticipant does not exist, creating it at: {}", broker_url),
+                );
                 let pacticipants_href = get_broker_relation(
                     hal_client.clone(),
                     "pb:pacticipants".to_string(),
@@ -105,37 +112,48 @@ pub fn create_or_update_pacticipant(args: &clap::ArgMatches) -> Result<String, P
                     "name": pacticipant_name,
                 });
                 if let Some(display_name) = display_name {
-                    println!("Creating pacticipant with display name: {}", display_name);
+                    output::status(
+                        &output,
+                        format!("Creating pacticipant with display name: {}", display_name),
+                    );
                     pacticipant_data["displayName"] =
                         serde_json::Value::String(display_name.to_string());
                 }
                 if let Some(main_branch) = main_branch {
-                    println!("Creating pacticipant with main branch: {}", main_branch);
+                    output::status(
+                        &output,
+                        format!("Creating pacticipant with main branch: {}", main_branch),
+                    );
                     pacticipant_data["mainBranch"] =
                         serde_json::Value::String(main_branch.to_string());
                 }
                 if let Some(repository_url) = repository_url {
-                    println!(
-                        "Creating pacticipant with repository URL: {}",
-                        repository_url
+                    output::status(
+                        &output,
+                        format!(
+                            "Creating pacticipant with repository URL: {}",
+                            repository_url
+                        ),
                     );
                     pacticipant_data["repositoryUrl"] =
                         serde_json::Value::String(repository_url.to_string());
                 }
 
                 let pacticipant_data_str = pacticipant_data.to_string();
-                hal_client
+                let resource = hal_client
                     .post_json(&pacticipants_href, &pacticipant_data_str, None)
                     .await
                     .map_err(|e| {
-                        PactBrokerError::IoError(format!(
-                            "Failed to create pacticipant '{}': {}",
-                            pacticipant_name, e
-                        ))
+                        e.map_unless_dry_run(|e| {
+                            PactBrokerError::IoError(format!(
+                                "Failed to create pacticipant '{}': {}",
+                                pacticipant_name, e
+                            ))
+                        })
                     })?;
-                Ok(format!(
-                    "Pacticipant '{}' created successfully",
-                    pacticipant_name
+                Ok((
+                    format!("Pacticipant '{}' created successfully", pacticipant_name),
+                    resource,
                 ))
             }
             Err(err) => Err(err.clone()),
@@ -143,7 +161,8 @@ pub fn create_or_update_pacticipant(args: &clap::ArgMatches) -> Result<String, P
     });
 
     match res {
-        Ok(message) => {
+        Ok((_, resource)) if output.is_structured() => output::print(&resource, &output),
+        Ok((message, _)) => {
             println!("{}", message);
             Ok(message)
         }
@@ -4,7 +4,8 @@ use maplit::hashmap;
 use crate::{
     cli::pact_broker::main::{HALClient, PactBrokerError},
     cli::pact_broker::main::{
-        types::{BrokerDetails, OutputType},
+        output::{self, OutputType},
+        types::BrokerDetails,
         utils::{follow_templated_broker_relation, get_broker_relation},
     },
 };
@@ -18,14 +19,14 @@ pub fn describe_pacticipant(
     let broker_url = &broker_details.url;
     let auth = &broker_details.auth;
     let custom_headers = &broker_details.custom_headers;
-    let ssl_options = &broker_details.ssl_options;
+    let connection_options = &broker_details.connection_options;
 
     tokio::runtime::Runtime::new().unwrap().block_on(async {
         // query pact broker index and get hal relation link
         let hal_client: HALClient = HALClient::with_url(
             broker_url,
             auth.clone(),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(broker_details.retries);
@@ -54,11 +55,7 @@ pub fn describe_pacticipant(
         .await;
         match res {
             Ok(result) => match output_type {
-                OutputType::Json => {
-                    let json: String = serde_json::to_string(&result).unwrap();
-                    println!("{}", json);
-                    Ok(json)
-                }
+                ref output if output.is_structured() => output::print(&result, output),
                 OutputType::Table => {
                     let names = [
                         vec!["name"],
@@ -115,7 +112,7 @@ pub fn describe_pacticipant(
                     }
                     Ok(text)
                 }
-                OutputType::Pretty => {
+                _ => {
                     let json: String = serde_json::to_string(&result).unwrap();
                     println!("{}", json);
                     Ok(json)
@@ -213,7 +210,7 @@ mod describe_pacticipant_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: Default::default(),
+            connection_options: Default::default(),
             custom_headers: None,
             retries: 0,
         };
@@ -279,7 +276,7 @@ mod describe_pacticipant_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: Default::default(),
+            connection_options: Default::default(),
             custom_headers: None,
             retries: 0,
         };
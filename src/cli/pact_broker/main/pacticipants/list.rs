@@ -3,19 +3,21 @@ use comfy_table::{Table, presets::UTF8_FULL};
 use crate::{
     cli::pact_broker::main::{HALClient, PactBrokerError},
     cli::pact_broker::main::{
-        types::{BrokerDetails, OutputType},
-        utils::{follow_broker_relation, get_broker_relation},
+        output::{self, OutputType},
+        types::{BrokerDetails, Pagination},
+        utils::get_broker_relation,
     },
 };
 
 pub fn list_pacticipants(
     broker_details: &BrokerDetails,
     output_type: OutputType,
+    pagination: &Pagination,
 ) -> Result<String, PactBrokerError> {
     // setup client with broker url and credentials
     let broker_url = &broker_details.url;
     let auth = &broker_details.auth;
-    let ssl_options = &broker_details.ssl_options;
+    let connection_options = &broker_details.connection_options;
     let custom_headers = &broker_details.custom_headers;
 
     tokio::runtime::Runtime::new().unwrap().block_on(async {
@@ -23,7 +25,7 @@ pub fn list_pacticipants(
         let hal_client: HALClient = HALClient::with_url(
             broker_url,
             auth.clone(),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(broker_details.retries);
@@ -41,20 +43,17 @@ pub fn list_pacticipants(
             }
         }
 
-        // query the hal relation link to get the latest pact versions
-        let res = follow_broker_relation(
-            hal_client.clone(),
-            "pacticipants".to_string(),
-            pb_pacticipants_href_path.unwrap(),
-        )
-        .await;
+        // query the hal relation link to get the pacticipants, from every page needed
+        let res = hal_client
+            .fetch_collection(
+                &pb_pacticipants_href_path.unwrap(),
+                &["/pacticipants", "/_embedded/pacticipants"],
+                pagination,
+            )
+            .await;
         match res {
             Ok(result) => match output_type {
-                OutputType::Json => {
-                    let json: String = serde_json::to_string(&result).unwrap();
-                    println!("{}", json);
-                    Ok(json)
-                }
+                ref output if output.is_structured() => output::print(&result, output),
                 OutputType::Table => {
                     let names = [vec!["name"], vec!["displayName"]];
                     let mut table = Table::new();
@@ -80,16 +79,16 @@ pub fn list_pacticipants(
                     Ok(table.to_string())
                 }
 
-                OutputType::Text => {
-                    let text = result.to_string();
-                    println!("{:?}", text);
-                    Ok(text)
-                }
                 OutputType::Pretty => {
                     let json: String = serde_json::to_string(&result).unwrap();
                     println!("{}", json);
                     Ok(json)
                 }
+                _ => {
+                    let text = result.to_string();
+                    println!("{:?}", text);
+                    Ok(text)
+                }
             },
             Err(err) => Err(err),
         }
@@ -187,12 +186,12 @@ mod list_pacticipants_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: Default::default(),
+            connection_options: Default::default(),
             custom_headers: None,
             retries: 0,
         };
 
-        let result = list_pacticipants(&broker_details, OutputType::Json);
+        let result = list_pacticipants(&broker_details, OutputType::Json, &Pagination::all());
 
         assert!(result.is_ok());
         let json: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
@@ -203,4 +202,130 @@ mod list_pacticipants_tests {
         assert_eq!(pacticipants[1]["name"], pacticipant1["name"]);
         assert_eq!(pacticipants[1]["displayName"], pacticipant1["displayName"]);
     }
+
+    fn index_interaction(mut i: InteractionBuilder) -> InteractionBuilder {
+        i.given("the pb:pacticipants relation exists in the index resource");
+        i.request
+            .path("/")
+            .header("Accept", "application/hal+json")
+            .header("Accept", "application/json");
+        i.response
+            .header("Content-Type", "application/hal+json;charset=utf-8")
+            .json_body(json_pattern!({
+                "_links": {
+                    "pb:pacticipants": {
+                        "href": term!("http:\\/\\/[^/]+\\/pacticipants", "http://localhost/pacticipants")
+                    }
+                }
+            }));
+        i
+    }
+
+    fn page_interaction(page: usize, next: bool) -> InteractionBuilder {
+        let mut i = InteractionBuilder::new(
+            format!("a request for page {} of the pacticipants", page),
+            "".to_string(),
+        );
+        i.given("there are 3 pacticipants in the pact-broker");
+        i.request
+            .get()
+            .path("/pacticipants")
+            .query_param("pageNumber", page.to_string())
+            .query_param("pageSize", "2")
+            .header("Accept", "application/hal+json")
+            .header("Accept", "application/json");
+        let names = if page == 1 {
+            vec!["Bar", "Baz"]
+        } else {
+            vec!["Foo"]
+        };
+        let links = if next {
+            serde_json::json!({
+                "next": {
+                    "href": format!("http://localhost/pacticipants?pageNumber={}&pageSize=2", page + 1)
+                }
+            })
+        } else {
+            serde_json::json!({})
+        };
+        i.response
+            .status(200)
+            .header("Content-Type", "application/hal+json;charset=utf-8")
+            .json_body(serde_json::json!({
+                "pacticipants": names
+                    .iter()
+                    .map(|name| serde_json::json!({ "name": name }))
+                    .collect::<Vec<_>>(),
+                "page": { "number": page, "size": 2, "totalElements": 3, "totalPages": 2 },
+                "_links": links
+            }));
+        i
+    }
+
+    fn broker_details(url: &str) -> BrokerDetails {
+        BrokerDetails {
+            url: url.to_string(),
+            auth: None,
+            connection_options: Default::default(),
+            custom_headers: None,
+            retries: 0,
+        }
+    }
+
+    fn pacticipant_names(result: Result<String, PactBrokerError>) -> Vec<String> {
+        let json: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert!(json.get("page").is_none());
+        assert!(json["_links"].get("next").is_none());
+        json["pacticipants"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn list_pacticipants_follows_next_links() {
+        let mock_server = setup_mock_server(vec![
+            index_interaction(InteractionBuilder::new(
+                "a request for the index resource",
+                "",
+            )),
+            page_interaction(1, true),
+            page_interaction(2, false),
+        ]);
+
+        let result = list_pacticipants(
+            &broker_details(mock_server.url().as_str()),
+            OutputType::Json,
+            &Pagination {
+                page_size: Some(2),
+                limit: None,
+            },
+        );
+
+        assert_eq!(pacticipant_names(result), vec!["Bar", "Baz", "Foo"]);
+    }
+
+    #[test]
+    fn list_pacticipants_stops_fetching_pages_at_the_limit() {
+        let mock_server = setup_mock_server(vec![
+            index_interaction(InteractionBuilder::new(
+                "a request for the index resource",
+                "",
+            )),
+            page_interaction(1, true),
+        ]);
+
+        let result = list_pacticipants(
+            &broker_details(mock_server.url().as_str()),
+            OutputType::Json,
+            &Pagination {
+                page_size: Some(2),
+                limit: Some(1),
+            },
+        );
+
+        assert_eq!(pacticipant_names(result), vec!["Bar"]);
+    }
 }
@@ -0,0 +1,620 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cli::{
+    pact_broker::main::{
+        HALClient, PactBrokerError,
+        output::{self, OutputType},
+        pact_publish::same_description_and_state,
+        utils::{
+            broker_url, follow_templated_broker_relation, get_auth, get_connection_options,
+            get_custom_headers, get_retries,
+        },
+    },
+    utils,
+};
+
+/// Where to load one side of the diff from
+#[derive(Debug, Clone, PartialEq)]
+pub enum PactSource {
+    Version(String),
+    Branch(String),
+    Tag(String),
+    File(String),
+}
+
+impl PactSource {
+    fn from_args(args: &clap::ArgMatches, side: &str) -> Option<PactSource> {
+        let value = |name: &str| {
+            args.get_one::<String>(&format!("{}-{}", side, name))
+                .cloned()
+        };
+        value("version")
+            .map(PactSource::Version)
+            .or_else(|| value("branch").map(PactSource::Branch))
+            .or_else(|| value("tag").map(PactSource::Tag))
+            .or_else(|| value("file").map(PactSource::File))
+    }
+}
+
+impl fmt::Display for PactSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PactSource::Version(version) => write!(f, "version {}", version),
+            PactSource::Branch(branch) => write!(f, "latest from branch {}", branch),
+            PactSource::Tag(tag) => write!(f, "latest with tag {}", tag),
+            PactSource::File(file) => write!(f, "file {}", file),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    ProviderState,
+    MatchingRule,
+    Generator,
+    Content,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InteractionChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InteractionSummary {
+    pub description: String,
+    #[serde(rename = "providerStates", skip_serializing_if = "Vec::is_empty")]
+    pub provider_states: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedInteraction {
+    #[serde(flatten)]
+    pub interaction: InteractionSummary,
+    pub changes: Vec<InteractionChange>,
+}
+
+/// The interaction-level differences between two pacts
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PactDiff {
+    pub added: Vec<InteractionSummary>,
+    pub removed: Vec<InteractionSummary>,
+    pub changed: Vec<ChangedInteraction>,
+}
+
+impl PactDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Returns the interactions of a pact, or the messages of a V3 message pact
+fn interactions(pact: &Value) -> Vec<&Value> {
+    ["interactions", "messages"]
+        .iter()
+        .filter_map(|key| pact[*key].as_array())
+        .flatten()
+        .collect()
+}
+
+fn provider_states(interaction: &Value) -> Vec<String> {
+    if let Some(state) = interaction["providerState"].as_str() {
+        vec![state.to_string()]
+    } else {
+        interaction["providerStates"]
+            .as_array()
+            .map(|states| {
+                states
+                    .iter()
+                    .filter_map(|state| state["name"].as_str().map(|name| name.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn summarise(interaction: &Value) -> InteractionSummary {
+    InteractionSummary {
+        description: interaction["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        provider_states: provider_states(interaction),
+    }
+}
+
+fn change_kind(path: &str) -> ChangeKind {
+    if path.starts_with("providerState") {
+        ChangeKind::ProviderState
+    } else if path.contains("matchingRules") {
+        ChangeKind::MatchingRule
+    } else if path.contains("generators") {
+        ChangeKind::Generator
+    } else {
+        ChangeKind::Content
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn diff_values(path: &str, from: &Value, to: &Value, changes: &mut Vec<InteractionChange>) {
+    match (from, to) {
+        (Value::Object(from_map), Value::Object(to_map)) => {
+            let keys: BTreeSet<&String> = from_map.keys().chain(to_map.keys()).collect();
+            for key in keys {
+                // The broker adds an _id to each interaction it stores
+                if path.is_empty() && key == "_id" {
+                    continue;
+                }
+                diff_values(
+                    &join_path(path, key),
+                    from_map.get(key).unwrap_or(&Value::Null),
+                    to_map.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        (Value::Array(from_items), Value::Array(to_items))
+            if from_items.len() == to_items.len() =>
+        {
+            for (index, (from_item, to_item)) in from_items.iter().zip(to_items).enumerate() {
+                diff_values(&format!("{}[{}]", path, index), from_item, to_item, changes);
+            }
+        }
+        _ if from != to => changes.push(InteractionChange {
+            path: path.to_string(),
+            kind: change_kind(path),
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Compares the interactions of two pacts. Interactions are matched on their description and
+/// provider states, and then on description alone, so that an interaction whose provider state
+/// has changed is reported as changed rather than as removed and added.
+pub fn compare_pacts(from: &Value, to: &Value) -> PactDiff {
+    let from_interactions = interactions(from);
+    let to_interactions = interactions(to);
+    let mut unmatched_from: Vec<&Value> = from_interactions.clone();
+    let mut unmatched_to: Vec<&Value> = vec![];
+    let mut pairs: Vec<(&Value, &Value)> = vec![];
+
+    for to_interaction in &to_interactions {
+        match unmatched_from.iter().position(|from_interaction| {
+            same_description_and_state(from_interaction, to_interaction)
+        }) {
+            Some(index) => pairs.push((unmatched_from.remove(index), to_interaction)),
+            None => unmatched_to.push(to_interaction),
+        }
+    }
+    let mut added = vec![];
+    for to_interaction in unmatched_to {
+        match unmatched_from.iter().position(|from_interaction| {
+            from_interaction.get("description") == to_interaction.get("description")
+        }) {
+            Some(index) => pairs.push((unmatched_from.remove(index), to_interaction)),
+            None => added.push(summarise(to_interaction)),
+        }
+    }
+
+    let changed = pairs
+        .into_iter()
+        .filter_map(|(from_interaction, to_interaction)| {
+            let mut changes = vec![];
+            diff_values("", from_interaction, to_interaction, &mut changes);
+            (!changes.is_empty()).then(|| ChangedInteraction {
+                interaction: summarise(to_interaction),
+                changes,
+            })
+        })
+        .collect();
+
+    PactDiff {
+        added,
+        removed: unmatched_from.into_iter().map(summarise).collect(),
+        changed,
+    }
+}
+
+async fn load_pact(
+    hal_client: Option<&HALClient>,
+    provider: Option<&String>,
+    consumer: Option<&String>,
+    source: &PactSource,
+) -> Result<Value, PactBrokerError> {
+    if let PactSource::File(file) = source {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| PactBrokerError::IoError(format!("Failed to read {}: {}", file, e)))?;
+        return serde_json::from_str(&content).map_err(|e| {
+            PactBrokerError::ContentError(format!("{} is not a valid pact file: {}", file, e))
+        });
+    }
+
+    let (Some(hal_client), Some(provider), Some(consumer)) = (hal_client, provider, consumer)
+    else {
+        return Err(PactBrokerError::ValidationError(vec![
+            "--broker-base-url or --profile, --consumer and --provider are required to fetch pacts from the Pact Broker".to_string(),
+        ]));
+    };
+    let mut template_values = HashMap::from([
+        ("provider".to_string(), provider.to_string()),
+        ("consumer".to_string(), consumer.to_string()),
+    ]);
+    let (relation, path) = match source {
+        PactSource::Version(version) => {
+            template_values.insert("consumerApplicationVersion".to_string(), version.clone());
+            (
+                "pb:pact-version",
+                "/pacts/provider/{provider}/consumer/{consumer}/version/{consumerApplicationVersion}",
+            )
+        }
+        PactSource::Branch(branch) => {
+            template_values.insert("branch".to_string(), branch.clone());
+            (
+                "pb:latest-branch-pact-version",
+                "/pacts/provider/{provider}/consumer/{consumer}/branch/{branch}/latest",
+            )
+        }
+        PactSource::Tag(tag) => {
+            template_values.insert("tag".to_string(), tag.clone());
+            (
+                "pb:latest-tagged-pact-version",
+                "/pacts/provider/{provider}/consumer/{consumer}/latest/{tag}",
+            )
+        }
+        PactSource::File(_) => unreachable!(),
+    };
+    follow_templated_broker_relation(
+        hal_client.clone(),
+        relation.to_string(),
+        path.to_string(),
+        template_values,
+    )
+    .await
+}
+
+fn format_interaction(interaction: &InteractionSummary) -> String {
+    if interaction.provider_states.is_empty() {
+        format!("\"{}\"", interaction.description)
+    } else {
+        format!(
+            "\"{}\" given \"{}\"",
+            interaction.description,
+            interaction.provider_states.join("\", \"")
+        )
+    }
+}
+
+fn format_diff(diff: &PactDiff) -> String {
+    let mut lines = vec![];
+    for interaction in &diff.added {
+        lines.push(
+            utils::GREEN
+                .apply_to(format!("+ {}", format_interaction(interaction)))
+                .to_string(),
+        );
+    }
+    for interaction in &diff.removed {
+        lines.push(
+            utils::RED
+                .apply_to(format!("- {}", format_interaction(interaction)))
+                .to_string(),
+        );
+    }
+    for changed in &diff.changed {
+        lines.push(
+            utils::YELLOW
+                .apply_to(format!("~ {}", format_interaction(&changed.interaction)))
+                .to_string(),
+        );
+        for change in &changed.changes {
+            let kind = match change.kind {
+                ChangeKind::ProviderState => " (provider state)",
+                ChangeKind::MatchingRule => " (matching rule)",
+                ChangeKind::Generator => " (generator)",
+                ChangeKind::Content => "",
+            };
+            lines.push(format!(
+                "    {}{}: {} → {}",
+                change.path, kind, change.from, change.to
+            ));
+        }
+    }
+    lines.push(format!(
+        "{} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    ));
+    lines.join("\n")
+}
+
+pub fn diff_pacts(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let from = PactSource::from_args(args, "from").unwrap();
+    let to = PactSource::from_args(args, "to").unwrap();
+    let provider = args.get_one::<String>("provider");
+    let consumer = args.get_one::<String>("consumer");
+    let hal_client = broker_url(args).map(|broker_url| {
+        HALClient::with_url(
+            broker_url.trim_end_matches('/'),
+            Some(get_auth(args)),
+            get_connection_options(args),
+            get_custom_headers(args),
+        )
+        .with_retry_count(get_retries(args))
+    });
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let from_pact = load_pact(hal_client.as_ref(), provider, consumer, &from).await?;
+        let to_pact = load_pact(hal_client.as_ref(), provider, consumer, &to).await?;
+        let diff = compare_pacts(&from_pact, &to_pact);
+
+        let output = OutputType::from_args(args);
+        if output.is_structured() {
+            return output::print(&serde_json::to_value(&diff).unwrap(), &output);
+        }
+
+        println!("🔍 Comparing the pact from {} with {}", from, to);
+        if diff.is_empty() {
+            let message = "✅ The pacts have the same interactions".to_string();
+            println!("{}", message);
+            return Ok(message);
+        }
+        let text = format_diff(&diff);
+        println!("{}", text);
+        Ok(text)
+    })
+}
+
+#[cfg(test)]
+mod diff_pacts_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::subcommands::add_diff_pacts_subcommand;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn pact(interactions: Value) -> Value {
+        json!({
+            "consumer": { "name": "Foo" },
+            "provider": { "name": "Bar" },
+            "interactions": interactions,
+            "metadata": { "pactSpecification": { "version": "3.0.0" } }
+        })
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_interactions() {
+        let from = pact(json!([
+            {
+                "_id": "1",
+                "description": "a request for an order",
+                "providerStates": [{ "name": "an order exists" }],
+                "request": { "method": "GET", "path": "/orders/1" },
+                "response": {
+                    "status": 200,
+                    "body": { "id": 1 },
+                    "matchingRules": { "body": { "$.id": { "matchers": [{ "match": "integer" }] } } }
+                }
+            },
+            {
+                "description": "a request to delete an order",
+                "request": { "method": "DELETE", "path": "/orders/1" },
+                "response": { "status": 204 }
+            }
+        ]));
+        let to = pact(json!([
+            {
+                "_id": "2",
+                "description": "a request for an order",
+                "providerStates": [{ "name": "an order exists" }],
+                "request": { "method": "GET", "path": "/orders/1" },
+                "response": {
+                    "status": 200,
+                    "body": { "id": 1 },
+                    "matchingRules": { "body": { "$.id": { "matchers": [{ "match": "number" }] } } }
+                }
+            },
+            {
+                "description": "a request to create an order",
+                "request": { "method": "POST", "path": "/orders" },
+                "response": { "status": 201 }
+            }
+        ]));
+
+        let diff = compare_pacts(&from, &to);
+
+        assert_eq!(
+            diff.added,
+            vec![InteractionSummary {
+                description: "a request to create an order".to_string(),
+                provider_states: vec![],
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![InteractionSummary {
+                description: "a request to delete an order".to_string(),
+                provider_states: vec![],
+            }]
+        );
+        assert_eq!(
+            diff.changed[0].changes,
+            vec![InteractionChange {
+                path: "response.matchingRules.body.$.id.matchers[0].match".to_string(),
+                kind: ChangeKind::MatchingRule,
+                from: json!("integer"),
+                to: json!("number"),
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_a_changed_provider_state_as_a_change() {
+        let from = pact(json!([{
+            "description": "a request for an order",
+            "providerState": "an order exists",
+            "request": { "method": "GET", "path": "/orders/1" },
+            "response": { "status": 200 }
+        }]));
+        let to = pact(json!([{
+            "description": "a request for an order",
+            "providerState": "a paid order exists",
+            "request": { "method": "GET", "path": "/orders/1" },
+            "response": { "status": 200 }
+        }]));
+
+        let diff = compare_pacts(&from, &to);
+
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed[0].changes[0].kind, ChangeKind::ProviderState);
+        assert!(compare_pacts(&from, &from).is_empty());
+    }
+
+    #[test]
+    fn diffs_a_pact_in_the_broker_with_a_local_file() {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction(
+                "a request for the latest pact from a branch",
+                "",
+                |mut i| {
+                    i.given("a pact between Foo and Bar has been published from the main branch");
+                    i.request
+                        .get()
+                        .path("/pacts/provider/Bar/consumer/Foo/branch/main/latest")
+                        .header("Accept", "application/hal+json")
+                        .header("Accept", "application/json");
+                    i.response
+                        .status(200)
+                        .header("Content-Type", "application/hal+json;charset=utf-8")
+                        .json_body(json_pattern!({
+                            "consumer": { "name": "Foo" },
+                            "provider": { "name": "Bar" },
+                            "interactions": [{
+                                "description": "a request for an order",
+                                "request": { "method": "GET", "path": "/orders/1" },
+                                "response": { "status": 200 }
+                            }]
+                        }));
+                    i
+                },
+            )
+            .start_mock_server(None, Some(config));
+        let mock_server_url = pact_broker_service.url();
+
+        let file = std::env::temp_dir().join(format!(
+            "pact-broker-cli-diff-pacts-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &file,
+            pact(json!([{
+                "description": "a request for an order",
+                "request": { "method": "GET", "path": "/orders/1" },
+                "response": { "status": 404 }
+            }]))
+            .to_string(),
+        )
+        .unwrap();
+
+        let matches = add_diff_pacts_subcommand().get_matches_from(vec![
+            "diff-pacts",
+            "-b",
+            mock_server_url.as_str(),
+            "--consumer",
+            "Foo",
+            "--provider",
+            "Bar",
+            "--from-branch",
+            "main",
+            "--to-file",
+            file.to_str().unwrap(),
+            "--output",
+            "json",
+        ]);
+        let result = diff_pacts(&matches);
+        let _ = std::fs::remove_file(&file);
+
+        let diff: Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(
+            diff["changed"][0]["changes"],
+            json!([{ "path": "response.status", "kind": "content", "from": 200, "to": 404 }])
+        );
+    }
+
+    #[test]
+    fn fetches_pacts_from_the_broker_of_the_selected_profile() {
+        use crate::cli::pact_broker::main::config::CONFIG_FILE_ENV;
+        use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+        use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let state = serde_json::from_value::<MockBrokerState>(json!({
+            "pacticipants": [{ "name": "Foo" }, { "name": "Bar" }],
+            "versions": [{ "pacticipant": "Foo", "number": "1.0.0" }],
+            "pacts": [{
+                "consumer": "Foo",
+                "consumerVersion": "1.0.0",
+                "provider": "Bar",
+                "content": pact(json!([]))
+            }]
+        }))
+        .unwrap();
+        let (_broker, url) = runtime.block_on(spawn_mock_broker(state));
+        let config = std::env::temp_dir().join(format!(
+            "pact-broker-cli-diff-pacts-config-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(
+            &config,
+            format!("[profiles.local]\nurl = \"{}\"\nretries = 0\n", url),
+        )
+        .unwrap();
+        unsafe { std::env::set_var(CONFIG_FILE_ENV, &config) };
+
+        let matches = crate::cli::build_cli().get_matches_from(vec![
+            "pact-broker-cli",
+            "diff-pacts",
+            "--profile",
+            "local",
+            "--consumer",
+            "Foo",
+            "--provider",
+            "Bar",
+            "--from-version",
+            "1.0.0",
+            "--to-version",
+            "1.0.0",
+            "--output",
+            "json",
+        ]);
+        let result = diff_pacts(matches.subcommand_matches("diff-pacts").unwrap());
+        unsafe { std::env::remove_var(CONFIG_FILE_ENV) };
+        let _ = std::fs::remove_file(&config);
+
+        let diff: Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(diff, json!({ "added": [], "removed": [], "changed": [] }));
+    }
+}
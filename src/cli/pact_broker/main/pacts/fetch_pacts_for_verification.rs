@@ -0,0 +1,471 @@
+use crate::{
+    cli::pact_broker::main::output::{self, OutputType},
+    cli::pact_broker::main::types::BrokerDetails,
+    cli::pact_broker::main::{
+        ConsumerVersionSelector, PactBrokerError, fetch_pacts_dynamically_from_broker,
+    },
+    cli::utils,
+};
+use comfy_table::{Table, presets::UTF8_FULL};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A pact returned from the pacts for verification endpoint, along with the reasons the broker
+/// gave for including it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PactForVerificationSummary {
+    pub consumer: String,
+    pub provider: String,
+    pub url: Option<String>,
+    pub short_description: Option<String>,
+    pub pending: bool,
+    pub wip: bool,
+    pub notices: Vec<HashMap<String, String>>,
+    #[serde(skip)]
+    pub pact_json: serde_json::Value,
+}
+
+/// Parses the JSON consumer version selectors provided on the command line
+pub fn parse_consumer_version_selectors(
+    selectors: &[String],
+) -> Result<Vec<ConsumerVersionSelector>, PactBrokerError> {
+    let mut errors = vec![];
+    let parsed = selectors
+        .iter()
+        .filter_map(
+            |selector| match serde_json::from_str::<ConsumerVersionSelector>(selector) {
+                Ok(selector) => Some(selector),
+                Err(err) => {
+                    errors.push(format!(
+                        "Invalid consumer version selector '{}' - {}",
+                        selector, err
+                    ));
+                    None
+                }
+            },
+        )
+        .collect();
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(PactBrokerError::ValidationError(errors))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn fetch_pacts_for_verification(
+    broker_details: &BrokerDetails,
+    provider: &str,
+    consumer_version_selectors: Vec<ConsumerVersionSelector>,
+    enable_pending: bool,
+    include_wip_pacts_since: Option<String>,
+    provider_version_branch: Option<String>,
+    provider_version_tags: Vec<String>,
+    output_type: OutputType,
+    download: bool,
+    download_dir: &str,
+) -> Result<String, PactBrokerError> {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let results = fetch_pacts_dynamically_from_broker(
+            &broker_details.url,
+            provider.to_string(),
+            enable_pending,
+            include_wip_pacts_since,
+            provider_version_tags,
+            provider_version_branch,
+            consumer_version_selectors,
+            broker_details.auth.clone(),
+            broker_details.connection_options.clone(),
+            None,
+            broker_details.custom_headers.clone(),
+        )
+        .await
+        .map_err(|err| match err.downcast::<PactBrokerError>() {
+            Ok(err) => err,
+            Err(err) => PactBrokerError::IoError(err.to_string()),
+        })?;
+
+        let mut pacts = vec![];
+        for result in results {
+            let (pact, context, links) = result?;
+            let url = links
+                .iter()
+                .find(|link| link.name == "self")
+                .and_then(|link| link.href.clone());
+            let properties = context
+                .as_ref()
+                .map(|c| c.verification_properties.clone())
+                .unwrap_or_default();
+            pacts.push(PactForVerificationSummary {
+                consumer: pact.consumer().name,
+                provider: pact.provider().name,
+                url,
+                short_description: context.map(|c| c.short_description),
+                pending: properties.pending,
+                wip: properties.wip,
+                notices: properties.notices,
+                pact_json: pact
+                    .to_json(pact.specification_version())
+                    .map_err(|err| PactBrokerError::ContentError(err.to_string()))?,
+            });
+        }
+
+        if download {
+            download_pacts(&pacts, download_dir, &output_type)?;
+        }
+
+        if output_type.is_structured() {
+            return output::print(&serde_json::to_value(&pacts).unwrap(), &output_type);
+        }
+        let output = match output_type {
+            OutputType::Pretty => serde_json::to_string_pretty(&pacts).unwrap(),
+            _ => generate_pacts_for_verification_table(&pacts),
+        };
+
+        println!("{}", output);
+        Ok(output)
+    })
+}
+
+fn download_pacts(
+    pacts: &[PactForVerificationSummary],
+    download_dir: &str,
+    output_type: &OutputType,
+) -> Result<(), PactBrokerError> {
+    fs::create_dir_all(download_dir).map_err(|e| {
+        PactBrokerError::IoError(format!("Failed to create download directory: {}", e))
+    })?;
+
+    tracing::info!("Downloading {} pact(s) to {}", pacts.len(), download_dir);
+
+    for (index, pact) in pacts.iter().enumerate() {
+        // The same consumer can be selected more than once (e.g. main branch and deployed
+        // versions), so use the pact version from the URL to keep the file names unique
+        let suffix = pact
+            .url
+            .as_deref()
+            .and_then(pact_version_from_url)
+            .unwrap_or_else(|| (index + 1).to_string());
+        let filename = format!("{}-{}-{}.json", pact.consumer, pact.provider, suffix);
+        let file_path = Path::new(download_dir).join(&filename);
+
+        let content_str = serde_json::to_string_pretty(&pact.pact_json).map_err(|e| {
+            PactBrokerError::ContentError(format!("Failed to serialize pact content: {}", e))
+        })?;
+        fs::write(&file_path, content_str).map_err(|e| {
+            PactBrokerError::IoError(format!("Failed to write pact file {}: {}", filename, e))
+        })?;
+
+        output::status(output_type, format!("  → {}", file_path.display()));
+    }
+
+    Ok(())
+}
+
+fn pact_version_from_url(url: &str) -> Option<String> {
+    let mut segments = url.split('/');
+    segments.find(|segment| *segment == "pact-version")?;
+    segments
+        .next()
+        .filter(|sha| !sha.is_empty())
+        .map(|sha| sha.to_string())
+}
+
+fn generate_pacts_for_verification_table(pacts: &[PactForVerificationSummary]) -> String {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(vec![
+        "CONSUMER",
+        "PROVIDER",
+        "PENDING",
+        "WIP",
+        "DESCRIPTION",
+    ]);
+    for pact in pacts {
+        table.add_row(vec![
+            pact.consumer.clone(),
+            pact.provider.clone(),
+            pact.pending.to_string(),
+            pact.wip.to_string(),
+            pact.short_description.clone().unwrap_or_default(),
+        ]);
+    }
+
+    let mut output = table.to_string();
+    for pact in pacts.iter().filter(|pact| !pact.notices.is_empty()) {
+        output.push_str(&format!(
+            "\n\n{}",
+            utils::CYAN.apply_to(format!(
+                "Notices for {} ({}):",
+                pact.consumer,
+                pact.short_description.clone().unwrap_or_default()
+            ))
+        ));
+        for notice in &pact.notices {
+            if let Some(text) = notice.get("text") {
+                output.push_str(&format!("\n  - {}", text));
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod fetch_pacts_for_verification_tests {
+    use super::*;
+    use crate::cli::pact_broker::main::types::ConnectionOptions;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+    use serde_json::json;
+
+    #[test]
+    fn parse_consumer_version_selectors_accepts_json_selectors() {
+        let selectors = parse_consumer_version_selectors(&[
+            r#"{"mainBranch": true}"#.to_string(),
+            r#"{"deployedOrReleased": true, "consumer": "Foo"}"#.to_string(),
+        ])
+        .unwrap();
+
+        assert_eq!(selectors.len(), 2);
+        assert_eq!(selectors[0].main_branch, Some(true));
+        assert_eq!(selectors[1].deployed_or_released, Some(true));
+        assert_eq!(selectors[1].consumer, Some("Foo".to_string()));
+    }
+
+    #[test]
+    fn parse_consumer_version_selectors_rejects_invalid_json() {
+        let result = parse_consumer_version_selectors(&["mainBranch".to_string()]);
+
+        match result {
+            Err(PactBrokerError::ValidationError(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert!(errors[0].contains("Invalid consumer version selector 'mainBranch'"));
+            }
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pact_version_from_url_extracts_the_sha() {
+        assert_eq!(
+            pact_version_from_url(
+                "http://localhost/pacts/provider/Bar/consumer/Foo/pact-version/1234abcd/metadata/abc"
+            ),
+            Some("1234abcd".to_string())
+        );
+        assert_eq!(
+            pact_version_from_url("http://localhost/pacts/provider/Bar/consumer/Foo/latest"),
+            None
+        );
+    }
+
+    /// A broker with one pact for the provider Bar, from the main branch of Foo
+    fn pacts_for_verification_broker() -> Box<dyn ValidatingMockServer> {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+
+        PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction("a request for the index resource", "", |mut i| {
+                i.given("the pb:provider-pacts-for-verification relation exists in the index resource");
+                i.request
+                    .get()
+                    .path("/")
+                    .header("Accept", "application/hal+json")
+                    .header("Accept", "application/json");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "_links": {
+                            "pb:provider-pacts-for-verification": {
+                                "href": term!(
+                                    "http:\\/\\/.*\\{provider\\}",
+                                    "http://localhost/pacts/provider/{provider}/for-verification"
+                                ),
+                                "templated": true
+                            }
+                        }
+                    }));
+                i
+            })
+            .interaction(
+                "a request to the pacts for verification endpoint",
+                "",
+                |mut i| {
+                    i.given("Bar has pact with consumer Foo");
+                    i.request
+                        .get()
+                        .path("/pacts/provider/Bar/for-verification")
+                        .header("Accept", "application/hal+json")
+                        .header("Accept", "application/json");
+                    i.response
+                        .status(200)
+                        .header("Content-Type", "application/hal+json;charset=utf-8")
+                        .json_body(json_pattern!({
+                            "_links": {
+                                "self": {
+                                    "href": like!("http://localhost/pacts/provider/Bar/for-verification")
+                                }
+                            }
+                        }));
+                    i
+                },
+            )
+            .interaction("a request for pacts for verification", "", |mut i| {
+                i.given("Foo has a pact with provider Bar");
+                i.request
+                    .post()
+                    .path("/pacts/provider/Bar/for-verification")
+                    .header("Accept", "application/hal+json")
+                    .header("Content-Type", "application/json")
+                    .json_body(json_pattern!({
+                        "consumerVersionSelectors": [{ "mainBranch": true }],
+                        "includePendingStatus": true,
+                        "providerVersionBranch": "main"
+                    }));
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "_embedded": {
+                            "pacts": each_like!({
+                                "shortDescription": "latest from main branch",
+                                "verificationProperties": {
+                                    "pending": true,
+                                    "notices": each_like!({
+                                        "when": "before_verification",
+                                        "text": "The pact at ... is being verified because it matches the following configured selection criterion: latest pact for a consumer version from the main branch"
+                                    })
+                                },
+                                "_links": {
+                                    "self": {
+                                        "href": term!(
+                                            "http:\\/\\/[^/]+\\/pacts\\/provider\\/Bar\\/consumer\\/Foo\\/pact-version\\/[a-f0-9]+\\/metadata\\/.*",
+                                            "http://localhost/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/metadata/c1tdW2xd"
+                                        ),
+                                        "name": "Pact between Foo (1.0.0) and Bar"
+                                    }
+                                }
+                            })
+                        }
+                    }));
+                i
+            })
+            .interaction("a request for a pact", "", |mut i| {
+                i.given("Foo has a pact with provider Bar");
+                i.request
+                    .get()
+                    .path("/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/metadata/c1tdW2xd")
+                    .header("Accept", "application/hal+json")
+                    .header("Accept", "application/json");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "consumer": { "name": "Foo" },
+                        "provider": { "name": "Bar" },
+                        "interactions": [],
+                        "metadata": { "pactSpecification": { "version": "2.0.0" } },
+                        "_links": {
+                            "self": {
+                                "href": "http://localhost/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/metadata/c1tdW2xd"
+                            }
+                        }
+                    }));
+                i
+            })
+            .start_mock_server(None, Some(config))
+    }
+
+    #[test]
+    fn fetch_pacts_for_verification_returns_pacts_with_their_context() {
+        let pact_broker_service = pacts_for_verification_broker();
+        let mock_server_url = pact_broker_service.url();
+
+        let broker_details = BrokerDetails {
+            url: mock_server_url.to_string(),
+            auth: None,
+            connection_options: ConnectionOptions::default(),
+            custom_headers: None,
+            retries: 0,
+        };
+
+        let result = fetch_pacts_for_verification(
+            &broker_details,
+            "Bar",
+            parse_consumer_version_selectors(&[r#"{"mainBranch":true}"#.to_string()]).unwrap(),
+            true,
+            None,
+            Some("main".to_string()),
+            vec![],
+            OutputType::Json,
+            false,
+            "./pacts",
+        );
+
+        let output: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(output[0]["consumer"], json!("Foo"));
+        assert_eq!(output[0]["provider"], json!("Bar"));
+        assert_eq!(
+            output[0]["shortDescription"],
+            json!("latest from main branch")
+        );
+        assert_eq!(output[0]["pending"], json!(true));
+        assert_eq!(output[0]["wip"], json!(false));
+        assert_eq!(
+            output[0]["url"],
+            json!(
+                "http://localhost/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/metadata/c1tdW2xd"
+            )
+        );
+        assert_eq!(
+            output[0]["notices"][0]["when"],
+            json!("before_verification")
+        );
+    }
+
+    #[test]
+    fn fetch_pacts_for_verification_is_not_held_back_by_dry_run() {
+        let pact_broker_service = pacts_for_verification_broker();
+        let mock_server_url = pact_broker_service.url();
+        let matches = crate::cli::build_cli().get_matches_from([
+            "pact-broker-cli",
+            "fetch-pacts-for-verification",
+            "-b",
+            mock_server_url.as_str(),
+            "--provider",
+            "Bar",
+            "--consumer-version-selector",
+            r#"{"mainBranch":true}"#,
+            "--provider-version-branch",
+            "main",
+            "--enable-pending",
+            "--dry-run",
+        ]);
+        let (_, args) = matches.subcommand().unwrap();
+        let broker_details = BrokerDetails::from_args(args).unwrap();
+        assert!(broker_details.connection_options.dry_run);
+
+        let result = fetch_pacts_for_verification(
+            &broker_details,
+            "Bar",
+            parse_consumer_version_selectors(&[r#"{"mainBranch":true}"#.to_string()]).unwrap(),
+            true,
+            None,
+            Some("main".to_string()),
+            vec![],
+            OutputType::Json,
+            false,
+            "./pacts",
+        );
+
+        let output: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(output[0]["consumer"], json!("Foo"));
+        assert_eq!(output[0]["provider"], json!("Bar"));
+    }
+}
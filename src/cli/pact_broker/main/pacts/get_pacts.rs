@@ -1,10 +1,9 @@
 use crate::{
-    cli::pact_broker::main::types::{BrokerDetails, OutputType},
-    cli::pact_broker::main::utils::{follow_templated_broker_relation, generate_table},
+    cli::pact_broker::main::output::{self, OutputType, generate_table},
+    cli::pact_broker::main::types::{BrokerDetails, Pagination},
     cli::pact_broker::main::{HALClient, PactBrokerError},
 };
 use serde_json::{Value, json};
-use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
@@ -18,6 +17,7 @@ pub fn get_pacts(
     output_type: OutputType,
     download: bool,
     download_dir: &str,
+    pagination: &Pagination,
 ) -> Result<String, PactBrokerError> {
     let rt = tokio::runtime::Runtime::new().unwrap();
     rt.block_on(async {
@@ -25,28 +25,18 @@ pub fn get_pacts(
         let hal_client = HALClient::with_url(
             &broker_details.url,
             broker_details.auth.clone(),
-            broker_details.ssl_options.clone(),
+            broker_details.connection_options.clone(),
             broker_details.custom_headers.clone(),
         )
         .with_retry_count(broker_details.retries);
 
-        // Build the appropriate HAL relation and template parameters
-        let (relation, path) = build_pacts_path(provider, consumer, branch, latest);
+        // Build the path of the appropriate HAL relation
+        let (_, path) = build_pacts_path(provider, consumer, branch, latest);
 
-        // Create template parameters for the HAL relation
-        let mut template_params = HashMap::new();
-        template_params.insert("provider".to_string(), provider.to_string());
-        if let Some(consumer_name) = consumer {
-            template_params.insert("consumer".to_string(), consumer_name.to_string());
-        }
-        if let Some(branch_name) = branch {
-            template_params.insert("branch".to_string(), branch_name.to_string());
-        }
-
-        // Follow the HAL relation with template parameters
-        let result =
-            follow_templated_broker_relation(hal_client.clone(), relation, path, template_params)
-                .await?;
+        // Fetch the pacts, following the pages of the collection
+        let result = hal_client
+            .fetch_collection(&path, &["/_links/pb:pacts"], pagination)
+            .await?;
 
         // Parse the response based on the structure
         let pacts_data = if let Some(pacts_array) =
@@ -62,14 +52,15 @@ pub fn get_pacts(
 
         // Download pacts if requested
         if download {
-            download_pacts(&pacts_data, &hal_client, download_dir).await?;
+            download_pacts(&pacts_data, &hal_client, download_dir, &output_type).await?;
         }
 
+        if output_type.is_structured() {
+            return output::print(&pacts_data, &output_type);
+        }
         let output = match output_type {
-            OutputType::Json => serde_json::to_string_pretty(&pacts_data).unwrap(),
-            OutputType::Table => generate_pacts_table(&pacts_data, consumer.is_some()),
-            OutputType::Text => generate_pacts_table(&pacts_data, consumer.is_some()),
             OutputType::Pretty => serde_json::to_string_pretty(&pacts_data).unwrap(),
+            _ => generate_pacts_table(&pacts_data, consumer.is_some()),
         };
 
         println!("{}", output);
@@ -153,6 +144,7 @@ async fn download_pacts(
     pacts_data: &Value,
     hal_client: &HALClient,
     download_dir: &str,
+    output_type: &OutputType,
 ) -> Result<(), PactBrokerError> {
     // Create download directory if it doesn't exist
     fs::create_dir_all(download_dir).map_err(|e| {
@@ -239,7 +231,7 @@ async fn download_pacts(
             PactBrokerError::IoError(format!("Failed to write pact file {}: {}", filename, e))
         })?;
 
-        println!("  → {}", file_path.display());
+        output::status(output_type, format!("  → {}", file_path.display()));
     }
 
     Ok(())
@@ -258,7 +250,7 @@ fn generate_pacts_table(result: &Value, _has_consumer_filter: bool) -> String {
 #[cfg(test)]
 mod get_pacts_tests {
     use super::*;
-    use crate::cli::pact_broker::main::types::{BrokerDetails, OutputType, SslOptions};
+    use crate::cli::pact_broker::main::types::{BrokerDetails, ConnectionOptions, OutputType};
     use pact_consumer::prelude::*;
     use pact_models::PactSpecification;
     use serde_json::json;
@@ -409,7 +401,7 @@ mod get_pacts_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: SslOptions::default(),
+            connection_options: ConnectionOptions::default(),
             custom_headers: None,
             retries: 0,
         };
@@ -424,6 +416,7 @@ mod get_pacts_tests {
             OutputType::Json,
             false,
             "./pacts",
+            &Pagination::all(),
         );
 
         // assert
@@ -532,7 +525,7 @@ mod get_pacts_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: SslOptions::default(),
+            connection_options: ConnectionOptions::default(),
             custom_headers: None,
             retries: 0,
         };
@@ -547,6 +540,7 @@ mod get_pacts_tests {
             OutputType::Json,
             false,
             "./pacts",
+            &Pagination::all(),
         );
 
         // assert
@@ -621,7 +615,7 @@ mod get_pacts_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: SslOptions::default(),
+            connection_options: ConnectionOptions::default(),
             custom_headers: None,
             retries: 0,
         };
@@ -636,6 +630,7 @@ mod get_pacts_tests {
             OutputType::Json,
             false,
             "./pacts",
+            &Pagination::all(),
         );
 
         // assert
@@ -1,19 +1,22 @@
 use crate::{
     cli::pact_broker::main::{HALClient, PactBrokerError},
     cli::pact_broker::main::{
-        types::{BrokerDetails, OutputType},
-        utils::{follow_broker_relation, generate_table, get_broker_relation},
+        output::generate_table,
+        output::{self, OutputType},
+        types::{BrokerDetails, Pagination},
+        utils::get_broker_relation,
     },
 };
 
 pub fn list_latest_pact_versions(
     broker_details: &BrokerDetails,
     output_type: OutputType,
+    pagination: &Pagination,
 ) -> Result<String, PactBrokerError> {
     // setup client with broker url and credentials
     let broker_url = &broker_details.url;
     let auth = &broker_details.auth;
-    let ssl_options = &broker_details.ssl_options;
+    let connection_options = &broker_details.connection_options;
     let custom_headers = &broker_details.custom_headers;
 
     tokio::runtime::Runtime::new().unwrap().block_on(async {
@@ -21,7 +24,7 @@ pub fn list_latest_pact_versions(
         let hal_client: HALClient = HALClient::with_url(
             broker_url,
             auth.clone(),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(broker_details.retries);
@@ -40,19 +43,16 @@ pub fn list_latest_pact_versions(
         }
 
         // query the hal relation link to get the latest pact versions
-        let res = follow_broker_relation(
-            hal_client.clone(),
-            "pb:latest-pact-versions".to_string(),
-            pb_latest_pact_versions_href_path.unwrap(),
-        )
-        .await;
+        let res = hal_client
+            .fetch_collection(
+                &pb_latest_pact_versions_href_path.unwrap(),
+                &["/pacts"],
+                pagination,
+            )
+            .await;
         match res {
             Ok(result) => match output_type {
-                OutputType::Json => {
-                    let json: String = serde_json::to_string(&result).unwrap();
-                    println!("{}", json);
-                    Ok(json)
-                }
+                ref output if output.is_structured() => output::print(&result, output),
                 OutputType::Table => {
                     let table = generate_table(
                         &result,
@@ -73,7 +73,7 @@ pub fn list_latest_pact_versions(
                     println!("{:?}", text);
                     Ok(text)
                 }
-                OutputType::Pretty => {
+                _ => {
                     let json: String = serde_json::to_string(&result).unwrap();
                     println!("{}", json);
                     Ok(json)
@@ -87,7 +87,9 @@ pub fn list_latest_pact_versions(
 #[cfg(test)]
 mod lists_latest_pact_versions_tests {
     use crate::cli::pact_broker::main::pacts::list_latest_pact_versions::list_latest_pact_versions;
-    use crate::cli::pact_broker::main::types::{BrokerDetails, OutputType, SslOptions};
+    use crate::cli::pact_broker::main::types::{
+        BrokerDetails, ConnectionOptions, OutputType, Pagination,
+    };
     use pact_consumer::prelude::*;
     use pact_models::PactSpecification;
     use serde_json::json;
@@ -180,13 +182,14 @@ mod lists_latest_pact_versions_tests {
         let broker_details = BrokerDetails {
             url: mock_server_url.to_string(),
             auth: None,
-            ssl_options: SslOptions::default(),
+            connection_options: ConnectionOptions::default(),
             custom_headers: None,
             retries: 0,
         };
 
         // act
-        let result = list_latest_pact_versions(&broker_details, OutputType::Json);
+        let result =
+            list_latest_pact_versions(&broker_details, OutputType::Json, &Pagination::all());
 
         // assert
         assert!(result.is_ok());
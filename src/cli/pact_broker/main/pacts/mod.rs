@@ -1,2 +1,5 @@
+pub mod diff_pacts;
+pub mod fetch_pacts_for_verification;
 pub mod get_pacts;
 pub mod list_latest_pact_versions;
+pub mod publish_verification_results;
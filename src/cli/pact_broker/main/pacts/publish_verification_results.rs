@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use clap::ArgMatches;
+use serde_json::{Value, json};
+
+use crate::cli::{
+    pact_broker::main::{
+        HALClient, Notice, PactBrokerError,
+        output::{self, OutputType},
+        process_notices,
+        utils::{
+            follow_broker_relation, follow_templated_broker_relation, get_auth, get_broker_url,
+            get_connection_options, get_custom_headers, get_retries,
+        },
+    },
+    utils::{self, git_info},
+};
+
+pub fn publish_verification_results(args: &ArgMatches) -> Result<Value, PactBrokerError> {
+    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
+    let auth = get_auth(args);
+    let custom_headers = get_custom_headers(args);
+    let connection_options = get_connection_options(args);
+    let hal_client: HALClient =
+        HALClient::with_url(&broker_url, Some(auth), connection_options, custom_headers)
+            .with_retry_count(get_retries(args));
+    let output = OutputType::from_args(args);
+
+    let auto_detect_version_properties = args.get_flag("auto-detect-version-properties");
+    let provider_app_version = match args.get_one::<String>("provider-app-version") {
+        Some(version) => Some(version.to_string()),
+        None if auto_detect_version_properties => {
+            let commit = git_info::commit(false);
+            if let Some(ref v) = commit {
+                output::status(&output, format!("🔍 Auto detected git commit: {}", v));
+            }
+            commit
+        }
+        None => None,
+    }
+    .ok_or_else(|| {
+        PactBrokerError::ValidationError(vec![
+            "A provider version is required, either via --provider-app-version or --auto-detect-version-properties".to_string(),
+        ])
+    })?;
+    let branch = match args.get_one::<String>("branch") {
+        Some(branch) => Some(branch.to_string()),
+        None if auto_detect_version_properties => {
+            let branch = git_info::branch(false);
+            if let Some(ref b) = branch {
+                output::status(&output, format!("🔍 Auto detected git branch: {}", b));
+            }
+            branch
+        }
+        None => None,
+    };
+    let build_url = match args.get_one::<String>("build-url") {
+        Some(build_url) => Some(build_url.to_string()),
+        None if auto_detect_version_properties => git_info::build_url(),
+        None => None,
+    };
+
+    // Exactly one of these is given, which the verification-result group enforces
+    let success = match args.get_one::<i32>("verification-exit-code") {
+        Some(exit_code) => *exit_code == 0,
+        None => args.get_flag("verification-success"),
+    };
+
+    let test_results = match args.get_one::<String>("verification-results") {
+        Some(file_path) => {
+            let content = std::fs::read_to_string(file_path).map_err(|e| {
+                PactBrokerError::IoError(format!(
+                    "Failed to read verification results file '{}': {}",
+                    file_path, e
+                ))
+            })?;
+            Some(serde_json::from_str::<Value>(&content).map_err(|e| {
+                PactBrokerError::ContentError(format!(
+                    "Verification results file '{}' is not valid JSON: {}",
+                    file_path, e
+                ))
+            })?)
+        }
+        None => None,
+    };
+
+    let mut payload = json!({
+        "success": success,
+        "providerApplicationVersion": provider_app_version,
+    });
+    if let Some(ref branch) = branch {
+        payload["providerVersionBranch"] = Value::String(branch.to_string());
+    }
+    if let Some(build_url) = build_url {
+        payload["buildUrl"] = Value::String(build_url);
+    }
+    if let Some(test_results) = test_results {
+        payload["testResults"] = test_results;
+    }
+    if let Some(verifier) = args.get_one::<String>("verifier") {
+        let mut verified_by = json!({ "implementation": verifier });
+        if let Some(verifier_version) = args.get_one::<String>("verifier-version") {
+            verified_by["version"] = Value::String(verifier_version.to_string());
+        }
+        payload["verifiedBy"] = verified_by;
+    }
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let pact = match args.get_one::<String>("pact-url") {
+            Some(pact_url) => {
+                follow_broker_relation(hal_client.clone(), "self".to_string(), pact_url.clone())
+                    .await?
+            }
+            None => {
+                let template_values = HashMap::from([
+                    (
+                        "provider".to_string(),
+                        args.get_one::<String>("provider").unwrap().to_string(),
+                    ),
+                    (
+                        "consumer".to_string(),
+                        args.get_one::<String>("consumer").unwrap().to_string(),
+                    ),
+                    (
+                        "consumerApplicationVersion".to_string(),
+                        args.get_one::<String>("consumer-version")
+                            .unwrap()
+                            .to_string(),
+                    ),
+                ]);
+                follow_templated_broker_relation(
+                    hal_client.clone(),
+                    "pb:pact-version".to_string(),
+                    "/pacts/provider/{provider}/consumer/{consumer}/version/{consumerApplicationVersion}".to_string(),
+                    template_values,
+                )
+                .await?
+            }
+        };
+
+        let publish_href = pact
+            .get("_links")
+            .and_then(|links| links.get("pb:publish-verification-results"))
+            .and_then(|link| link.get("href"))
+            .and_then(|href| href.as_str())
+            .ok_or_else(|| {
+                PactBrokerError::LinkError(
+                    "Link 'pb:publish-verification-results' was not found in the pact resource"
+                        .to_string(),
+                )
+            })?;
+        let consumer_name = pact
+            .get("consumer")
+            .and_then(|c| c.get("name"))
+            .and_then(|n| n.as_str())
+            .unwrap_or("unknown");
+        let provider_name = pact
+            .get("provider")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+            .unwrap_or("unknown");
+
+        let res = hal_client
+            .post_json(publish_href, &payload.to_string(), None)
+            .await?;
+
+        if output.is_structured() {
+            output::print(&res, &output)?;
+        } else {
+            let outcome = if success {
+                utils::GREEN.apply_to("successful")
+            } else {
+                utils::RED.apply_to("failed")
+            };
+            println!(
+                "✅ Published {} verification results for the pact between {} and {} version {}",
+                outcome,
+                utils::GREEN.apply_to(consumer_name),
+                utils::GREEN.apply_to(provider_name),
+                utils::GREEN.apply_to(&provider_app_version)
+            );
+            if let Some(notices) = res.get("notices").and_then(|n| {
+                serde_json::from_value::<Vec<Notice>>(n.clone())
+                    .ok()
+            }) {
+                process_notices(&notices);
+            }
+        }
+        Ok(res)
+    })
+}
+
+#[cfg(test)]
+mod publish_verification_results_tests {
+    use super::publish_verification_results;
+    use crate::cli::pact_broker::main::subcommands::add_publish_verification_results_subcommand;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+
+    #[test]
+    fn publishes_verification_results_for_a_consumer_version() {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction("a request for a pact by consumer version", "", |mut i| {
+                i.given("the pact for Foo version 1.2.3 has been published");
+                i.request
+                    .get()
+                    .path("/pacts/provider/Bar/consumer/Foo/version/1.2.3")
+                    .header("Accept", "application/hal+json")
+                    .header("Accept", "application/json");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "consumer": { "name": "Foo" },
+                        "provider": { "name": "Bar" },
+                        "_links": {
+                            "pb:publish-verification-results": {
+                                "href": term!(
+                                    "http:\\/\\/.*\\/pacts\\/provider\\/Bar\\/consumer\\/Foo\\/pact-version\\/[a-f0-9]+\\/verification-results",
+                                    "http://localhost/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/verification-results"
+                                )
+                            }
+                        }
+                    }));
+                i
+            })
+            .interaction("a request to publish verification results", "", |mut i| {
+                i.given("the pact for Foo version 1.2.3 has been published");
+                i.request
+                    .post()
+                    .path("/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/verification-results")
+                    .header("Accept", "application/hal+json")
+                    .header("Content-Type", "application/json")
+                    .json_body(json_pattern!({
+                        "success": true,
+                        "providerApplicationVersion": "4.5.6",
+                        "providerVersionBranch": "main",
+                        "testResults": [{ "interactionId": "1", "success": true }]
+                    }));
+                i.response
+                    .status(201)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "success": true,
+                        "providerApplicationVersion": "4.5.6",
+                        "_links": {
+                            "self": {
+                                "href": like!("http://localhost/pacts/provider/Bar/consumer/Foo/pact-version/0e3369199f4008231946e0245474537443ccda2a/verification-results/1")
+                            }
+                        }
+                    }));
+                i
+            })
+            .start_mock_server(None, Some(config));
+        let mock_server_url = pact_broker_service.url();
+
+        let results_path = std::env::temp_dir().join(format!(
+            "pact-broker-cli-verification-results-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &results_path,
+            r#"[{ "interactionId": "1", "success": true }]"#,
+        )
+        .unwrap();
+        let results_path = results_path.to_str().unwrap().to_string();
+
+        let matches = add_publish_verification_results_subcommand().get_matches_from(vec![
+            "publish-verification-results",
+            "-b",
+            mock_server_url.as_str(),
+            "--consumer",
+            "Foo",
+            "--provider",
+            "Bar",
+            "--consumer-version",
+            "1.2.3",
+            "--provider-app-version",
+            "4.5.6",
+            "--branch",
+            "main",
+            "--verification-success",
+            "--verification-results",
+            results_path.as_str(),
+        ]);
+
+        let result = publish_verification_results(&matches);
+        let _ = std::fs::remove_file(&results_path);
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(result.unwrap()["success"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn requires_a_pact_url_or_consumer_details() {
+        let result = add_publish_verification_results_subcommand().try_get_matches_from(vec![
+            "publish-verification-results",
+            "-b",
+            "http://localhost",
+            "--provider-app-version",
+            "4.5.6",
+            "--verification-success",
+        ]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn requires_exactly_one_verification_result() {
+        let parse = |result_args: &[&str]| {
+            let mut args = vec![
+                "publish-verification-results",
+                "-b",
+                "http://localhost",
+                "--pact-url",
+                "http://localhost/pacts/provider/Bar/consumer/Foo/version/1.2.3",
+                "--provider-app-version",
+                "4.5.6",
+            ];
+            args.extend_from_slice(result_args);
+            add_publish_verification_results_subcommand().try_get_matches_from(args)
+        };
+
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--verification-success", "--no-verification-success"]).is_err());
+        assert!(parse(&["--no-verification-success", "--verification-exit-code", "0"]).is_err());
+        assert!(parse(&["--verification-exit-code", "passed"]).is_err());
+        assert_eq!(
+            parse(&["--verification-exit-code", "1"])
+                .unwrap()
+                .get_one::<i32>("verification-exit-code"),
+            Some(&1)
+        );
+        assert!(parse(&["--no-verification-success"]).is_ok());
+    }
+}
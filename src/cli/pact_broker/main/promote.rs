@@ -0,0 +1,485 @@
+//! Promotes a pacticipant version to an environment: checks it with can-i-deploy, records the
+//! deployment or release, and optionally wraps the deploy command so a failed deploy is rolled
+//! back in the broker.
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::cli::{
+    pact_broker::main::{
+        PactBrokerError,
+        client::{
+            CanIDeployOutcome, CanIDeployRequest, CurrentDeployment, PactBrokerClient,
+            PacticipantSelector, RecordDeploymentRequest, RecordReleaseRequest,
+        },
+        output::{self, OutputType},
+        types::BrokerDetails,
+    },
+    utils,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PromoteRequest {
+    pub pacticipant: String,
+    pub version: String,
+    pub environment: String,
+    pub application_instance: Option<String>,
+    /// Record a release rather than a deployment
+    pub release: bool,
+    /// Mark the other versions of the pacticipant deployed to the environment as undeployed
+    pub undeploy_previous: bool,
+    pub retry_while_unknown: u32,
+    pub retry_interval: u64,
+    /// The deploy command to run once the deployment is recorded. If it fails, the deployment
+    /// is rolled back.
+    pub deploy_command: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoteResult {
+    pub pacticipant: String,
+    pub version: String,
+    pub environment: String,
+    pub outcome: CanIDeployOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub recorded: bool,
+    pub undeployed_versions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deploy_command_exit_code: Option<i32>,
+    pub rolled_back: bool,
+}
+
+impl PromoteResult {
+    /// The can-i-deploy exit code when the check fails, otherwise the exit code of the deploy
+    /// command
+    pub fn exit_code(&self) -> i32 {
+        if self.outcome != CanIDeployOutcome::Deployable {
+            self.outcome.exit_code()
+        } else {
+            self.deploy_command_exit_code.unwrap_or(0)
+        }
+    }
+}
+
+/// Marks a recorded deployment as undeployed, or a recorded release as no longer supported
+async fn end(client: &PactBrokerClient, href: &str, release: bool) -> Result<(), PactBrokerError> {
+    let body = if release {
+        json!({ "currentlySupported": false })
+    } else {
+        json!({ "currentlyDeployed": false })
+    };
+    client
+        .hal_client()
+        .patch_json(href, &body.to_string(), None)
+        .await?;
+    Ok(())
+}
+
+/// Runs the deploy command through the shell, returning its exit code
+pub fn run_deploy_command(command_line: &str) -> Result<i32, PactBrokerError> {
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", command_line]);
+        command
+    } else {
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", command_line]);
+        command
+    };
+    let status = command.status().map_err(|err| {
+        PactBrokerError::IoError(format!(
+            "Failed to run deploy command '{}': {}",
+            command_line, err
+        ))
+    })?;
+    // A command killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Checks that the version can be deployed and records it. `run` is given the deploy command,
+/// if there is one, and returns its exit code.
+pub async fn promote_version(
+    client: &PactBrokerClient,
+    request: &PromoteRequest,
+    run: impl FnOnce(&str) -> Result<i32, PactBrokerError>,
+) -> Result<PromoteResult, PactBrokerError> {
+    let matrix = client
+        .can_i_deploy(&CanIDeployRequest {
+            pacticipants: vec![PacticipantSelector::version(
+                &request.pacticipant,
+                &request.version,
+            )],
+            to_environment: Some(request.environment.clone()),
+            retry_while_unknown: request.retry_while_unknown,
+            retry_interval: request.retry_interval,
+            ..CanIDeployRequest::default()
+        })
+        .await?;
+    let mut result = PromoteResult {
+        pacticipant: request.pacticipant.clone(),
+        version: request.version.clone(),
+        environment: request.environment.clone(),
+        outcome: matrix.outcome(),
+        reason: matrix.reason().map(str::to_string),
+        recorded: false,
+        undeployed_versions: vec![],
+        deploy_command_exit_code: None,
+        rolled_back: false,
+    };
+    if result.outcome != CanIDeployOutcome::Deployable {
+        return Ok(result);
+    }
+
+    let current: Vec<CurrentDeployment> = if request.release {
+        vec![]
+    } else {
+        client
+            .currently_deployed_versions(&request.environment, Some(&request.pacticipant))
+            .await?
+    };
+    // Recording a version that is already deployed to the application instance returns the
+    // existing deployment, which a rollback must leave alone
+    let already_deployed = current.iter().any(|deployed| {
+        deployed.version == request.version
+            && deployed.application_instance == request.application_instance
+    });
+    // Other versions deployed to the environment, which the new deployment may replace
+    let previous: Vec<CurrentDeployment> = current
+        .into_iter()
+        .filter(|deployed| deployed.version != request.version)
+        .collect();
+
+    let recorded = if request.release {
+        client
+            .record_release_json(&RecordReleaseRequest {
+                pacticipant: request.pacticipant.clone(),
+                version: request.version.clone(),
+                environment: request.environment.clone(),
+            })
+            .await?
+    } else {
+        client
+            .record_deployment_json(&RecordDeploymentRequest {
+                pacticipant: request.pacticipant.clone(),
+                version: request.version.clone(),
+                environment: request.environment.clone(),
+                application_instance: request.application_instance.clone(),
+            })
+            .await?
+    };
+    result.recorded = true;
+
+    // The broker undeploys the previous version of the same application instance itself
+    let mut replaced = vec![];
+    for deployed in previous {
+        if deployed.application_instance == request.application_instance {
+            replaced.push(deployed);
+        } else if request.undeploy_previous {
+            client.end_deployment(&deployed).await?;
+            result.undeployed_versions.push(deployed.version.clone());
+            replaced.push(deployed);
+        }
+    }
+
+    if let Some(command) = &request.deploy_command {
+        let exit_code = run(command)?;
+        result.deploy_command_exit_code = Some(exit_code);
+        if exit_code != 0 {
+            rollback(client, request, &recorded, already_deployed, &replaced).await?;
+            result.rolled_back = true;
+        }
+    }
+    Ok(result)
+}
+
+/// Undoes a promotion: ends the new deployment or release, unless the version was already
+/// deployed, and records the versions it replaced as deployed again
+async fn rollback(
+    client: &PactBrokerClient,
+    request: &PromoteRequest,
+    recorded: &Value,
+    already_deployed: bool,
+    replaced: &[CurrentDeployment],
+) -> Result<(), PactBrokerError> {
+    if !already_deployed {
+        let href = recorded["_links"]["self"]["href"].as_str().ok_or_else(|| {
+            PactBrokerError::LinkError(
+                "Can not roll back, the recorded deployment has no self link".to_string(),
+            )
+        })?;
+        end(client, href, request.release).await?;
+    }
+    for deployed in replaced {
+        client
+            .record_deployment_json(&RecordDeploymentRequest {
+                pacticipant: request.pacticipant.clone(),
+                version: deployed.version.clone(),
+                environment: request.environment.clone(),
+                application_instance: deployed.application_instance.clone(),
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+pub fn promote(args: &clap::ArgMatches) -> Result<PromoteResult, PactBrokerError> {
+    let request = PromoteRequest {
+        pacticipant: args.get_one::<String>("pacticipant").unwrap().clone(),
+        version: args.get_one::<String>("version").unwrap().clone(),
+        environment: args.get_one::<String>("to-environment").unwrap().clone(),
+        application_instance: args.get_one::<String>("application-instance").cloned(),
+        release: args.get_flag("release"),
+        undeploy_previous: args.get_flag("undeploy-previous"),
+        retry_while_unknown: args
+            .get_one::<u32>("retry-while-unknown")
+            .copied()
+            .unwrap_or(0),
+        retry_interval: args.get_one::<u64>("retry-interval").copied().unwrap_or(10),
+        deploy_command: args.get_one::<String>("rollback-on-failure").cloned(),
+    };
+    let output = OutputType::from_args(args);
+    let client = PactBrokerClient::new(BrokerDetails::from_args(args)?);
+
+    let result = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(promote_version(&client, &request, run_deploy_command))?;
+
+    if output.is_structured() {
+        output::print(&serde_json::to_value(&result).unwrap(), &output)?;
+        return Ok(result);
+    }
+    let subject = format!(
+        "{} version {}",
+        utils::GREEN.apply_to(&request.pacticipant),
+        utils::GREEN.apply_to(&request.version)
+    );
+    if !result.recorded {
+        println!(
+            "❌ {} can not be deployed to {} ({:?}). {}",
+            subject,
+            request.environment,
+            result.outcome,
+            result.reason.clone().unwrap_or_default()
+        );
+        return Ok(result);
+    }
+    println!(
+        "✅ Computer says yes \\o/ {}",
+        result.reason.clone().unwrap_or_default()
+    );
+    println!(
+        "✅ Recorded {} of {} to {} environment in the Pact Broker.",
+        if request.release {
+            "release"
+        } else {
+            "deployment"
+        },
+        subject,
+        utils::GREEN.apply_to(&request.environment)
+    );
+    if !result.undeployed_versions.is_empty() {
+        println!(
+            "✅ Recorded undeployment of previous versions {}",
+            result.undeployed_versions.join(", ")
+        );
+    }
+    if result.rolled_back {
+        println!(
+            "{}",
+            utils::RED.apply_to(format!(
+                "❌ Deploy command exited with {}, rolled back the {} of {} to {}",
+                result.deploy_command_exit_code.unwrap_or_default(),
+                if request.release {
+                    "release"
+                } else {
+                    "deployment"
+                },
+                request.version,
+                request.environment
+            ))
+        );
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod promote_tests {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::{PromoteRequest, promote_version};
+    use crate::cli::pact_broker::main::client::{CanIDeployOutcome, PactBrokerClient};
+    use crate::cli::pact_broker::main::mock_broker::server::MockBroker;
+    use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+    use crate::cli::pact_broker::main::subcommands::add_promote_subcommand;
+    use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+    use crate::cli::pact_broker::main::types::BrokerDetails;
+
+    async fn broker() -> (Arc<MockBroker>, PactBrokerClient) {
+        let state: MockBrokerState = serde_json::from_value(json!({
+            "environments": [{ "uuid": "1", "name": "production", "production": true }],
+            "pacticipants": [{ "name": "Foo" }, { "name": "Bar" }],
+            "versions": [
+                { "pacticipant": "Foo", "number": "1" },
+                { "pacticipant": "Foo", "number": "2" },
+                { "pacticipant": "Foo", "number": "3" },
+                { "pacticipant": "Bar", "number": "10" }
+            ],
+            "pacts": [
+                { "consumer": "Foo", "consumerVersion": "2", "provider": "Bar", "content": {} },
+                { "consumer": "Foo", "consumerVersion": "3", "provider": "Bar", "content": {} }
+            ],
+            "verificationResults": [
+                { "consumer": "Foo", "consumerVersion": "2", "provider": "Bar", "providerVersion": "10", "success": true },
+                { "consumer": "Foo", "consumerVersion": "3", "provider": "Bar", "providerVersion": "10", "success": false }
+            ],
+            "deployedVersions": [
+                { "pacticipant": "Foo", "version": "1", "environment": "1" },
+                { "pacticipant": "Foo", "version": "1", "environment": "1", "applicationInstance": "eu" },
+                { "pacticipant": "Bar", "version": "10", "environment": "1" }
+            ]
+        }))
+        .unwrap();
+        let (broker, url) = spawn_mock_broker(state).await;
+        (
+            broker,
+            PactBrokerClient::new(BrokerDetails::new(&url).with_retries(0)),
+        )
+    }
+
+    fn request(version: &str) -> PromoteRequest {
+        PromoteRequest {
+            pacticipant: "Foo".to_string(),
+            version: version.to_string(),
+            environment: "production".to_string(),
+            ..PromoteRequest::default()
+        }
+    }
+
+    fn current_foo_versions(broker: &MockBroker) -> Vec<(String, Option<String>)> {
+        broker
+            .snapshot()
+            .deployed_versions
+            .into_iter()
+            .filter(|d| d.current && d.pacticipant == "Foo")
+            .map(|d| (d.version, d.application_instance))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn does_not_record_a_version_that_can_not_be_deployed() {
+        let (broker, client) = broker().await;
+
+        let result = promote_version(&client, &request("3"), |_| panic!("not deployed"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.outcome, CanIDeployOutcome::VerificationFailed);
+        assert_eq!(result.exit_code(), 2);
+        assert!(!result.recorded);
+        assert_eq!(broker.snapshot().deployed_versions.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn records_the_deployment_and_undeploys_previous_versions() {
+        let (broker, client) = broker().await;
+
+        let result = promote_version(
+            &client,
+            &PromoteRequest {
+                undeploy_previous: true,
+                ..request("2")
+            },
+            |_| unreachable!(),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.recorded);
+        assert_eq!(result.undeployed_versions, vec!["1".to_string()]);
+        assert_eq!(current_foo_versions(&broker), vec![("2".to_string(), None)]);
+    }
+
+    #[tokio::test]
+    async fn rolls_back_when_the_deploy_command_fails() {
+        let (broker, client) = broker().await;
+
+        let result = promote_version(
+            &client,
+            &PromoteRequest {
+                deploy_command: Some("./deploy.sh".to_string()),
+                ..request("2")
+            },
+            |command| {
+                assert_eq!(command, "./deploy.sh");
+                Ok(3)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(result.rolled_back);
+        assert_eq!(result.exit_code(), 3);
+        assert_eq!(
+            current_foo_versions(&broker),
+            vec![
+                ("1".to_string(), Some("eu".to_string())),
+                ("1".to_string(), None)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_an_already_deployed_version_when_rolling_back() {
+        let (broker, client) = broker().await;
+
+        let result = promote_version(
+            &client,
+            &PromoteRequest {
+                deploy_command: Some("./deploy.sh".to_string()),
+                ..request("1")
+            },
+            |_| Ok(1),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.rolled_back);
+        assert_eq!(
+            current_foo_versions(&broker),
+            vec![
+                ("1".to_string(), Some("eu".to_string())),
+                ("1".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_arguments_must_be_numbers() {
+        let parse = |retry_args: &[&str]| {
+            let mut args = vec![
+                "promote",
+                "-b",
+                "http://localhost",
+                "--pacticipant",
+                "Foo",
+                "--version",
+                "1",
+                "--to-environment",
+                "production",
+            ];
+            args.extend_from_slice(retry_args);
+            add_promote_subcommand().try_get_matches_from(args)
+        };
+
+        assert!(parse(&["--retry-while-unknown", "often"]).is_err());
+        assert!(parse(&["--retry-interval", "-1"]).is_err());
+        let args = parse(&["--retry-while-unknown", "3"]).unwrap();
+        assert_eq!(args.get_one::<u32>("retry-while-unknown"), Some(&3));
+        assert_eq!(args.get_one::<u64>("retry-interval"), Some(&10));
+    }
+}
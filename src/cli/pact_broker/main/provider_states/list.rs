@@ -2,7 +2,8 @@
 
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
-    types::{BrokerDetails, OutputType},
+    output::{self, OutputType},
+    types::BrokerDetails,
 };
 use clap::ArgMatches;
 use comfy_table::{Table, presets::UTF8_FULL};
@@ -44,7 +45,7 @@ pub fn list_provider_states(
         let hal_client = HALClient::with_url(
             &broker_details.url,
             broker_details.auth.clone(),
-            broker_details.ssl_options.clone(),
+            broker_details.connection_options.clone(),
             broker_details.custom_headers.clone(),
         )
         .with_retry_count(broker_details.retries);
@@ -63,20 +64,16 @@ pub fn list_provider_states(
 
         // Format output based on requested type
         match output_type {
-            OutputType::Json => Ok(format_json_output(&provider_states)?),
-            OutputType::Table => Ok(format_table_output(
-                &provider_states,
-                provider,
-                branch,
-                environment,
-            )),
-            OutputType::Text => Ok(format_table_output(
+            ref output if output.is_structured() => {
+                output::render(&serde_json::to_value(&provider_states).unwrap(), output)
+            }
+            OutputType::Pretty => Ok(format_json_output(&provider_states)?),
+            _ => Ok(format_table_output(
                 &provider_states,
                 provider,
                 branch,
                 environment,
             )),
-            OutputType::Pretty => Ok(format_json_output(&provider_states)?),
         }
     })
 }
@@ -179,10 +176,10 @@ pub fn handle_list_provider_states_command(args: &ArgMatches) -> Result<String,
         ]));
     }
 
-    let output_type = if args.get_flag("json") {
-        OutputType::Json
-    } else {
-        OutputType::Table
+    let output_type = match OutputType::from_args(args) {
+        output if output.is_structured() => output,
+        _ if args.get_flag("json") => OutputType::Json,
+        output => output,
     };
 
     let broker_details = BrokerDetails::from_args(args)?;
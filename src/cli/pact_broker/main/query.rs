@@ -0,0 +1,229 @@
+//! Filtering of the JSON output of the commands with `--query`.
+//!
+//! Queries are [JMESPath](https://jmespath.org/specification.html) expressions, evaluated with
+//! the `jmespath` crate. This module only adapts the JSON documents of the commands so that
+//! queries can be written against their records and embedded resources.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use jmespath::Expression;
+use serde_json::{Map, Value};
+
+use crate::cli::pact_broker::main::PactBrokerError;
+use crate::cli::pact_broker::main::output::records;
+
+/// A parsed `--query` expression
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    expression: Expression<'static>,
+}
+
+impl Query {
+    /// Applies the query to the JSON document of a command. The query sees a collection as the
+    /// array of its records, and the embedded resources of an object as fields of the object, so
+    /// that `[].name` works for a list of pacticipants, and `tags[].name` for a version.
+    pub fn search(&self, document: &Value) -> Result<Value, PactBrokerError> {
+        let records = records(document);
+        let input = match records.as_slice() {
+            [record] if std::ptr::eq(*record, document) && !document.is_array() => {
+                with_embedded_fields(document)
+            }
+            _ => Value::Array(records.into_iter().map(with_embedded_fields).collect()),
+        };
+        self.expression
+            .search(&input)
+            .and_then(|result| Ok(serde_json::to_value(&*result)?))
+            .map_err(|err| PactBrokerError::ValidationError(vec![err.to_string()]))
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        jmespath::compile(expression)
+            .map(|expression| Query { expression })
+            .map_err(|err| err.to_string())
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+/// Lifts the fields of `_embedded` into the object holding them, for the object and everything
+/// it contains. Fields of the object itself take precedence.
+fn with_embedded_fields(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut result: Map<String, Value> = fields
+                .iter()
+                .map(|(name, value)| (name.clone(), with_embedded_fields(value)))
+                .collect();
+            if let Some(Value::Object(embedded)) = result.get("_embedded").cloned() {
+                for (name, value) in embedded {
+                    result.entry(name).or_insert(value);
+                }
+            }
+            Value::Object(result)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(with_embedded_fields).collect()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::{Value, json};
+
+    use super::Query;
+    use crate::cli::pact_broker::main::PactBrokerError;
+
+    fn search(expression: &str, document: &Value) -> Value {
+        expression
+            .parse::<Query>()
+            .unwrap()
+            .search(document)
+            .unwrap()
+    }
+
+    fn pacticipants() -> Value {
+        json!({
+            "_links": { "self": { "href": "http://localhost/pacticipants" } },
+            "_embedded": {
+                "pacticipants": [
+                    { "name": "Foo", "mainBranch": "main", "_embedded": { "labels": [{ "name": "ui" }] } },
+                    { "name": "Bar", "mainBranch": "master", "_embedded": { "labels": [] } },
+                    { "name": "Baz", "_embedded": { "labels": [{ "name": "ui" }, { "name": "api" }] } }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn queries_the_records_of_collections() {
+        assert_eq!(
+            search("[].name", &pacticipants()),
+            json!(["Foo", "Bar", "Baz"])
+        );
+        assert_eq!(
+            search("[*].mainBranch", &pacticipants()),
+            json!(["main", "master"])
+        );
+        assert_eq!(search("length(@)", &pacticipants()), json!(3));
+        assert_eq!(search("[-1].name", &pacticipants()), json!("Baz"));
+        assert_eq!(search("[1:].name", &pacticipants()), json!(["Bar", "Baz"]));
+        assert_eq!(
+            search("[].labels[].name", &pacticipants()),
+            json!(["ui", "ui", "api"])
+        );
+    }
+
+    #[test]
+    fn filters_with_conditions() {
+        assert_eq!(
+            search("[?mainBranch == 'main'].name", &pacticipants()),
+            json!(["Foo"])
+        );
+        assert_eq!(
+            search("[?!mainBranch].name", &pacticipants()),
+            json!(["Baz"])
+        );
+        assert_eq!(
+            search(
+                "[?contains(labels[].name, 'ui') && name != `\"Foo\"`].name",
+                &pacticipants()
+            ),
+            json!(["Baz"])
+        );
+        assert_eq!(
+            search("[?length(labels) >= `1`] | [0].name", &pacticipants()),
+            json!("Foo")
+        );
+
+        let version = json!({
+            "number": "1.0.0",
+            "_embedded": {
+                "deployedVersions": [
+                    { "uuid": "a", "currentlyDeployed": false },
+                    { "uuid": "b", "currentlyDeployed": true }
+                ]
+            }
+        });
+        assert_eq!(
+            search("deployedVersions[?currentlyDeployed].uuid", &version),
+            json!(["b"])
+        );
+        assert_eq!(
+            search("_embedded.deployedVersions[0].uuid", &version),
+            json!("a")
+        );
+    }
+
+    #[test]
+    fn selects_multiple_fields() {
+        assert_eq!(
+            search("[0].{name: name, branch: mainBranch}", &pacticipants()),
+            json!({ "name": "Foo", "branch": "main" })
+        );
+        assert_eq!(
+            search("[:2].[name, mainBranch]", &pacticipants()),
+            json!([["Foo", "main"], ["Bar", "master"]])
+        );
+        assert_eq!(
+            search("join(', ', [].name)", &pacticipants()),
+            json!("Foo, Bar, Baz")
+        );
+        assert_eq!(
+            search(
+                "*.\"a b\"",
+                &json!({ "x": { "a b": 1 }, "y": { "a b": 2 } })
+            ),
+            json!([1, 2])
+        );
+    }
+
+    #[test]
+    fn sorts_with_expression_references() {
+        assert_eq!(
+            search("sort_by(@, &name)[].name", &pacticipants()),
+            json!(["Bar", "Baz", "Foo"])
+        );
+        assert_eq!(
+            search("max_by(@, &length(labels)).name", &pacticipants()),
+            json!("Baz")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        for expression in ["[?name == 'Foo'", "name ==", "name name"] {
+            assert!(expression.parse::<Query>().is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn returns_an_error_for_invalid_function_calls() {
+        for expression in [
+            "foo(@)",
+            "length(@, @)",
+            "length(`1`)",
+            "[].join(', ', name)",
+            "sort_by(@, &labels)",
+            "starts_with(mainBranch, `1`)",
+        ] {
+            let query = expression.parse::<Query>().unwrap();
+            let result = query.search(&pacticipants());
+            assert!(
+                matches!(result, Err(PactBrokerError::ValidationError(_))),
+                "{}: {:?}",
+                expression,
+                result
+            );
+        }
+    }
+}
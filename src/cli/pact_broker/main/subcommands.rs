@@ -6,8 +6,7 @@ pub fn add_broker_auth_arguments() -> Vec<Arg> {
             .short('b')
             .long("broker-base-url")
             .num_args(1)
-            .help("The base URL of the Pact Broker")
-            .required(true)
+            .help("The base URL of the Pact Broker. Required unless set by --profile")
             .value_name("PACT_BROKER_BASE_URL")
             .env("PACT_BROKER_BASE_URL"),
         Arg::new("broker-username")
@@ -31,6 +30,48 @@ pub fn add_broker_auth_arguments() -> Vec<Arg> {
             .help("Pact Broker bearer token")
             .value_name("PACT_BROKER_TOKEN")
             .env("PACT_BROKER_TOKEN"),
+        Arg::new("oauth-token-url")
+            .long("oauth-token-url")
+            .num_args(1)
+            .help("The OAuth2 token endpoint to get a bearer token from, in place of the broker credentials")
+            .value_name("PACT_BROKER_OAUTH_TOKEN_URL")
+            .env("PACT_BROKER_OAUTH_TOKEN_URL"),
+        Arg::new("oauth-client-id")
+            .long("oauth-client-id")
+            .num_args(1)
+            .requires("oauth-token-url")
+            .help("The OAuth2 client ID")
+            .value_name("PACT_BROKER_OAUTH_CLIENT_ID")
+            .env("PACT_BROKER_OAUTH_CLIENT_ID"),
+        Arg::new("oauth-client-secret")
+            .long("oauth-client-secret")
+            .num_args(1)
+            .requires("oauth-token-url")
+            .help("The OAuth2 client secret, for the client credentials grant")
+            .value_name("PACT_BROKER_OAUTH_CLIENT_SECRET")
+            .env("PACT_BROKER_OAUTH_CLIENT_SECRET")
+            .hide_env_values(true),
+        Arg::new("oauth-scope")
+            .long("oauth-scope")
+            .num_args(1)
+            .requires("oauth-token-url")
+            .help("Space separated OAuth2 scopes to request")
+            .value_name("PACT_BROKER_OAUTH_SCOPE")
+            .env("PACT_BROKER_OAUTH_SCOPE"),
+        Arg::new("oauth-audience")
+            .long("oauth-audience")
+            .num_args(1)
+            .requires("oauth-token-url")
+            .help("The audience to request the OAuth2 token for")
+            .value_name("PACT_BROKER_OAUTH_AUDIENCE")
+            .env("PACT_BROKER_OAUTH_AUDIENCE"),
+        Arg::new("oauth-subject-token-file")
+            .long("oauth-subject-token-file")
+            .num_args(1)
+            .requires("oauth-token-url")
+            .help("A file with a token, such as a CI OIDC token, to exchange for a broker token (RFC 8693)")
+            .value_name("PACT_BROKER_OAUTH_SUBJECT_TOKEN_FILE")
+            .env("PACT_BROKER_OAUTH_SUBJECT_TOKEN_FILE"),
         Arg::new("custom-header")
             // .short('H')
             .long("custom-header")
@@ -48,6 +89,30 @@ pub fn add_broker_auth_arguments() -> Vec<Arg> {
             .env("PACT_BROKER_HTTP_RETRIES"),
     ]
 }
+/// Arguments for the commands that list a paginated collection. Every page is fetched unless
+/// `--limit` is given.
+pub fn add_pagination_arguments() -> Vec<Arg> {
+    vec![
+        Arg::new("page-size")
+            .long("page-size")
+            .num_args(1)
+            .value_name("SIZE")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .help("The number of items to request from the Pact Broker in each page"),
+        Arg::new("limit")
+            .long("limit")
+            .num_args(1)
+            .value_name("COUNT")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .overrides_with("all")
+            .help("The most items to list. Pages are fetched until this many items are found"),
+        Arg::new("all")
+            .long("all")
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with("limit")
+            .help("Fetch every page (the default). Overrides an earlier --limit"),
+    ]
+}
 pub fn add_publish_pacts_subcommand() -> Command {
     Command::new("publish")
     .args(add_broker_auth_arguments())
@@ -132,10 +197,41 @@ See https://docs.rs/glob/0.3.0/glob/struct.Pattern.html")
 .args(crate::cli::add_output_arguments(["json", "text", "pretty"].to_vec(),"text"))
 .args(crate::cli::add_ssl_arguments())
 }
+pub fn add_lint_subcommand() -> Command {
+    Command::new("lint")
+        .about("Lint pact files against a configurable set of rules, without contacting a Pact Broker")
+        .arg(
+            Arg::new("pact-files-dirs-or-globs")
+                .value_name("PACT_FILES_DIRS_OR_GLOBS")
+                .help("Pact files, directories or glob patterns containing pact files to lint (can be repeated)")
+                .required(true)
+                .num_args(1..)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Lint rule configuration file. Defaults to .pactlint.toml in the current directory, if it exists"),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue)
+                .help("Require strict validation of the pact specification"),
+        )
+        .args(crate::cli::add_output_arguments(
+            ["text", "json", "sarif"].to_vec(),
+            "text",
+        ))
+}
+
 pub fn add_list_latest_pact_versions_subcommand() -> Command {
     Command::new("list-latest-pact-versions")
         .about("List the latest pact for each integration")
         .args(add_broker_auth_arguments())
+        .args(add_pagination_arguments())
         .args(crate::cli::add_ssl_arguments())
         .args(crate::cli::add_output_arguments(
             ["json", "table"].to_vec(),
@@ -188,12 +284,221 @@ pub fn add_get_pacts_subcommand() -> Command {
                 .default_value("./pacts"),
         )
         .args(add_broker_auth_arguments())
+        .args(add_pagination_arguments())
         .args(crate::cli::add_ssl_arguments())
         .args(crate::cli::add_output_arguments(
             ["json", "table"].to_vec(),
             "table",
         ))
 }
+/// Arguments to select one side of a pact diff, named `<side>-version`, `<side>-branch`,
+/// `<side>-tag` and `<side>-file`
+fn add_pact_source_arguments(names: [&'static str; 4], label: &str) -> Vec<Arg> {
+    let [version, branch, tag, file] = names;
+    vec![
+        Arg::new(version)
+            .long(version)
+            .value_name("VERSION")
+            .help(format!("The consumer version of the {} pact", label)),
+        Arg::new(branch)
+            .long(branch)
+            .value_name("BRANCH")
+            .help(format!(
+                "Use the latest pact from this consumer branch as the {} pact",
+                label
+            )),
+        Arg::new(tag).long(tag).value_name("TAG").help(format!(
+            "Use the latest pact with this consumer version tag as the {} pact",
+            label
+        )),
+        Arg::new(file)
+            .long(file)
+            .value_name("FILE")
+            .help(format!("Read the {} pact from a local file", label)),
+    ]
+}
+/// Subcommands that take the Pact Broker arguments but can run without a broker. diff-pacts only
+/// needs one when one of the pacts is not a local file.
+pub const BROKER_URL_OPTIONAL_SUBCOMMANDS: [&str; 1] = ["diff-pacts"];
+
+pub fn add_diff_pacts_subcommand() -> Command {
+    Command::new("diff-pacts")
+        .about("Show the interactions that were added, removed or changed between two pacts for the same consumer and provider")
+        .arg(
+            Arg::new("consumer")
+                .long("consumer")
+                .value_name("CONSUMER")
+                .help("The name of the consumer. Required unless both pacts are files"),
+        )
+        .arg(
+            Arg::new("provider")
+                .long("provider")
+                .value_name("PROVIDER")
+                .help("The name of the provider. Required unless both pacts are files"),
+        )
+        .args(add_pact_source_arguments(
+            ["from-version", "from-branch", "from-tag", "from-file"],
+            "original",
+        ))
+        .args(add_pact_source_arguments(
+            ["to-version", "to-branch", "to-tag", "to-file"],
+            "new",
+        ))
+        .group(
+            ArgGroup::new("from")
+                .args(["from-version", "from-branch", "from-tag", "from-file"])
+                .required(true),
+        )
+        .group(
+            ArgGroup::new("to")
+                .args(["to-version", "to-branch", "to-tag", "to-file"])
+                .required(true),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+}
+pub fn add_fetch_pacts_for_verification_subcommand() -> Command {
+    Command::new("fetch-pacts-for-verification")
+        .about("Fetch the pacts a provider should verify, as selected by the consumer version selectors, along with the reason each pact was selected")
+        .arg(
+            Arg::new("provider")
+                .long("provider")
+                .help("The name of the provider")
+                .required(true)
+                .value_name("PROVIDER"),
+        )
+        .arg(
+            Arg::new("consumer-version-selector")
+                .long("consumer-version-selector")
+                .help("A consumer version selector as JSON, e.g. '{\"mainBranch\":true}'. Can be specified multiple times")
+                .action(clap::ArgAction::Append)
+                .value_name("SELECTOR"),
+        )
+        .arg(
+            Arg::new("include-wip-pacts-since")
+                .long("include-wip-pacts-since")
+                .help("Include work in progress pacts created since the given date (e.g. 2025-01-01)")
+                .value_name("DATE"),
+        )
+        .arg(
+            Arg::new("enable-pending")
+                .long("enable-pending")
+                .help("Include the pending status of each pact")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("provider-version-branch")
+                .long("provider-version-branch")
+                .help("The branch of the provider version that will be verifying the pacts, used to calculate the pending and WIP status")
+                .value_name("BRANCH"),
+        )
+        .arg(
+            Arg::new("provider-version-tag")
+                .long("provider-version-tag")
+                .help("The tag of the provider version that will be verifying the pacts. Can be specified multiple times")
+                .action(clap::ArgAction::Append)
+                .value_name("TAG"),
+        )
+        .arg(
+            Arg::new("download")
+                .long("download")
+                .help("Download the pact files to local directory")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("download-dir")
+                .long("download-dir")
+                .help("Directory to download pact files to (defaults to ./pacts)")
+                .value_name("DIR")
+                .default_value("./pacts"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "table"].to_vec(),
+            "table",
+        ))
+}
+pub fn add_publish_verification_results_subcommand() -> Command {
+    Command::new("publish-verification-results")
+    .about("Publish provider verification results for a pact to the Pact Broker")
+    .arg(Arg::new("pact-url")
+        .long("pact-url")
+        .value_name("PACT_URL")
+        .conflicts_with_all(["consumer", "consumer-version"])
+        .required_unless_present_all(["consumer", "provider", "consumer-version"])
+        .help("The URL of the pact that was verified"))
+    .arg(Arg::new("consumer")
+        .long("consumer")
+        .value_name("CONSUMER")
+        .requires_all(["provider", "consumer-version"])
+        .help("The name of the consumer of the verified pact"))
+    .arg(Arg::new("provider")
+        .long("provider")
+        .value_name("PROVIDER")
+        .help("The name of the provider of the verified pact"))
+    .arg(Arg::new("consumer-version")
+        .long("consumer-version")
+        .value_name("CONSUMER_VERSION")
+        .requires_all(["consumer", "provider"])
+        .help("The consumer version of the verified pact"))
+    .arg(Arg::new("provider-app-version")
+        .short('a')
+        .long("provider-app-version")
+        .value_name("PROVIDER_APP_VERSION")
+        .required_unless_present("auto-detect-version-properties")
+        .help("The provider application version that performed the verification"))
+    .arg(Arg::new("branch")
+        .long("branch")
+        .value_name("BRANCH")
+        .help("Repository branch of the provider version"))
+    .arg(Arg::new("verification-success")
+        .long("verification-success")
+        .action(clap::ArgAction::SetTrue)
+        .help("Whether or not the verification passed successfully."))
+    .arg(Arg::new("no-verification-success")
+        .long("no-verification-success")
+        .action(clap::ArgAction::SetTrue)
+        .help("Whether or not the verification failed."))
+    .arg(Arg::new("verification-exit-code")
+        .long("verification-exit-code")
+        .value_name("N")
+        .value_parser(clap::value_parser!(i32))
+        .help("The exit code of the verification process. Can be used instead of --verification-success|--no-verification-success for a simpler build script."))
+    .arg(Arg::new("verification-results")
+        .long("verification-results")
+        .value_name("VERIFICATION_RESULTS")
+        .help("The path to a JSON file containing the test results from the verification process"))
+    .arg(Arg::new("verifier")
+        .long("verifier")
+        .value_name("VERIFIER")
+        .help("The tool used to verify the pact"))
+    .arg(Arg::new("verifier-version")
+        .long("verifier-version")
+        .value_name("VERIFIER_VERSION")
+        .requires("verifier")
+        .help("The version of the tool used to verify the pact"))
+    .arg(Arg::new("build-url")
+        .long("build-url")
+        .value_name("BUILD_URL")
+        .help("The build URL that performed the verification"))
+    .arg(Arg::new("auto-detect-version-properties")
+        .short('r')
+        .long("auto-detect-version-properties")
+        .num_args(0)
+        .action(clap::ArgAction::SetTrue)
+        .help("Automatically detect the repository commit, branch and build URL from known CI environment variables or git CLI. Supports Buildkite, Circle CI, Travis CI, GitHub Actions, Jenkins, Hudson, AppVeyor, GitLab, CodeShip, Bitbucket and Azure DevOps."))
+    .group(ArgGroup::new("verification-result")
+        .args(["verification-success", "no-verification-success", "verification-exit-code"])
+        .required(true))
+    .args(add_broker_auth_arguments())
+    .args(crate::cli::add_ssl_arguments())
+    .args(crate::cli::add_output_arguments(["json", "text"].to_vec(), "text"))
+}
 pub fn add_create_environment_subcommand() -> Command {
     Command::new("create-environment")
     .about("Create an environment resource in the Pact Broker to represent a real world deployment or release environment")
@@ -283,6 +588,10 @@ pub fn add_delete_environment_subcommand() -> Command {
                 .help("The UUID of the environment to delete"),
         )
         .args(add_broker_auth_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_list_environments_subcommand() -> Command {
@@ -293,6 +602,32 @@ pub fn add_list_environments_subcommand() -> Command {
             "text",
         ))
         .args(add_broker_auth_arguments())
+        .args(add_pagination_arguments())
+        .args(crate::cli::add_ssl_arguments())
+}
+pub fn add_environment_status_subcommand() -> Command {
+    Command::new("environment-status")
+        .about("Show the versions currently deployed to and released in each environment")
+        .arg(
+            Arg::new("environment")
+                .long("environment")
+                .value_name("ENVIRONMENT")
+                .action(clap::ArgAction::Append)
+                .help("The name of an environment to show. Can be used multiple times. Shows every environment when not given"),
+        )
+        .arg(
+            Arg::new("compare")
+                .long("compare")
+                .value_names(["ENVIRONMENT", "ENVIRONMENT"])
+                .num_args(2)
+                .conflicts_with("environment")
+                .help("Compare the versions of every pacticipant in two environments and highlight the ones that differ"),
+        )
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+        .args(add_broker_auth_arguments())
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_record_deployment_subcommand() -> Command {
@@ -332,6 +667,66 @@ pub fn add_record_deployment_subcommand() -> Command {
 .args(add_broker_auth_arguments())
 .args(crate::cli::add_ssl_arguments())
 }
+pub fn add_record_deployments_from_manifest_subcommand() -> Command {
+    Command::new("record-deployments-from-manifest")
+    .about("Record the deployments of every annotated workload in Kubernetes manifests to an environment")
+    .long_about(
+    r#"
+Record the deployments of every annotated workload in Kubernetes manifests to an environment.
+
+The manifests are read offline, for example the output of `helm template` or `kubectl get deployments -o yaml`.
+Workloads (Deployments, StatefulSets, DaemonSets, Jobs, CronJobs, Pods) are included when they, or their pod template,
+have the `pact.io/pacticipant` annotation naming the pacticipant. The version is taken from the image of the container,
+selected with the `pact.io/container` annotation when the workload has more than one.
+
+Deployments that are already recorded are left as they are. Unless --no-undeploy is given, pacticipants deployed to an
+application instance covered by the manifests, but no longer in them, are recorded as undeployed.
+"#)
+    .arg(Arg::new("file")
+        .short('f')
+        .long("file")
+        .value_name("FILE")
+        .required(true)
+        .num_args(1)
+        .action(clap::ArgAction::Append)
+        .help("The manifest file to read, or - to read from stdin. Can be used multiple times"))
+    .arg(Arg::new("environment")
+        .long("environment")
+        .value_name("ENVIRONMENT")
+        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+        .required(true)
+        .help("The name of the environment that the workloads were deployed to"))
+    .arg(Arg::new("version-from")
+        .long("version-from")
+        .value_name("PART")
+        .value_parser(["tag", "digest"])
+        .default_value("tag")
+        .help("The part of the image reference to use as the pacticipant version. The other part is used for images without it"))
+    .arg(Arg::new("instance-from")
+        .long("instance-from")
+        .value_name("SOURCE")
+        .value_parser(["namespace", "cluster", "none"])
+        .default_value("namespace")
+        .help("What to use as the application instance of each deployment"))
+    .arg(Arg::new("namespace")
+        .long("namespace")
+        .value_name("NAMESPACE")
+        .default_value("default")
+        .help("The namespace of workloads that do not declare one, as for `helm template` output"))
+    .arg(Arg::new("cluster")
+        .long("cluster")
+        .value_name("CLUSTER")
+        .required_if_eq("instance-from", "cluster")
+        .help("The name of the cluster, used as the application instance with --instance-from cluster"))
+    .arg(Arg::new("no-undeploy")
+        .long("no-undeploy")
+        .num_args(0)
+        .action(clap::ArgAction::SetTrue)
+        .help("Do not record the undeployment of pacticipants that are no longer in the manifests"))
+    .args(crate::cli::add_output_arguments(["json", "text"].to_vec(), "text"))
+    .args(add_broker_auth_arguments())
+    .args(crate::cli::add_ssl_arguments())
+}
 pub fn add_record_undeployment_subcommand() -> Command {
     Command::new("record-undeployment")
     .about("Record undeployment of a pacticipant version from an environment")
@@ -438,9 +833,10 @@ pub fn add_can_i_deploy_subcommand() -> Command {
     Check if a pacticipant can be deployed.
 
     Description:
-    Returns exit code 0 or 1, indicating whether or not the specified application (pacticipant) has a successful verification result with
-    each of the application versions that are already deployed to a particular environment. Prints out the relevant pact/verification
-    details, indicating any missing or failed verification results.
+    Returns exit code 0 when the specified application (pacticipant) has a successful verification result with each of the application
+    versions that are already deployed to a particular environment. Otherwise the exit code says why: 2 when a verification failed,
+    3 when a verification is missing, 4 when the result is unknown (eg. a verification is still running) and 5 when no versions matched.
+    Prints out the relevant pact/verification details, indicating any missing or failed verification results.
   
     The can-i-deploy tool was originally written to support specifying versions and dependencies using tags. This usage has now been
     superseded by first class support for environments, deployments and releases. For documentation on how to use can-i-deploy with tags,
@@ -546,14 +942,16 @@ pub fn add_can_i_deploy_subcommand() -> Command {
         .long("to")
         .value_name("TO")
         .help("The tag that represents the branch or environment of the integrated applications for which you want to check the verification result status."))
-    .args(crate::cli::add_output_arguments(["json", "table"].to_vec(), "table"))
+    .args(crate::cli::add_output_arguments(["json", "table", "junit", "markdown"].to_vec(), "table"))
     .arg(Arg::new("retry-while-unknown")
         .long("retry-while-unknown")
         .value_name("TIMES")
+        .value_parser(clap::value_parser!(u32))
         .help("The number of times to retry while there is an unknown verification result (ie. the provider verification is likely still running)"))
     .arg(Arg::new("retry-interval")
         .long("retry-interval")
         .value_name("SECONDS")
+        .value_parser(clap::value_parser!(u64))
         .help("The time between retries in seconds. Use in conjuction with --retry-while-unknown"))
     .arg(Arg::new("dry-run")
         .long("dry-run")
@@ -583,14 +981,16 @@ pub fn add_can_i_merge_subcommand() -> Command {
         .num_args(1)
         .action(clap::ArgAction::Append)
         .help("The pacticipant version. Must be entered after the --pacticipant that it relates to."))
-        .args(crate::cli::add_output_arguments(["json", "table"].to_vec(), "table"))
+        .args(crate::cli::add_output_arguments(["json", "table", "junit", "markdown"].to_vec(), "table"))
     .arg(Arg::new("retry-while-unknown")
         .long("retry-while-unknown")
         .value_name("TIMES")
+        .value_parser(clap::value_parser!(u32))
         .help("The number of times to retry while there is an unknown verification result (ie. the provider verification is likely still running)"))
     .arg(Arg::new("retry-interval")
         .long("retry-interval")
         .value_name("SECONDS")
+        .value_parser(clap::value_parser!(u64))
         .help("The time between retries in seconds. Use in conjuction with --retry-while-unknown"))
     .arg(Arg::new("dry-run")
         .long("dry-run")
@@ -600,6 +1000,73 @@ pub fn add_can_i_merge_subcommand() -> Command {
 
 .args(crate::cli::add_ssl_arguments())
 }
+pub fn add_promote_subcommand() -> Command {
+    Command::new("promote")
+    .about("Checks if the specified pacticipant version can be deployed to an environment, and if so, records the deployment (or release) in the Pact Broker")
+    .long_about(
+    r#"
+Checks if the specified pacticipant version can be deployed to an environment, and if so, records the deployment (or release) in the Pact Broker.
+
+This combines can-i-deploy with record-deployment or record-release. Use --rollback-on-failure to wrap the deploy command:
+it is run once the deployment has been recorded, and if it exits with a non-zero code, the deployment is recorded as
+undeployed again and the versions it replaced are recorded as deployed. The exit code is that of can-i-deploy when the
+version can not be deployed, otherwise that of the deploy command.
+"#)
+    .args(add_broker_auth_arguments())
+    .arg(Arg::new("pacticipant")
+        .short('a')
+        .long("pacticipant")
+        .value_name("PACTICIPANT")
+        .required(true)
+        .help("The name of the pacticipant that is being promoted"))
+    .arg(Arg::new("version")
+        .short('e')
+        .long("version")
+        .value_name("VERSION")
+        .required(true)
+        .help("The pacticipant version number that is being promoted"))
+    .arg(Arg::new("to-environment")
+        .long("to-environment")
+        .value_name("ENVIRONMENT")
+        .required(true)
+        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+        .help("The environment to promote the pacticipant version to"))
+    .arg(Arg::new("application-instance")
+        .long("application-instance")
+        .value_name("APPLICATION_INSTANCE")
+        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+        .conflicts_with("release")
+        .help("Optional. The application instance to which the deployment is made"))
+    .arg(Arg::new("release")
+        .long("release")
+        .num_args(0)
+        .action(clap::ArgAction::SetTrue)
+        .help("Record a release rather than a deployment, for applications such as mobile apps or libraries where several versions are supported at once"))
+    .arg(Arg::new("undeploy-previous")
+        .long("undeploy-previous")
+        .num_args(0)
+        .action(clap::ArgAction::SetTrue)
+        .conflicts_with("release")
+        .help("Record the undeployment of every other version of the pacticipant deployed to the environment, including those on other application instances"))
+    .arg(Arg::new("rollback-on-failure")
+        .long("rollback-on-failure")
+        .value_name("COMMAND")
+        .help("The deploy command to run once the deployment is recorded. If it exits with a non-zero code, the deployment is rolled back in the Pact Broker"))
+    .arg(Arg::new("retry-while-unknown")
+        .long("retry-while-unknown")
+        .value_name("TIMES")
+        .value_parser(clap::value_parser!(u32))
+        .default_value("0")
+        .help("The number of times to retry while there is an unknown verification result (ie. the provider verification is likely still running)"))
+    .arg(Arg::new("retry-interval")
+        .long("retry-interval")
+        .value_name("SECONDS")
+        .value_parser(clap::value_parser!(u64))
+        .default_value("10")
+        .help("The time between retries in seconds. Use in conjuction with --retry-while-unknown"))
+    .args(crate::cli::add_output_arguments(["json", "text"].to_vec(), "text"))
+    .args(crate::cli::add_ssl_arguments())
+}
 pub fn add_create_or_update_pacticipant_subcommand() -> Command {
     Command::new("create-or-update-pacticipant")
         .about("Create or update pacticipant by name")
@@ -656,6 +1123,7 @@ pub fn add_list_pacticipants_subcommand() -> Command {
     Command::new("list-pacticipants")
         .about("List pacticipants")
         .args(add_broker_auth_arguments())
+        .args(add_pagination_arguments())
         .args(crate::cli::add_output_arguments(
             ["json", "table"].to_vec(),
             "table",
@@ -752,6 +1220,7 @@ pub fn add_create_webhook_subcommand() -> Command {
         .help("UUID of the PactFlow team to which the webhook should be assigned (PactFlow only)"))
 
 .args(add_broker_auth_arguments())
+.args(crate::cli::add_output_arguments(["json", "text"].to_vec(), "text"))
 .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_create_or_update_webhook_subcommand() -> Command {
@@ -760,13 +1229,18 @@ pub fn add_create_or_update_webhook_subcommand() -> Command {
     .args(add_broker_auth_arguments())
     .arg(Arg::new("url")
         .value_name("URL")
-        .required(true)
+        .required_unless_present("file")
         .help("Webhook URL"))
     .arg(Arg::new("uuid")
         .long("uuid")
         .value_name("UUID")
-        .required(true)
+        .required_unless_present("file")
         .help("Specify the uuid for the webhook"))
+    .arg(Arg::new("file")
+        .long("file")
+        .value_name("FILE")
+        .conflicts_with_all(["url", "uuid"])
+        .help("Create or update all the webhooks in a file written by export-webhooks"))
     .arg(Arg::new("request")
         .short('X')
         .long("request")
@@ -848,6 +1322,82 @@ pub fn add_create_or_update_webhook_subcommand() -> Command {
         .long("team-uuid")
         .value_name("UUID")
         .help("UUID of the PactFlow team to which the webhook should be assigned (PactFlow only)"))
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+        .args(crate::cli::add_ssl_arguments())
+}
+fn add_webhook_filter_arguments() -> Vec<Arg> {
+    vec![
+        Arg::new("consumer")
+            .long("consumer")
+            .value_name("CONSUMER")
+            .help("Only include webhooks for this consumer"),
+        Arg::new("provider")
+            .long("provider")
+            .value_name("PROVIDER")
+            .help("Only include webhooks for this provider"),
+        Arg::new("event")
+            .long("event")
+            .value_name("EVENT")
+            .value_parser([
+                "contract_content_changed",
+                "contract_published",
+                "provider_verification_published",
+                "provider_verification_failed",
+                "provider_verification_succeeded",
+                "contract_requiring_verification_published",
+            ])
+            .help("Only include webhooks triggered by this event"),
+    ]
+}
+pub fn add_list_webhooks_subcommand() -> Command {
+    Command::new("list-webhooks")
+        .about("List webhooks")
+        .args(add_webhook_filter_arguments())
+        .args(add_broker_auth_arguments())
+        .args(add_pagination_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "table"].to_vec(),
+            "table",
+        ))
+}
+pub fn add_describe_webhook_subcommand() -> Command {
+    Command::new("describe-webhook")
+        .about("Describe a webhook")
+        .arg(
+            Arg::new("uuid")
+                .long("uuid")
+                .value_name("UUID")
+                .num_args(1)
+                .required(true)
+                .help("UUID of the webhook to describe"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+}
+pub fn add_export_webhooks_subcommand() -> Command {
+    Command::new("export-webhooks")
+        .about("Export webhooks to a JSON file that can be re-applied with create-or-update-webhook --file")
+        .arg(
+            Arg::new("file")
+                .long("file")
+                .value_name("FILE")
+                .default_value("webhooks.json")
+                .help("The file to write the webhooks to"),
+        )
+        .args(add_webhook_filter_arguments())
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_test_webhook_subcommand() -> Command {
@@ -862,6 +1412,10 @@ pub fn add_test_webhook_subcommand() -> Command {
                 .help("Specify the uuid for the webhook"),
         )
         .args(add_broker_auth_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_delete_webhook_subcommand() -> Command {
@@ -876,6 +1430,10 @@ pub fn add_delete_webhook_subcommand() -> Command {
                 .help("UUID of the webhook to delete"),
         )
         .args(add_broker_auth_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_delete_branch_subcommand() -> Command {
@@ -898,6 +1456,10 @@ pub fn add_delete_branch_subcommand() -> Command {
         .num_args(1)
         .action(clap::ArgAction::SetTrue)
         .help("Raise an error if the branch that is to be deleted is not found"))
+    .args(crate::cli::add_output_arguments(
+        ["json", "text"].to_vec(),
+        "text",
+    ))
     .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_create_version_tag_subcommand() -> Command {
@@ -948,6 +1510,10 @@ pub fn add_create_version_tag_subcommand() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .help("Tag pacticipant version with the name of the current git branch"),
         )
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_delete_version_tag_subcommand() -> Command {
@@ -979,6 +1545,10 @@ pub fn add_delete_version_tag_subcommand() -> Command {
                 .value_parser(clap::builder::NonEmptyStringValueParser::new())
                 .help("Tag name to delete from the pacticipant version"),
         )
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 pub fn add_describe_version_subcommand() -> Command {
@@ -1095,8 +1665,12 @@ pub fn add_list_provider_states_subcommand() -> Command {
             Arg::new("json")
                 .long("json")
                 .action(clap::ArgAction::SetTrue)
-                .help("Output in JSON format"),
+                .help("Output in JSON format, the same as --output json"),
         )
+        .args(crate::cli::add_output_arguments(
+            ["json", "table"].to_vec(),
+            "table",
+        ))
         .args(crate::cli::add_ssl_arguments())
 }
 
@@ -1105,3 +1679,385 @@ pub fn add_provider_states_subcommand() -> Command {
         .about("Manage provider states")
         .subcommand(add_list_provider_states_subcommand())
 }
+
+pub fn add_profile_argument() -> Arg {
+    Arg::new("profile")
+        .long("profile")
+        .global(true)
+        .num_args(1)
+        .value_name("PROFILE")
+        .help("Use the Pact Broker URL, credentials and settings from this profile in the config file")
+        .env("PACT_BROKER_PROFILE")
+}
+
+pub fn add_api_subcommand() -> Command {
+    Command::new("api")
+        .about("Make an authenticated request to any Pact Broker endpoint and print the JSON response")
+        .arg(
+            Arg::new("method")
+                .value_name("METHOD")
+                .required(true)
+                .value_parser(clap::builder::PossibleValuesParser::new([
+                    "GET", "POST", "PUT", "PATCH", "DELETE",
+                ]))
+                .ignore_case(true)
+                .help("The HTTP method"),
+        )
+        .arg(
+            Arg::new("path")
+                .value_name("PATH")
+                .required(true)
+                .help("The path of the endpoint, relative to the Pact Broker base URL"),
+        )
+        .arg(
+            Arg::new("field")
+                .short('f')
+                .long("field")
+                .value_name("KEY=VALUE")
+                .action(clap::ArgAction::Append)
+                .help("Add a string parameter. Used to fill in templated links, then sent as query parameters for GET and DELETE or a JSON body for other methods"),
+        )
+        .arg(
+            Arg::new("typed-field")
+                .short('F')
+                .long("typed-field")
+                .value_name("KEY=VALUE")
+                .action(clap::ArgAction::Append)
+                .help("Add a parameter like --field, with values such as true, 10 or null sent as JSON types"),
+        )
+        .arg(
+            Arg::new("input")
+                .long("input")
+                .value_name("FILE")
+                .help("Read the request body from a file, or stdin with '-'. Any fields are then sent as query parameters"),
+        )
+        .arg(
+            Arg::new("rel")
+                .long("rel")
+                .value_name("RELATION")
+                .action(clap::ArgAction::Append)
+                .help("Follow a HAL link from the resource at PATH before sending the request (can be repeated to follow a chain of links)"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["pretty", "json"].to_vec(),
+            "pretty",
+        ))
+}
+
+pub fn add_login_subcommand() -> Command {
+    Command::new("login")
+        .about("Check a Pact Broker token and save it, so later commands for the broker don't need --broker-token")
+        .arg(
+            Arg::new("token-stdin")
+                .long("token-stdin")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("broker-token")
+                .help("Read the token from stdin. Without this or --broker-token, the token is prompted for"),
+        )
+        .arg(
+            Arg::new("credential-helper")
+                .long("credential-helper")
+                .value_name("COMMAND")
+                .help("Save the token with this credential helper command instead of the credentials file")
+                .env("PACT_BROKER_CREDENTIAL_HELPER"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+}
+
+pub fn add_logout_subcommand() -> Command {
+    Command::new("logout")
+        .about("Remove the token saved by login for a Pact Broker")
+        .arg(
+            Arg::new("broker-base-url")
+                .short('b')
+                .long("broker-base-url")
+                .num_args(1)
+                .help("The base URL of the Pact Broker. Required unless set by --profile")
+                .value_name("PACT_BROKER_BASE_URL")
+                .env("PACT_BROKER_BASE_URL"),
+        )
+        .arg(
+            Arg::new("credential-helper")
+                .long("credential-helper")
+                .value_name("COMMAND")
+                .help("Remove the token with this credential helper command instead of the credentials file")
+                .env("PACT_BROKER_CREDENTIAL_HELPER"),
+        )
+}
+
+pub fn add_config_subcommand() -> Command {
+    Command::new("config")
+        .about("Manage named Pact Broker profiles in the config file")
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("add-profile")
+                .about("Add a profile, or replace an existing one with the same name")
+                .arg(
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .help("The name of the profile"),
+                )
+                .arg(
+                    Arg::new("url")
+                        .long("url")
+                        .value_name("URL")
+                        .required(true)
+                        .help("The base URL of the Pact Broker"),
+                )
+                .arg(
+                    Arg::new("token")
+                        .long("token")
+                        .value_name("TOKEN")
+                        .conflicts_with("username")
+                        .help("Pact Broker bearer token"),
+                )
+                .arg(
+                    Arg::new("username")
+                        .long("username")
+                        .value_name("USERNAME")
+                        .help("Pact Broker basic auth username"),
+                )
+                .arg(
+                    Arg::new("password")
+                        .long("password")
+                        .value_name("PASSWORD")
+                        .requires("username")
+                        .help("Pact Broker basic auth password"),
+                )
+                .arg(
+                    Arg::new("custom-header")
+                        .long("custom-header")
+                        .value_name("HEADER")
+                        .action(clap::ArgAction::Append)
+                        .help("Custom header(s) to send with requests (format: 'Header-Name: Value', can be used multiple times)"),
+                )
+                .arg(
+                    Arg::new("ca-cert")
+                        .long("ca-cert")
+                        .value_name("FILE")
+                        .help("The path to a CA certificate to verify the Pact Broker with"),
+                )
+                .arg(
+                    Arg::new("skip-ssl-verification")
+                        .long("skip-ssl-verification")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Skip SSL certificate verification"),
+                )
+                .arg(
+                    Arg::new("retries")
+                        .long("retries")
+                        .value_name("RETRIES")
+                        .value_parser(clap::value_parser!(u8))
+                        .help("The number of times to retry failed HTTP requests"),
+                ),
+        )
+        .subcommand(
+            Command::new("list-profiles")
+                .about("List the profiles in the config file")
+                .args(crate::cli::add_output_arguments(
+                    ["table", "json"].to_vec(),
+                    "table",
+                )),
+        )
+        .subcommand(
+            Command::new("remove-profile")
+                .about("Remove a profile from the config file")
+                .arg(
+                    Arg::new("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .help("The name of the profile"),
+                ),
+        )
+}
+
+pub fn add_apply_subcommand() -> Command {
+    Command::new("apply")
+        .about("Apply a declarative configuration of environments, pacticipants and webhooks to the Pact Broker")
+        .arg(
+            Arg::new("file")
+                .short('f')
+                .long("file")
+                .value_name("FILE")
+                .required(true)
+                .help("The YAML or JSON file describing the desired environments, pacticipants and webhooks"),
+        )
+        .arg(
+            Arg::new("plan")
+                .long("plan")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print the changes that would be made, without making them"),
+        )
+        .arg(
+            Arg::new("prune")
+                .long("prune")
+                .action(clap::ArgAction::SetTrue)
+                .help("Delete environments and webhooks that are not in the file. Pacticipants are never deleted"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+}
+pub fn add_mock_broker_subcommand() -> Command {
+    Command::new("mock-broker")
+        .about("Run a local, in-memory Pact Broker to test pipeline scripts against")
+        .long_about("Run a local, in-memory Pact Broker to test pipeline scripts against. It implements the index relations, contract publishing, pacticipants, environments, deployed and released versions, the matrix and webhooks. Nothing is persisted once it stops.")
+        .arg(
+            Arg::new("host")
+                .long("host")
+                .value_name("HOST")
+                .default_value("127.0.0.1")
+                .help("The host to bind to"),
+        )
+        .arg(
+            Arg::new("port")
+                .short('p')
+                .long("port")
+                .value_name("PORT")
+                .value_parser(clap::value_parser!(u16))
+                .default_value("9292")
+                .help("The port to listen on. Use 0 to pick a free port"),
+        )
+        .arg(
+            Arg::new("fixture")
+                .long("fixture")
+                .value_name("FILE")
+                .help("A JSON file of pacticipants, versions, pacts, verification results, environments, deployments and webhooks to start with"),
+        )
+}
+
+/// The URL and credential arguments for one side of a migration, named by `side` ("from" or "to")
+fn add_migration_broker_arguments(
+    side: &'static str,
+    names: [&'static str; 4],
+    envs: [&'static str; 4],
+) -> Vec<Arg> {
+    let [url, username, password, token] = names;
+    let [url_env, username_env, password_env, token_env] = envs;
+    vec![
+        Arg::new(url)
+            .long(url)
+            .num_args(1)
+            .required(true)
+            .value_name("URL")
+            .env(url_env)
+            .help(format!(
+                "The base URL of the Pact Broker to migrate {}",
+                side
+            )),
+        Arg::new(username)
+            .long(username)
+            .num_args(1)
+            .value_name("USERNAME")
+            .env(username_env)
+            .help(format!(
+                "Basic auth username for the Pact Broker to migrate {}",
+                side
+            )),
+        Arg::new(password)
+            .long(password)
+            .num_args(1)
+            .value_name("PASSWORD")
+            .env(password_env)
+            .help(format!(
+                "Basic auth password for the Pact Broker to migrate {}",
+                side
+            )),
+        Arg::new(token)
+            .long(token)
+            .num_args(1)
+            .value_name("TOKEN")
+            .env(token_env)
+            .conflicts_with(username)
+            .help(format!(
+                "Bearer token for the Pact Broker to migrate {}",
+                side
+            )),
+    ]
+}
+
+pub fn add_migrate_subcommand() -> Command {
+    Command::new("migrate")
+        .about("Copy the contents of one Pact Broker to another")
+        .long_about("Copy environments, pacticipants, versions with their branches and tags, pacts, verification results, deployed and released versions, and webhooks from one Pact Broker to another. Resources that already exist in the destination are skipped, and progress is saved after each resource so an interrupted migration can be resumed by running the same command again. Webhook passwords and masked header values, such as Authorization, are not exposed by the Pact Broker and must be set again after migrating, so the webhooks that use them are listed.")
+        .args(add_migration_broker_arguments(
+            "from",
+            ["from", "from-username", "from-password", "from-token"],
+            [
+                "PACT_BROKER_FROM_BASE_URL",
+                "PACT_BROKER_FROM_USERNAME",
+                "PACT_BROKER_FROM_PASSWORD",
+                "PACT_BROKER_FROM_TOKEN",
+            ],
+        ))
+        .args(add_migration_broker_arguments(
+            "to",
+            ["to", "to-username", "to-password", "to-token"],
+            [
+                "PACT_BROKER_TO_BASE_URL",
+                "PACT_BROKER_TO_USERNAME",
+                "PACT_BROKER_TO_PASSWORD",
+                "PACT_BROKER_TO_TOKEN",
+            ],
+        ))
+        .arg(
+            Arg::new("progress-file")
+                .long("progress-file")
+                .value_name("FILE")
+                .default_value(".pact-broker-migration.json")
+                .help("The file that records what has been copied, used to resume an interrupted migration"),
+        )
+        .args(
+            add_broker_auth_arguments()
+                .into_iter()
+                .filter(|arg| arg.get_id() == "retries"),
+        )
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+}
+
+pub fn add_backup_subcommand() -> Command {
+    Command::new("backup")
+        .about("Back up the resources of a Pact Broker to a local archive")
+        .long_about("Back up the resources of a Pact Broker to a local archive. Environments, pacticipants, versions, pacts, verification results, currently deployed and released versions, and webhooks are found by following the links from the index, and each is stored as JSON in a gzipped tar file with a manifest of checksums. The archive can be replayed against an empty Pact Broker with the restore command.")
+        .arg(
+            Arg::new("output-file")
+                .long("output-file")
+                .value_name("FILE")
+                .default_value("broker-backup.tar.gz")
+                .help("The archive to write the backup to"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+        .args(crate::cli::add_ssl_arguments())
+}
+
+pub fn add_restore_subcommand() -> Command {
+    Command::new("restore")
+        .about("Restore a backup archive to an empty Pact Broker")
+        .arg(
+            Arg::new("archive")
+                .value_name("ARCHIVE")
+                .required(true)
+                .help("The archive written by the backup command"),
+        )
+        .args(add_broker_auth_arguments())
+        .args(crate::cli::add_ssl_arguments())
+        .args(crate::cli::add_output_arguments(
+            ["json", "text"].to_vec(),
+            "text",
+        ))
+}
@@ -1,13 +1,14 @@
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
-    utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+    output::{self, OutputType},
+    utils::{get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries},
 };
 
 pub fn create_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let pacticipant_name = args.get_one::<String>("pacticipant").unwrap();
     let version_number = args.get_one::<String>("version").unwrap();
@@ -18,9 +19,10 @@ pub fn create_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerE
         .collect::<Vec<_>>();
     let auto_create_version = args.get_flag("auto-create-version");
     let tag_with_git_branch = args.get_flag("tag-with-git-branch");
+    let output = OutputType::from_args(args);
     // ensure version exists if auto-create is not set
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
-            let hal_client: HALClient = HALClient::with_url(&broker_url, Some(auth.clone()), ssl_options.clone(), custom_headers.clone())
+            let hal_client: HALClient = HALClient::with_url(&broker_url, Some(auth.clone()), connection_options.clone(), custom_headers.clone())
                 .with_retry_count(get_retries(args));
             let version_href = format!("{}/pacticipants/{}/versions/{}", broker_url, pacticipant_name, version_number);
             let version_exists = hal_client.fetch(&version_href).await.is_ok();
@@ -39,16 +41,17 @@ pub fn create_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerE
 
     match res {
         Ok(_) => {
-            tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let created = tokio::runtime::Runtime::new().unwrap().block_on(async {
                 let hal_client: HALClient = HALClient::with_url(
                     &broker_url,
                     Some(auth.clone()),
-                    ssl_options.clone(),
+                    connection_options.clone(),
                     custom_headers.clone(),
                 )
                 .with_retry_count(get_retries(args));
+                let mut created = vec![];
                 for tag in tags {
-                    print!(
+                    let tagging = format!(
                         "Tagging version '{}' of pacticipant '{}' with tag '{}'",
                         version_number, pacticipant_name, tag
                     );
@@ -62,24 +65,39 @@ pub fn create_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerE
                         .put_json(&tag_href, &tag_data_str, None)
                         .await
                         .map_err(|e| {
-                            PactBrokerError::IoError(format!(
-                                "Failed to create tag '{}': {}",
-                                tag, e
-                            ))
+                            e.map_unless_dry_run(|e| {
+                                PactBrokerError::IoError(format!(
+                                    "Failed to create tag '{}': {}",
+                                    tag, e
+                                ))
+                            })
                         });
                     match tag_post_result {
-                        Ok(_) => println!(" - Success"),
-                        Err(e) => println!(" - Failed: {}", e),
+                        Ok(tag) => {
+                            output::status(&output, format!("{} - Success", tagging));
+                            created.push(tag);
+                        }
+                        Err(PactBrokerError::DryRun(_)) => {
+                            output::status(&output, format!("{} - Dry run, not sent", tagging))
+                        }
+                        Err(e) => output::status(&output, format!("{} - Failed: {}", tagging, e)),
                     }
                 }
 
                 if tag_with_git_branch {
-                    println!(
-                        "Tagged version '{}' of pacticipant '{}' with git branch",
-                        version_number, pacticipant_name
+                    output::status(
+                        &output,
+                        format!(
+                            "Tagged version '{}' of pacticipant '{}' with git branch",
+                            version_number, pacticipant_name
+                        ),
                     );
                 }
+                created
             });
+            if output.is_structured() {
+                return output::print(&serde_json::Value::Array(created), &output);
+            }
 
             Ok(format!(
                 "Successfully tagged version '{}' of pacticipant '{}'",
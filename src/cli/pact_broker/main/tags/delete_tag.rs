@@ -1,10 +1,13 @@
 use maplit::hashmap;
 
+use serde_json::json;
+
 use crate::cli::pact_broker::main::{
     HALClient, Link, PactBrokerError,
+    output::{self, OutputType},
     utils::{
-        get_auth, get_broker_relation, get_broker_url, get_custom_headers, get_retries,
-        get_ssl_options,
+        get_auth, get_broker_relation, get_broker_url, get_connection_options, get_custom_headers,
+        get_retries,
     },
 };
 
@@ -12,17 +15,18 @@ pub fn delete_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerE
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let pacticipant_name = args.get_one::<String>("pacticipant").unwrap();
     let version_number = args.get_one::<String>("version").unwrap();
     let tag_name = args.get_one::<String>("tag").unwrap();
+    let output = OutputType::from_args(args);
 
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -84,13 +88,25 @@ pub fn delete_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerE
             .await
     });
 
+    let result = |deleted| {
+        json!({
+            "pacticipant": pacticipant_name,
+            "version": version_number,
+            "name": tag_name,
+            "deleted": deleted
+        })
+    };
     match res {
         Ok(_) => {
             let message = format!(
                 "Successfully deleted tag '{}' from version '{}' of pacticipant '{}'",
                 tag_name, version_number, pacticipant_name
             );
-            println!("{}", message);
+            if output.is_structured() {
+                return output::print(&result(true), &output);
+            } else {
+                println!("{}", message);
+            }
             Ok(message)
         }
         Err(PactBrokerError::NotFound(_)) => {
@@ -98,7 +114,10 @@ pub fn delete_version_tag(args: &clap::ArgMatches) -> Result<String, PactBrokerE
                 "Tag '{}' for version '{}' of pacticipant '{}' was not found (may have already been deleted)",
                 tag_name, version_number, pacticipant_name
             );
-            println!("{}", message);
+            output::status(&output, &message);
+            if output.is_structured() {
+                return output::print(&result(false), &output);
+            }
             Ok(message)
         }
         Err(err) => Err(err),
@@ -1,3 +1,5 @@
+use crate::cli::pact_broker::main::mock_broker::server::{MockBroker, bind, router};
+use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
 use pact_consumer::prelude::JsonPattern;
 
 // Merge two serde_json::Value objects (both are objects)
@@ -11,3 +13,14 @@ pub fn merge_json_objects(a: &mut JsonPattern, b: &serde_json::Value) {
         }
     }
 }
+
+/// Starts a mock Pact Broker on a free port, returning it along with its base URL
+pub async fn spawn_mock_broker(state: MockBrokerState) -> (std::sync::Arc<MockBroker>, String) {
+    let (listener, base_url) = bind("127.0.0.1", 0).await.unwrap();
+    let broker = std::sync::Arc::new(MockBroker::new(&base_url, state));
+    let app = router(broker.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (broker, base_url)
+}
@@ -1,62 +1,91 @@
-use std::str::FromStr;
+use std::collections::HashMap;
 
 use pact_models::http_utils::HttpAuth;
 
 use crate::cli::pact_broker::main::CustomHeaders;
+use crate::cli::pact_broker::main::http_archive::HttpArchive;
+use crate::cli::pact_broker::main::oauth::OAuthOptions;
+use crate::cli::pact_broker::main::proxy::ProxyOptions;
 
 #[derive(Clone)]
 pub struct BrokerDetails {
     pub(crate) auth: Option<HttpAuth>,
     pub(crate) url: String,
-    pub(crate) ssl_options: SslOptions,
+    pub(crate) connection_options: ConnectionOptions,
     pub(crate) custom_headers: Option<CustomHeaders>,
     pub(crate) retries: u8,
 }
 
 impl BrokerDetails {
+    /// Details for the Pact Broker at the given base URL, with no authentication and the default
+    /// SSL options and retry count
+    pub fn new(url: &str) -> Self {
+        BrokerDetails {
+            auth: None,
+            url: url.trim_end_matches('/').to_string(),
+            connection_options: ConnectionOptions::default(),
+            custom_headers: None,
+            retries: 8,
+        }
+    }
+
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn with_ssl_options(mut self, ssl_options: SslOptions) -> Self {
+        self.connection_options.ssl = ssl_options;
+        self
+    }
+
+    /// The TLS, proxy and other connection settings, replacing any set with `with_ssl_options`
+    pub fn with_connection_options(mut self, connection_options: ConnectionOptions) -> Self {
+        self.connection_options = connection_options;
+        self
+    }
+
+    /// Headers to send with every request to the broker
+    pub fn with_custom_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.custom_headers = Some(CustomHeaders { headers });
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u8) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Details from the command line arguments. Each setting is taken from its flag, then its
+    /// environment variable, then the profile selected with `--profile`
     pub fn from_args(
         args: &clap::ArgMatches,
     ) -> Result<Self, crate::cli::pact_broker::main::PactBrokerError> {
         use crate::cli::pact_broker::main::utils::{
-            get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options,
+            get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries,
         };
 
         let url = get_broker_url(args).trim_end_matches('/').to_string();
         let auth = get_auth(args);
         let custom_headers = get_custom_headers(args);
-        let ssl_options = get_ssl_options(args);
+        let connection_options = get_connection_options(args);
         let retries = get_retries(args);
 
         Ok(BrokerDetails {
             auth: Some(auth),
             url,
-            ssl_options,
+            connection_options,
             custom_headers,
             retries,
         })
     }
 }
-#[derive(Clone)]
-pub enum OutputType {
-    Json,
-    Table,
-    Text,
-    Pretty,
-}
-
-impl FromStr for OutputType {
-    type Err = ();
-
-    fn from_str(input: &str) -> Result<Self, Self::Err> {
-        match input.to_lowercase().as_str() {
-            "json" => Ok(OutputType::Json),
-            "table" => Ok(OutputType::Table),
-            "text" => Ok(OutputType::Text),
-            "pretty" => Ok(OutputType::Pretty),
-            _ => Err(()),
-        }
-    }
-}
+/// Moved to the shared output module, re-exported for existing users
+pub use crate::cli::pact_broker::main::output::OutputType;
 
 #[derive(Clone, Debug)]
 pub struct SslOptions {
@@ -74,3 +103,130 @@ impl Default for SslOptions {
         }
     }
 }
+
+/// How the HTTP client connects to the broker: TLS, client certificates, proxies, OAuth2 and the
+/// request recording and previewing options
+#[derive(Clone, Debug)]
+pub struct ConnectionOptions {
+    pub ssl: SslOptions,
+    /// The client certificate for brokers behind a gateway that requires mutual TLS
+    pub client_identity: Option<ClientIdentity>,
+    pub proxy: ProxyOptions,
+    /// Fetches bearer tokens from an authorization server, in place of the broker credentials
+    pub oauth: Option<OAuthOptions>,
+    /// Records the traffic with the broker to a HAR file, or replays it from one
+    pub http_archive: Option<HttpArchive>,
+    /// Prints each request as a curl command
+    pub print_curl: bool,
+    /// Prints the requests that would change the broker as curl commands instead of sending them
+    pub dry_run: bool,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        ConnectionOptions {
+            ssl: SslOptions::default(),
+            client_identity: None,
+            proxy: ProxyOptions::from_env(),
+            oauth: None,
+            http_archive: None,
+            print_curl: false,
+            dry_run: false,
+        }
+    }
+}
+
+/// A client certificate and its private key, presented when the server requests one
+#[derive(Clone, PartialEq)]
+pub enum ClientIdentity {
+    /// A PEM certificate, optionally followed by its chain, and a PKCS#8 PEM private key
+    Pem { cert_path: String, key_path: String },
+    /// A PKCS#12 archive with the certificate chain and private key
+    Pkcs12 { path: String, password: String },
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientIdentity::Pem {
+                cert_path,
+                key_path,
+            } => f
+                .debug_struct("Pem")
+                .field("cert_path", cert_path)
+                .field("key_path", key_path)
+                .finish(),
+            ClientIdentity::Pkcs12 { path, .. } => f
+                .debug_struct("Pkcs12")
+                .field("path", path)
+                .field("password", &"***")
+                .finish(),
+        }
+    }
+}
+
+impl ClientIdentity {
+    /// Reads the certificate and key into the identity used by the HTTP client
+    pub fn load(&self) -> Result<reqwest::Identity, String> {
+        let read = |path: &str| {
+            std::fs::read(path).map_err(|err| format!("Could not read '{}' - {}", path, err))
+        };
+        match self {
+            ClientIdentity::Pem {
+                cert_path,
+                key_path,
+            } => reqwest::Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?).map_err(
+                |err| {
+                    format!(
+                        "Could not load the client certificate '{}' with the key '{}' - {}. The key must be a PKCS#8 PEM file (BEGIN PRIVATE KEY), other keys can be converted with 'openssl pkcs8 -topk8 -nocrypt'",
+                        cert_path, key_path, err
+                    )
+                },
+            ),
+            ClientIdentity::Pkcs12 { path, password } => {
+                reqwest::Identity::from_pkcs12_der(&read(path)?, password).map_err(|err| {
+                    format!(
+                        "Could not load the PKCS#12 client certificate '{}' - {}. Check the password in PACT_BROKER_CLIENT_PKCS12_PASSWORD",
+                        path, err
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// How much of a paginated collection to fetch from the broker
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pagination {
+    /// The number of items requested in each page, otherwise the broker's default is used
+    pub page_size: Option<usize>,
+    /// The most items to fetch. Pages are fetched until this is reached, or there are no more
+    pub limit: Option<usize>,
+}
+
+impl Pagination {
+    /// Every page, with the broker's default page size
+    pub fn all() -> Self {
+        Pagination::default()
+    }
+
+    /// Pagination from the `--page-size`, `--limit` and `--all` arguments
+    pub fn from_args(args: &clap::ArgMatches) -> Self {
+        let all = args.try_get_one::<bool>("all").ok().flatten() == Some(&true);
+        Pagination {
+            page_size: args
+                .try_get_one::<u64>("page-size")
+                .ok()
+                .flatten()
+                .map(|size| *size as usize),
+            limit: if all {
+                None
+            } else {
+                args.try_get_one::<u64>("limit")
+                    .ok()
+                    .flatten()
+                    .map(|limit| *limit as usize)
+            },
+        }
+    }
+}
@@ -3,13 +3,19 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use comfy_table::{Table, presets::UTF8_FULL};
+use clap::parser::ValueSource;
 use maplit::hashmap;
 use pact_models::http_utils::HttpAuth;
 use reqwest::StatusCode;
 use serde_json::Value;
 
-use crate::cli::pact_broker::main::types::SslOptions;
+use crate::cli::pact_broker::main::config::{Profile, selected_profile};
+use crate::cli::pact_broker::main::credentials::saved_token;
+use crate::cli::pact_broker::main::http_archive::HttpArchive;
+use crate::cli::pact_broker::main::oauth::OAuthOptions;
+use crate::cli::pact_broker::main::proxy::ProxyOptions;
+use crate::cli::pact_broker::main::subcommands::BROKER_URL_OPTIONAL_SUBCOMMANDS;
+use crate::cli::pact_broker::main::types::{ClientIdentity, ConnectionOptions, SslOptions};
 
 use super::{CustomHeaders, HALClient, Link, PactBrokerError};
 
@@ -63,30 +69,48 @@ pub(crate) fn compute_retry_delay(
 //     }
 // }
 
-pub(crate) fn generate_table(res: &Value, columns: Vec<&str>, names: Vec<Vec<&str>>) -> Table {
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL).set_header(columns);
-    if let Some(items) = res.get("pacts").unwrap().as_array() {
-        for item in items {
-            let mut values = vec![item; names.len()];
+pub(crate) fn get_broker_url(args: &clap::ArgMatches) -> String {
+    broker_url(args).expect("url is required")
+}
 
-            for (i, name) in names.iter().enumerate() {
-                for n in name.clone() {
-                    values[i] = values[i].get(n).unwrap();
-                }
-            }
+/// The Pact Broker URL from `--broker-base-url`/`PACT_BROKER_BASE_URL`, or otherwise from the
+/// selected profile, for commands where the Pact Broker is optional
+pub(crate) fn broker_url(args: &clap::ArgMatches) -> Option<String> {
+    args.try_get_one::<String>("broker-base-url")
+        .ok()
+        .flatten()
+        .cloned()
+        .or_else(|| get_profile(args).map(|profile| profile.url))
+}
 
-            let records: Vec<String> = values.iter().map(|v| v.to_string()).collect();
-            table.add_row(records.as_slice());
-        }
+/// The profile selected with `--profile`. Problems loading it are reported by
+/// [`check_broker_arguments`] before any command runs, so are ignored here.
+fn get_profile(args: &clap::ArgMatches) -> Option<Profile> {
+    selected_profile(args).ok().flatten()
+}
+
+/// Checks that the selected profile exists and that the Pact Broker URL has been given, either
+/// with `--broker-base-url`/`PACT_BROKER_BASE_URL` or by the profile
+pub(crate) fn check_broker_arguments(args: &clap::ArgMatches) -> Result<(), PactBrokerError> {
+    let (command, args) = leaf_subcommand(args);
+    let Ok(url) = args.try_get_one::<String>("broker-base-url") else {
+        return Ok(());
     };
-    table
+    let profile = selected_profile(args)?;
+    if url.is_none() && profile.is_none() && !BROKER_URL_OPTIONAL_SUBCOMMANDS.contains(&command) {
+        return Err(PactBrokerError::ValidationError(vec![
+            "The Pact Broker URL is required, set it with --broker-base-url, PACT_BROKER_BASE_URL or --profile".to_string(),
+        ]));
+    }
+    Ok(())
 }
 
-pub(crate) fn get_broker_url(args: &clap::ArgMatches) -> String {
-    args.get_one::<String>("broker-base-url")
-        .expect("url is required")
-        .to_string()
+fn leaf_subcommand(args: &clap::ArgMatches) -> (&str, &clap::ArgMatches) {
+    let mut leaf = ("", args);
+    while let Some(subcommand) = leaf.1.subcommand() {
+        leaf = subcommand;
+    }
+    leaf
 }
 
 /// Reads the `--retries` / `PACT_BROKER_HTTP_RETRIES` value from parsed CLI arguments.
@@ -94,17 +118,31 @@ pub(crate) fn get_broker_url(args: &clap::ArgMatches) -> String {
 /// Falls back to `8` if the argument is absent (which should not happen in practice
 /// because the flag carries a `default_value`).
 pub(crate) fn get_retries(args: &clap::ArgMatches) -> u8 {
-    args.get_one::<u8>("retries").copied().unwrap_or(8)
+    let from_profile = match args.value_source("retries") {
+        Some(ValueSource::DefaultValue) | None => {
+            get_profile(args).and_then(|profile| profile.retries)
+        }
+        _ => None,
+    };
+    from_profile
+        .or_else(|| args.get_one::<u8>("retries").copied())
+        .unwrap_or(8)
 }
 pub(crate) fn get_ssl_options(args: &clap::ArgMatches) -> SslOptions {
+    let profile = get_profile(args);
     SslOptions {
         skip_ssl: args
             .get_one::<bool>("skip-ssl-verification")
             .copied()
-            .unwrap_or(false),
+            .unwrap_or(false)
+            || profile
+                .as_ref()
+                .map(|profile| profile.skip_ssl_verification)
+                .unwrap_or(false),
         ssl_cert_path: args
             .get_one::<String>("ssl-certificate")
-            .map(|s| s.to_string()),
+            .map(|s| s.to_string())
+            .or_else(|| profile.and_then(|profile| profile.ca_cert)),
         use_root_trust_store: args
             .get_one::<bool>("ssl-trust-store")
             .copied()
@@ -112,6 +150,78 @@ pub(crate) fn get_ssl_options(args: &clap::ArgMatches) -> SslOptions {
     }
 }
 
+/// The TLS settings along with the client certificate, proxy, OAuth2, HAR and curl options
+pub(crate) fn get_connection_options(args: &clap::ArgMatches) -> ConnectionOptions {
+    ConnectionOptions {
+        ssl: get_ssl_options(args),
+        client_identity: get_client_identity(args),
+        proxy: get_proxy_options(args),
+        oauth: get_oauth_options(args),
+        http_archive: get_http_archive(args),
+        print_curl: args.try_get_one::<bool>("print-curl").ok().flatten() == Some(&true),
+        dry_run: args.try_get_one::<bool>("dry-run").ok().flatten() == Some(&true),
+    }
+}
+
+/// The HAR file to record the HTTP traffic to, or to replay it from
+pub(crate) fn get_http_archive(args: &clap::ArgMatches) -> Option<HttpArchive> {
+    let arg = |name: &str| args.try_get_one::<String>(name).ok().flatten().cloned();
+    arg("record-http")
+        .map(HttpArchive::Record)
+        .or_else(|| arg("replay-http").map(HttpArchive::Replay))
+}
+
+/// The OAuth2 settings, if a token endpoint has been given
+pub(crate) fn get_oauth_options(args: &clap::ArgMatches) -> Option<OAuthOptions> {
+    let arg = |name: &str| args.try_get_one::<String>(name).ok().flatten().cloned();
+    arg("oauth-token-url").map(|token_url| OAuthOptions {
+        token_url,
+        client_id: arg("oauth-client-id"),
+        client_secret: arg("oauth-client-secret"),
+        scope: arg("oauth-scope"),
+        audience: arg("oauth-audience"),
+        subject_token_file: arg("oauth-subject-token-file"),
+    })
+}
+
+/// The client certificate for mutual TLS. The password of a PKCS#12 archive is only read from the
+/// `PACT_BROKER_CLIENT_PKCS12_PASSWORD` environment variable, to keep it out of shell histories
+pub(crate) fn get_client_identity(args: &clap::ArgMatches) -> Option<ClientIdentity> {
+    let arg = |name: &str| args.try_get_one::<String>(name).ok().flatten().cloned();
+    match (arg("client-cert"), arg("client-key"), arg("client-pkcs12")) {
+        (Some(cert_path), Some(key_path), _) => Some(ClientIdentity::Pem {
+            cert_path,
+            key_path,
+        }),
+        (_, _, Some(path)) => Some(ClientIdentity::Pkcs12 {
+            path,
+            password: std::env::var("PACT_BROKER_CLIENT_PKCS12_PASSWORD").unwrap_or_default(),
+        }),
+        _ => None,
+    }
+}
+
+/// The proxy settings of the environment, overridden by the proxy options
+pub(crate) fn get_proxy_options(args: &clap::ArgMatches) -> ProxyOptions {
+    let mut options = ProxyOptions::from_env();
+    if let Ok(Some(proxy)) = args.try_get_one::<String>("proxy") {
+        options = options.with_proxy(proxy);
+    }
+    if let Ok(Some(hosts)) = args.try_get_one::<String>("no-proxy") {
+        options = options.with_no_proxy(hosts);
+    }
+    if let Ok(Some(username)) = args.try_get_one::<String>("proxy-username") {
+        options.username = Some(username.clone());
+    }
+    if let Ok(Some(password)) = args.try_get_one::<String>("proxy-password") {
+        options.password = Some(password.clone());
+    }
+    if let Ok(Some(path)) = args.try_get_one::<String>("proxy-ca-certificate") {
+        options.ca_cert_path = Some(path.clone());
+    }
+    options
+}
+
 // setup client with broker url and credentials
 pub(crate) fn get_auth(args: &clap::ArgMatches) -> HttpAuth {
     let token = args.try_get_one::<String>("broker-token");
@@ -134,7 +244,15 @@ pub(crate) fn get_auth(args: &clap::ArgMatches) -> HttpAuth {
                 Err(_) => todo!(),
             },
             Ok(None) => {
-                auth = HttpAuth::None;
+                auth = get_profile(args)
+                    .map(|profile| profile.auth())
+                    .filter(|auth| !matches!(auth, HttpAuth::None))
+                    .or_else(|| {
+                        broker_url(args)
+                            .and_then(|url| saved_token(&url))
+                            .map(HttpAuth::Token)
+                    })
+                    .unwrap_or(HttpAuth::None);
             }
             Err(_) => todo!(),
         },
@@ -147,10 +265,12 @@ pub(crate) fn get_auth(args: &clap::ArgMatches) -> HttpAuth {
 // Parse custom headers from CLI arguments in curl format ("Header-Name: Value")
 pub(crate) fn get_custom_headers(args: &clap::ArgMatches) -> Option<CustomHeaders> {
     let custom_headers = args.get_many::<String>("custom-header");
+    // headers from the profile can be overridden on the command line
+    let mut headers: HashMap<String, String> = get_profile(args)
+        .map(|profile| profile.custom_headers.into_iter().collect())
+        .unwrap_or_default();
 
     if let Some(header_strings) = custom_headers {
-        let mut headers = std::collections::HashMap::new();
-
         for header_str in header_strings {
             if let Some(colon_pos) = header_str.find(':') {
                 let name = header_str[..colon_pos].trim().to_string();
@@ -161,13 +281,13 @@ pub(crate) fn get_custom_headers(args: &clap::ArgMatches) -> Option<CustomHeader
                 }
             }
         }
-
-        if !headers.is_empty() {
-            return Some(CustomHeaders { headers });
-        }
     }
 
-    None
+    if headers.is_empty() {
+        None
+    } else {
+        Some(CustomHeaders { headers })
+    }
 }
 
 #[cfg(test)]
@@ -441,6 +561,51 @@ mod custom_headers_tests {
     }
 }
 
+#[cfg(test)]
+mod broker_arguments_tests {
+    use super::*;
+
+    fn check(args: Vec<&str>) -> Result<(), PactBrokerError> {
+        let matches = crate::cli::build_cli().get_matches_from(args);
+        check_broker_arguments(&matches)
+    }
+
+    #[test]
+    fn broker_url_is_required_without_a_profile() {
+        let result = check(vec!["pact-broker-cli", "list-environments"]);
+
+        assert!(matches!(result, Err(PactBrokerError::ValidationError(_))));
+    }
+
+    #[test]
+    fn broker_url_can_be_given_on_the_command_line() {
+        let result = check(vec![
+            "pact-broker-cli",
+            "list-environments",
+            "--broker-base-url",
+            "http://localhost:9292",
+        ]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn commands_without_a_broker_are_not_checked() {
+        assert!(check(vec!["pact-broker-cli", "generate-uuid"]).is_ok());
+        assert!(
+            check(vec![
+                "pact-broker-cli",
+                "diff-pacts",
+                "--from-file",
+                "a.json",
+                "--to-file",
+                "b.json"
+            ])
+            .is_ok()
+        );
+    }
+}
+
 pub async fn get_broker_relation(
     hal_client: HALClient,
     relation: String,
@@ -520,16 +685,111 @@ pub(crate) fn handle_error(err: PactBrokerError) -> PactBrokerError {
         | PactBrokerError::ContentError(error)
         | PactBrokerError::IoError(error)
         | PactBrokerError::NotFound(error) => {
-            println!("❌ {}", error);
+            eprintln!("❌ {}", error);
         }
         PactBrokerError::ValidationError(errors) => {
             for error in errors {
-                println!("❌ {}", error);
+                eprintln!("❌ {}", error);
             }
         }
         _ => {
-            println!("❌ {}", err);
+            eprintln!("❌ {}", err);
         }
     }
     err
 }
+
+/// The result of a command that stopped with an error. A request held back by `--dry-run` is not
+/// a failure: the command stops there, as what follows depends on the broker's response, and
+/// reports that nothing was sent. Any other error is reported with exit code 1
+pub(crate) fn handle_command_error(err: PactBrokerError) -> Result<Value, i32> {
+    match err {
+        PactBrokerError::DryRun(request) => {
+            println!(
+                "{} Dry run, nothing was sent to the Pact Broker. Stopped at {}",
+                crate::cli::utils::YELLOW.apply_to("⚠️"),
+                request
+            );
+            Ok(serde_json::json!({ "dryRun": true, "notSent": request }))
+        }
+        err => {
+            handle_error(err);
+            Err(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod client_identity_tests {
+    use super::*;
+    use clap::Command;
+    use pretty_assertions::assert_eq;
+
+    const FIXTURES: &str = "tests/fixtures/client-cert";
+
+    fn parse_args(args: &[&str]) -> Result<clap::ArgMatches, clap::Error> {
+        Command::new("test")
+            .args(crate::cli::add_ssl_arguments())
+            .try_get_matches_from([&["test"], args].concat())
+    }
+
+    #[test]
+    fn pem_certificate_and_key() {
+        let cert = format!("{}/client.crt", FIXTURES);
+        let key = format!("{}/client.key", FIXTURES);
+        let args = parse_args(&["--client-cert", &cert, "--client-key", &key]).unwrap();
+
+        let identity = get_client_identity(&args).unwrap();
+
+        assert_eq!(
+            identity,
+            ClientIdentity::Pem {
+                cert_path: cert,
+                key_path: key
+            }
+        );
+        assert!(identity.load().is_ok());
+    }
+
+    #[test]
+    fn no_client_certificate() {
+        let args = parse_args(&[]).unwrap();
+
+        assert_eq!(get_client_identity(&args), None);
+    }
+
+    #[test]
+    fn the_key_is_required_with_the_certificate() {
+        let cert = format!("{}/client.crt", FIXTURES);
+
+        assert!(parse_args(&["--client-cert", &cert]).is_err());
+    }
+
+    #[test]
+    fn keys_that_are_not_pkcs8_are_rejected_with_how_to_convert_them() {
+        let cert = format!("{}/client.crt", FIXTURES);
+        let key = format!("{}/client-rsa.key", FIXTURES);
+
+        let err = parse_args(&["--client-cert", &cert, "--client-key", &key]).unwrap_err();
+
+        assert!(err.to_string().contains("openssl pkcs8 -topk8"));
+    }
+
+    #[test]
+    fn pkcs12_archive() {
+        let path = format!("{}/client.p12", FIXTURES);
+        let identity = |password: &str| ClientIdentity::Pkcs12 {
+            path: path.clone(),
+            password: password.to_string(),
+        };
+
+        assert!(identity("changeit").load().is_ok());
+        assert!(
+            identity("wrong")
+                .load()
+                .unwrap_err()
+                .contains("PACT_BROKER_CLIENT_PKCS12_PASSWORD")
+        );
+        assert!(!format!("{:?}", identity("changeit")).contains("changeit"));
+    }
+}
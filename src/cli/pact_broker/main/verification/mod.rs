@@ -1,5 +1,4 @@
 //! Functions to verify a Pact file
-use log::error;
 use serde::Serialize;
 use serde_json::Value;
 
@@ -12,6 +11,7 @@ use pact_models::verify_json::{
     PactFileVerificationResult, PactJsonVerifier, ResultLevel, json_type_of,
 };
 
+use crate::cli::pact_broker::main::output::{self, OutputType};
 use crate::cli::utils;
 
 #[derive(Debug, Clone, Serialize)]
@@ -71,9 +71,13 @@ pub fn verify_json(
     }
 }
 
-pub fn display_results(result: &[VerificationResult], output_type: &str) -> anyhow::Result<()> {
-    if output_type == "json" {
-        generate_json_output(result)
+pub fn display_results(
+    result: &[VerificationResult],
+    output_type: &OutputType,
+) -> anyhow::Result<()> {
+    if output_type.is_structured() {
+        output::print(&serde_json::to_value(result)?, output_type)?;
+        Ok(())
     } else {
         display_output(result)
     }
@@ -165,16 +169,3 @@ fn display_output(results: &[VerificationResult]) -> anyhow::Result<()> {
 
     Ok(())
 }
-
-fn generate_json_output(results: &[VerificationResult]) -> anyhow::Result<()> {
-    match serde_json::to_string_pretty(&results) {
-        Ok(json) => {
-            println!("{}", json);
-            Ok(())
-        }
-        Err(err) => {
-            error!("ERROR: Failed to generate JSON - {}", err);
-            Err(err.into())
-        }
-    }
-}
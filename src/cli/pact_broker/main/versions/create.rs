@@ -1,13 +1,14 @@
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
-    utils::{get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options},
+    output::{self, OutputType},
+    utils::{get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries},
 };
 
 pub fn create_or_update_version(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let pacticipant_name = args.get_one::<String>("pacticipant").unwrap();
     let version_number = args.get_one::<String>("version").unwrap();
@@ -17,12 +18,13 @@ pub fn create_or_update_version(args: &clap::ArgMatches) -> Result<String, PactB
         .unwrap_or_default()
         .cloned()
         .collect::<Vec<_>>();
+    let output = OutputType::from_args(args);
 
     tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -43,9 +45,12 @@ pub fn create_or_update_version(args: &clap::ArgMatches) -> Result<String, PactB
                 .put_json(&branch_href, &branch_data_str, None)
                 .await;
             res?;
-            println!(
-                "Branch '{}' created for version '{}'",
-                branch, version_number
+            output::status(
+                &output,
+                format!(
+                    "Branch '{}' created for version '{}'",
+                    branch, version_number
+                ),
             );
         }
 
@@ -60,7 +65,10 @@ pub fn create_or_update_version(args: &clap::ArgMatches) -> Result<String, PactB
             let tag_data_str = tag_data.to_string();
             let res = hal_client.put_json(&tag_href, &tag_data_str, None).await;
             res?;
-            println!("Tag '{}' created for version '{}'", tag, version_number);
+            output::status(
+                &output,
+                format!("Tag '{}' created for version '{}'", tag, version_number),
+            );
         }
         // if no tags or branches, create version
         if tags.is_empty() && branch_name.is_none() {
@@ -71,19 +79,28 @@ pub fn create_or_update_version(args: &clap::ArgMatches) -> Result<String, PactB
                 .await;
             match res {
                 Ok(_) => {
-                    println!(
-                        "Version '{}' created or updated successfully",
-                        version_number
+                    output::status(
+                        &output,
+                        format!(
+                            "Version '{}' created or updated successfully",
+                            version_number
+                        ),
                     );
                 }
                 Err(err) => {
-                    return Err(PactBrokerError::IoError(format!(
-                        "Failed to create or update version '{}': {}",
-                        version_number, err
-                    )));
+                    return Err(err.map_unless_dry_run(|err| {
+                        PactBrokerError::IoError(format!(
+                            "Failed to create or update version '{}': {}",
+                            version_number, err
+                        ))
+                    }));
                 }
             }
         }
+        if output.is_structured() {
+            let version = hal_client.fetch(&version_href).await?;
+            return output::print(&version, &output);
+        }
         Ok("Version created or updated successfully".to_string())
     })
 }
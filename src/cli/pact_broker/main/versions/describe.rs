@@ -5,10 +5,11 @@ use serde_json::Value;
 use crate::cli::pact_broker::main::HttpAuth;
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
-    types::{OutputType, SslOptions},
+    output::{self, OutputType},
+    types::ConnectionOptions,
     utils::{
         follow_broker_relation, follow_templated_broker_relation, get_auth, get_broker_relation,
-        get_broker_url, get_custom_headers, get_retries, get_ssl_options,
+        get_broker_url, get_connection_options, get_custom_headers, get_retries,
     },
 };
 
@@ -16,17 +17,14 @@ pub fn describe_version(args: &clap::ArgMatches) -> Result<String, PactBrokerErr
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let version: Option<&String> = args.try_get_one::<String>("version").unwrap();
     let latest: Option<&String> = args.try_get_one::<String>("latest").unwrap();
     let environment: Option<&String> = args.try_get_one::<String>("environment").unwrap();
     let deployed_only = args.get_flag("deployed");
     let released_only = args.get_flag("released");
-    let output_type: OutputType = args
-        .get_one::<String>("output")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(OutputType::Table);
+    let output_type = OutputType::from_args(args);
     let pacticipant_name = args.get_one::<String>("pacticipant").unwrap();
 
     // If environment is specified, use environment-based queries
@@ -35,7 +33,7 @@ pub fn describe_version(args: &clap::ArgMatches) -> Result<String, PactBrokerErr
             &broker_url,
             &auth,
             &custom_headers,
-            &ssl_options,
+            &connection_options,
             get_retries(args),
             pacticipant_name,
             env_name,
@@ -57,7 +55,7 @@ pub fn describe_version(args: &clap::ArgMatches) -> Result<String, PactBrokerErr
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -79,11 +77,7 @@ pub fn describe_version(args: &clap::ArgMatches) -> Result<String, PactBrokerErr
 
     match res {
         Ok(result) => match output_type {
-            OutputType::Json => {
-                let json: String = serde_json::to_string(&result).unwrap();
-                println!("{}", json);
-                Ok(json)
-            }
+            ref output if output.is_structured() => output::print(&result, output),
             OutputType::Table => {
                 let mut table = Table::new();
                 table
@@ -109,14 +103,14 @@ pub fn describe_version(args: &clap::ArgMatches) -> Result<String, PactBrokerErr
                 Ok(table.to_string())
             }
 
-            OutputType::Text => Err(PactBrokerError::NotFound(
-                "Text output is not supported for describe versions".to_string(),
-            )),
             OutputType::Pretty => {
                 let json: String = serde_json::to_string(&result).unwrap();
                 println!("{}", json);
                 Ok(json)
             }
+            _ => Err(PactBrokerError::NotFound(
+                "Text output is not supported for describe versions".to_string(),
+            )),
         },
         Err(PactBrokerError::NotFound(_)) => Err(PactBrokerError::NotFound(
             "Pacticipant version not found".to_string(),
@@ -132,7 +126,7 @@ fn describe_version_by_environment(
     broker_url: &str,
     auth: &HttpAuth,
     custom_headers: &Option<crate::cli::pact_broker::main::CustomHeaders>,
-    ssl_options: &SslOptions,
+    connection_options: &ConnectionOptions,
     retries: u8,
     pacticipant_name: &str,
     environment_name: &str,
@@ -144,7 +138,7 @@ fn describe_version_by_environment(
         let hal_client: HALClient = HALClient::with_url(
             broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(retries);
@@ -268,12 +262,7 @@ fn format_environment_versions_output(
     output_type: OutputType,
 ) -> Result<String, PactBrokerError> {
     match output_type {
-        OutputType::Json => {
-            let json = serde_json::to_string(&versions)
-                .map_err(|e| PactBrokerError::ContentError(e.to_string()))?;
-            println!("{}", json);
-            Ok(json)
-        }
+        ref output if output.is_structured() => output::print(&Value::Array(versions), output),
         OutputType::Table => {
             let mut table = Table::new();
             table.load_preset(UTF8_FULL).set_header(vec![
@@ -324,15 +313,15 @@ fn format_environment_versions_output(
             println!("{}", table_str);
             Ok(table_str)
         }
-        OutputType::Text => Err(PactBrokerError::NotFound(
-            "Text output is not supported for environment versions".to_string(),
-        )),
         OutputType::Pretty => {
             let json = serde_json::to_string_pretty(&versions)
                 .map_err(|e| PactBrokerError::ContentError(e.to_string()))?;
             println!("{}", json);
             Ok(json)
         }
+        _ => Err(PactBrokerError::NotFound(
+            "Text output is not supported for environment versions".to_string(),
+        )),
     }
 }
 
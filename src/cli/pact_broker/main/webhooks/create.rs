@@ -2,9 +2,10 @@ use maplit::hashmap;
 
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
+    output::{self, OutputType},
     utils::{
         follow_templated_broker_relation, get_auth, get_broker_relation, get_broker_url,
-        get_custom_headers, get_retries, get_ssl_options,
+        get_connection_options, get_custom_headers, get_retries,
     },
 };
 
@@ -65,7 +66,7 @@ pub fn create_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let url = args.try_get_one::<String>("url");
     let http_method = args.try_get_one::<String>("request").unwrap();
@@ -91,6 +92,7 @@ pub fn create_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError
         args.get_flag("contract-requiring-verification-published");
     let team_uuid = args.try_get_one::<String>("team-uuid").unwrap();
     let webhook_uuid = args.try_get_one::<String>("uuid").ok().flatten();
+    let output = OutputType::from_args(args);
 
     let (username, password) = if let Some(user) = user {
         if let Some((username, password)) = user.split_once(':') {
@@ -104,7 +106,7 @@ pub fn create_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError
 
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient =
-            HALClient::with_url(&broker_url, Some(auth.clone()), ssl_options.clone(), custom_headers.clone())
+            HALClient::with_url(&broker_url, Some(auth.clone()), connection_options.clone(), custom_headers.clone())
             .with_retry_count(get_retries(args));
       let pb_webhook_href_path = get_broker_relation(
             hal_client.clone(),
@@ -257,20 +259,120 @@ pub fn create_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError
 
     match res {
         Ok(result) => {
+            // The text output is the webhook's JSON
+            if output.is_structured() {
+                return output::print(&result, &output);
+            }
             let json: String = serde_json::to_string(&result).unwrap();
             println!("{}", json);
             Ok(json)
         }
-        Err(e) => Err(PactBrokerError::IoError(format!(
-            "Failed to create webhook: {}",
-            e
-        ))),
+        Err(e) => Err(e.map_unless_dry_run(|e| {
+            PactBrokerError::IoError(format!("Failed to create webhook: {}", e))
+        })),
     }
 }
 
+/// Creates or updates a webhook with a PUT to the templated `pb:webhook` href, filled in with the
+/// webhook's UUID. The UUID is taken out of the body, as the broker takes it from the URL.
+pub(crate) async fn create_or_update_webhook(
+    hal_client: &HALClient,
+    webhook_href: &str,
+    webhook: &serde_json::Value,
+) -> Result<serde_json::Value, PactBrokerError> {
+    let uuid = webhook["uuid"].as_str().unwrap_or_default();
+    let mut webhook_data = webhook.clone();
+    if let Some(data) = webhook_data.as_object_mut() {
+        data.remove("uuid");
+    }
+    let href = webhook_href.replace("{uuid}", uuid);
+    hal_client
+        .put_json(&href, &webhook_data.to_string(), None)
+        .await
+}
+
+/// Creates or updates every webhook in a file written by `export-webhooks`. Each webhook must have
+/// a UUID, so that re-applying the same file is idempotent.
+pub fn create_or_update_webhooks_from_file(
+    args: &clap::ArgMatches,
+) -> Result<String, PactBrokerError> {
+    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
+    let auth = get_auth(args);
+    let custom_headers = get_custom_headers(args);
+    let connection_options = get_connection_options(args);
+    let file = args.get_one::<String>("file").unwrap();
+    // As with a single webhook, the text output is the JSON of the webhooks, so the progress
+    // messages go to stderr
+    let output = match OutputType::from_args(args) {
+        OutputType::Text => OutputType::Json,
+        output => output,
+    };
+
+    let content = std::fs::read_to_string(file).map_err(|e| {
+        PactBrokerError::IoError(format!("Failed to read webhooks file {}: {}", file, e))
+    })?;
+    let document: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+        PactBrokerError::ContentError(format!("Webhooks file {} is not valid JSON: {}", file, e))
+    })?;
+    let webhooks = document["webhooks"].as_array().ok_or_else(|| {
+        PactBrokerError::ContentError(format!(
+            "Webhooks file {} does not contain a 'webhooks' array",
+            file
+        ))
+    })?;
+    let missing_uuids: Vec<String> = webhooks
+        .iter()
+        .enumerate()
+        .filter(|(_, webhook)| webhook["uuid"].as_str().is_none_or(|uuid| uuid.is_empty()))
+        .map(|(index, _)| format!("Webhook at index {} does not have a uuid", index))
+        .collect();
+    if !missing_uuids.is_empty() {
+        return Err(PactBrokerError::ValidationError(missing_uuids));
+    }
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let hal_client: HALClient = HALClient::with_url(
+            &broker_url,
+            Some(auth.clone()),
+            connection_options.clone(),
+            custom_headers.clone(),
+        )
+        .with_retry_count(get_retries(args));
+        let pb_webhook_href_path = get_broker_relation(
+            hal_client.clone(),
+            "pb:webhook".to_string(),
+            broker_url.to_string(),
+        )
+        .await?;
+
+        let mut results = vec![];
+        for webhook in webhooks {
+            let uuid = webhook["uuid"].as_str().unwrap_or_default();
+            match create_or_update_webhook(&hal_client, &pb_webhook_href_path, webhook).await {
+                Ok(result) => {
+                    output::status(&output, format!("✅ Webhook {} created or updated", uuid));
+                    results.push(result);
+                }
+                // Each webhook is independent, so the rest can still be previewed
+                Err(PactBrokerError::DryRun(_)) => output::status(
+                    &output,
+                    format!("⚠️ Dry run, webhook {} was not created or updated", uuid),
+                ),
+                Err(e) => {
+                    return Err(PactBrokerError::IoError(format!(
+                        "Failed to create or update webhook {}: {}",
+                        uuid, e
+                    )));
+                }
+            }
+        }
+        output::print(&serde_json::Value::Array(results), &output)
+    })
+}
+
 #[cfg(test)]
 mod create_webhook_tests {
-    use super::create_webhook;
+    use super::{create_or_update_webhooks_from_file, create_webhook};
     use crate::cli::pact_broker::main::subcommands::{
         add_create_or_update_webhook_subcommand, add_create_webhook_subcommand,
     };
@@ -1070,4 +1172,99 @@ mod create_webhook_tests {
         let json = result.unwrap();
         assert!(json.contains("a webhook"));
     }
+
+    #[test]
+    fn create_or_update_webhooks_from_exported_file() {
+        let uuid = "696c5f93-1b7f-44bc-8d03-59440fcaa9a0";
+        let webhook = json!({
+            "description": "a webhook",
+            "events": [ { "name": "contract_published" } ],
+            "request": {
+                "url": "https://webhook",
+                "method": "POST"
+            },
+            "provider": { "name": "Pricing Service" }
+        });
+        let mut exported = webhook.clone();
+        exported["uuid"] = json!(uuid);
+
+        let interaction_put = |mut i: InteractionBuilder| {
+            i.given("the 'Pricing Service' already exists in the pact-broker");
+            i.request
+                .put()
+                .path(format!("/webhooks/{}", uuid))
+                .header("Accept", "application/hal+json")
+                .header("Content-Type", "application/json")
+                .json_body(webhook.clone());
+            i.response
+                .status(200)
+                .header("Content-Type", "application/hal+json;charset=utf-8")
+                .json_body(json_pattern!({
+                    "description": "a webhook",
+                    "_links": {
+                        "self": {
+                            "href": term!(r"http://.*","http://localhost:1234/some-url")
+                        }
+                    }
+                }));
+            i
+        };
+
+        let mock_server = setup_mock_server(vec![
+            index_interaction_with_webhook_relation()(InteractionBuilder::new(
+                "a request for the index resource with the webhook relation",
+                "",
+            )),
+            interaction_put(InteractionBuilder::new(
+                "a request to update a webhook with a uuid",
+                "",
+            )),
+        ]);
+        let mock_server_url = mock_server.url();
+
+        let file = std::env::temp_dir().join(format!(
+            "pact-broker-cli-webhooks-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&file, json!({ "webhooks": [exported] }).to_string()).unwrap();
+        let matches = add_create_or_update_webhook_subcommand().get_matches_from(vec![
+            "create-or-update-webhook",
+            "-b",
+            mock_server_url.as_str(),
+            "--file",
+            file.to_str().unwrap(),
+        ]);
+
+        let result = create_or_update_webhooks_from_file(&matches);
+        let _ = std::fs::remove_file(&file);
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert!(result.unwrap().contains("a webhook"));
+    }
+
+    #[test]
+    fn prints_the_uuid_of_the_created_webhook_with_output_id() {
+        use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+        use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (broker, url) = runtime.block_on(spawn_mock_broker(MockBrokerState::default()));
+        let matches = add_create_webhook_subcommand().get_matches_from(vec![
+            "create-webhook",
+            "https://ci.example.com",
+            "-X",
+            "POST",
+            "--contract-published",
+            "-b",
+            url.as_str(),
+            "--output",
+            "id",
+        ]);
+
+        let result = create_webhook(&matches).unwrap();
+
+        let state = broker.snapshot();
+        assert_eq!(state.webhooks.len(), 1);
+        assert_eq!(result, state.webhooks[0]["uuid"].as_str().unwrap());
+    }
 }
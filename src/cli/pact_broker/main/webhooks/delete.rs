@@ -1,10 +1,13 @@
 use maplit::hashmap;
 
+use serde_json::json;
+
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
+    output::{self, OutputType},
     utils::{
         follow_templated_broker_relation, get_auth, get_broker_relation, get_broker_url,
-        get_custom_headers, get_retries, get_ssl_options,
+        get_connection_options, get_custom_headers, get_retries,
     },
 };
 
@@ -12,18 +15,19 @@ pub fn delete_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
 
     let webhook_uuid = args
         .try_get_one::<String>("uuid")
         .unwrap()
         .ok_or_else(|| PactBrokerError::IoError("Webhook UUID is required".to_string()))?;
+    let output = OutputType::from_args(args);
 
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -63,15 +67,23 @@ pub fn delete_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError
         }
     });
 
+    let result = |deleted| json!({ "uuid": webhook_uuid, "deleted": deleted });
     match res {
         Ok(_) => {
             let message = format!("Webhook with UUID {} successfully deleted", webhook_uuid);
-            println!("{}", message);
+            if output.is_structured() {
+                return output::print(&result(true), &output);
+            } else {
+                println!("{}", message);
+            }
             Ok(message)
         }
         Err(PactBrokerError::NotFound(_)) => {
             let message = format!("Webhook with UUID {} was not found", webhook_uuid);
-            println!("{}", message);
+            output::status(&output, &message);
+            if output.is_structured() {
+                return output::print(&result(false), &output);
+            }
             Ok(message)
         }
         Err(err) => Err(err),
@@ -232,4 +244,32 @@ mod delete_webhook_tests {
         assert!(message.contains("was not found"));
         assert!(message.contains(uuid));
     }
+
+    #[test]
+    fn prints_the_result_of_deleting_a_webhook_as_json() {
+        use crate::cli::pact_broker::main::mock_broker::state::MockBrokerState;
+        use crate::cli::pact_broker::main::test_utils::spawn_mock_broker;
+
+        let state = serde_json::from_value::<MockBrokerState>(serde_json::json!({
+            "webhooks": [{
+                "uuid": "abcd",
+                "events": [{ "name": "contract_published" }],
+                "request": { "method": "POST", "url": "https://ci.example.com" }
+            }]
+        }))
+        .unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (broker, url) = runtime.block_on(spawn_mock_broker(state));
+
+        let mut args = base_args(&url, "abcd");
+        args.extend(["--output".to_string(), "json".to_string()]);
+        let deleted = delete_webhook(&add_delete_webhook_subcommand().get_matches_from(args));
+        let mut args = base_args(&url, "abcd");
+        args.extend(["--output".to_string(), "json".to_string()]);
+        let not_found = delete_webhook(&add_delete_webhook_subcommand().get_matches_from(args));
+
+        assert_eq!(deleted.unwrap(), r#"{"deleted":true,"uuid":"abcd"}"#);
+        assert_eq!(not_found.unwrap(), r#"{"deleted":false,"uuid":"abcd"}"#);
+        assert!(broker.snapshot().webhooks.is_empty());
+    }
 }
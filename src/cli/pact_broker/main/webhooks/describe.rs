@@ -0,0 +1,174 @@
+use maplit::hashmap;
+
+use crate::cli::{
+    pact_broker::main::{
+        HALClient, PactBrokerError,
+        output::{self, OutputType},
+        utils::{
+            follow_templated_broker_relation, get_auth, get_broker_relation, get_broker_url,
+            get_connection_options, get_custom_headers, get_retries,
+        },
+        webhooks::list::{webhook_events, webhook_uuid},
+    },
+    utils,
+};
+
+pub fn describe_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let uuid = args.get_one::<String>("uuid").unwrap().to_string();
+    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
+    let auth = get_auth(args);
+    let custom_headers = get_custom_headers(args);
+    let connection_options = get_connection_options(args);
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let hal_client: HALClient = HALClient::with_url(
+            &broker_url,
+            Some(auth.clone()),
+            connection_options.clone(),
+            custom_headers.clone(),
+        )
+        .with_retry_count(get_retries(args));
+        let pb_webhook_href_path = get_broker_relation(
+            hal_client.clone(),
+            "pb:webhook".to_string(),
+            broker_url.to_string(),
+        )
+        .await?;
+        let res = follow_templated_broker_relation(
+            hal_client.clone(),
+            "pb:webhook".to_string(),
+            pb_webhook_href_path,
+            hashmap! { "uuid".to_string() => uuid.clone() },
+        )
+        .await?;
+
+        let output = OutputType::from_args(args);
+        if output.is_structured() {
+            return output::print(&res, &output);
+        }
+
+        println!("✅");
+        println!("UUID: {}", utils::GREEN.apply_to(webhook_uuid(&res)));
+        if let Some(description) = res["description"].as_str() {
+            println!("Description: {}", utils::GREEN.apply_to(description));
+        }
+        if let Some(consumer) = res["consumer"]["name"].as_str() {
+            println!("Consumer: {}", utils::GREEN.apply_to(consumer));
+        }
+        if let Some(provider) = res["provider"]["name"].as_str() {
+            println!("Provider: {}", utils::GREEN.apply_to(provider));
+        }
+        println!(
+            "Enabled: {}",
+            utils::GREEN.apply_to(res["enabled"].as_bool().unwrap_or(true))
+        );
+        println!(
+            "Events: {}",
+            utils::GREEN.apply_to(webhook_events(&res).join(", "))
+        );
+        println!("Request:");
+        if let Some(method) = res["request"]["method"].as_str() {
+            println!(" - Method: {}", method);
+        }
+        if let Some(url) = res["request"]["url"].as_str() {
+            println!(" - URL: {}", url);
+        }
+        if let Some(headers) = res["request"]["headers"].as_object() {
+            println!(" - Headers:");
+            for (name, value) in headers {
+                println!("  - {}: {}", name, value.as_str().unwrap_or_default());
+            }
+        }
+        if let Some(body) = res["request"].get("body").filter(|body| !body.is_null()) {
+            println!(" - Body: {}", body);
+        }
+        if let Some(created_at) = res["createdAt"].as_str() {
+            println!("Created At: {}", utils::GREEN.apply_to(created_at));
+        }
+
+        Ok("".to_string())
+    })
+}
+
+#[cfg(test)]
+mod describe_webhook_tests {
+    use super::describe_webhook;
+    use crate::cli::pact_broker::main::subcommands::add_describe_webhook_subcommand;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+
+    #[test]
+    fn describes_a_webhook_by_uuid() {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+        let uuid = "696c5f93-1b7f-44bc-8d03-59440fcaa9a0";
+
+        let pact_broker_service = PactBuilder::new("pact-broker-cli", "Pact Broker")
+            .interaction("a request for the index resource", "", |mut i| {
+                i.given("the pb:webhook relation exists in the index resource");
+                i.request
+                    .get()
+                    .path("/")
+                    .header("Accept", "application/hal+json")
+                    .header("Accept", "application/json");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "_links": {
+                            "pb:webhook": {
+                                "href": term!(
+                                    "http:\\/\\/.*\\{uuid\\}",
+                                    "http://localhost/webhooks/{uuid}"
+                                ),
+                                "templated": true
+                            }
+                        }
+                    }));
+                i
+            })
+            .interaction("a request for a webhook", "", |mut i| {
+                i.given(format!("a webhook with the uuid {} exists", uuid));
+                i.request
+                    .get()
+                    .path(format!("/webhooks/{}", uuid))
+                    .header("Accept", "application/hal+json")
+                    .header("Accept", "application/json");
+                i.response
+                    .status(200)
+                    .header("Content-Type", "application/hal+json;charset=utf-8")
+                    .json_body(json_pattern!({
+                        "uuid": uuid,
+                        "description": like!("a webhook"),
+                        "events": each_like!({ "name": "contract_published" }),
+                        "request": {
+                            "method": like!("POST"),
+                            "url": like!("https://example.org/hook")
+                        }
+                    }));
+                i
+            })
+            .start_mock_server(None, Some(config));
+        let mock_server_url = pact_broker_service.url();
+
+        let matches = add_describe_webhook_subcommand().get_matches_from(vec![
+            "describe-webhook",
+            "-b",
+            mock_server_url.as_str(),
+            "--uuid",
+            uuid,
+            "--output",
+            "json",
+        ]);
+        let result: serde_json::Value =
+            serde_json::from_str(&describe_webhook(&matches).unwrap()).unwrap();
+
+        assert_eq!(result["uuid"], serde_json::json!(uuid));
+        assert_eq!(
+            result["events"][0]["name"],
+            serde_json::json!("contract_published")
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use serde_json::{Value, json};
+
+use crate::cli::{
+    pact_broker::main::{
+        HALClient, PactBrokerError,
+        output::{self, OutputType},
+        types::Pagination,
+        utils::{
+            get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries,
+        },
+        webhooks::list::{WebhookFilter, fetch_webhooks, webhook_uuid},
+    },
+    utils,
+};
+
+/// The names of the request headers of a webhook whose values the broker returned masked, such as
+/// `"Authorization": "**********"`
+pub fn masked_headers(webhook: &Value) -> Vec<String> {
+    webhook["request"]["headers"]
+        .as_object()
+        .map(|headers| {
+            headers
+                .iter()
+                .filter(|(_, value)| {
+                    value
+                        .as_str()
+                        .is_some_and(|value| !value.is_empty() && value.chars().all(|c| c == '*'))
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Converts a webhook resource from the broker into the form accepted when creating or updating
+/// a webhook, dropping the HAL links and timestamps.
+///
+/// The broker never returns webhook passwords or the values of headers such as `Authorization`,
+/// so these are removed rather than exported in their masked form.
+pub fn exportable_webhook(webhook: &Value) -> Value {
+    let mut exported = json!({ "uuid": webhook_uuid(webhook) });
+    for key in [
+        "description",
+        "consumer",
+        "provider",
+        "events",
+        "request",
+        "enabled",
+        "teamUuid",
+    ] {
+        if let Some(value) = webhook.get(key).filter(|value| !value.is_null()) {
+            exported[key] = value.clone();
+        }
+    }
+    let masked_headers = masked_headers(webhook);
+    if let Some(request) = exported["request"].as_object_mut() {
+        request.remove("password");
+        if let Some(headers) = request.get_mut("headers").and_then(Value::as_object_mut) {
+            headers.retain(|name, _| !masked_headers.contains(name));
+        }
+    }
+    exported
+}
+
+pub fn export_webhooks(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
+    let auth = get_auth(args);
+    let custom_headers = get_custom_headers(args);
+    let connection_options = get_connection_options(args);
+    let filter = WebhookFilter::from_args(args);
+    let file = args.get_one::<String>("file").unwrap();
+    let output = OutputType::from_args(args);
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let hal_client: HALClient = HALClient::with_url(
+            &broker_url,
+            Some(auth.clone()),
+            connection_options.clone(),
+            custom_headers.clone(),
+        )
+        .with_retry_count(get_retries(args));
+
+        let webhooks: Vec<Value> = fetch_webhooks(&hal_client, &broker_url, &Pagination::all())
+            .await?
+            .iter()
+            .filter(|webhook| filter.matches(webhook))
+            .map(|webhook| {
+                if webhook["request"].get("password").is_some() {
+                    output::status(&output, format!(
+                        "⚠️  The password for webhook {} can not be exported, it will need to be added to the export before it is re-applied",
+                        utils::YELLOW.apply_to(webhook_uuid(webhook))
+                    ));
+                }
+                let masked_headers = masked_headers(webhook);
+                if !masked_headers.is_empty() {
+                    output::status(&output, format!(
+                        "⚠️  The {} header(s) of webhook {} can not be exported, they will need to be added to the export before it is re-applied",
+                        masked_headers.join(", "),
+                        utils::YELLOW.apply_to(webhook_uuid(webhook))
+                    ));
+                }
+                exportable_webhook(webhook)
+            })
+            .collect();
+
+        let export = json!({ "webhooks": webhooks });
+        let content = serde_json::to_string_pretty(&export).unwrap();
+        std::fs::write(file, &content).map_err(|e| {
+            PactBrokerError::IoError(format!("Failed to write webhooks to {}: {}", file, e))
+        })?;
+
+        if output.is_structured() {
+            return output::print(&export, &output);
+        } else {
+            println!(
+                "✅ Exported {} webhook(s) to {}",
+                utils::GREEN.apply_to(webhooks.len()),
+                utils::GREEN.apply_to(file)
+            );
+        }
+        Ok(content)
+    })
+}
+
+#[cfg(test)]
+mod export_webhooks_tests {
+    use super::exportable_webhook;
+    use serde_json::json;
+
+    #[test]
+    fn exportable_webhook_strips_links_timestamps_and_passwords() {
+        let webhook = json!({
+            "uuid": "696c5f93-1b7f-44bc-8d03-59440fcaa9a0",
+            "description": "a webhook",
+            "consumer": { "name": "Foo" },
+            "events": [{ "name": "contract_published" }],
+            "request": {
+                "method": "POST",
+                "url": "https://example.org/hook",
+                "username": "user",
+                "password": "**********",
+                "headers": {
+                    "Authorization": "**********",
+                    "Content-Type": "application/json"
+                }
+            },
+            "enabled": true,
+            "createdAt": "2025-01-01T00:00:00+00:00",
+            "_links": { "self": { "href": "http://localhost/webhooks/696c5f93-1b7f-44bc-8d03-59440fcaa9a0" } }
+        });
+
+        assert_eq!(
+            exportable_webhook(&webhook),
+            json!({
+                "uuid": "696c5f93-1b7f-44bc-8d03-59440fcaa9a0",
+                "description": "a webhook",
+                "consumer": { "name": "Foo" },
+                "events": [{ "name": "contract_published" }],
+                "request": {
+                    "method": "POST",
+                    "url": "https://example.org/hook",
+                    "username": "user",
+                    "headers": { "Content-Type": "application/json" }
+                },
+                "enabled": true
+            })
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use comfy_table::Table;
+use comfy_table::presets::UTF8_FULL;
+use serde_json::Value;
+
+use crate::cli::pact_broker::main::{
+    HALClient, PactBrokerError,
+    output::{self, OutputType},
+    types::Pagination,
+    utils::{
+        follow_broker_relation, get_auth, get_broker_relation, get_broker_url,
+        get_connection_options, get_custom_headers, get_retries,
+    },
+};
+
+/// Filters that can be applied to the webhooks returned from the broker
+#[derive(Debug, Clone, Default)]
+pub struct WebhookFilter {
+    pub consumer: Option<String>,
+    pub provider: Option<String>,
+    pub event: Option<String>,
+}
+
+impl WebhookFilter {
+    pub fn from_args(args: &clap::ArgMatches) -> Self {
+        WebhookFilter {
+            consumer: args
+                .try_get_one::<String>("consumer")
+                .ok()
+                .flatten()
+                .cloned(),
+            provider: args
+                .try_get_one::<String>("provider")
+                .ok()
+                .flatten()
+                .cloned(),
+            event: args.try_get_one::<String>("event").ok().flatten().cloned(),
+        }
+    }
+
+    pub fn matches(&self, webhook: &Value) -> bool {
+        let consumer_matches = self
+            .consumer
+            .as_ref()
+            .is_none_or(|consumer| webhook["consumer"]["name"].as_str() == Some(consumer.as_str()));
+        let provider_matches = self
+            .provider
+            .as_ref()
+            .is_none_or(|provider| webhook["provider"]["name"].as_str() == Some(provider.as_str()));
+        let event_matches = self
+            .event
+            .as_ref()
+            .is_none_or(|event| webhook_events(webhook).contains(event));
+        consumer_matches && provider_matches && event_matches
+    }
+}
+
+/// Returns the names of the events that trigger the webhook
+pub fn webhook_events(webhook: &Value) -> Vec<String> {
+    webhook["events"]
+        .as_array()
+        .map(|events| {
+            events
+                .iter()
+                .filter_map(|event| event["name"].as_str().map(|name| name.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the UUID of the webhook, falling back to the last segment of its self link
+pub fn webhook_uuid(webhook: &Value) -> String {
+    match webhook["uuid"].as_str() {
+        Some(uuid) => uuid.to_string(),
+        None => webhook["_links"]["self"]["href"]
+            .as_str()
+            .and_then(|href| href.rsplit('/').next())
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Fetches the full details of every webhook in the broker. The webhooks collection resource
+/// only contains links, so each webhook is fetched in turn.
+pub async fn fetch_webhooks(
+    hal_client: &HALClient,
+    broker_url: &str,
+    pagination: &Pagination,
+) -> Result<Vec<Value>, PactBrokerError> {
+    let pb_webhooks_href_path = get_broker_relation(
+        hal_client.clone(),
+        "pb:webhooks".to_string(),
+        broker_url.to_string(),
+    )
+    .await?;
+    let res = hal_client
+        .fetch_collection(&pb_webhooks_href_path, &["/_links/pb:webhooks"], pagination)
+        .await?;
+
+    let mut webhooks = vec![];
+    if let Some(links) = res["_links"]["pb:webhooks"].as_array() {
+        for link in links {
+            if let Some(href) = link["href"].as_str() {
+                let webhook = follow_broker_relation(
+                    hal_client.clone(),
+                    "pb:webhook".to_string(),
+                    href.to_string(),
+                )
+                .await?;
+                webhooks.push(webhook);
+            }
+        }
+    }
+    Ok(webhooks)
+}
+
+pub fn list_webhooks(args: &clap::ArgMatches) -> Result<String, PactBrokerError> {
+    let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
+    let auth = get_auth(args);
+    let custom_headers = get_custom_headers(args);
+    let connection_options = get_connection_options(args);
+    let filter = WebhookFilter::from_args(args);
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let hal_client: HALClient = HALClient::with_url(
+            &broker_url,
+            Some(auth.clone()),
+            connection_options.clone(),
+            custom_headers.clone(),
+        )
+        .with_retry_count(get_retries(args));
+
+        // The limit applies to the webhooks that match the filters, so every page is fetched
+        let pagination = Pagination::from_args(args);
+        let webhooks: Vec<Value> = fetch_webhooks(
+            &hal_client,
+            &broker_url,
+            &Pagination {
+                limit: None,
+                ..pagination.clone()
+            },
+        )
+        .await?
+        .into_iter()
+        .filter(|webhook| filter.matches(webhook))
+        .take(pagination.limit.unwrap_or(usize::MAX))
+        .collect();
+
+        let output = OutputType::from_args(args);
+        if output.is_structured() {
+            return output::print(&Value::Array(webhooks), &output);
+        }
+        let result = generate_webhooks_table(&webhooks).to_string();
+        println!("{}", result);
+        Ok(result)
+    })
+}
+
+fn generate_webhooks_table(webhooks: &[Value]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(vec![
+        "UUID",
+        "DESCRIPTION",
+        "CONSUMER",
+        "PROVIDER",
+        "EVENTS",
+        "ENABLED",
+    ]);
+    for webhook in webhooks {
+        table.add_row(vec![
+            webhook_uuid(webhook),
+            webhook["description"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            webhook["consumer"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            webhook["provider"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            webhook_events(webhook).join(", "),
+            webhook["enabled"].as_bool().unwrap_or(true).to_string(),
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod list_webhooks_tests {
+    use super::{WebhookFilter, list_webhooks, webhook_uuid};
+    use crate::cli::pact_broker::main::subcommands::add_list_webhooks_subcommand;
+    use pact_consumer::builders::InteractionBuilder;
+    use pact_consumer::prelude::*;
+    use pact_models::PactSpecification;
+    use serde_json::json;
+
+    fn setup_mock_server(interactions: Vec<InteractionBuilder>) -> Box<dyn ValidatingMockServer> {
+        let config = MockServerConfig {
+            pact_specification: PactSpecification::V2,
+            ..MockServerConfig::default()
+        };
+        let mut pact_builder = PactBuilder::new("pact-broker-cli", "Pact Broker");
+        for i in interactions {
+            pact_builder.push_interaction(&i.build());
+        }
+        pact_builder.start_mock_server(None, Some(config))
+    }
+
+    #[test]
+    fn filter_matches_on_consumer_provider_and_event() {
+        let webhook = json!({
+            "consumer": { "name": "Foo" },
+            "provider": { "name": "Bar" },
+            "events": [{ "name": "contract_published" }]
+        });
+
+        assert!(WebhookFilter::default().matches(&webhook));
+        assert!(
+            WebhookFilter {
+                consumer: Some("Foo".to_string()),
+                provider: Some("Bar".to_string()),
+                event: Some("contract_published".to_string()),
+            }
+            .matches(&webhook)
+        );
+        assert!(
+            !WebhookFilter {
+                consumer: Some("Other".to_string()),
+                ..WebhookFilter::default()
+            }
+            .matches(&webhook)
+        );
+        assert!(
+            !WebhookFilter {
+                event: Some("provider_verification_failed".to_string()),
+                ..WebhookFilter::default()
+            }
+            .matches(&webhook)
+        );
+    }
+
+    #[test]
+    fn webhook_uuid_falls_back_to_the_self_link() {
+        let webhook = json!({
+            "_links": { "self": { "href": "http://localhost/webhooks/abc-123" } }
+        });
+        assert_eq!(webhook_uuid(&webhook), "abc-123");
+    }
+
+    #[test]
+    fn lists_webhooks_filtered_by_provider() {
+        let index_interaction = |mut i: InteractionBuilder| {
+            i.given("the pb:webhooks relation exists in the index resource");
+            i.request
+                .path("/")
+                .header("Accept", "application/hal+json")
+                .header("Accept", "application/json");
+            i.response
+                .header("Content-Type", "application/hal+json;charset=utf-8")
+                .json_body(json_pattern!({
+                    "_links": {
+                        "pb:webhooks": {
+                            "href": term!("http:\\/\\/.*\\/webhooks", "http://localhost/webhooks")
+                        }
+                    }
+                }));
+            i
+        };
+        let webhooks_interaction = |mut i: InteractionBuilder| {
+            i.given("a webhook with the uuid 696c5f93-1b7f-44bc-8d03-59440fcaa9a0 exists");
+            i.request
+                .get()
+                .path("/webhooks")
+                .header("Accept", "application/hal+json")
+                .header("Accept", "application/json");
+            i.response
+                .status(200)
+                .header("Content-Type", "application/hal+json;charset=utf-8")
+                .json_body(json_pattern!({
+                    "_links": {
+                        "pb:webhooks": [{
+                            "title": like!("a webhook"),
+                            "href": term!(
+                                "http:\\/\\/.*\\/webhooks\\/696c5f93-1b7f-44bc-8d03-59440fcaa9a0",
+                                "http://localhost/webhooks/696c5f93-1b7f-44bc-8d03-59440fcaa9a0"
+                            )
+                        }]
+                    }
+                }));
+            i
+        };
+        let webhook_interaction = |mut i: InteractionBuilder| {
+            i.given("a webhook with the uuid 696c5f93-1b7f-44bc-8d03-59440fcaa9a0 exists");
+            i.request
+                .get()
+                .path("/webhooks/696c5f93-1b7f-44bc-8d03-59440fcaa9a0")
+                .header("Accept", "application/hal+json")
+                .header("Accept", "application/json");
+            i.response
+                .status(200)
+                .header("Content-Type", "application/hal+json;charset=utf-8")
+                .json_body(json_pattern!({
+                    "uuid": "696c5f93-1b7f-44bc-8d03-59440fcaa9a0",
+                    "description": like!("a webhook"),
+                    "consumer": { "name": "Foo" },
+                    "provider": { "name": "Bar" },
+                    "enabled": like!(true),
+                    "events": [{ "name": "contract_published" }],
+                    "request": {
+                        "method": like!("POST"),
+                        "url": like!("https://example.org/hook")
+                    }
+                }));
+            i
+        };
+
+        let mock_server = setup_mock_server(vec![
+            index_interaction(InteractionBuilder::new(
+                "a request for the index resource",
+                "",
+            )),
+            webhooks_interaction(InteractionBuilder::new("a request to list webhooks", "")),
+            webhook_interaction(InteractionBuilder::new("a request for a webhook", "")),
+        ]);
+        let mock_server_url = mock_server.url();
+
+        let matches = add_list_webhooks_subcommand().get_matches_from(vec![
+            "list-webhooks",
+            "-b",
+            mock_server_url.as_str(),
+            "--provider",
+            "Bar",
+            "--output",
+            "json",
+        ]);
+        let result: serde_json::Value =
+            serde_json::from_str(&list_webhooks(&matches).unwrap()).unwrap();
+        assert_eq!(result.as_array().unwrap().len(), 1);
+        assert_eq!(
+            result[0]["uuid"],
+            json!("696c5f93-1b7f-44bc-8d03-59440fcaa9a0")
+        );
+
+        let matches = add_list_webhooks_subcommand().get_matches_from(vec![
+            "list-webhooks",
+            "-b",
+            mock_server_url.as_str(),
+            "--provider",
+            "Other",
+            "--output",
+            "json",
+        ]);
+        let result: serde_json::Value =
+            serde_json::from_str(&list_webhooks(&matches).unwrap()).unwrap();
+        assert_eq!(result, json!([]));
+    }
+}
@@ -1,3 +1,6 @@
 pub mod create;
 pub mod delete;
+pub mod describe;
+pub mod export;
+pub mod list;
 pub mod test;
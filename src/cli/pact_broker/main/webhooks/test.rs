@@ -2,9 +2,10 @@ use maplit::hashmap;
 
 use crate::cli::pact_broker::main::{
     HALClient, PactBrokerError,
+    output::{self, OutputType},
     utils::{
         follow_templated_broker_relation, get_auth, get_broker_relation, get_broker_url,
-        get_custom_headers, get_retries, get_ssl_options,
+        get_connection_options, get_custom_headers, get_retries,
     },
 };
 
@@ -12,14 +13,15 @@ pub fn test_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
     let webhook_uuid = args.try_get_one::<String>("uuid").unwrap_or_default();
+    let output = OutputType::from_args(args);
 
     let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
         let hal_client: HALClient = HALClient::with_url(
             &broker_url,
             Some(auth.clone()),
-            ssl_options.clone(),
+            connection_options.clone(),
             custom_headers.clone(),
         )
         .with_retry_count(get_retries(args));
@@ -62,9 +64,12 @@ pub fn test_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
         let webhook_data = serde_json::json!({});
 
         let webhook_data_str = webhook_data.to_string();
-        println!(
-            "Executing webhook at: {} with data: {}",
-            pb_webhooks_href_path, webhook_data_str
+        output::status(
+            &output,
+            format!(
+                "Executing webhook at: {} with data: {}",
+                pb_webhooks_href_path, webhook_data_str
+            ),
         );
         hal_client
             .post_json(&pb_webhooks_href_path, &webhook_data_str, None)
@@ -74,13 +79,16 @@ pub fn test_webhook(args: &clap::ArgMatches) -> Result<String, PactBrokerError>
     match res {
         Ok(result) => {
             let json: String = serde_json::to_string(&result).unwrap();
-            println!("{}", json);
+            if output.is_structured() {
+                return output::print(&result, &output);
+            } else {
+                println!("{}", json);
+            }
             Ok(json)
         }
-        Err(e) => Err(PactBrokerError::IoError(format!(
-            "Failed to execute webhook: {}",
-            e
-        ))),
+        Err(e) => Err(e.map_unless_dry_run(|e| {
+            PactBrokerError::IoError(format!("Failed to execute webhook: {}", e))
+        })),
     }
 }
 
@@ -1,5 +1,9 @@
+use crate::cli::pact_broker::main::api::api;
+use crate::cli::pact_broker::main::apply::apply;
+use crate::cli::pact_broker::main::backup::{backup, restore};
 use crate::cli::pact_broker::main::branches::delete_branch::{self};
 use crate::cli::pact_broker::main::deployments::record_deployment::record_deployment;
+use crate::cli::pact_broker::main::deployments::record_deployments_from_manifest::record_deployments_from_manifest;
 use crate::cli::pact_broker::main::deployments::record_release::record_release;
 use crate::cli::pact_broker::main::deployments::record_support_ended::record_support_ended;
 use crate::cli::pact_broker::main::deployments::record_undeployment::record_undeployment;
@@ -7,61 +11,97 @@ use crate::cli::pact_broker::main::environments::create::create_environment;
 use crate::cli::pact_broker::main::environments::delete::delete_environment;
 use crate::cli::pact_broker::main::environments::describe::describe_environment;
 use crate::cli::pact_broker::main::environments::list::list_environments;
+use crate::cli::pact_broker::main::environments::status::environment_status;
 use crate::cli::pact_broker::main::environments::update::update_environment;
+use crate::cli::pact_broker::main::lint::lint;
+use crate::cli::pact_broker::main::migrate::migrate;
+#[cfg(feature = "mock-broker")]
+use crate::cli::pact_broker::main::mock_broker::server::mock_broker;
+use crate::cli::pact_broker::main::output::{self, OutputType};
 use crate::cli::pact_broker::main::pacticipants::create::create_or_update_pacticipant;
 use crate::cli::pact_broker::main::pacticipants::describe::describe_pacticipant;
 use crate::cli::pact_broker::main::pacticipants::list::list_pacticipants;
+use crate::cli::pact_broker::main::pacts::diff_pacts::diff_pacts;
+use crate::cli::pact_broker::main::pacts::fetch_pacts_for_verification::{
+    fetch_pacts_for_verification, parse_consumer_version_selectors,
+};
 use crate::cli::pact_broker::main::pacts::get_pacts::get_pacts;
 use crate::cli::pact_broker::main::pacts::list_latest_pact_versions::list_latest_pact_versions;
+use crate::cli::pact_broker::main::pacts::publish_verification_results::publish_verification_results;
+use crate::cli::pact_broker::main::promote::promote;
+#[cfg(feature = "mock-broker")]
+use crate::cli::pact_broker::main::subcommands::add_mock_broker_subcommand;
 use crate::cli::pact_broker::main::subcommands::{
-    add_can_i_deploy_subcommand, add_can_i_merge_subcommand, add_create_environment_subcommand,
+    add_api_subcommand, add_apply_subcommand, add_backup_subcommand, add_can_i_deploy_subcommand,
+    add_can_i_merge_subcommand, add_config_subcommand, add_create_environment_subcommand,
     add_create_or_update_pacticipant_subcommand, add_create_or_update_version_subcommand,
     add_create_or_update_webhook_subcommand, add_create_version_tag_subcommand,
     add_create_webhook_subcommand, add_delete_branch_subcommand, add_delete_environment_subcommand,
     add_delete_version_tag_subcommand, add_delete_webhook_subcommand,
     add_describe_environment_subcommand, add_describe_pacticipant_subcommand,
-    add_describe_version_subcommand, add_generate_uuid_subcommand, add_get_pacts_subcommand,
-    add_list_environments_subcommand, add_list_latest_pact_versions_subcommand,
-    add_list_pacticipants_subcommand, add_provider_states_subcommand, add_publish_pacts_subcommand,
-    add_record_deployment_subcommand, add_record_release_subcommand,
+    add_describe_version_subcommand, add_describe_webhook_subcommand, add_diff_pacts_subcommand,
+    add_environment_status_subcommand, add_export_webhooks_subcommand,
+    add_fetch_pacts_for_verification_subcommand, add_generate_uuid_subcommand,
+    add_get_pacts_subcommand, add_lint_subcommand, add_list_environments_subcommand,
+    add_list_latest_pact_versions_subcommand, add_list_pacticipants_subcommand,
+    add_list_webhooks_subcommand, add_login_subcommand, add_logout_subcommand,
+    add_migrate_subcommand, add_profile_argument, add_promote_subcommand,
+    add_provider_states_subcommand, add_publish_pacts_subcommand,
+    add_publish_verification_results_subcommand, add_record_deployment_subcommand,
+    add_record_deployments_from_manifest_subcommand, add_record_release_subcommand,
     add_record_support_ended_subcommand, add_record_undeployment_subcommand,
-    add_test_webhook_subcommand, add_update_environment_subcommand,
+    add_restore_subcommand, add_test_webhook_subcommand, add_update_environment_subcommand,
 };
 use crate::cli::pact_broker::main::tags::create_version_tag;
 use crate::cli::pact_broker::main::tags::delete_tag::delete_version_tag;
-use crate::cli::pact_broker::main::types::{BrokerDetails, OutputType};
+use crate::cli::pact_broker::main::types::{BrokerDetails, Pagination};
 use crate::cli::pact_broker::main::utils::{
-    get_auth, get_broker_url, get_custom_headers, get_retries, get_ssl_options, handle_error,
+    get_auth, get_broker_url, get_connection_options, get_custom_headers, get_retries,
+    handle_command_error,
 };
 use crate::cli::pact_broker::main::versions::create::create_or_update_version;
 use crate::cli::pact_broker::main::versions::describe::describe_version;
-use crate::cli::pact_broker::main::webhooks::create::create_webhook;
+use crate::cli::pact_broker::main::webhooks::create::{
+    create_or_update_webhooks_from_file, create_webhook,
+};
 use crate::cli::pact_broker::main::webhooks::delete::delete_webhook;
+use crate::cli::pact_broker::main::webhooks::describe::describe_webhook;
+use crate::cli::pact_broker::main::webhooks::export::export_webhooks;
+use crate::cli::pact_broker::main::webhooks::list::list_webhooks;
 use crate::cli::pact_broker::main::webhooks::test::test_webhook;
-use crate::cli::pact_broker::main::{can_i_deploy, pact_publish};
+use crate::cli::pact_broker::main::{can_i_deploy, config, credentials, pact_publish};
 use clap::{ArgMatches, Command, command};
 use tracing::error;
 pub fn add_pact_broker_client_command() -> Command {
-    command!()
+    let command = command!()
         .arg_required_else_help(true)
         .args(crate::cli::add_output_arguments(
             ["json", "text", "table", "pretty"].to_vec(),
             "text",
         ))
+        .arg(add_profile_argument())
+        .arg(output::query_argument())
         .subcommand(add_publish_pacts_subcommand())
+        .subcommand(add_lint_subcommand())
         .subcommand(add_list_latest_pact_versions_subcommand())
         .subcommand(add_get_pacts_subcommand())
+        .subcommand(add_diff_pacts_subcommand())
+        .subcommand(add_fetch_pacts_for_verification_subcommand())
+        .subcommand(add_publish_verification_results_subcommand())
         .subcommand(add_create_environment_subcommand())
         .subcommand(add_update_environment_subcommand())
         .subcommand(add_delete_environment_subcommand())
         .subcommand(add_describe_environment_subcommand())
         .subcommand(add_list_environments_subcommand())
+        .subcommand(add_environment_status_subcommand())
         .subcommand(add_record_deployment_subcommand())
+        .subcommand(add_record_deployments_from_manifest_subcommand())
         .subcommand(add_record_undeployment_subcommand())
         .subcommand(add_record_release_subcommand())
         .subcommand(add_record_support_ended_subcommand())
         .subcommand(add_can_i_deploy_subcommand())
         .subcommand(add_can_i_merge_subcommand())
+        .subcommand(add_promote_subcommand())
         .subcommand(add_create_or_update_pacticipant_subcommand())
         .subcommand(add_describe_pacticipant_subcommand())
         .subcommand(add_list_pacticipants_subcommand())
@@ -69,13 +109,27 @@ pub fn add_pact_broker_client_command() -> Command {
         .subcommand(add_create_or_update_webhook_subcommand())
         .subcommand(add_delete_webhook_subcommand())
         .subcommand(add_test_webhook_subcommand())
+        .subcommand(add_list_webhooks_subcommand())
+        .subcommand(add_describe_webhook_subcommand())
+        .subcommand(add_export_webhooks_subcommand())
         .subcommand(add_delete_branch_subcommand())
         .subcommand(add_create_version_tag_subcommand())
         .subcommand(add_delete_version_tag_subcommand())
         .subcommand(add_describe_version_subcommand())
         .subcommand(add_create_or_update_version_subcommand())
         .subcommand(add_generate_uuid_subcommand())
+        .subcommand(add_apply_subcommand())
+        .subcommand(add_api_subcommand());
+    #[cfg(feature = "mock-broker")]
+    let command = command.subcommand(add_mock_broker_subcommand());
+    command
+        .subcommand(add_migrate_subcommand())
+        .subcommand(add_backup_subcommand())
+        .subcommand(add_restore_subcommand())
         .subcommand(add_provider_states_subcommand().arg_required_else_help(true))
+        .subcommand(add_config_subcommand())
+        .subcommand(add_login_subcommand())
+        .subcommand(add_logout_subcommand())
 }
 
 pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value, i32> {
@@ -94,33 +148,39 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
                 Err(err) => Err(err),
             }
         }
+        Some(("lint", args)) => match lint(args) {
+            Ok(report) if report.has_errors() => Err(2),
+            Ok(report) => Ok(serde_json::to_value(report).unwrap()),
+            Err(err) => handle_command_error(err),
+        },
         Some(("list-latest-pact-versions", args)) => {
             // setup client with broker url and credentials
             let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
             let auth = get_auth(args);
             let custom_headers = get_custom_headers(args);
-            let ssl_options = get_ssl_options(args);
+            let connection_options = get_connection_options(args);
 
             let broker_details = BrokerDetails {
                 url: broker_url.clone(),
                 auth: Some(auth),
-                ssl_options: ssl_options.clone(),
+                connection_options: connection_options.clone(),
                 custom_headers: custom_headers.clone(),
                 retries: get_retries(args),
             };
-            let default_output: String = "text".to_string();
-            let output_arg: &String = args.get_one::<String>("output").unwrap_or(&default_output);
-            let output = match output_arg.as_str() {
-                "json" => OutputType::Json,
-                "table" => OutputType::Table,
-                "pretty" => OutputType::Pretty,
-                _ => OutputType::Text,
-            };
+            let output = OutputType::from_args(args);
 
-            let res = list_latest_pact_versions(&broker_details, output);
+            let res =
+                list_latest_pact_versions(&broker_details, output, &Pagination::from_args(args));
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("diff-pacts", args)) => {
+            let res = diff_pacts(args);
+            if let Err(err) = res {
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -129,25 +189,18 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
             // setup client with broker url and credentials
             let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
             let auth = get_auth(args);
-            let ssl_options = get_ssl_options(args);
+            let connection_options = get_connection_options(args);
             let custom_headers = get_custom_headers(args);
 
             let broker_details = BrokerDetails {
                 url: broker_url.clone(),
                 auth: Some(auth),
-                ssl_options: ssl_options.clone(),
+                connection_options: connection_options.clone(),
                 custom_headers: custom_headers.clone(),
                 retries: get_retries(args),
             };
 
-            let default_output: String = "text".to_string();
-            let output_arg: &String = args.get_one::<String>("output").unwrap_or(&default_output);
-            let output = match output_arg.as_str() {
-                "json" => OutputType::Json,
-                "table" => OutputType::Table,
-                "pretty" => OutputType::Pretty,
-                _ => OutputType::Text,
-            };
+            let output = OutputType::from_args(args);
 
             let provider = args.get_one::<String>("provider").unwrap();
             let consumer = args.get_one::<String>("consumer");
@@ -165,19 +218,58 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
                 output,
                 download,
                 download_dir,
+                &Pagination::from_args(args),
             );
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("fetch-pacts-for-verification", args)) => {
+            let output = OutputType::from_args(args);
+
+            let selectors: Vec<String> = args
+                .get_many::<String>("consumer-version-selector")
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default();
+            let provider_version_tags: Vec<String> = args
+                .get_many::<String>("provider-version-tag")
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default();
+
+            let res = parse_consumer_version_selectors(&selectors).and_then(|selectors| {
+                fetch_pacts_for_verification(
+                    &BrokerDetails::from_args(args)?,
+                    args.get_one::<String>("provider").unwrap(),
+                    selectors,
+                    args.get_flag("enable-pending"),
+                    args.get_one::<String>("include-wip-pacts-since").cloned(),
+                    args.get_one::<String>("provider-version-branch").cloned(),
+                    provider_version_tags,
+                    output,
+                    args.get_flag("download"),
+                    args.get_one::<String>("download-dir").unwrap(),
+                )
+            });
+            if let Err(err) = res {
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
+        Some(("publish-verification-results", args)) => {
+            let res = publish_verification_results(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(res.unwrap())
+            }
+        }
         Some(("create-environment", args)) => {
             let res = create_environment(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -185,8 +277,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("update-environment", args)) => {
             let res = update_environment(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -194,8 +285,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("describe-environment", args)) => {
             let res = describe_environment(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -203,8 +293,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("delete-environment", args)) => {
             let res = delete_environment(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -212,71 +301,78 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("list-environments", args)) => {
             let res = list_environments(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
-        Some(("record-deployment", args)) => {
-            let res = record_deployment(args);
+        Some(("environment-status", args)) => {
+            let res = environment_status(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
-        Some(("record-undeployment", args)) => {
-            let res = record_undeployment(args);
+        Some(("record-deployment", args)) => {
+            let res = record_deployment(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
-        Some(("record-release", args)) => {
-            let res = record_release(args);
+        Some(("record-deployments-from-manifest", args)) => {
+            let res = record_deployments_from_manifest(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
-        Some(("record-support-ended", args)) => {
-            let res = record_support_ended(args);
+        Some(("record-undeployment", args)) => {
+            let res = record_undeployment(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
-        Some(("can-i-deploy", args)) => {
-            let res = can_i_deploy::can_i_deploy(args, raw_args, false);
+        Some(("record-release", args)) => {
+            let res = record_release(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
-        Some(("can-i-merge", args)) => {
-            let res = can_i_deploy::can_i_deploy(args, raw_args, true);
+        Some(("record-support-ended", args)) => {
+            let res = record_support_ended(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
+        Some(("can-i-deploy", args)) => match can_i_deploy::can_i_deploy(args, raw_args, false) {
+            Ok(result) if result.exit_code() == 0 => Ok(serde_json::to_value(result).unwrap()),
+            Ok(result) => Err(result.exit_code()),
+            Err(err) => handle_command_error(err),
+        },
+        Some(("can-i-merge", args)) => match can_i_deploy::can_i_deploy(args, raw_args, true) {
+            Ok(result) if result.exit_code() == 0 => Ok(serde_json::to_value(result).unwrap()),
+            Ok(result) => Err(result.exit_code()),
+            Err(err) => handle_command_error(err),
+        },
+        Some(("promote", args)) => match promote(args) {
+            Ok(result) if result.exit_code() == 0 => Ok(serde_json::to_value(result).unwrap()),
+            Ok(result) => Err(result.exit_code()),
+            Err(err) => handle_command_error(err),
+        },
         Some(("create-or-update-pacticipant", args)) => {
             let res = create_or_update_pacticipant(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -285,29 +381,21 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
             let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
             let auth = get_auth(args);
             let custom_headers = get_custom_headers(args);
-            let ssl_options = get_ssl_options(args);
+            let connection_options = get_connection_options(args);
 
             let broker_details = BrokerDetails {
                 url: broker_url.clone(),
                 auth: Some(auth),
-                ssl_options: ssl_options.clone(),
+                connection_options: connection_options.clone(),
                 custom_headers: custom_headers.clone(),
                 retries: get_retries(args),
             };
-            let default_output: String = "table".to_string();
-            let output_arg: &String = args.get_one::<String>("output").unwrap_or(&default_output);
             let pacticipant_name: &String = args.get_one::<String>("name").unwrap();
-            let output = match output_arg.as_str() {
-                "json" => OutputType::Json,
-                "table" => OutputType::Table,
-                "pretty" => OutputType::Pretty,
-                _ => OutputType::Text,
-            };
+            let output = OutputType::from_args(args);
 
             let res = describe_pacticipant(pacticipant_name.to_string(), &broker_details, output);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -316,28 +404,20 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
             let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
             let auth = get_auth(args);
             let custom_headers = get_custom_headers(args);
-            let ssl_options = get_ssl_options(args);
+            let connection_options = get_connection_options(args);
 
             let broker_details = BrokerDetails {
                 url: broker_url.clone(),
                 auth: Some(auth),
-                ssl_options: ssl_options.clone(),
+                connection_options: connection_options.clone(),
                 custom_headers: custom_headers.clone(),
                 retries: get_retries(args),
             };
-            let default_output: String = "table".to_string();
-            let output_arg: &String = args.get_one::<String>("output").unwrap_or(&default_output);
-            let output = match output_arg.as_str() {
-                "json" => OutputType::Json,
-                "table" => OutputType::Table,
-                "pretty" => OutputType::Pretty,
-                _ => OutputType::Text,
-            };
+            let output = OutputType::from_args(args);
 
-            let res = list_pacticipants(&broker_details, output);
+            let res = list_pacticipants(&broker_details, output, &Pagination::from_args(args));
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -345,17 +425,19 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("create-webhook", args)) => {
             let res = create_webhook(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
         }
         Some(("create-or-update-webhook", args)) => {
-            let res = create_webhook(args);
+            let res = if args.contains_id("file") {
+                create_or_update_webhooks_from_file(args)
+            } else {
+                create_webhook(args)
+            };
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -363,8 +445,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("test-webhook", args)) => {
             let res = test_webhook(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -372,8 +453,80 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("delete-webhook", args)) => {
             let res = delete_webhook(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("list-webhooks", args)) => {
+            let res = list_webhooks(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("describe-webhook", args)) => {
+            let res = describe_webhook(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("export-webhooks", args)) => {
+            let res = export_webhooks(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("api", args)) => {
+            let res = api(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(res.unwrap())
+            }
+        }
+        Some(("backup", args)) => {
+            let res = backup(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("restore", args)) => {
+            let res = restore(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("migrate", args)) => {
+            let res = migrate(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        #[cfg(feature = "mock-broker")]
+        Some(("mock-broker", args)) => {
+            let res = mock_broker(args);
+            if let Err(err) = res {
+                handle_command_error(err)
+            } else {
+                Ok(serde_json::to_value(res.unwrap()).unwrap())
+            }
+        }
+        Some(("apply", args)) => {
+            let res = apply(args);
+            if let Err(err) = res {
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -381,8 +534,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("delete-branch", args)) => {
             let res = delete_branch::delete_branch(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -390,8 +542,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("create-version-tag", args)) => {
             let res = create_version_tag::create_version_tag(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -399,8 +550,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("delete-version-tag", args)) => {
             let res = delete_version_tag(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -408,8 +558,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("describe-version", args)) => {
             let res = describe_version(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -417,8 +566,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
         Some(("create-or-update-version", args)) => {
             let res = create_or_update_version(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -430,6 +578,38 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
             println!("{}", value["uuid"].as_str().unwrap());
             Ok(value)
         }
+        Some(("config", args)) => {
+            let res = match args.subcommand() {
+                Some(("add-profile", args)) => config::add_profile(args),
+                Some(("list-profiles", args)) => config::list_profiles(args),
+                Some(("remove-profile", args)) => config::remove_profile(args),
+                _ => {
+                    error!("⚠️ No config subcommand provided, try running config --help");
+                    return Err(1);
+                }
+            };
+            match res {
+                Ok(output) => {
+                    println!("{}", output);
+                    Ok(serde_json::Value::String(output))
+                }
+                Err(err) => handle_command_error(err),
+            }
+        }
+        Some(("login", args)) => match credentials::login(args) {
+            Ok(output) => {
+                println!("{}", output);
+                Ok(serde_json::Value::String(output))
+            }
+            Err(err) => handle_command_error(err),
+        },
+        Some(("logout", args)) => match credentials::logout(args) {
+            Ok(output) => {
+                println!("{}", output);
+                Ok(serde_json::Value::String(output))
+            }
+            Err(err) => handle_command_error(err),
+        },
         Some(("provider-states", args)) => match args.subcommand() {
             Some(("list", list_args)) => {
                 use crate::cli::pact_broker::main::provider_states::list::handle_list_provider_states_command;
@@ -439,10 +619,7 @@ pub fn run(args: &ArgMatches, raw_args: Vec<String>) -> Result<serde_json::Value
                         println!("{}", output);
                         Ok(serde_json::Value::String(output))
                     }
-                    Err(err) => {
-                        handle_error(err);
-                        Err(1)
-                    }
+                    Err(err) => handle_command_error(err),
                 }
             }
             _ => {
@@ -6,10 +6,12 @@ use serde_json::{Value, json};
 
 use crate::cli::{
     pact_broker::main::{
-        HALClient, Notice, PactBrokerError, process_notices,
+        HALClient, Notice, PactBrokerError,
+        output::{self, OutputType},
+        process_notices,
         utils::{
-            get_auth, get_broker_relation, get_broker_url, get_custom_headers, get_retries,
-            get_ssl_options,
+            get_auth, get_broker_relation, get_broker_url, get_connection_options,
+            get_custom_headers, get_retries,
         },
     },
     utils::git_info,
@@ -57,11 +59,11 @@ pub fn publish(args: &ArgMatches) -> Result<Value, PactBrokerError> {
     let broker_url = get_broker_url(args).trim_end_matches('/').to_string();
     let auth = get_auth(args);
     let custom_headers = get_custom_headers(args);
-    let ssl_options = get_ssl_options(args);
+    let connection_options = get_connection_options(args);
     let hal_client: HALClient = HALClient::with_url(
         &broker_url,
         Some(auth.clone()),
-        ssl_options.clone(),
+        connection_options.clone(),
         custom_headers.clone(),
     )
     .with_retry_count(get_retries(args));
@@ -243,13 +245,15 @@ pub fn publish(args: &ArgMatches) -> Result<Value, PactBrokerError> {
             }
 
             // Output option
-            let output: Result<Option<&String>, clap::parser::MatchesError> =
-                args.try_get_one::<String>("output");
-
-            println!(
-                "📨 Attempting to publish provider contract for provider: {} version: {}",
-                provider_name,
-                provider_app_version.unwrap()
+            let output = OutputType::from_args(args);
+
+            output::status(
+                &output,
+                format!(
+                    "📨 Attempting to publish provider contract for provider: {} version: {}",
+                    provider_name,
+                    provider_app_version.unwrap()
+                ),
             );
             let res = tokio::runtime::Runtime::new().unwrap().block_on(async {
                 hal_client
@@ -270,53 +274,51 @@ pub fn publish(args: &ArgMatches) -> Result<Value, PactBrokerError> {
             });
             match res {
                 Ok(res) => match output {
-                    Ok(Some(output)) => {
-                        if output == "pretty" {
-                            let json = serde_json::to_string_pretty(&res).unwrap();
-                            println!("{}", json);
-                        } else if output == "json" {
-                            return Ok(res.clone());
-                        } else {
-                            let parsed_res =
-                                serde_json::from_value::<ProviderContractPublishRoot>(res);
-                            match parsed_res {
-                                Ok(parsed_res) => {
-                                    print!("✅ ");
-                                    process_notices(&parsed_res.notices);
-                                }
-                                Err(err) => {
-                                    println!(
-                                        "✅ Provider contract published successfully for provider: {} version: {}",
-                                        provider_name,
-                                        provider_app_version.unwrap()
-                                    );
-                                    println!(
-                                        "⚠️ Warning: Failed to process response notices - Error: {:?}",
-                                        err
-                                    );
-                                    return Err(PactBrokerError::ContentError(err.to_string()));
-                                }
-                            }
-                        }
+                    OutputType::Pretty => {
+                        let json = serde_json::to_string_pretty(&res).unwrap();
+                        println!("{}", json);
+                    }
+                    ref output if output.is_structured() => {
+                        output::print(&res, output)?;
+                        return Ok(res);
                     }
                     _ => {
-                        println!("{:?}", res.clone());
+                        let parsed_res = serde_json::from_value::<ProviderContractPublishRoot>(res);
+                        match parsed_res {
+                            Ok(parsed_res) => {
+                                print!("✅ ");
+                                process_notices(&parsed_res.notices);
+                            }
+                            Err(err) => {
+                                println!(
+                                    "✅ Provider contract published successfully for provider: {} version: {}",
+                                    provider_name,
+                                    provider_app_version.unwrap()
+                                );
+                                println!(
+                                    "⚠️ Warning: Failed to process response notices - Error: {:?}",
+                                    err
+                                );
+                                return Err(PactBrokerError::ContentError(err.to_string()));
+                            }
+                        }
                     }
                 },
                 Err(err) => {
                     match &err {
                         crate::cli::pact_broker::main::PactBrokerError::ValidationErrorWithNotices(messages, notices) => {
-                            println!("❌ Provider contract publication failed:");
+                            eprintln!("❌ Provider contract publication failed:");
                             for message in messages {
-                                println!("   {}", message);
+                                eprintln!("   {}", message);
                             }
                             if !notices.is_empty() {
                                 println!("\nDetails:");
                                 process_notices(notices);
                             }
                         },
+                        PactBrokerError::DryRun(_) => {}
                         _ => {
-                            println!("❌ {}", err);
+                            eprintln!("❌ {}", err);
                         }
                     }
                     return Err(err);
@@ -2,7 +2,7 @@ use clap::{ArgMatches, Command};
 use tracing::error;
 
 use crate::cli::{
-    pact_broker::main::utils::handle_error,
+    pact_broker::main::utils::handle_command_error,
     pactflow::main::{provider_contracts, subcommands::add_publish_provider_contract_subcommand},
 };
 pub fn add_pactflow_client_command() -> Command {
@@ -17,8 +17,7 @@ pub fn run(args: &ArgMatches) -> Result<serde_json::Value, i32> {
         Some(("publish-provider-contract", args)) => {
             let res = provider_contracts::publish::publish(args);
             if let Err(err) = res {
-                handle_error(err);
-                Err(1)
+                handle_command_error(err)
             } else {
                 Ok(serde_json::to_value(res.unwrap()).unwrap())
             }
@@ -1,5 +1,9 @@
 pub mod cli;
 pub use cli::build_cli;
+pub use cli::pact_broker::main::client::{
+    PactBrokerClient, PublishContract, PublishPactsRequest, PublishPactsResult,
+};
+pub use cli::pact_broker::main::types::BrokerDetails;
 
 use clap::ArgMatches;
 use clap::error::ErrorKind;
@@ -69,6 +73,11 @@ pub fn handle_matches(
             };
             let _enter = span.enter();
 
+            if let Err(err) = cli::pact_broker::main::utils::check_broker_arguments(results) {
+                cli::pact_broker::main::utils::handle_error(err);
+                return Err(ExitCode::from(1));
+            }
+
             match results.subcommand() {
                 Some(("pactflow", args)) => match cli::pactflow_client::run(args) {
                     Ok(_) => Ok(()),